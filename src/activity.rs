@@ -0,0 +1,71 @@
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::app::Host;
+
+/// traffic this recent makes a host active
+const ACTIVE_WITHIN: Duration = Duration::from_secs(10);
+/// after this long without traffic a host is silent
+const IDLE_WITHIN: Duration = Duration::from_secs(5 * 60);
+
+/// How recently a host exchanged traffic, unlike its last ARP sighting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActivityBucket {
+    /// traffic in the last 10 seconds
+    Active,
+    /// up to 5 minutes ago
+    Idle,
+    /// longer ago, or never
+    Silent,
+}
+
+impl ActivityBucket {
+    pub fn of(host: &Host, now: Instant) -> Self {
+        match host
+            .last_active
+            .map(|last_active| now.saturating_duration_since(last_active))
+        {
+            Some(quiet) if quiet <= ACTIVE_WITHIN => ActivityBucket::Active,
+            Some(quiet) if quiet <= IDLE_WITHIN => ActivityBucket::Idle,
+            _ => ActivityBucket::Silent,
+        }
+    }
+
+    /// The filter after `filter`: every host, then each bucket in turn.
+    pub fn next_filter(filter: Option<Self>) -> Option<Self> {
+        match filter {
+            None => Some(ActivityBucket::Active),
+            Some(ActivityBucket::Active) => Some(ActivityBucket::Idle),
+            Some(ActivityBucket::Idle) => Some(ActivityBucket::Silent),
+            Some(ActivityBucket::Silent) => None,
+        }
+    }
+}
+
+/// Whether `host` is silent and hasn't answered ARP for as long either, an
+/// export lists it as down.
+pub fn is_down(host: &Host, now: Instant) -> bool {
+    ActivityBucket::of(host, now) == ActivityBucket::Silent
+        && now.saturating_duration_since(host.seen_at) > IDLE_WITHIN
+}
+
+/// The hosts the filter lets through, all of them without one.
+pub fn shown_hosts(hosts: &[Host], filter: Option<ActivityBucket>, now: Instant) -> Vec<&Host> {
+    hosts
+        .iter()
+        .filter(|host| filter.is_none_or(|bucket| ActivityBucket::of(host, now) == bucket))
+        .collect()
+}
+
+impl Display for ActivityBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ActivityBucket::Active => "active",
+            ActivityBucket::Idle => "idle",
+            ActivityBucket::Silent => "silent",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -1,15 +1,61 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use std::{error, net::Ipv4Addr};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::{
+    collections::{HashMap, HashSet},
+    error,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use crate::{
+    activity::{self, ActivityBucket},
+    budget::{BudgetLevel, DailyUsage},
+    capture_stats::CaptureStats,
+    clipboard,
+    command::{self, Action},
+    config::SameIpPolicy,
+    connections_table, destinations_table, dry_run,
+    enrich::{Annotations, EnrichCtx, KnownHosts, Oui, Pipeline},
     event::ScannerEvent,
-    stats_aggregator::{Speed, StatsAggregator},
+    export,
+    format::format_bytes,
+    hints::Hint,
+    hosts_table::{HostsTable, MIN_COLUMN_WIDTH},
+    interface_info::InterfaceInfo,
+    inventory::{self, Change, Snapshot},
+    limits::{CollectionSize, Limits},
+    mac_format::MacFormat,
+    networks::NetworkPlan,
+    report::{self, Report},
+    resolver::Resolver,
+    service_hint::ServiceHint,
+    settings::Setting,
+    snapshot::HostsSnapshot,
+    state::State,
+    stats_aggregator::{
+        ConnectionSort, DestinationMask, FooterMetric, HostTrend, Speed, SpeedSource,
+        StatsAggregator,
+    },
+    strings,
+    sweep_report::SweepReport,
+    syslog::EVENT_TARGET,
+    theme::Theme,
+    traffic_category::TrafficCategory,
+    watch::{Transition, Watch, WatchOptions},
+    web::WebSnapshot,
 };
 
-use pnet::util::MacAddr;
+use pnet::{
+    ipnetwork::IpNetwork,
+    packet::arp::{ArpOperation, ArpOperations},
+    util::MacAddr,
+};
 use ratatui::widgets::{ScrollbarState, TableState};
 
-use crate::scanner::Scanner;
+use crate::scanner::{Scanner, SweepSummary, ARP_SWEEP_TASK};
+
+/// stat ticks waiting longer than this to be handled mean the UI can't keep up
+pub const STAT_TICK_SKEW_WARNING: Duration = Duration::from_millis(500);
 
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
@@ -19,25 +65,366 @@ pub struct App {
     /// Is the application running?
     pub running: bool,
     pub sending_arps: bool,
+    /// listening only, no ARP requests are sent
+    pub monitor_only: bool,
+    /// nothing is ever transmitted, the user asked for it
+    pub passive: bool,
+    /// started with `--no-capture`: no speeds, flows nor sweeps
+    pub no_capture: bool,
+    /// the only VLAN looked at, from `--vlan`
+    pub vlan: Option<u16>,
     /// hosts
     pub hosts: Vec<Host>,
+    /// every host found this session, cleaned or not
+    pub seen_hosts: HashSet<(Ipv4Addr, MacAddr)>,
+    /// hosts whose going silent was logged, until they talk again
+    pub offline_hosts: HashSet<Ipv4Addr>,
+    /// alerts already logged, until they clear
+    pub logged_alerts: HashSet<(Ipv4Addr, &'static str)>,
+    /// caps on the hosts and on what the aggregator keeps
+    pub limits: Limits,
+    /// looks up the names of new hosts, none when passive
+    pub resolver: Option<Resolver>,
+    /// annotate new hosts, in order
+    pub enrichers: Pipeline,
     pub table_state: TableState,
     pub scroll_state: ScrollbarState,
+    /// widths of the hosts table columns as last drawn, `<` and `>` start
+    /// from them
+    pub hosts_column_layout: Vec<u16>,
+    /// inside `begin_bulk_update`, the rows are synced once at its end
+    bulk_update: bool,
+    /// the selected host as of the first change not synced yet
+    rows_changed: Option<Option<(Ipv4Addr, MacAddr)>>,
+    /// how many times the rows were synced, for the tests
+    row_syncs: usize,
     pub interface: String,
+    pub interface_info: Option<InterfaceInfo>,
+    /// when set, host ages are computed against this instant instead of now
+    pub frozen_time: Option<Instant>,
+    /// popup drawn over the hosts table, it receives the key events while shown
+    pub popup: Option<Popup>,
+    /// short feedback message shown in the footer
+    notice: Option<(String, Instant)>,
+    /// something shown changed since the last frame, `main` only draws then
+    pub dirty: bool,
+    /// when the last frame was drawn
+    drawn_at: Instant,
+    pub started_at: chrono::DateTime<chrono::Local>,
+    /// where `x` writes the nmap XML export, also written on exit when set from the CLI
+    pub export_nmap_path: Option<PathBuf>,
+    /// same for the HTML report written by `R`
+    pub export_html_path: Option<PathBuf>,
 
     scanner: Scanner,
 
     pub stats_aggregator: StatsAggregator,
+    pub connections_sort: ConnectionSort,
+    /// what the main area lists
+    pub view: View,
+    /// selection in the connections view
+    pub connections_state: TableState,
+    /// grouping of the destinations view and the report
+    pub destination_mask: DestinationMask,
+    pub destinations_state: TableState,
+    /// traffic added up by the footer speed
+    pub footer_metric: FooterMetric,
+    /// traffic shown in the speed columns
+    pub speed_source: SpeedSource,
+    /// download and upload share one column
+    pub combined_speed: bool,
+    /// from the config, for interfaces that don't report their link speed
+    pub link_speed_override: Option<u64>,
+    /// speeds up a held `j` or `k`
+    pub key_repeat: KeyRepeat,
+    /// only the hosts of this bucket are listed
+    pub activity_filter: Option<ActivityBucket>,
+    /// long running work that quitting would abandon
+    pub operations: Vec<Operation>,
+    /// how the last ARP sweep went
+    pub last_sweep: Option<SweepSummary>,
+    /// what the sweep going on or the last one found
+    pub sweep_report: Option<SweepReport>,
+    /// row selected in the sweep report popup
+    pub sweep_report_selected: usize,
+    pub theme: Theme,
+    pub mac_format: MacFormat,
+    /// ARP requests per minute above which a host is flagged as scanning
+    pub arp_scan_threshold: u32,
+    /// sweep networks larger than a /20 without asking, `--force-large-scan`
+    /// or once confirmed
+    pub force_large_scan: bool,
+    /// state shared with the `--web` dashboard, refreshed on every stat tick
+    pub web_snapshot: Option<WebSnapshot>,
+    /// address the web dashboard listens on
+    pub web_address: Option<SocketAddr>,
+    pub switched_network_hint: SwitchedNetworkHint,
+    /// today's traffic of every host, checked against the configured budgets
+    pub daily_usage: DailyUsage,
+    /// remembered between runs
+    pub state: State,
+    /// why the state isn't saved
+    pub state_error: Option<String>,
+    /// the audit trail changed since the state was last saved
+    audit_unsaved: bool,
+    /// row selected in the settings popup
+    pub settings_selected: usize,
+    /// value being typed for the selected setting
+    pub settings_input: Option<String>,
+    /// why the last value typed was refused
+    pub settings_error: Option<String>,
+    /// label being typed in the `EditLabel` popup
+    pub label_input: String,
+    /// command typed in the palette
+    pub command_input: String,
+    /// the only host whose traffic is accounted
+    pub solo: Option<Ipv4Addr>,
+    /// how watched hosts are announced
+    pub watch_options: WatchOptions,
+    /// whether an address seen with several MACs gets a row per MAC
+    pub same_ip: SameIpPolicy,
+    /// a watched host changed, `main` rings the terminal bell once
+    pub ring_bell: bool,
+    /// the snapshot the hosts were last compared with, and the changes since
+    pub snapshot_diff: Option<(String, Vec<Change>)>,
+    /// scanner tasks that panicked, shown until netui is restarted
+    pub failed_tasks: Vec<String>,
+    /// where the log is written
+    pub log_file: Option<PathBuf>,
+    /// why there is no log, netui runs anyway
+    pub logging_error: Option<String>,
+    /// the capture counters as of the last stat tick
+    pub capture_stats: CaptureStats,
+    /// frames the capture loop read per second over the last stat tick
+    pub capture_frames_per_sec: f64,
+    /// how long the last stat tick waited between its flush and its handling
+    pub stat_tick_skew: Duration,
+    /// the longest of these waits this session
+    pub max_stat_tick_skew: Duration,
+}
+
+/// Long running work tracked so quitting can ask for confirmation first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub description: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperationKind {
+    ArpSweep,
+}
+
+/// The tables the main area can show, `Tab` switches between them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    Hosts,
+    /// every tracked flow
+    Connections,
+    /// internet traffic by remote network
+    Destinations,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Popup {
+    InterfaceInfo,
+    HostDetails(Ipv4Addr),
+    /// `snapshot_diff`
+    CompareSnapshot,
+    ConfirmQuit,
+    /// a sweep would probe networks larger than a /20
+    ConfirmLargeScan,
+    SwitchedNetwork,
+    /// typing the label of a host, the text is in `App::label_input`
+    EditLabel(Ipv4Addr, MacAddr),
+    /// everything the footer summarizes, in full
+    Status,
+    /// typing a command, the text is in `App::command_input`
+    CommandPalette,
+    /// how netui's own capture is doing
+    CaptureStats,
+    /// the runtime settings, one being edited when `settings_input` is set
+    Settings,
+    /// every key of the view, `?`
+    Help,
+    /// what the sweep that just ended found, `App::sweep_report`
+    SweepReport,
+}
+
+const MORE: Hint = Hint::new(&[KeyCode::Char('?')], "hint.all_keys");
+const COMMAND: Hint = Hint::new(&[KeyCode::Char(':')], "hint.command");
+const CLOSE: Hint = Hint::new(&[KeyCode::Esc], "hint.close");
+const CANCEL: Hint = Hint::new(&[KeyCode::Char('n')], "hint.cancel");
+const DETAILS: Hint = Hint::new(&[KeyCode::Enter], "hint.details");
+const LABEL: Hint = Hint::new(&[KeyCode::Char('e')], "hint.label");
+const TYPING: [Hint; 2] = [
+    Hint::new(&[KeyCode::Backspace], "hint.delete"),
+    Hint::new(&[KeyCode::Esc], "hint.cancel"),
+];
+
+const HOSTS_HINTS: &[Hint] = &[
+    Hint::new(&[KeyCode::Char('s')], "hint.sweep"),
+    DETAILS,
+    LABEL,
+    COMMAND,
+    MORE,
+];
+/// nothing is sent, the sweep key does nothing
+const PASSIVE_HOSTS_HINTS: &[Hint] = &[
+    DETAILS,
+    LABEL,
+    Hint::new(&[KeyCode::Char('F')], "hint.solo"),
+    COMMAND,
+    MORE,
+];
+const CONNECTIONS_HINTS: &[Hint] = &[
+    Hint::new(&[KeyCode::Char('o')], "hint.next_order"),
+    Hint::new(&[KeyCode::Enter], "hint.host_details"),
+    Hint::new(&[KeyCode::Tab], "hint.destinations"),
+    COMMAND,
+    MORE,
+];
+const DESTINATIONS_HINTS: &[Hint] = &[
+    Hint::new(&[KeyCode::Char('p')], "hint.next_grouping"),
+    Hint::new(&[KeyCode::Char('R')], "hint.report"),
+    Hint::new(&[KeyCode::Tab], "hint.hosts"),
+    COMMAND,
+    MORE,
+];
+
+impl View {
+    /// The keys that matter most in the view, for the hint line.
+    pub fn hints(self, passive: bool) -> &'static [Hint] {
+        match self {
+            View::Hosts if passive => PASSIVE_HOSTS_HINTS,
+            View::Hosts => HOSTS_HINTS,
+            View::Connections => CONNECTIONS_HINTS,
+            View::Destinations => DESTINATIONS_HINTS,
+        }
+    }
+}
+
+impl Popup {
+    /// The keys of the popup, `editing` when a setting is being typed.
+    pub fn hints(self, editing: bool) -> &'static [Hint] {
+        const CONFIRM_QUIT: &[Hint] = &[Hint::new(&[KeyCode::Char('y')], "hint.quit"), CANCEL];
+        const CONFIRM_SWEEP: &[Hint] = &[Hint::new(&[KeyCode::Char('y')], "hint.sweep"), CANCEL];
+        const EDIT_LABEL: &[Hint] = &[
+            Hint::new(&[KeyCode::Enter], "hint.save"),
+            TYPING[0],
+            TYPING[1],
+        ];
+        const COMMAND_PALETTE: &[Hint] = &[
+            Hint::new(&[KeyCode::Enter], "hint.run"),
+            Hint::new(&[KeyCode::Tab], "hint.complete"),
+            TYPING[0],
+            TYPING[1],
+        ];
+        const EDIT_SETTING: &[Hint] = &[
+            Hint::new(&[KeyCode::Enter], "hint.apply"),
+            Hint::new(&[KeyCode::Esc], "hint.discard"),
+        ];
+        const SETTINGS: &[Hint] = &[
+            Hint::new(&[KeyCode::Char('j'), KeyCode::Char('k')], "hint.move"),
+            Hint::new(&[KeyCode::Enter], "hint.edit"),
+            CLOSE,
+        ];
+        const INTERFACE_INFO: &[Hint] = &[Hint::new(&[KeyCode::Char('y')], "hint.copy_ip"), CLOSE];
+        const SWEEP_REPORT: &[Hint] = &[
+            Hint::new(&[KeyCode::Char('j'), KeyCode::Char('k')], "hint.move"),
+            Hint::new(&[KeyCode::Enter], "hint.go_to"),
+            CLOSE,
+        ];
+        match self {
+            Popup::ConfirmQuit => CONFIRM_QUIT,
+            Popup::ConfirmLargeScan => CONFIRM_SWEEP,
+            Popup::EditLabel(..) => EDIT_LABEL,
+            Popup::CommandPalette => COMMAND_PALETTE,
+            Popup::Settings if editing => EDIT_SETTING,
+            Popup::Settings => SETTINGS,
+            Popup::InterfaceInfo => INTERFACE_INFO,
+            Popup::SweepReport => SWEEP_REPORT,
+            Popup::HostDetails(_)
+            | Popup::CompareSnapshot
+            | Popup::SwitchedNetwork
+            | Popup::Status
+            | Popup::CaptureStats
+            | Popup::Help => &[CLOSE],
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Host {
+    /// wall clock time of the last sighting, for display and exports
     pub time: chrono::DateTime<chrono::Local>,
+    /// monotonic time of the last sighting, ages are computed from it so they
+    /// don't jump with clock steps or suspend
+    pub seen_at: Instant,
     pub ipv4: Ipv4Addr,
     pub mac: MacAddr,
     pub hostname: Option<String>,
+    /// set by the user, remembered by MAC across runs
+    pub label: Option<String>,
     pub is_my_device_mac: bool,
+    /// traffic with off-subnet addresses
     pub speed: Option<Speed>,
+    /// traffic with other LAN hosts
+    pub local_speed: Option<Speed>,
+    /// highest averaged speed seen since discovery or the last clean
+    pub peak_speed: Option<Speed>,
+    /// what most of the traffic in the window looks like
+    pub category: Option<TrafficCategory>,
+    /// the service carrying most of the traffic in the window
+    pub service: Option<ServiceHint>,
+    /// how `speed` moved since the previous window
+    pub trend: Option<HostTrend>,
+    /// monotonic time of the last stat tick with traffic of the host
+    pub last_active: Option<Instant>,
+    /// set on the first non-zero speed, tells a host never heard from apart
+    /// from an idle one
+    pub has_traffic: bool,
+    pub arp: ArpActivity,
+    /// what the enrichers found, by source
+    pub annotations: Annotations,
+    /// time between our sweep's ARP request and the host's reply
+    pub arp_rtt: Option<Duration>,
+    /// set with `b`, its traffic starting or stopping is announced
+    pub watch: Option<Watch>,
+}
+
+impl Host {
+    /// A host seen just now.
+    pub fn new(ipv4: Ipv4Addr, mac: MacAddr, is_my_device_mac: bool) -> Self {
+        Self {
+            time: chrono::Local::now(),
+            seen_at: Instant::now(),
+            ipv4,
+            mac,
+            hostname: None,
+            label: None,
+            is_my_device_mac,
+            speed: None,
+            local_speed: None,
+            peak_speed: None,
+            category: None,
+            service: None,
+            trend: None,
+            last_active: None,
+            has_traffic: false,
+            arp: Default::default(),
+            annotations: Default::default(),
+            arp_rtt: None,
+            watch: None,
+        }
+    }
+
+    /// The manufacturer of its network card, from the MAC's prefix.
+    pub fn vendor(&self) -> Option<&str> {
+        self.annotations
+            .get(Oui::SOURCE, "vendor")
+            .map(String::as_str)
+    }
 }
 
 impl PartialEq for Host {
@@ -46,34 +433,382 @@ impl PartialEq for Host {
     }
 }
 
+/// ARP packets sent by a host, a device sending lots of requests is probing
+/// the network (or caught in an ARP storm).
+#[derive(Clone, Debug, Default)]
+pub struct ArpActivity {
+    pub requests: u64,
+    pub replies: u64,
+    /// requests sent since `window_start`
+    window_requests: u32,
+    window_start: Option<Instant>,
+    /// requests sent during the last complete window
+    pub requests_per_window: u32,
+    /// the request rate went over the threshold at some point
+    pub flagged: bool,
+}
+
+/// the request rate is measured over this period
+pub const ARP_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+impl ArpActivity {
+    /// Activity of a single packet.
+    pub fn from_operation(operation: ArpOperation) -> Self {
+        let mut activity = Self::default();
+        match operation {
+            ArpOperations::Request => activity.requests = 1,
+            ArpOperations::Reply => activity.replies = 1,
+            _ => {}
+        }
+        activity
+    }
+
+    /// Adds the packets counted in `seen`, returns true when this pushes the
+    /// host over `threshold` requests per window for the first time.
+    fn record(&mut self, seen: &ArpActivity, threshold: u32, now: Instant) -> bool {
+        self.requests += seen.requests;
+        self.replies += seen.replies;
+        match self.window_start {
+            Some(start) if now.saturating_duration_since(start) < ARP_RATE_WINDOW => {}
+            Some(_) => {
+                self.requests_per_window = self.window_requests;
+                self.window_requests = 0;
+                self.window_start = Some(now);
+            }
+            None => self.window_start = Some(now),
+        }
+        self.window_requests += seen.requests as u32;
+        let over_threshold = self.window_requests.max(self.requests_per_window) > threshold;
+        let newly_flagged = over_threshold && !self.flagged;
+        self.flagged |= over_threshold;
+        newly_flagged
+    }
+}
+
+/// Detects a capture on a switched port: many hosts answer ARP but the only
+/// traffic captured is this device's and the gateway's.
+#[derive(Clone, Debug)]
+pub struct SwitchedNetworkHint {
+    /// cleared once the hint was shown, or when suppressed from the CLI
+    armed: bool,
+    /// since when hosts are known but no third party traffic was captured
+    blind_since: Option<Instant>,
+}
+
+/// how long the capture must stay blind to other hosts before hinting
+const SWITCHED_NETWORK_DELAY: Duration = Duration::from_secs(180);
+/// discovered hosts, besides this device and the gateway, needed to hint
+const SWITCHED_NETWORK_MIN_HOSTS: usize = 5;
+
+impl SwitchedNetworkHint {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            armed: enabled,
+            blind_since: None,
+        }
+    }
+
+    /// Feeds one stat tick, returns true the one time the hint should show.
+    ///
+    /// Traffic of any other host means a hub, a mirror port or a router
+    /// deployment, the hint is then disarmed for good.
+    fn observe(&mut self, other_hosts: usize, third_party_traffic: bool, now: Instant) -> bool {
+        if !self.armed {
+            return false;
+        }
+        if third_party_traffic {
+            self.armed = false;
+            return false;
+        }
+        if other_hosts < SWITCHED_NETWORK_MIN_HOSTS {
+            self.blind_since = None;
+            return false;
+        }
+        let since = *self.blind_since.get_or_insert(now);
+        if now.saturating_duration_since(since) < SWITCHED_NETWORK_DELAY {
+            return false;
+        }
+        self.armed = false;
+        true
+    }
+}
+
 const ITEM_HEIGHT: usize = 4;
+/// presses of the same key closer than this are a held key
+const KEY_REPEAT_WINDOW: Duration = Duration::from_millis(150);
+/// held presses before the selection moves faster
+const KEY_REPEATS_BEFORE_ACCELERATION: u32 = 8;
+/// rows per press once accelerated
+const ACCELERATED_ROWS: usize = 5;
+
+/// Counts the presses of a held navigation key to speed it up.
+#[derive(Debug, Default)]
+pub struct KeyRepeat {
+    code: Option<KeyCode>,
+    count: u32,
+    last_at: Option<Instant>,
+}
+
+impl KeyRepeat {
+    /// Rows to move for a press of `code` at `now`.
+    fn rows(&mut self, code: KeyCode, now: Instant) -> usize {
+        let held = self.code == Some(code)
+            && self
+                .last_at
+                .is_some_and(|last_at| now.saturating_duration_since(last_at) <= KEY_REPEAT_WINDOW);
+        self.count = if held { self.count + 1 } else { 0 };
+        self.code = Some(code);
+        self.last_at = Some(now);
+        if self.count >= KEY_REPEATS_BEFORE_ACCELERATION {
+            ACCELERATED_ROWS
+        } else {
+            1
+        }
+    }
+}
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
+/// ages are shown to the second, finer changes of time don't show
+const AGE_RESOLUTION: Duration = Duration::from_secs(1);
+/// the longest average of the footer, traffic this old still moves it
+const FOOTER_AVERAGE_SPAN: Duration = Duration::from_secs(15 * 60);
+const DEFAULT_NMAP_EXPORT_FILE: &str = "hosts.xml";
+const DEFAULT_HTML_REPORT_FILE: &str = "report.html";
+pub const DEFAULT_ARP_SCAN_THRESHOLD: u32 = 60;
+/// cells a column grows or shrinks by with `>` and `<`
+const COLUMN_WIDTH_STEP: i16 = 2;
 impl App {
     /// Constructs a new instance of [`App`].
     pub fn new(scanner: Scanner) -> AppResult<Self> {
         Ok(Self {
             running: true,
             sending_arps: false,
+            monitor_only: scanner.is_monitor_only(),
+            passive: scanner.is_passive(),
+            no_capture: !scanner.is_capturing(),
+            vlan: scanner.vlan(),
             hosts: vec![],
+            seen_hosts: HashSet::new(),
+            offline_hosts: HashSet::new(),
+            logged_alerts: HashSet::new(),
+            limits: Limits::default(),
+            resolver: None,
+            enrichers: Pipeline::new().with(KnownHosts),
             interface: "".to_string(),
+            interface_info: None,
+            frozen_time: None,
+            popup: None,
+            notice: None,
+            dirty: true,
+            drawn_at: Instant::now(),
+            started_at: chrono::Local::now(),
+            export_nmap_path: None,
+            export_html_path: None,
             table_state: TableState::default(),
+            hosts_column_layout: vec![],
             scanner,
             scroll_state: ScrollbarState::new(0),
+            bulk_update: false,
+            rows_changed: None,
+            row_syncs: 0,
             stats_aggregator: Default::default(),
+            connections_sort: Default::default(),
+            view: View::Hosts,
+            connections_state: TableState::default(),
+            destination_mask: DestinationMask::default(),
+            destinations_state: TableState::default(),
+            footer_metric: Default::default(),
+            speed_source: Default::default(),
+            combined_speed: false,
+            link_speed_override: None,
+            key_repeat: Default::default(),
+            activity_filter: None,
+            operations: vec![],
+            last_sweep: None,
+            sweep_report: None,
+            sweep_report_selected: 0,
+            theme: Theme::default(),
+            mac_format: Default::default(),
+            arp_scan_threshold: DEFAULT_ARP_SCAN_THRESHOLD,
+            force_large_scan: false,
+            web_snapshot: None,
+            web_address: None,
+            switched_network_hint: SwitchedNetworkHint::new(true),
+            daily_usage: Default::default(),
+            state: Default::default(),
+            state_error: None,
+            audit_unsaved: false,
+            settings_selected: 0,
+            settings_input: None,
+            settings_error: None,
+            label_input: String::new(),
+            command_input: String::new(),
+            solo: None,
+            watch_options: WatchOptions::default(),
+            same_ip: SameIpPolicy::default(),
+            ring_bell: false,
+            snapshot_diff: None,
+            failed_tasks: vec![],
+            log_file: None,
+            logging_error: None,
+            capture_stats: Default::default(),
+            capture_frames_per_sec: 0.0,
+            stat_tick_skew: Duration::ZERO,
+            max_stat_tick_skew: Duration::ZERO,
         })
     }
 
     /// Handles the tick event of the terminal.
-    pub fn tick(&self) {}
+    /// Marks the frame dirty when the passing time shows: an age moved on
+    /// to the next second or the notice expired. Nothing ages while time is
+    /// frozen.
+    pub fn tick(&mut self) {
+        if self
+            .notice
+            .as_ref()
+            .is_some_and(|(_, at)| at.elapsed() >= NOTICE_DURATION)
+        {
+            self.notice = None;
+            self.dirty = true;
+        }
+        let shows_time = !self.hosts.is_empty() || self.popup.is_some();
+        if self.frozen_time.is_none() && shows_time && self.drawn_at.elapsed() >= AGE_RESOLUTION {
+            self.dirty = true;
+        }
+    }
+
+    /// The frame was just drawn, what's shown is up to date.
+    pub fn mark_drawn(&mut self) {
+        self.dirty = false;
+        self.drawn_at = Instant::now();
+    }
 
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
+        self.save_audit();
         self.running = false;
     }
 
+    /// Defers syncing the selection and the scrollbar with the hosts until
+    /// `end_bulk_update`, for a batch of changes.
+    pub fn begin_bulk_update(&mut self) {
+        self.bulk_update = true;
+    }
+
+    /// Syncs the rows once for every change since `begin_bulk_update`.
+    pub fn end_bulk_update(&mut self) {
+        self.bulk_update = false;
+        self.sync_rows();
+    }
+
+    /// Shows the hosts table with `key` selected, when it's listed.
+    fn select_host(&mut self, key: (Ipv4Addr, MacAddr)) {
+        self.view = View::Hosts;
+        self.rows_changed = Some(Some(key));
+        self.sync_rows();
+    }
+
+    fn selected_key(&self) -> Option<(Ipv4Addr, MacAddr)> {
+        self.selected_host().map(|host| (host.ipv4, host.mac))
+    }
+
+    /// To call before changing the hosts, with the selection as it was.
+    fn hosts_changing(&mut self) {
+        if self.rows_changed.is_none() {
+            self.rows_changed = Some(self.selected_key());
+        }
+    }
+
+    /// To call after changing the hosts: syncs now, or at the end of the
+    /// bulk update.
+    fn hosts_changed(&mut self) {
+        if !self.bulk_update {
+            self.sync_rows();
+        }
+    }
+
+    /// Keeps the selected host selected wherever its row went, the row
+    /// under it when it's gone, and sizes the scrollbar to the rows.
+    fn sync_rows(&mut self) {
+        let Some(selected) = self.rows_changed.take() else {
+            return;
+        };
+        self.row_syncs += 1;
+        let shown = self.shown_hosts();
+        let len = shown.len();
+        let row = match selected {
+            Some(key) => shown
+                .iter()
+                .position(|host| (host.ipv4, host.mac) == key)
+                .or_else(|| {
+                    self.table_state
+                        .selected()
+                        .map(|i| i.min(len.saturating_sub(1)))
+                }),
+            None => self.table_state.selected(),
+        };
+        let row = row.filter(|_| len > 0);
+        self.table_state.select(row);
+        self.scroll_state = self
+            .scroll_state
+            .content_length(len.saturating_sub(1) * ITEM_HEIGHT)
+            .position(row.unwrap_or(0) * ITEM_HEIGHT);
+    }
+
+    /// Drops the least recently seen hosts past the limit.
+    fn evict_stale_hosts(&mut self) {
+        while self.hosts.len() > self.limits.hosts {
+            let Some(stalest) = self
+                .hosts
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, host)| host.seen_at)
+                .map(|(i, _)| i)
+            else {
+                return;
+            };
+            self.hosts.remove(stalest);
+        }
+    }
+
+    /// How full the bounded collections are, the aggregator's included.
+    pub fn collection_sizes(&self) -> Vec<CollectionSize> {
+        let mut sizes = vec![
+            CollectionSize::new("Hosts", self.hosts.len(), self.limits.hosts),
+            CollectionSize::new("Seen hosts", self.seen_hosts.len(), self.limits.seen_hosts),
+        ];
+        sizes.extend(self.stats_aggregator.sizes());
+        sizes
+    }
+
+    /// The keys of the open popup, else of the view.
+    pub fn hints(&self) -> &'static [Hint] {
+        match self.popup {
+            Some(popup) => popup.hints(self.settings_input.is_some()),
+            None => self.view.hints(self.passive || self.no_capture),
+        }
+    }
+
+    /// Hosts with traffic in the last seconds, in the list, and found this session.
+    pub fn host_counts(&self) -> (usize, usize, usize) {
+        let now = self.time_reference();
+        let active = self
+            .hosts
+            .iter()
+            .filter(|host| ActivityBucket::of(host, now) == ActivityBucket::Active)
+            .count();
+        (active, self.hosts.len(), self.seen_hosts.len())
+    }
+
+    /// The hosts listed in the table, in its order.
+    pub fn shown_hosts(&self) -> Vec<&Host> {
+        activity::shown_hosts(&self.hosts, self.activity_filter, self.time_reference())
+    }
+
     pub fn next_row(&mut self) {
+        let len = self.shown_hosts().len();
         let i = match self.table_state.selected() {
             Some(i) => {
-                if i + 1 >= self.hosts.len() {
+                if i + 1 >= len {
                     None
                 } else {
                     Some(i + 1)
@@ -84,10 +819,11 @@ impl App {
         self.table_state.select(i);
         self.scroll_state = self
             .scroll_state
-            .position(i.unwrap_or(self.hosts.len().saturating_sub(1)) * ITEM_HEIGHT);
+            .position(i.unwrap_or(len.saturating_sub(1)) * ITEM_HEIGHT);
     }
 
     pub fn previous_row(&mut self) {
+        let len = self.shown_hosts().len();
         let i = match self.table_state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -96,15 +832,43 @@ impl App {
                     Some(i - 1)
                 }
             }
-            None => Some(self.hosts.len().saturating_sub(1)),
+            None => Some(len.saturating_sub(1)),
         };
         self.table_state.select(i);
         self.scroll_state = self.scroll_state.position(i.unwrap_or(0) * ITEM_HEIGHT);
     }
 
+    /// Moves `rows` down; past one row, stops on the last row instead of
+    /// wrapping around.
+    pub fn next_rows(&mut self, rows: usize) {
+        if rows <= 1 {
+            return self.next_row();
+        }
+        let last = self.shown_hosts().len().saturating_sub(1);
+        let i = self
+            .table_state
+            .selected()
+            .map_or(0, |i| (i + rows).min(last));
+        self.table_state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+    }
+
+    /// Moves `rows` up, stopping on the first row like `next_rows`.
+    pub fn previous_rows(&mut self, rows: usize) {
+        if rows <= 1 {
+            return self.previous_row();
+        }
+        let i = self
+            .table_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(rows));
+        self.table_state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+    }
+
     pub fn next_column(&mut self) {
         if let Some(selected) = self.table_state.selected_column() {
-            if selected == 2 {
+            if selected + 1 >= HostsTable::column_count(self.combined_speed) {
                 self.table_state.select_column(None);
                 return;
             }
@@ -112,6 +876,36 @@ impl App {
         self.table_state.select_next_column();
     }
 
+    /// Widens or narrows the selected hosts column by `delta` cells, or
+    /// gives it back its computed width without; saved with the labels.
+    fn resize_column(&mut self, delta: Option<i16>) {
+        let Some(column) = self.table_state.selected_column() else {
+            self.notify("select a column with h and l first".to_string());
+            return;
+        };
+        let Some(key) = HostsTable::column_key(column, self.combined_speed) else {
+            return;
+        };
+        let width = delta.map(|delta| {
+            // as drawn, a width too large for the terminal was cut down
+            let current = self
+                .hosts_column_layout
+                .get(column)
+                .copied()
+                .or(self.state.column_width(key))
+                .unwrap_or(MIN_COLUMN_WIDTH);
+            current.saturating_add_signed(delta).max(MIN_COLUMN_WIDTH)
+        });
+        self.state.set_column_width(key, width);
+        // a key held down goes on from the new width before the next draw
+        if let (Some(width), Some(drawn)) = (width, self.hosts_column_layout.get_mut(column)) {
+            *drawn = width;
+        }
+        if let Err(e) = self.state.save() {
+            self.notify(format!("column width not saved: {}", e));
+        }
+    }
+
     pub fn previous_column(&mut self) {
         if let Some(selected) = self.table_state.selected_column() {
             if selected == 0 {
@@ -122,88 +916,1522 @@ impl App {
         self.table_state.select_previous_column();
     }
     pub fn handle_worker_events(&mut self, worker_event: ScannerEvent) -> AppResult<()> {
+        // a stat tick decides for itself, an idle network changes nothing
+        self.dirty |= !matches!(worker_event, ScannerEvent::StatTick(..));
         match worker_event {
             ScannerEvent::HostFound(mut host) => {
-                if let Some(h) = self.hosts.iter_mut().find(|h| h == &&host) {
+                let host_key = (host.ipv4, host.mac);
+                // only replies to our requests are timed
+                if let Some(report) = self
+                    .sweep_report
+                    .as_mut()
+                    .filter(|_| host.arp_rtt.is_some())
+                {
+                    report.reply(host.ipv4, host.mac);
+                }
+                if self.seen_hosts.len() < self.limits.seen_hosts {
+                    self.seen_hosts.insert(host_key);
+                }
+                let seen = std::mem::take(&mut host.arp);
+                self.audit_unsaved |= self.state.record_binding(host.ipv4, host.mac, host.time);
+                let same_ip = self.same_ip;
+                let same_row = |h: &Host| match same_ip {
+                    SameIpPolicy::Separate => *h == *host,
+                    SameIpPolicy::Merge => h.ipv4 == host.ipv4,
+                };
+                if let Some(h) = self.hosts.iter_mut().find(|h| same_row(h)) {
                     host.speed = h.speed;
-                    *h = host;
+                    host.local_speed = h.local_speed;
+                    host.peak_speed = h.peak_speed;
+                    host.category = h.category;
+                    host.service = h.service;
+                    host.trend = h.trend;
+                    host.last_active = h.last_active;
+                    host.has_traffic = h.has_traffic;
+                    host.arp_rtt = host.arp_rtt.or(h.arp_rtt);
+                    host.watch = h.watch;
+                    host.hostname = h.hostname.take();
+                    host.label = h.label.take();
+                    host.annotations = std::mem::take(&mut h.annotations);
+                    host.arp = std::mem::take(&mut h.arp);
+                    *h = *host;
                 } else {
-                    self.hosts.push(host);
-                    self.scroll_state = self
-                        .scroll_state
-                        .content_length((self.hosts.len().saturating_sub(1)) * ITEM_HEIGHT);
+                    tracing::info!(
+                        target: EVENT_TARGET,
+                        ip = %host.ipv4,
+                        mac = %host.mac,
+                        interface = %self.interface,
+                        "host_discovered"
+                    );
+                    if let Some(resolver) = &self.resolver {
+                        resolver.enqueue(host.ipv4);
+                    }
+                    Self::enrich_host(&self.enrichers, &self.state, &mut host);
+                    self.hosts_changing();
+                    self.hosts.push(*host);
+                    self.evict_stale_hosts();
+                    self.hosts_changed();
                 }
+                self.record_arp_activity(host_key, &seen);
             }
-            ScannerEvent::Complete => {
+            ScannerEvent::Complete(summary) => {
                 self.sending_arps = false;
+                self.end_operation(OperationKind::ArpSweep);
+                if summary.failed > 0 {
+                    self.notify(format!(
+                        "ARP sweep done, {} of {} sends failed",
+                        summary.failed,
+                        summary.sent + summary.failed
+                    ));
+                }
+                if let Some(report) = &mut self.sweep_report {
+                    report.probed = Some(summary.sent);
+                    // not over what the user is doing
+                    if self.popup.is_none() {
+                        self.sweep_report_selected = 0;
+                        self.popup = Some(Popup::SweepReport);
+                    }
+                }
+                self.last_sweep = Some(summary);
+            }
+            ScannerEvent::TaskFailed { task, reason } => {
+                if task == ARP_SWEEP_TASK {
+                    self.sending_arps = false;
+                    self.end_operation(OperationKind::ArpSweep);
+                }
+                self.failed_tasks
+                    .push(format!("{} stopped: {}", task, reason));
+            }
+            ScannerEvent::Resolved(ip, name) => {
+                for host in self.hosts.iter_mut().filter(|h| h.ipv4 == ip) {
+                    host.hostname = Some(name.clone());
+                    Self::enrich_host(&self.enrichers, &self.state, host);
+                }
+            }
+            ScannerEvent::Error(message) => {
+                self.notify(message);
+            }
+            ScannerEvent::NetworkWarnings(warnings) => {
+                for warning in &warnings {
+                    tracing::warn!("{}: {}", self.interface, warning);
+                }
+                self.notify(warnings.join("; "));
             }
-            ScannerEvent::BeginScan => {
+            ScannerEvent::NoNetwork => {
+                self.notify(format!(
+                    "no IPv4 network on {}, nothing to sweep; give it an address or watch it with --monitor-only",
+                    self.interface
+                ));
+            }
+            ScannerEvent::BeginScan(network) => {
                 self.sending_arps = true;
+                let known = self
+                    .hosts
+                    .iter()
+                    .filter(|host| !host.is_my_device_mac)
+                    .map(|host| (host.ipv4, host.mac));
+                self.sweep_report = Some(SweepReport::begin(network, known));
+                let description = format!("an ARP sweep is probing {}", self.interface);
+                self.begin_operation(OperationKind::ArpSweep, description);
             }
-            ScannerEvent::InterfaceName(interface_name) => {
-                self.interface = interface_name;
+            ScannerEvent::Interface(interface_info) => {
+                self.interface = interface_info.name.clone();
+                // overlapping networks would make a host local twice
+                let networks: Vec<IpNetwork> =
+                    interface_info.networks.iter().map(|n| n.network).collect();
+                let lan = NetworkPlan::analyze(&networks).scan;
+                self.stats_aggregator.set_lan(interface_info.gateway, lan);
+                self.interface_info = Some(interface_info);
             }
-            ScannerEvent::StatTick(hash_map) => {
-                self.stats_aggregator.tick(hash_map);
+            ScannerEvent::StatTick(hash_map, elapsed, flushed_at) => {
+                let had_speeds = self.hosts.iter().any(|h| {
+                    [h.speed, h.local_speed]
+                        .iter()
+                        .flatten()
+                        .any(|speed| speed.total() > 0)
+                });
+                self.dirty |= !hash_map.is_empty()
+                    || had_speeds
+                    || self.footer_averages_move()
+                    || self.popup == Some(Popup::CaptureStats);
+                self.stat_tick_skew = flushed_at.elapsed();
+                self.max_stat_tick_skew = self.max_stat_tick_skew.max(self.stat_tick_skew);
+                self.stats_aggregator.tick(hash_map, elapsed, flushed_at);
+                self.save_audit();
+                let capture_stats = self.scanner.capture_stats();
+                self.capture_frames_per_sec = capture_stats.frames_per_sec(&self.capture_stats);
+                self.capture_stats = capture_stats;
                 let speeds = self.stats_aggregator.speed_per_host();
+                let categories = self.stats_aggregator.dominant_category_per_host();
+                let services = self.stats_aggregator.dominant_service_per_host();
+                let trends = self.stats_aggregator.trend_per_host();
+                let local_speeds = self.stats_aggregator.local_speed_per_host();
+                let last_tick = self
+                    .stats_aggregator
+                    .last_tick_per_host()
+                    .unwrap_or_default();
+                let now = Instant::now();
                 self.hosts.iter_mut().for_each(|h| {
+                    if last_tick.get(&h.ipv4).is_some_and(|bits| bits.total() > 0) {
+                        h.last_active = Some(now);
+                    }
+                    // out of the window a host is idle, not stuck at its last speed
+                    h.local_speed = local_speeds
+                        .get(&h.ipv4)
+                        .copied()
+                        .or(h.local_speed.map(|_| Speed::default()));
+                    if let Some(category) = categories.get(&h.ipv4) {
+                        h.category = Some(*category);
+                    }
+                    if let Some(service) = services.get(&h.ipv4) {
+                        h.service = Some(*service);
+                    }
+                    h.trend = trends.get(&h.ipv4).copied();
+                    h.speed = speeds
+                        .get(&h.ipv4)
+                        .copied()
+                        .or(h.speed.map(|_| Speed::default()));
                     if let Some(speed) = speeds.get(&h.ipv4) {
-                        h.speed = Some(*speed);
+                        h.peak_speed = Some(h.peak_speed.map_or(*speed, |peak| peak.max(*speed)));
+                    }
+                    if [h.speed, h.local_speed]
+                        .iter()
+                        .flatten()
+                        .any(|speed| speed.total() > 0)
+                    {
+                        h.has_traffic = true;
                     }
                 });
+                self.announce_watched_hosts(&last_tick);
+                self.record_daily_usage();
+                self.log_offline_hosts(now);
+                self.log_alerts();
+                self.check_switched_network(&speeds);
+                self.publish_web_snapshot();
             }
         }
         Ok(())
     }
 
+    /// Moving around the connections and destinations views, `false` for
+    /// the keys they leave to the hosts view's bindings.
+    fn handle_view_key(&mut self, code: KeyCode) -> bool {
+        let (len, columns) = match self.view {
+            View::Hosts => return false,
+            View::Connections => (
+                self.stats_aggregator
+                    .connections(self.connections_sort)
+                    .len(),
+                connections_table::COLUMNS,
+            ),
+            View::Destinations => (
+                self.stats_aggregator
+                    .destinations(self.destination_mask)
+                    .len(),
+                destinations_table::COLUMNS,
+            ),
+        };
+        let state = match self.view {
+            View::Destinations => &mut self.destinations_state,
+            _ => &mut self.connections_state,
+        };
+        match code {
+            KeyCode::Char('j') => {
+                let rows = self.key_repeat.rows(code, Instant::now());
+                let last = len.saturating_sub(1);
+                let i = state.selected().map_or(0, |i| (i + rows).min(last));
+                state.select(Some(i));
+            }
+            KeyCode::Char('k') => {
+                let rows = self.key_repeat.rows(code, Instant::now());
+                let i = state.selected().map_or(0, |i| i.saturating_sub(rows));
+                state.select(Some(i));
+            }
+            KeyCode::Char('l') => match state.selected_column() {
+                Some(column) if column + 1 >= columns => state.select_column(None),
+                _ => state.select_next_column(),
+            },
+            KeyCode::Char('h') => match state.selected_column() {
+                Some(0) => state.select_column(None),
+                _ => state.select_previous_column(),
+            },
+            KeyCode::Enter if self.view == View::Connections => {
+                let local = self.connections_state.selected().and_then(|i| {
+                    let connections = self.stats_aggregator.connections(self.connections_sort);
+                    connections.get(i).map(|connection| connection.local.0)
+                });
+                if let Some(ip) = local.filter(|ip| self.hosts.iter().any(|h| h.ipv4 == *ip)) {
+                    self.popup = Some(Popup::HostDetails(ip));
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
     pub fn handle_key_events(&mut self, key_event: KeyEvent) -> AppResult<()> {
-        match key_event.code {
-            // Exit application on `ESC` or `q`
-            KeyCode::Esc | KeyCode::Char('q') => {
-                self.quit();
+        self.dirty = true;
+        // keys act on the rows as shown
+        self.sync_rows();
+        // a held key only repeats what is harmless to repeat: moving and typing
+        let is_repeat = key_event.kind == KeyEventKind::Repeat;
+        if let Some(popup) = self.popup {
+            let is_typing = matches!(popup, Popup::EditLabel(..) | Popup::CommandPalette)
+                || (popup == Popup::Settings && self.settings_input.is_some());
+            if is_repeat && !is_typing {
+                return Ok(());
             }
+            return self.handle_popup_key_events(popup, key_event);
+        }
+        let is_navigation = matches!(key_event.code, KeyCode::Char('j' | 'k' | 'h' | 'l'));
+        if is_repeat && !is_navigation {
+            return Ok(());
+        }
+        if self.handle_view_key(key_event.code) {
+            return Ok(());
+        }
+        let action = match key_event.code {
+            // Exit application on `ESC` or `q`
+            KeyCode::Esc | KeyCode::Char('q') => Action::Quit,
+            // Exit even with operations in flight
+            KeyCode::Char('Q') => Action::ForceQuit,
             // Exit application on `Ctrl-C`
             KeyCode::Char('c') | KeyCode::Char('C') => {
                 if key_event.modifiers == KeyModifiers::CONTROL {
-                    self.quit();
+                    Action::ForceQuit
                 } else {
-                    self.clean_host_and_olders();
+                    Action::Clean
                 }
             }
             // Counter handlers
             KeyCode::Char('j') => {
-                self.next_row();
+                let rows = self.key_repeat.rows(key_event.code, Instant::now());
+                self.next_rows(rows);
+                return Ok(());
             }
             KeyCode::Char('k') => {
-                self.previous_row();
+                let rows = self.key_repeat.rows(key_event.code, Instant::now());
+                self.previous_rows(rows);
+                return Ok(());
             }
             KeyCode::Char('l') => {
                 self.next_column();
+                return Ok(());
             }
             KeyCode::Char('h') => {
                 self.previous_column();
+                return Ok(());
+            }
+            KeyCode::Char('<') => {
+                self.resize_column(Some(-COLUMN_WIDTH_STEP));
+                return Ok(());
+            }
+            KeyCode::Char('>') => {
+                self.resize_column(Some(COLUMN_WIDTH_STEP));
+                return Ok(());
             }
-            KeyCode::Char('s') => {
-                if !self.sending_arps {
-                    self.scanner.send_arp_packets();
+            KeyCode::Char('=') => {
+                self.resize_column(None);
+                return Ok(());
+            }
+            KeyCode::Enter => {
+                if let Some(host) = self.selected_host() {
+                    self.popup = Some(Popup::HostDetails(host.ipv4));
                 }
+                return Ok(());
+            }
+            KeyCode::Char('?') => Action::Help,
+            KeyCode::Char(':') => {
+                self.command_input.clear();
+                self.popup = Some(Popup::CommandPalette);
+                return Ok(());
             }
+            KeyCode::Tab => Action::NextView,
+            KeyCode::Char('o') => Action::NextConnectionOrder,
+            KeyCode::Char('p') => Action::NextDestinationMask,
+            KeyCode::Char('O') => Action::ReverseConnectionOrder,
+            KeyCode::Char('s') => Action::Scan,
+            KeyCode::Char('I') => Action::InterfaceInfo,
+            KeyCode::Char('S') => Action::Status,
+            KeyCode::Char('D') => Action::CaptureStats,
+            KeyCode::Char(',') => Action::Settings,
+            KeyCode::Char('e') => Action::EditLabel,
+            KeyCode::Char('t') => Action::FreezeTime,
+            KeyCode::Char('T') => Action::NextTheme,
+            KeyCode::Char('g') => Action::NextFooterMetric,
+            KeyCode::Char('v') => Action::NextSpeedSource,
+            KeyCode::Char('m') => Action::ToggleCombinedSpeed,
+            KeyCode::Char('a') => Action::NextActivityFilter,
+            KeyCode::Char('x') => Action::Export,
+            KeyCode::Char('R') => Action::ExportReport,
+            KeyCode::Char('F') => Action::ToggleSolo,
+            KeyCode::Char('b') => Action::ToggleWatch,
+            KeyCode::Char('w') => Action::SaveSnapshot,
+            KeyCode::Char('W') => Action::CompareSnapshot,
             // Other handlers you could add here.
+            _ => return Ok(()),
+        };
+        self.perform(action);
+        Ok(())
+    }
+
+    /// Runs an action the same way whether it came from a key or the palette.
+    pub fn perform(&mut self, action: Action) {
+        match action {
+            Action::Quit => {
+                if self.operations.is_empty() {
+                    self.quit();
+                } else {
+                    self.popup = Some(Popup::ConfirmQuit);
+                }
+            }
+            Action::ForceQuit => self.quit(),
+            Action::Scan if self.sending_arps || self.passive || self.no_capture => {}
+            Action::Scan
+                if self.scanner.network_plan().needs_confirmation() && !self.force_large_scan =>
+            {
+                self.popup = Some(Popup::ConfirmLargeScan);
+            }
+            Action::Scan => self.scanner.send_arp_packets(),
+            Action::Clean => {
+                self.clean_host_and_olders();
+            }
+            Action::EditLabel => {
+                if let Some(host) = self.selected_host() {
+                    let popup = Popup::EditLabel(host.ipv4, host.mac);
+                    self.label_input = host.label.clone().unwrap_or_default();
+                    self.popup = Some(popup);
+                }
+            }
+            Action::InterfaceInfo => self.popup = Some(Popup::InterfaceInfo),
+            Action::Status => self.popup = Some(Popup::Status),
+            Action::Help => self.popup = Some(Popup::Help),
+            Action::CaptureStats => self.popup = Some(Popup::CaptureStats),
+            Action::FreezeTime => self.toggle_frozen_time(),
+            Action::Theme(name) => self.theme = Theme::new(name),
+            Action::NextTheme => self.theme = Theme::new(self.theme.name.next()),
+            Action::Settings => {
+                self.settings_input = None;
+                self.settings_error = None;
+                self.popup = Some(Popup::Settings);
+            }
+            Action::NextFooterMetric => self.footer_metric = self.footer_metric.next(),
+            Action::NextSpeedSource => self.speed_source = self.speed_source.next(),
+            Action::NextView => {
+                self.view = match self.view {
+                    View::Hosts => View::Connections,
+                    View::Connections => View::Destinations,
+                    View::Destinations => View::Hosts,
+                };
+            }
+            Action::NextDestinationMask => {
+                self.destination_mask = self.destination_mask.next();
+                self.destinations_state.select(None);
+            }
+            Action::NextConnectionOrder => {
+                self.connections_sort.order = self.connections_sort.order.next();
+            }
+            Action::ReverseConnectionOrder => {
+                self.connections_sort.descending = !self.connections_sort.descending;
+            }
+            Action::NextActivityFilter => {
+                self.activity_filter = ActivityBucket::next_filter(self.activity_filter);
+                // the rows are not the same ones anymore
+                self.table_state.select(None);
+                self.scroll_state = self.scroll_state.position(0);
+            }
+            Action::ToggleCombinedSpeed => {
+                self.combined_speed = !self.combined_speed;
+                // the last column may be gone
+                let columns = HostsTable::column_count(self.combined_speed);
+                if self.table_state.selected_column() >= Some(columns) {
+                    self.table_state.select_column(Some(columns - 1));
+                }
+            }
+            Action::ExportReport => {
+                let path = self.export_html_path.clone().unwrap_or_else(|| {
+                    crate::logging::get_data_dir().join(DEFAULT_HTML_REPORT_FILE)
+                });
+                match self.export_html(&path) {
+                    Ok(()) => self.notify(format!("report written to {}", path.display())),
+                    Err(e) => self.notify(format!("report failed: {}", e)),
+                }
+            }
+            Action::ToggleWatch => {
+                let Some(key) = self.selected_host().map(|host| (host.ipv4, host.mac)) else {
+                    return;
+                };
+                if let Some(host) = self.hosts.iter_mut().find(|h| (h.ipv4, h.mac) == key) {
+                    let sending = host.speed.is_some_and(|speed| speed.total() > 0);
+                    host.watch = match host.watch {
+                        Some(_) => None,
+                        None => Some(Watch::new(sending)),
+                    };
+                }
+            }
+            Action::SaveSnapshot => match Snapshot::of(&self.interface, &self.hosts).save() {
+                Ok(name) => self.notify(format!("hosts saved as snapshot {}", name)),
+                Err(e) => self.notify(format!("snapshot failed: {}", e)),
+            },
+            Action::CompareSnapshot => match Snapshot::latest(&self.interface) {
+                Some((name, snapshot)) => {
+                    let now = Snapshot::of(&self.interface, &self.hosts);
+                    self.snapshot_diff = Some((name, inventory::diff(&snapshot.hosts, &now.hosts)));
+                    self.popup = Some(Popup::CompareSnapshot);
+                }
+                None => {
+                    self.notify("no snapshot of this interface yet, save one with w".to_string())
+                }
+            },
+            Action::ToggleSolo => {
+                let solo = match self.solo {
+                    Some(_) => None,
+                    None => self.selected_host().map(|host| host.ipv4),
+                };
+                self.set_solo(solo);
+            }
+            Action::Export => {
+                let path = self.export_nmap_path.clone().unwrap_or_else(|| {
+                    crate::logging::get_data_dir().join(DEFAULT_NMAP_EXPORT_FILE)
+                });
+                match self.export_nmap(&path) {
+                    Ok(()) => self.notify(format!("exported to {}", path.display())),
+                    Err(e) => self.notify(format!("export failed: {}", e)),
+                }
+            }
+        }
+    }
+
+    fn handle_popup_key_events(&mut self, popup: Popup, key_event: KeyEvent) -> AppResult<()> {
+        match (popup, key_event.code) {
+            (_, KeyCode::Char('c')) if key_event.modifiers == KeyModifiers::CONTROL => {
+                self.quit();
+            }
+            (Popup::ConfirmQuit, KeyCode::Char('y' | 'q' | 'Q')) => {
+                self.quit();
+            }
+            (Popup::ConfirmQuit, KeyCode::Char('n')) => {
+                self.popup = None;
+            }
+            (Popup::ConfirmLargeScan, KeyCode::Char('y')) => {
+                self.popup = None;
+                self.force_large_scan = true;
+                self.perform(Action::Scan);
+            }
+            (Popup::ConfirmLargeScan, KeyCode::Char('n')) => {
+                self.popup = None;
+            }
+            (Popup::EditLabel(ip, mac), KeyCode::Enter) => {
+                self.popup = None;
+                self.save_label(ip, mac);
+            }
+            (Popup::EditLabel(..), KeyCode::Backspace) => {
+                self.label_input.pop();
+            }
+            (Popup::EditLabel(..), KeyCode::Char(c)) => {
+                self.label_input.push(c);
+            }
+            (Popup::CommandPalette, KeyCode::Enter) => {
+                self.popup = None;
+                match command::parse(&self.command_input) {
+                    Ok(action) => self.perform(action),
+                    Err(e) => self.notify(e),
+                }
+            }
+            (Popup::CommandPalette, KeyCode::Tab) => {
+                // completes the name with the best suggestion
+                if let Some((name, _)) = command::suggestions(&self.command_input).first() {
+                    self.command_input = format!("{} ", name);
+                }
+            }
+            (Popup::Settings, code) if self.settings_input.is_some() => match code {
+                KeyCode::Enter => {
+                    let setting = Setting::ALL[self.settings_selected];
+                    let input = self.settings_input.take().unwrap_or_default();
+                    if let Err(e) = setting.apply(self, &input) {
+                        // the value stays for fixing
+                        self.settings_error = Some(e);
+                        self.settings_input = Some(input);
+                    }
+                }
+                // discards the edit, the popup stays
+                KeyCode::Esc => {
+                    self.settings_input = None;
+                    self.settings_error = None;
+                }
+                KeyCode::Backspace => {
+                    if let Some(input) = &mut self.settings_input {
+                        input.pop();
+                    }
+                }
+                KeyCode::Char(c) => {
+                    if let Some(input) = &mut self.settings_input {
+                        input.push(c);
+                    }
+                }
+                _ => {}
+            },
+            (Popup::Settings, KeyCode::Char('j') | KeyCode::Down) => {
+                self.settings_selected = (self.settings_selected + 1) % Setting::ALL.len();
+            }
+            (Popup::Settings, KeyCode::Char('k') | KeyCode::Up) => {
+                self.settings_selected =
+                    (self.settings_selected + Setting::ALL.len() - 1) % Setting::ALL.len();
+            }
+            (Popup::Settings, KeyCode::Enter) => {
+                let setting = Setting::ALL[self.settings_selected];
+                self.settings_input = Some(setting.value(self));
+                self.settings_error = None;
+            }
+            (Popup::SweepReport, KeyCode::Char('j') | KeyCode::Down) => {
+                let len = self.sweep_report.as_ref().map_or(0, |r| r.entries().len());
+                self.sweep_report_selected =
+                    (self.sweep_report_selected + 1).min(len.saturating_sub(1));
+            }
+            (Popup::SweepReport, KeyCode::Char('k') | KeyCode::Up) => {
+                self.sweep_report_selected = self.sweep_report_selected.saturating_sub(1);
+            }
+            (Popup::SweepReport, KeyCode::Enter) => {
+                let entry = self
+                    .sweep_report
+                    .as_ref()
+                    .and_then(|report| report.entries().get(self.sweep_report_selected).copied());
+                self.popup = None;
+                if let Some(key) = entry {
+                    self.select_host(key);
+                }
+            }
+            (Popup::CommandPalette, KeyCode::Backspace) => {
+                self.command_input.pop();
+            }
+            (Popup::CommandPalette, KeyCode::Char(c)) => {
+                self.command_input.push(c);
+            }
+            (_, KeyCode::Esc | KeyCode::Char('q'))
+            | (Popup::SwitchedNetwork, KeyCode::Enter)
+            | (Popup::InterfaceInfo, KeyCode::Char('I'))
+            | (Popup::Status, KeyCode::Char('S'))
+            | (Popup::Help, KeyCode::Char('?'))
+            | (Popup::CaptureStats, KeyCode::Char('D'))
+            | (Popup::CompareSnapshot, KeyCode::Char('W'))
+            | (Popup::Settings, KeyCode::Char(','))
+            | (Popup::HostDetails(_), KeyCode::Enter) => {
+                self.popup = None;
+            }
+            (Popup::InterfaceInfo, KeyCode::Char('y')) => {
+                if let Some(ip) = self
+                    .interface_info
+                    .as_ref()
+                    .and_then(|info| info.primary_ipv4())
+                {
+                    clipboard::copy(&ip.to_string())?;
+                }
+            }
             _ => {}
         }
         Ok(())
     }
 
-    fn clean_host_and_olders(&mut self) -> Option<()> {
-        let host = self.hosts.get(self.table_state.selected()?)?;
-        let time = host.time;
-        self.hosts = self
+    /// Tells when a watched host started or stopped sending, from its
+    /// traffic during the last stat tick.
+    fn announce_watched_hosts(&mut self, last_tick: &HashMap<Ipv4Addr, Speed>) {
+        let options = self.watch_options;
+        let mut announced = vec![];
+        for host in &mut self.hosts {
+            let Some(watch) = &mut host.watch else {
+                continue;
+            };
+            let sending = last_tick
+                .get(&host.ipv4)
+                .is_some_and(|bits| bits.total() > 0);
+            match watch.observe(sending, options.debounce) {
+                Some(transition) if options.on.announces(transition) => {
+                    let name = host.label.as_ref().or(host.hostname.as_ref());
+                    let host = match name {
+                        Some(name) => format!("{} ({})", name, host.ipv4),
+                        None => host.ipv4.to_string(),
+                    };
+                    announced.push(match transition {
+                        Transition::Started => format!("{} started sending", host),
+                        Transition::Stopped => format!("{} went quiet", host),
+                    });
+                }
+                _ => {}
+            }
+        }
+        if !announced.is_empty() {
+            self.ring_bell |= options.bell;
+            self.notify(announced.join(", "));
+        }
+    }
+
+    /// Counts the ARP packets of a host and raises a notice when it starts
+    /// sending requests faster than the threshold.
+    fn record_arp_activity(&mut self, (ipv4, mac): (Ipv4Addr, MacAddr), seen: &ArpActivity) {
+        let threshold = self.arp_scan_threshold;
+        let Some(host) = self
             .hosts
-            .clone()
+            .iter_mut()
+            .find(|h| h.ipv4 == ipv4 && h.mac == mac)
+        else {
+            return;
+        };
+        let newly_flagged = host.arp.record(seen, threshold, host.seen_at);
+        // our own sweeps are expected to be noisy
+        if newly_flagged && !host.is_my_device_mac {
+            self.notify(format!(
+                "{} sends many ARP requests, it may be scanning the network",
+                ipv4
+            ));
+        }
+    }
+
+    pub fn begin_operation(&mut self, kind: OperationKind, description: String) {
+        self.end_operation(kind);
+        self.operations.push(Operation { kind, description });
+    }
+
+    pub fn end_operation(&mut self, kind: OperationKind) {
+        self.operations.retain(|operation| operation.kind != kind);
+    }
+
+    /// The networks sweeps probe, see [`Scanner::network_plan`].
+    pub fn network_plan(&self) -> &NetworkPlan {
+        self.scanner.network_plan()
+    }
+
+    /// Pause before each ARP request of a sweep.
+    pub fn scan_delay(&self) -> Duration {
+        self.scanner.scan_delay()
+    }
+
+    /// Sets the pause, the sweep in flight takes it from its next request.
+    pub fn set_scan_delay(&mut self, delay: Duration) {
+        self.scanner.set_scan_delay(delay);
+    }
+
+    /// Asks every operation in flight to wrap up, they unregister once done.
+    pub fn stop_operations(&self) {
+        for operation in &self.operations {
+            match operation.kind {
+                OperationKind::ArpSweep => self.scanner.cancel_scan(),
+            }
+        }
+        if let Some(resolver) = &self.resolver {
+            resolver.cancel();
+        }
+    }
+
+    /// Writes the discovered hosts as nmap XML.
+    pub fn export_nmap(&self, path: &std::path::Path) -> std::io::Result<()> {
+        export::write_nmap_xml(
+            path,
+            &self.hosts,
+            &self.interface,
+            self.started_at,
+            self.time_reference(),
+        )
+    }
+
+    /// The hosts table as the exports show it, with `total` as the total speed.
+    pub fn snapshot(&self, total: Option<Speed>) -> HostsSnapshot {
+        HostsSnapshot::new(
+            &self.interface,
+            total,
+            &self.hosts,
+            self.mac_format,
+            &self.daily_usage,
+        )
+    }
+
+    /// Writes a self-contained HTML report of the hosts and the traffic.
+    pub fn export_html(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let report = Report {
+            snapshot: self.snapshot(self.stats_aggregator.total(self.footer_metric.scope())),
+            history: self.stats_aggregator.history().total(),
+            alerts: self.alerts(),
+            destinations: self.stats_aggregator.destinations(self.destination_mask),
+            generated_at: chrono::Local::now(),
+        };
+        report::write_html_report(path, &report)
+    }
+
+    /// What currently deserves attention: hosts scanning the network and
+    /// hosts close to or over their daily budget.
+    pub fn alerts(&self) -> Vec<String> {
+        self.host_alerts()
             .into_iter()
-            .filter(|h| h.time > time)
+            .map(|(_, _, message)| message)
+            .collect()
+    }
+
+    /// The alerts with the host and the text's key, which names the kind.
+    fn host_alerts(&self) -> Vec<(Ipv4Addr, &'static str, String)> {
+        let mut alerts = vec![];
+        for host in &self.hosts {
+            if host.arp.flagged && !host.is_my_device_mac {
+                let key = "alert.arp_scan";
+                alerts.push((host.ipv4, key, strings::format(key, &[("ip", &host.ipv4)])));
+            }
+            if let Some(budget) = self.daily_usage.budget_usage(&host.mac) {
+                let key = match budget.level() {
+                    BudgetLevel::Normal => continue,
+                    BudgetLevel::Warning => "alert.budget_warning",
+                    BudgetLevel::Exceeded => "alert.budget_exceeded",
+                };
+                alerts.push((
+                    host.ipv4,
+                    key,
+                    strings::format(
+                        key,
+                        &[("ip", &host.ipv4), ("budget", &format_bytes(budget.budget))],
+                    ),
+                ));
+            }
+        }
+        alerts
+    }
+
+    /// Logs the alerts raised since the last tick, once each while they last.
+    fn log_alerts(&mut self) {
+        let alerts = self.host_alerts();
+        self.logged_alerts
+            .retain(|(ip, key)| alerts.iter().any(|(i, k, _)| i == ip && k == key));
+        for (ip, key, message) in alerts {
+            if self.logged_alerts.insert((ip, key)) {
+                tracing::warn!(
+                    target: EVENT_TARGET,
+                    ip = %ip,
+                    kind = key.trim_start_matches("alert."),
+                    text = %message,
+                    "alert"
+                );
+            }
+        }
+    }
+
+    /// Logs the hosts that had traffic and went silent, once until they
+    /// talk again.
+    fn log_offline_hosts(&mut self, now: Instant) {
+        let hosts = &self.hosts;
+        self.offline_hosts.retain(|ip| {
+            hosts
+                .iter()
+                .any(|h| h.ipv4 == *ip && h.last_active.is_some())
+        });
+        for host in &self.hosts {
+            if host.last_active.is_none() {
+                continue;
+            }
+            if ActivityBucket::of(host, now) != ActivityBucket::Silent {
+                self.offline_hosts.remove(&host.ipv4);
+            } else if self.offline_hosts.insert(host.ipv4) {
+                tracing::info!(
+                    target: EVENT_TARGET,
+                    ip = %host.ipv4,
+                    mac = %host.mac,
+                    "host_offline"
+                );
+            }
+        }
+    }
+
+    /// Restricts the capture accounting to the flows of `ip`.
+    pub fn set_solo(&mut self, ip: Option<Ipv4Addr>) {
+        self.scanner.set_solo(ip);
+        self.solo = ip;
+    }
+
+    pub fn notify(&mut self, message: String) {
+        let message = if dry_run::enabled() {
+            format!("{} {}", dry_run::TAG, message)
+        } else {
+            message
+        };
+        self.notice = Some((message, Instant::now()));
+        self.dirty = true;
+    }
+
+    /// The footer's averages still change while their history isn't full
+    /// or has traffic in it.
+    fn footer_averages_move(&self) -> bool {
+        let history = self.stats_aggregator.history().total();
+        history.span() < FOOTER_AVERAGE_SPAN
+            || history
+                .samples(FOOTER_AVERAGE_SPAN)
+                .iter()
+                .any(|speed| speed.total() > 0)
+    }
+
+    /// The last notice, while it is still recent enough to be shown.
+    pub fn notice(&self) -> Option<&str> {
+        self.notice
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < NOTICE_DURATION)
+            .map(|(message, _)| message.as_str())
+    }
+
+    pub fn toggle_frozen_time(&mut self) {
+        self.frozen_time = match self.frozen_time {
+            Some(_) => None,
+            None => Some(Instant::now()),
+        };
+    }
+
+    /// The instant host ages are relative to.
+    pub fn time_reference(&self) -> Instant {
+        self.frozen_time.unwrap_or_else(Instant::now)
+    }
+
+    /// Link speed in bits per second, the configured one first.
+    pub fn link_speed(&self) -> Option<u64> {
+        self.link_speed_override
+            .or_else(|| self.interface_info.as_ref()?.link_speed)
+    }
+
+    /// Runs the enrichers on `host`, its label is the one known hosts found.
+    fn enrich_host(enrichers: &Pipeline, state: &State, host: &mut Host) {
+        enrichers.run(host, &EnrichCtx { state });
+        host.label = host.annotations.get(KnownHosts::SOURCE, "label").cloned();
+    }
+
+    /// Saves the bindings recorded since the last save, at most once per
+    /// stat tick.
+    fn save_audit(&mut self) {
+        if !std::mem::take(&mut self.audit_unsaved) {
+            return;
+        }
+        if let Err(e) = self.state.save() {
+            self.notify(format!("audit trail not saved: {}", e));
+        }
+    }
+
+    /// Stores the typed label on the host and in the state file.
+    fn save_label(&mut self, ip: Ipv4Addr, mac: MacAddr) {
+        self.state.set_label(ip, mac, &self.label_input);
+        self.hosts
+            .iter_mut()
+            .filter(|h| h.ipv4 == ip && h.mac == mac)
+            .for_each(|h| Self::enrich_host(&self.enrichers, &self.state, h));
+        if let Err(e) = self.state.save() {
+            self.notify(format!("label not saved: {}", e));
+        }
+    }
+
+    /// Adds the last tick to today's usage and raises a notice for the hosts
+    /// going over their budget.
+    fn record_daily_usage(&mut self) {
+        let Some(last_tick) = self.stats_aggregator.last_tick_per_host() else {
+            return;
+        };
+        let usage: Vec<(MacAddr, u128)> = self
+            .hosts
+            .iter()
+            .filter_map(|h| last_tick.get(&h.ipv4).map(|s| (h.mac, s.total() / 8)))
             .collect();
+        let exceeded = self
+            .daily_usage
+            .record(chrono::Local::now().date_naive(), usage);
+        for mac in exceeded {
+            let Some(host) = self.hosts.iter().find(|h| h.mac == mac) else {
+                continue;
+            };
+            let budget = self
+                .daily_usage
+                .budget_usage(&mac)
+                .map_or(0, |usage| usage.budget);
+            let message = format!(
+                "{} went over its daily budget of {}",
+                host.ipv4,
+                format_bytes(budget)
+            );
+            self.notify(message);
+        }
+    }
+
+    /// Shows the switched network explanation once, when the capture only
+    /// ever sees this device's traffic. Never in monitor-only mode, a tap
+    /// sees the whole segment.
+    fn check_switched_network(&mut self, speeds: &HashMap<Ipv4Addr, Speed>) {
+        // keep the hint for when nothing else is shown
+        if self.monitor_only || self.popup.is_some() {
+            return;
+        }
+        let gateway = self.interface_info.as_ref().and_then(|info| info.gateway);
+        let is_other = |ip: &Ipv4Addr| {
+            Some(*ip) != gateway
+                && !self
+                    .hosts
+                    .iter()
+                    .any(|h| h.is_my_device_mac && h.ipv4 == *ip)
+                && !self.interface_info.as_ref().is_some_and(|info| {
+                    info.networks
+                        .iter()
+                        .any(|n| n.network.ip() == IpAddr::V4(*ip))
+                })
+        };
+        let other_hosts = self.hosts.iter().filter(|h| is_other(&h.ipv4)).count();
+        let third_party_traffic = speeds
+            .iter()
+            .any(|(ip, speed)| speed.total() > 0 && is_other(ip));
+        if self
+            .switched_network_hint
+            .observe(other_hosts, third_party_traffic, Instant::now())
+        {
+            self.popup = Some(Popup::SwitchedNetwork);
+        }
+    }
+
+    fn publish_web_snapshot(&self) {
+        let Some(snapshot) = &self.web_snapshot else {
+            return;
+        };
+        let json = self.snapshot(self.stats_aggregator.wan_speed()).to_json();
+        if let Ok(mut snapshot) = snapshot.lock() {
+            *snapshot = json;
+        }
+    }
+
+    pub fn selected_host(&self) -> Option<&Host> {
+        self.shown_hosts()
+            .get(self.table_state.selected()?)
+            .copied()
+    }
+
+    fn clean_host_and_olders(&mut self) -> Option<()> {
+        let host = self.selected_host()?;
+        let seen_at = host.seen_at;
+        self.hosts_changing();
+        self.hosts.retain(|h| h.seen_at > seen_at);
+        self.hosts.iter_mut().for_each(|h| h.peak_speed = None);
+        self.hosts_changed();
 
         Some(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ratatui::{backend::TestBackend, style::Style, Terminal};
+
+    use super::*;
+    use crate::{
+        smoke::{app_on, Lan},
+        stats_aggregator::{FlowScope, StatKey, StatValues, StatsMap, Transport, DEFAULT_WINDOW},
+        theme::ThemeName,
+        ui,
+    };
+
+    fn found(app: &mut App, last: u16) {
+        let [hi, lo] = last.to_be_bytes();
+        let host = Host::new(
+            Ipv4Addr::new(10, 0, hi, lo),
+            MacAddr(0, 0, 0, 0, hi, lo),
+            false,
+        );
+        app.handle_worker_events(ScannerEvent::HostFound(Box::new(host)))
+            .unwrap();
+    }
+
+    /// The ticks, a second apart, at which `hint` fires over `history`, each
+    /// tick the hosts besides us and the gateway and whether others' traffic
+    /// was captured.
+    fn hint_ticks(mut hint: SwitchedNetworkHint, history: &[(usize, bool)]) -> Vec<usize> {
+        let start = Instant::now();
+        history
+            .iter()
+            .enumerate()
+            .filter(|(tick, (hosts, traffic))| {
+                hint.observe(*hosts, *traffic, start + Duration::from_secs(*tick as u64))
+            })
+            .map(|(tick, _)| tick)
+            .collect()
+    }
+
+    #[test]
+    fn a_switched_network_is_hinted_once_after_three_blind_minutes() {
+        let blind = vec![(8, false); 600];
+        assert_eq!(hint_ticks(SwitchedNetworkHint::new(true), &blind), [180]);
+        assert!(hint_ticks(SwitchedNetworkHint::new(false), &blind).is_empty());
+    }
+
+    #[test]
+    fn a_hub_or_mirror_port_is_never_hinted() {
+        // a single frame between two other hosts is enough
+        let mut history = vec![(8, false); 600];
+        history[170] = (8, true);
+        assert!(hint_ticks(SwitchedNetworkHint::new(true), &history).is_empty());
+        history[0] = (8, true);
+        assert!(hint_ticks(SwitchedNetworkHint::new(true), &history).is_empty());
+    }
+
+    #[test]
+    fn too_few_hosts_restart_the_wait() {
+        let few = vec![(SWITCHED_NETWORK_MIN_HOSTS - 1, false); 600];
+        assert!(hint_ticks(SwitchedNetworkHint::new(true), &few).is_empty());
+        // hosts expiring at 100s, found again by the next sweep at 150s
+        let mut history = vec![(8, false); 600];
+        history[100..150].fill((2, false));
+        assert_eq!(hint_ticks(SwitchedNetworkHint::new(true), &history), [330]);
+    }
+
+    #[tokio::test]
+    async fn a_bulk_update_syncs_the_rows_once_keeping_the_selection() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        app.limits.hosts = 801;
+        for last in 0..3 {
+            found(&mut app, last);
+        }
+        assert_eq!(app.row_syncs, 3);
+        app.table_state.select(Some(2));
+        let selected = app.selected_key();
+
+        // a store of 800 hosts, the first two get evicted
+        app.begin_bulk_update();
+        found(&mut app, 2);
+        for last in 100..900 {
+            found(&mut app, last);
+        }
+        assert_eq!(app.row_syncs, 3);
+        app.end_bulk_update();
+
+        assert_eq!(app.row_syncs, 4);
+        assert_eq!(app.hosts.len(), 801);
+        assert_eq!(app.table_state.selected(), Some(0));
+        assert_eq!(app.selected_key(), selected);
+        assert_eq!(
+            app.scroll_state,
+            ScrollbarState::new(800 * ITEM_HEIGHT).position(0)
+        );
+        // nothing changed since
+        app.end_bulk_update();
+        assert_eq!(app.row_syncs, 4);
+    }
+
+    #[tokio::test]
+    async fn a_long_session_keeps_every_collection_under_its_limit() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        let limits = Limits {
+            hosts: 40,
+            seen_hosts: 80,
+            flows: 120,
+            remotes: 60,
+            host_histories: 20,
+            pair_totals: 150,
+        };
+        app.limits = limits;
+        app.stats_aggregator.set_limits(limits);
+        let start = Instant::now();
+        let mut steady = None;
+        // a new host every tick, talking to new addresses from new ports
+        for tick in 0..3000u16 {
+            found(&mut app, tick);
+            let [hi, lo] = tick.to_be_bytes();
+            let mut stats = StatsMap::new();
+            for n in 0..4u8 {
+                let key = StatKey {
+                    a_ip: Ipv4Addr::new(10, 0, hi, lo),
+                    a_port: 40000 + tick,
+                    b_ip: Ipv4Addr::new(93, n, hi, lo),
+                    b_port: 443,
+                    scope: FlowScope::External,
+                    transport: Transport::Tcp,
+                    network: None,
+                };
+                stats.insert(
+                    key,
+                    StatValues {
+                        a_sent: 8000,
+                        b_sent: 80_000,
+                    },
+                );
+            }
+            let flushed_at = start + Duration::from_secs(tick.into());
+            app.handle_worker_events(ScannerEvent::StatTick(
+                stats,
+                Duration::from_secs(1),
+                flushed_at,
+            ))
+            .unwrap();
+            let sizes = app.collection_sizes();
+            for size in &sizes {
+                assert!(size.len <= size.limit, "{} grew past its limit", size.name);
+            }
+            let lens = sizes.iter().map(|size| size.len).collect::<Vec<_>>();
+            match steady {
+                // every collection fills up within the first thousand ticks
+                None if tick == 1000 => steady = Some(lens),
+                Some(ref steady) => assert_eq!(&lens, steady, "tick {}", tick),
+                None => {}
+            }
+        }
+        assert_eq!(steady.unwrap(), [40, 80, 120, 60, 150, 20]);
+    }
+
+    /// The cells of the hosts table's header, drawn 120 wide.
+    fn header(app: &mut App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|frame| ui::render(app, frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.width)
+            .map(|x| buffer[(x, 0)].symbol().to_string())
+            .collect()
+    }
+
+    fn column_at(header: &[String], title: &str) -> usize {
+        (0..header.len())
+            .find(|x| header[*x..].concat().starts_with(title))
+            .unwrap()
+    }
+
+    fn press(app: &mut App, key: char) {
+        app.handle_key_events(KeyEvent::from(KeyCode::Char(key)))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn an_address_with_two_macs_gets_a_row_each_unless_merged() {
+        let ip = Ipv4Addr::new(10, 0, 0, 7);
+        let bond = [MacAddr(0, 0, 0, 0, 1, 7), MacAddr(0, 0, 0, 0, 2, 7)];
+        for (policy, rows) in [(SameIpPolicy::Separate, 2), (SameIpPolicy::Merge, 1)] {
+            let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+            app.same_ip = policy;
+            for mac in bond {
+                app.handle_worker_events(ScannerEvent::HostFound(Box::new(Host::new(
+                    ip, mac, false,
+                ))))
+                .unwrap();
+                app.hosts[0].label.get_or_insert_with(|| "nas".to_string());
+            }
+            assert_eq!(app.hosts.len(), rows, "{:?}", policy);
+            // merged, the row shows the last MAC and keeps what it had
+            assert_eq!(app.hosts.last().unwrap().mac, bond[1]);
+            assert_eq!(app.hosts[0].label.as_deref(), Some("nas"));
+            assert_eq!(app.hosts.last().unwrap().label.is_some(), rows == 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_column_width_survives_a_restart() {
+        let dir = std::env::temp_dir().join(format!("netui-columns-{}", std::process::id()));
+        let path = dir.join("state-eth0.toml");
+        let lan = Arc::new(Lan::default());
+        let (mut app, _events) = app_on(&lan);
+        app.state = State::at(path.clone()).unwrap();
+        found(&mut app, 1);
+        header(&mut app);
+        let drawn = app.hosts_column_layout[3];
+        app.table_state.select_column(Some(3));
+        for _ in 0..3 {
+            press(&mut app, '>');
+        }
+        press(&mut app, '<');
+        let width = drawn + 2 * COLUMN_WIDTH_STEP as u16;
+        assert_eq!(app.state.column_width("label"), Some(width));
+
+        let (mut app, _events) = app_on(&lan);
+        app.state = State::at(path.clone()).unwrap();
+        found(&mut app, 1);
+        let header = header(&mut app);
+        let label = column_at(&header, "Label");
+        assert_eq!(column_at(&header, "Speed") - label, width as usize + 1);
+        assert_eq!(app.hosts_column_layout[3], width);
+
+        app.table_state.select_column(Some(3));
+        press(&mut app, '=');
+        assert_eq!(State::at(path).unwrap().column_width("label"), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn the_selection_wins_over_the_row_and_cell_styles() {
+        for name in [ThemeName::Dark, ThemeName::Light, ThemeName::HighContrast] {
+            let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+            app.theme = Theme::new(name);
+            // never heard from, their addresses are drawn silent
+            for last in 1..=3 {
+                found(&mut app, last);
+            }
+            app.table_state.select(Some(0));
+            app.table_state.select_column(Some(0));
+            let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+            terminal.draw(|frame| ui::render(&mut app, frame)).unwrap();
+            let buffer = terminal.backend().buffer();
+            let header: Vec<String> = (0..buffer.area.width)
+                .map(|x| buffer[(x, 0)].symbol().to_string())
+                .collect();
+            let ip = column_at(&header, "IP") as u16;
+            let mac = column_at(&header, "Mac Address") as u16;
+            let colors = |x, y| {
+                let cell: &ratatui::buffer::Cell = &buffer[(x, y)];
+                (cell.fg, cell.bg)
+            };
+            let both = |style: Style| (style.fg.unwrap(), style.bg.unwrap());
+            let theme = &app.theme;
+            // the selected cell over the selected row over the silent address
+            assert_eq!(colors(ip, 1), both(theme.selected_cell), "{:?}", name);
+            assert_eq!(colors(mac, 1), both(theme.selected_row), "{:?}", name);
+            // the other rows keep their zebra, the address its own style
+            // under the selected column's
+            let alt_row = theme.row_style(1);
+            let in_column = alt_row.patch(theme.silent).patch(theme.selected_column);
+            assert_eq!(colors(ip, 2), both(in_column), "{:?}", name);
+            assert_eq!(colors(mac, 2), both(alt_row), "{:?}", name);
+            assert_eq!(colors(mac, 3), both(theme.row_style(2)), "{:?}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn hosts_without_a_known_vendor_say_so() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        found(&mut app, 1);
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|frame| ui::render(&mut app, frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let line = |y| {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol().to_string())
+                .collect::<Vec<_>>()
+        };
+        let vendor = column_at(&line(0), "Vendor");
+        assert!(column_at(&line(0), "Mac Address") < vendor);
+        assert!(vendor < column_at(&line(0), "Label"));
+        assert!(line(1)[vendor..].concat().starts_with("Unknown"));
+    }
+
+    #[tokio::test]
+    async fn the_sweep_report_jumps_to_the_picked_host() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        for last in 1..4 {
+            found(&mut app, last);
+        }
+        app.handle_worker_events(ScannerEvent::BeginScan("10.0.0.0/24".parse().unwrap()))
+            .unwrap();
+        // .2 answers, a new .9 too, .1 and .3 stay silent
+        for last in [2, 9] {
+            let mut host = Host::new(
+                Ipv4Addr::new(10, 0, 0, last),
+                MacAddr(0, 0, 0, 0, 0, last),
+                false,
+            );
+            host.arp_rtt = Some(Duration::from_millis(2));
+            app.handle_worker_events(ScannerEvent::HostFound(Box::new(host)))
+                .unwrap();
+        }
+        app.handle_worker_events(ScannerEvent::Complete(SweepSummary {
+            sent: 254,
+            failed: 0,
+        }))
+        .unwrap();
+        assert_eq!(app.popup, Some(Popup::SweepReport));
+        let report = app.sweep_report.as_ref().unwrap();
+        assert_eq!(report.replied(), 2);
+        let ips: Vec<u8> = report
+            .entries()
+            .iter()
+            .map(|(ip, _)| ip.octets()[3])
+            .collect();
+        assert_eq!(ips, [9, 1, 3]);
+
+        app.view = View::Connections;
+        app.handle_key_events(KeyEvent::from(KeyCode::Char('j')))
+            .unwrap();
+        app.handle_key_events(KeyEvent::from(KeyCode::Char('j')))
+            .unwrap();
+        app.handle_key_events(KeyEvent::from(KeyCode::Enter))
+            .unwrap();
+        assert_eq!(app.popup, None);
+        assert_eq!(app.view, View::Hosts);
+        assert_eq!(
+            app.selected_host().unwrap().ipv4,
+            Ipv4Addr::new(10, 0, 0, 3)
+        );
+    }
+
+    /// A second of 8 kb/s up and 80 kb/s down between 10.0.0.`last` and
+    /// the internet, or of nothing.
+    fn stat_tick(app: &mut App, last: Option<u8>, flushed_at: Instant) {
+        let stats = last
+            .map(|last| {
+                let key = StatKey {
+                    a_ip: Ipv4Addr::new(10, 0, 0, last),
+                    a_port: 50000,
+                    b_ip: Ipv4Addr::new(93, 184, 216, 34),
+                    b_port: 443,
+                    scope: FlowScope::External,
+                    transport: Transport::Tcp,
+                    network: None,
+                };
+                let bits = StatValues {
+                    a_sent: 8000,
+                    b_sent: 80_000,
+                };
+                StatsMap::from([(key, bits)])
+            })
+            .unwrap_or_default();
+        app.handle_worker_events(ScannerEvent::StatTick(
+            stats,
+            Duration::from_secs(1),
+            flushed_at,
+        ))
+        .unwrap();
+    }
+
+    /// The download cell of the first row and its color, drawn 120 wide.
+    fn download_cell(app: &mut App) -> (String, Option<ratatui::style::Color>) {
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|frame| ui::render(app, frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let header: Vec<String> = (0..buffer.area.width)
+            .map(|x| buffer[(x, 0)].symbol().to_string())
+            .collect();
+        let x = column_at(&header, "Speed ↓") as u16;
+        let text = (x..buffer.area.width)
+            .map(|x| buffer[(x, 1)].symbol())
+            .collect::<String>();
+        let text = text.split("  ").next().unwrap().to_string();
+        (text, buffer[(x, 1)].style().fg)
+    }
+
+    #[tokio::test]
+    async fn a_speed_shows_never_heard_from_apart_from_idle() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        found(&mut app, 1);
+        let start = Instant::now();
+        // found by ARP, no traffic yet
+        assert_eq!(download_cell(&mut app).0, "—");
+        assert!(!app.hosts[0].has_traffic);
+
+        stat_tick(&mut app, Some(1), start);
+        assert_eq!(download_cell(&mut app).0, "78.13 Kib/s");
+        assert!(app.hosts[0].has_traffic);
+
+        // idle once the window only has quiet ticks, dimmed
+        for n in 1..=DEFAULT_WINDOW as u64 {
+            stat_tick(&mut app, None, start + Duration::from_secs(n));
+        }
+        let silent = app.theme.silent.fg;
+        assert_eq!(download_cell(&mut app), ("0".to_string(), silent));
+        assert!(app.hosts[0].has_traffic);
+    }
+
+    #[tokio::test]
+    async fn the_skew_is_how_late_a_tick_is_handled() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        let late = Instant::now() - Duration::from_secs(3);
+        stat_tick(&mut app, None, late);
+        assert!(app.stat_tick_skew >= Duration::from_secs(3));
+        stat_tick(&mut app, None, Instant::now());
+        assert!(app.stat_tick_skew < Duration::from_secs(1));
+        // the worst one is kept
+        assert!(app.max_stat_tick_skew >= Duration::from_secs(3));
+    }
+
+    /// The line under the hosts table, drawn 120 wide.
+    fn totals_line(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|frame| ui::render(app, frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .find(|line| line.trim_start().starts_with("Total"))
+            .unwrap()
+            .trim_end()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn the_totals_line_adds_up_the_listed_hosts() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        app.frozen_time = Some(Instant::now());
+        let now = app.time_reference();
+        for (last, speed, quiet) in [(1, 8_000, 0), (2, 16_000, 0), (3, 1_000_000, 600)] {
+            found(&mut app, last);
+            let host = app.hosts.last_mut().unwrap();
+            host.speed = Some(Speed::new(speed, speed / 8));
+            host.has_traffic = true;
+            host.last_active = Some(now - Duration::from_secs(quiet));
+        }
+        assert_eq!(
+            totals_line(&mut app),
+            "   Total     3 hosts                            1000.00 Kib/s  125.00 Kib/s"
+        );
+        // only the hosts the filter lists
+        app.activity_filter = Some(ActivityBucket::Active);
+        assert_eq!(
+            totals_line(&mut app),
+            "   Total     2 hosts                             23.44 Kib/s   2.93 Kib/s"
+        );
+        app.activity_filter = Some(ActivityBucket::Idle);
+        assert_eq!(
+            totals_line(&mut app),
+            "   Total  0 hosts                                         0.00 Bit/s     0.00 Bit/s"
+        );
+    }
+
+    /// The hint line, drawn `width` wide.
+    fn hint_line(app: &mut App, width: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, 20)).unwrap();
+        terminal.draw(|frame| ui::render(app, frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        // above the footer's box, inside its own border
+        let y = buffer.area.height - 5;
+        (1..buffer.area.width - 1)
+            .map(|x| buffer[(x, y)].symbol())
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn the_hint_line_follows_the_view_and_popups() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        found(&mut app, 1);
+        assert_eq!(
+            hint_line(&mut app, 120),
+            "(s) sweep | (Enter) details | (e) label | (:) command | (?) all keys"
+        );
+        app.table_state.select(Some(0));
+        press(&mut app, 'e');
+        assert_eq!(
+            hint_line(&mut app, 120),
+            "(Enter) save | (Backspace) delete | (Esc) cancel"
+        );
+        app.handle_key_events(KeyEvent::from(KeyCode::Esc)).unwrap();
+        app.handle_key_events(KeyEvent::from(KeyCode::Tab)).unwrap();
+        assert!(hint_line(&mut app, 120).starts_with("(o) next order"));
+    }
+
+    #[tokio::test]
+    async fn the_hint_line_shows_the_prompt_keys_while_typing() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        press(&mut app, ':');
+        press(&mut app, 's');
+        assert_eq!(
+            hint_line(&mut app, 120),
+            "(Enter) run | (Tab) complete | (Backspace) delete | (Esc) cancel"
+        );
+        // a typed `?` is text, not the help
+        press(&mut app, '?');
+        assert_eq!(app.command_input, "s?");
+    }
+
+    #[tokio::test]
+    async fn the_hint_line_points_to_the_help_when_narrow() {
+        let (mut app, _events) = app_on(&Arc::new(Lan::default()));
+        assert_eq!(
+            hint_line(&mut app, 50),
+            "(s) sweep | (Enter) details | … ? for more"
+        );
+        press(&mut app, '?');
+        assert_eq!(app.popup, Some(Popup::Help));
+        press(&mut app, '?');
+        assert_eq!(app.popup, None);
+    }
+}
@@ -0,0 +1,250 @@
+use std::{collections::BTreeMap, net::Ipv4Addr};
+
+use chrono::{DateTime, Local};
+use pnet::util::MacAddr;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppResult,
+    state::State,
+    text_table::{Align, TextTable},
+};
+
+/// bindings kept per MAC, the oldest are dropped past it
+pub const MAX_BINDINGS_PER_HOST: usize = 32;
+
+/// An address a MAC was seen with, from `since` until the next binding of
+/// that MAC.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Binding {
+    pub ip: Ipv4Addr,
+    /// RFC 3339
+    pub since: String,
+}
+
+impl Binding {
+    fn since(&self) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&self.since)
+            .ok()
+            .map(|since| since.with_timezone(&Local))
+    }
+}
+
+/// Which address each MAC had and since when, saved with the state: when
+/// something looked wrong, what claimed which address can be worked out
+/// afterwards. Only changes are appended.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AuditTrail {
+    hosts: BTreeMap<String, Vec<Binding>>,
+}
+
+impl AuditTrail {
+    /// Records that `mac` answers for `ip` as of `at`, true when that's a
+    /// change. Hosts without a MAC have nothing to follow.
+    pub fn record(&mut self, ip: Ipv4Addr, mac: MacAddr, at: DateTime<Local>) -> bool {
+        if mac == MacAddr::zero() {
+            return false;
+        }
+        let bindings = self.hosts.entry(mac.to_string()).or_default();
+        if bindings.last().is_some_and(|binding| binding.ip == ip) {
+            return false;
+        }
+        bindings.push(Binding {
+            ip,
+            since: at.to_rfc3339(),
+        });
+        if bindings.len() > MAX_BINDINGS_PER_HOST {
+            bindings.remove(0);
+        }
+        true
+    }
+
+    /// The addresses of `mac`, oldest first.
+    pub fn history(&self, mac: MacAddr) -> &[Binding] {
+        self.hosts
+            .get(&mac.to_string())
+            .map_or(&[], |bindings| bindings.as_slice())
+    }
+
+    /// Every binding with the one after it of the same MAC, by MAC.
+    fn spans(&self) -> impl Iterator<Item = (&str, &Binding, Option<&Binding>)> {
+        self.hosts.iter().flat_map(|(mac, bindings)| {
+            bindings
+                .iter()
+                .enumerate()
+                .map(move |(i, binding)| (mac.as_str(), binding, bindings.get(i + 1)))
+        })
+    }
+
+    /// What the details of `mac`, now at `ip`, say about the past: the
+    /// addresses it had before and the other MACs that answered for `ip`.
+    pub fn describe(&self, ip: Ipv4Addr, mac: MacAddr) -> Vec<String> {
+        let mac = mac.to_string();
+        let mut lines: Vec<(String, String)> = self
+            .spans()
+            .filter_map(|(owner, binding, next)| {
+                let until = next.and_then(Binding::since).map(time);
+                match (owner == mac, binding.ip == ip, until) {
+                    // where it is now
+                    (true, true, None) => None,
+                    (true, _, Some(until)) => {
+                        Some(format!("previously {} until {}", binding.ip, until))
+                    }
+                    (false, true, Some(until)) => {
+                        Some(format!("{} had {} until {}", owner, binding.ip, until))
+                    }
+                    (false, true, None) => {
+                        let since = binding.since().map(time).unwrap_or_default();
+                        Some(format!(
+                            "{} claims {} too, since {}",
+                            owner, binding.ip, since
+                        ))
+                    }
+                    _ => None,
+                }
+                .map(|line| (binding.since.clone(), line))
+            })
+            .collect();
+        // latest first
+        lines.sort_by(|a, b| b.0.cmp(&a.0));
+        lines.into_iter().map(|(_, line)| line).collect()
+    }
+}
+
+/// `14:02`, with the date when it isn't today.
+fn time(at: DateTime<Local>) -> String {
+    if at.date_naive() == Local::now().date_naive() {
+        at.format("%H:%M").to_string()
+    } else {
+        at.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+/// Prints the audit trail saved for `interface`, a row per binding, as a
+/// table or CSV.
+pub fn print(interface: &str, csv: bool) -> AppResult<()> {
+    let state = State::load(interface)?;
+    let rows: Vec<[String; 4]> = state
+        .audit()
+        .spans()
+        .map(|(mac, binding, next)| {
+            [
+                mac.to_string(),
+                binding.ip.to_string(),
+                binding.since.clone(),
+                next.map_or_else(String::new, |next| next.since.clone()),
+            ]
+        })
+        .collect();
+    if csv {
+        println!("mac,ip,since,until");
+        for row in rows {
+            println!("{}", row.join(","));
+        }
+        return Ok(());
+    }
+    if rows.is_empty() {
+        println!("no binding recorded for {}", interface);
+        return Ok(());
+    }
+    let mut table = TextTable::new(&[
+        ("MAC", Align::Left, false),
+        ("IP", Align::Left, false),
+        ("Since", Align::Left, false),
+        ("Until", Align::Left, false),
+    ]);
+    for row in rows {
+        table.push(row.to_vec());
+    }
+    print!("{}", table.render(None));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const MAC: MacAddr = MacAddr(0, 0x1b, 0x63, 0, 0, 1);
+    const OTHER: MacAddr = MacAddr(0, 0x1b, 0x63, 0, 0, 2);
+
+    fn ip(last: u8) -> Ipv4Addr {
+        Ipv4Addr::new(192, 168, 1, last)
+    }
+
+    fn at(minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 3, 4, 14, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn the_same_binding_again_appends_nothing() {
+        let mut audit = AuditTrail::default();
+        assert!(audit.record(ip(87), MAC, at(0)));
+        assert!(!audit.record(ip(87), MAC, at(1)));
+        assert!(audit.record(ip(20), MAC, at(2)));
+        assert!(!audit.record(ip(20), MAC, at(3)));
+        // and back
+        assert!(audit.record(ip(87), MAC, at(4)));
+        let ips: Vec<Ipv4Addr> = audit.history(MAC).iter().map(|b| b.ip).collect();
+        assert_eq!(ips, [ip(87), ip(20), ip(87)]);
+        assert!(!audit.record(ip(1), MacAddr::zero(), at(5)));
+    }
+
+    #[test]
+    fn the_oldest_bindings_are_dropped() {
+        let mut audit = AuditTrail::default();
+        for i in 0..MAX_BINDINGS_PER_HOST as u8 + 5 {
+            audit.record(ip(i), MAC, at(0));
+        }
+        let history = audit.history(MAC);
+        assert_eq!(history.len(), MAX_BINDINGS_PER_HOST);
+        assert_eq!(history[0].ip, ip(5));
+        assert_eq!(
+            history.last().unwrap().ip,
+            ip(MAX_BINDINGS_PER_HOST as u8 + 4)
+        );
+    }
+
+    #[test]
+    fn the_details_tell_the_previous_addresses_and_claims() {
+        let mut audit = AuditTrail::default();
+        audit.record(ip(87), MAC, at(0));
+        audit.record(ip(20), OTHER, at(1));
+        audit.record(ip(20), MAC, at(2));
+        audit.record(ip(30), OTHER, at(3));
+        let date = "2026-03-04";
+        assert_eq!(
+            audit.describe(ip(20), MAC),
+            [
+                format!("00:1b:63:00:00:02 had 192.168.1.20 until {} 14:03", date),
+                format!("previously 192.168.1.87 until {} 14:02", date),
+            ]
+        );
+        // the other card still answers for .20 while it's quiet on .30
+        audit.record(ip(20), OTHER, at(4));
+        assert_eq!(
+            audit.describe(ip(20), MAC)[0],
+            format!(
+                "00:1b:63:00:00:02 claims 192.168.1.20 too, since {} 14:04",
+                date
+            )
+        );
+    }
+
+    #[test]
+    fn the_trail_is_saved_with_the_state() {
+        let dir = std::env::temp_dir().join(format!("netui-audit-{}", std::process::id()));
+        let path = dir.join("state-eth0.toml");
+        let mut state = State::at(path.clone()).unwrap();
+        state.set_label(ip(87), MAC, "nas");
+        state.record_binding(ip(87), MAC, at(0));
+        state.record_binding(ip(20), MAC, at(2));
+        state.save().unwrap();
+        let saved = State::at(path).unwrap();
+        assert_eq!(saved.audit(), state.audit());
+        assert_eq!(saved.audit().history(MAC).len(), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
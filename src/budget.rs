@@ -0,0 +1,150 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use pnet::util::MacAddr;
+
+/// Bytes used by every host today, and the daily budgets some of them have.
+///
+/// Budgets are only watched, nothing is enforced.
+#[derive(Debug, Default)]
+pub struct DailyUsage {
+    day: Option<NaiveDate>,
+    used: HashMap<MacAddr, u128>,
+    budgets: HashMap<MacAddr, u128>,
+    /// hosts already reported over budget today
+    exceeded: HashSet<MacAddr>,
+}
+
+/// Share of its budget a host used.
+#[derive(Clone, Copy, Debug)]
+pub struct BudgetUsage {
+    pub used: u128,
+    pub budget: u128,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetLevel {
+    Normal,
+    /// past `BUDGET_WARNING_RATIO`
+    Warning,
+    Exceeded,
+}
+
+const BUDGET_WARNING_RATIO: f64 = 0.8;
+
+impl BudgetUsage {
+    pub fn ratio(&self) -> f64 {
+        if self.budget == 0 {
+            return 1.0;
+        }
+        self.used as f64 / self.budget as f64
+    }
+
+    pub fn level(&self) -> BudgetLevel {
+        match self.ratio() {
+            r if r >= 1.0 => BudgetLevel::Exceeded,
+            r if r >= BUDGET_WARNING_RATIO => BudgetLevel::Warning,
+            _ => BudgetLevel::Normal,
+        }
+    }
+}
+
+impl DailyUsage {
+    pub fn new(budgets: HashMap<MacAddr, u128>) -> Self {
+        Self {
+            budgets,
+            ..Default::default()
+        }
+    }
+
+    /// Adds one tick of traffic, counters restart when `today` changes.
+    ///
+    /// Returns the hosts that went over their budget with this tick.
+    pub fn record(
+        &mut self,
+        today: NaiveDate,
+        usage: impl IntoIterator<Item = (MacAddr, u128)>,
+    ) -> Vec<MacAddr> {
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.used.clear();
+            self.exceeded.clear();
+        }
+        for (mac, bytes) in usage {
+            *self.used.entry(mac).or_default() += bytes;
+        }
+        let newly_exceeded: Vec<MacAddr> = self
+            .budgets
+            .keys()
+            .filter(|mac| !self.exceeded.contains(mac))
+            .filter(|mac| {
+                self.budget_usage(mac)
+                    .is_some_and(|usage| usage.level() == BudgetLevel::Exceeded)
+            })
+            .copied()
+            .collect();
+        self.exceeded.extend(newly_exceeded.iter().copied());
+        newly_exceeded
+    }
+
+    /// Bytes sent and received by `mac` today.
+    pub fn used(&self, mac: &MacAddr) -> u128 {
+        self.used.get(mac).copied().unwrap_or_default()
+    }
+
+    /// Today's usage against the budget, for hosts that have one.
+    pub fn budget_usage(&self, mac: &MacAddr) -> Option<BudgetUsage> {
+        let budget = *self.budgets.get(mac)?;
+        Some(BudgetUsage {
+            used: self.used(mac),
+            budget,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TV: MacAddr = MacAddr(0, 0x1b, 0x63, 0, 0, 1);
+    const LAPTOP: MacAddr = MacAddr(0, 0x1b, 0x63, 0, 0, 2);
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    fn level(usage: &DailyUsage, mac: MacAddr) -> Option<BudgetLevel> {
+        usage.budget_usage(&mac).map(|usage| usage.level())
+    }
+
+    #[test]
+    fn a_budget_warns_at_80_percent_and_alerts_once_over() {
+        let mut usage = DailyUsage::new(HashMap::from([(TV, 1000)]));
+        assert!(usage.record(day(1), [(TV, 799), (LAPTOP, 5000)]).is_empty());
+        assert_eq!(level(&usage, TV), Some(BudgetLevel::Normal));
+        assert_eq!(level(&usage, LAPTOP), None);
+        assert!(usage.record(day(1), [(TV, 1)]).is_empty());
+        assert_eq!(level(&usage, TV), Some(BudgetLevel::Warning));
+        assert_eq!(usage.record(day(1), [(TV, 200)]), [TV]);
+        assert_eq!(level(&usage, TV), Some(BudgetLevel::Exceeded));
+        // over it stays, but it's only reported the once
+        assert!(usage.record(day(1), [(TV, 200)]).is_empty());
+        assert_eq!(usage.used(&TV), 1200);
+        assert_eq!(usage.used(&LAPTOP), 5000);
+    }
+
+    #[test]
+    fn counters_restart_with_the_day() {
+        let mut usage = DailyUsage::new(HashMap::from([(TV, 1000)]));
+        assert_eq!(usage.record(day(1), [(TV, 1500)]), [TV]);
+        assert!(usage.record(day(2), [(TV, 10)]).is_empty());
+        assert_eq!(usage.used(&TV), 10);
+        assert_eq!(usage.record(day(2), [(TV, 990)]), [TV]);
+    }
+
+    #[test]
+    fn an_empty_budget_is_always_exceeded() {
+        let usage = BudgetUsage { used: 0, budget: 0 };
+        assert_eq!(usage.level(), BudgetLevel::Exceeded);
+    }
+}
@@ -0,0 +1,369 @@
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::stats_aggregator::{StatValues, Transport};
+
+/// how often the capture loop checks whether it keeps up
+const LOAD_WINDOW: Duration = Duration::from_secs(1);
+/// busier than this, frames pile up in the socket buffer: sample more
+pub const SATURATED: f64 = 0.9;
+/// less busy than this, the capture can afford to sample less
+const RELAXED: f64 = 0.4;
+/// at most one IPv4 frame in this many is accounted
+pub const MAX_SAMPLING_FACTOR: u32 = 64;
+
+/// Why the capture loop let a frame go without accounting it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ignored {
+    /// too short, or with a header that does not add up
+    Malformed,
+    /// neither ARP nor IPv4
+    OtherEtherType,
+    /// IPv4 that isn't TCP or UDP
+    OtherProtocol,
+    /// outside the soloed host's flows
+    Solo,
+    /// untagged or tagged with another VLAN than `--vlan`'s
+    OtherVlan,
+}
+
+/// netui's own processing of the capture, as opposed to the traffic it
+/// measures; bumped by the capture loop, read by the app.
+#[derive(Debug)]
+pub struct CaptureCounters {
+    started_at: Instant,
+    frames: AtomicU64,
+    bytes: AtomicU64,
+    arp: AtomicU64,
+    accounted: AtomicU64,
+    tcp_unparsed: AtomicU64,
+    udp_unparsed: AtomicU64,
+    truncated: AtomicU64,
+    malformed: AtomicU64,
+    other_ether_type: AtomicU64,
+    other_protocol: AtomicU64,
+    solo: AtomicU64,
+    other_vlan: AtomicU64,
+    read_errors: AtomicU64,
+    sampled_out: AtomicU64,
+    sampling_factor: AtomicU32,
+    /// share of the last window the loop spent processing, in thousandths
+    busy_permille: AtomicU32,
+}
+
+impl CaptureCounters {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            frames: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            arp: AtomicU64::new(0),
+            accounted: AtomicU64::new(0),
+            tcp_unparsed: AtomicU64::new(0),
+            udp_unparsed: AtomicU64::new(0),
+            truncated: AtomicU64::new(0),
+            malformed: AtomicU64::new(0),
+            other_ether_type: AtomicU64::new(0),
+            other_protocol: AtomicU64::new(0),
+            solo: AtomicU64::new(0),
+            other_vlan: AtomicU64::new(0),
+            read_errors: AtomicU64::new(0),
+            sampled_out: AtomicU64::new(0),
+            sampling_factor: AtomicU32::new(1),
+            busy_permille: AtomicU32::new(0),
+        }
+    }
+
+    /// A frame of `len` bytes was read.
+    pub fn frame(&self, len: usize) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    pub fn arp(&self) {
+        self.arp.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An IPv4 frame made it into the traffic stats.
+    pub fn accounted(&self) {
+        self.accounted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The TCP or UDP header of an IPv4 frame couldn't be read, its bytes
+    /// are still accounted, without ports.
+    pub fn unparsed(&self, transport: Transport) {
+        let counter = match transport {
+            Transport::Tcp => &self.tcp_unparsed,
+            Transport::Udp => &self.udp_unparsed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An IPv4 frame was longer than the capture buffer, the length its
+    /// header declares is accounted.
+    pub fn truncated(&self) {
+        self.truncated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn ignored(&self, reason: Ignored) {
+        let counter = match reason {
+            Ignored::Malformed => &self.malformed,
+            Ignored::OtherEtherType => &self.other_ether_type,
+            Ignored::OtherProtocol => &self.other_protocol,
+            Ignored::Solo => &self.solo,
+            Ignored::OtherVlan => &self.other_vlan,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Reading from the interface failed, timeouts aside.
+    pub fn read_error(&self) {
+        self.read_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An IPv4 frame was let go unparsed, sampling accounts for it.
+    pub fn sampled_out(&self) {
+        self.sampled_out.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// How busy the loop was over the last window and the sampling it led to.
+    pub fn load(&self, busy: f64, sampling_factor: u32) {
+        self.busy_permille
+            .store((busy * 1000.0).round() as u32, Ordering::Relaxed);
+        self.sampling_factor
+            .store(sampling_factor, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CaptureStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        CaptureStats {
+            uptime: self.started_at.elapsed(),
+            frames: load(&self.frames),
+            bytes: load(&self.bytes),
+            arp: load(&self.arp),
+            accounted: load(&self.accounted),
+            tcp_unparsed: load(&self.tcp_unparsed),
+            udp_unparsed: load(&self.udp_unparsed),
+            truncated: load(&self.truncated),
+            malformed: load(&self.malformed),
+            other_ether_type: load(&self.other_ether_type),
+            other_protocol: load(&self.other_protocol),
+            solo: load(&self.solo),
+            other_vlan: load(&self.other_vlan),
+            read_errors: load(&self.read_errors),
+            sampled_out: load(&self.sampled_out),
+            sampling_factor: self.sampling_factor.load(Ordering::Relaxed),
+            busy: f64::from(self.busy_permille.load(Ordering::Relaxed)) / 1000.0,
+        }
+    }
+}
+
+impl Default for CaptureCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The counters at one point in time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CaptureStats {
+    pub uptime: Duration,
+    pub frames: u64,
+    pub bytes: u64,
+    pub arp: u64,
+    pub accounted: u64,
+    /// accounted without ports
+    pub tcp_unparsed: u64,
+    pub udp_unparsed: u64,
+    /// cut short by the capture buffer, accounted at their declared length
+    pub truncated: u64,
+    pub malformed: u64,
+    pub other_ether_type: u64,
+    pub other_protocol: u64,
+    pub solo: u64,
+    pub other_vlan: u64,
+    pub read_errors: u64,
+    /// IPv4 frames skipped while sampling
+    pub sampled_out: u64,
+    /// one IPv4 frame in this many is accounted, 1 when not sampling
+    pub sampling_factor: u32,
+    /// share of the last second the loop spent processing rather than waiting
+    pub busy: f64,
+}
+
+impl CaptureStats {
+    /// Frames read per second since the `previous` snapshot.
+    pub fn frames_per_sec(&self, previous: &CaptureStats) -> f64 {
+        let elapsed = self.uptime.saturating_sub(previous.uptime).as_secs_f64();
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+        self.frames.saturating_sub(previous.frames) as f64 / elapsed
+    }
+
+    pub fn ignored(&self) -> u64 {
+        self.malformed + self.other_ether_type + self.other_protocol + self.solo + self.other_vlan
+    }
+}
+
+/// Share of the time the capture loop spends processing frames rather than
+/// waiting for the next one. Frames are processed one at a time, so when it
+/// can't keep up the backlog is in the socket buffer, out of sight: what
+/// shows is a loop that never waits.
+#[derive(Debug)]
+pub struct Load {
+    window_start: Instant,
+    waited: Duration,
+}
+
+impl Load {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            waited: Duration::ZERO,
+        }
+    }
+
+    /// Adds a wait for a frame, returns the busy share once a window is over.
+    pub fn waited(&mut self, waited: Duration, now: Instant) -> Option<f64> {
+        self.waited += waited;
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < LOAD_WINDOW {
+            return None;
+        }
+        let idle = (self.waited.as_secs_f64() / elapsed.as_secs_f64()).min(1.0);
+        *self = Self::new(now);
+        Some(1.0 - idle)
+    }
+}
+
+/// Accounts one IPv4 frame in `factor` while the capture can't keep up,
+/// its bits scaled by `factor` so the totals stay about right.
+#[derive(Debug)]
+pub struct Sampling {
+    factor: u32,
+    /// frames let go since the last accounted one
+    skipped: u32,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self {
+            factor: 1,
+            skipped: 0,
+        }
+    }
+}
+
+impl Sampling {
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    /// Doubles the factor while the loop is saturated, halves it once it
+    /// relaxes; the new factor when it changed.
+    pub fn adapt(&mut self, busy: f64) -> Option<u32> {
+        let factor = if busy > SATURATED {
+            (self.factor * 2).min(MAX_SAMPLING_FACTOR)
+        } else if busy < RELAXED {
+            (self.factor / 2).max(1)
+        } else {
+            self.factor
+        };
+        if factor == self.factor {
+            return None;
+        }
+        self.factor = factor;
+        self.skipped = 0;
+        Some(factor)
+    }
+
+    /// Whether the next frame is accounted: every `factor`th one.
+    pub fn admit(&mut self) -> bool {
+        if self.skipped + 1 >= self.factor {
+            self.skipped = 0;
+            true
+        } else {
+            self.skipped += 1;
+            false
+        }
+    }
+
+    /// An accounted frame's bits, standing for the frames let go with it.
+    pub fn scale(&self, values: StatValues) -> StatValues {
+        let factor = u128::from(self.factor);
+        StatValues {
+            a_sent: values.a_sent * factor,
+            b_sent: values.b_sent * factor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn without_sampling_every_frame_counts_once() {
+        let mut sampling = Sampling::default();
+        assert!((0..10).all(|_| sampling.admit()));
+        let values = sampling.scale(StatValues::sent(true, 800));
+        assert_eq!((values.a_sent, values.b_sent), (800, 0));
+    }
+
+    #[test]
+    fn sampled_totals_stay_right_on_steady_traffic() {
+        let mut sampling = Sampling::default();
+        assert_eq!(sampling.adapt(0.95), Some(2));
+        assert_eq!(sampling.adapt(0.95), Some(4));
+        let frames = 1000;
+        let mut total = 0;
+        for _ in 0..frames {
+            if sampling.admit() {
+                total += sampling.scale(StatValues::sent(false, 1200)).total();
+            }
+        }
+        assert_eq!(total, frames * 1200);
+    }
+
+    #[test]
+    fn the_factor_follows_the_load_within_bounds() {
+        let mut sampling = Sampling::default();
+        // neither saturated nor relaxed, nothing changes
+        assert_eq!(sampling.adapt(0.6), None);
+        for _ in 0..10 {
+            sampling.adapt(1.0);
+        }
+        assert_eq!(sampling.factor(), MAX_SAMPLING_FACTOR);
+        assert_eq!(sampling.adapt(0.2), Some(MAX_SAMPLING_FACTOR / 2));
+        for _ in 0..10 {
+            sampling.adapt(0.0);
+        }
+        assert_eq!(sampling.factor(), 1);
+        assert_eq!(sampling.adapt(0.0), None);
+    }
+
+    #[test]
+    fn load_is_the_share_of_a_window_not_spent_waiting() {
+        let start = Instant::now();
+        let mut load = Load::new(start);
+        assert_eq!(
+            load.waited(
+                Duration::from_millis(100),
+                start + Duration::from_millis(500)
+            ),
+            None
+        );
+        let busy = load
+            .waited(Duration::from_millis(150), start + Duration::from_secs(1))
+            .unwrap();
+        assert!((busy - 0.75).abs() < 1e-9, "{}", busy);
+        // a new window starts
+        let busy = load
+            .waited(Duration::from_secs(2), start + Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(busy, 0.0);
+    }
+}
@@ -0,0 +1,193 @@
+use clap::ValueEnum;
+
+use crate::theme::ThemeName;
+
+/// Everything the keybindings and the command palette can ask the app to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// quits, or asks first when operations are in flight
+    Quit,
+    ForceQuit,
+    Scan,
+    Clean,
+    EditLabel,
+    InterfaceInfo,
+    Status,
+    /// every key of the current view
+    Help,
+    CaptureStats,
+    FreezeTime,
+    Theme(ThemeName),
+    NextTheme,
+    /// the popup of the runtime settings
+    Settings,
+    /// what the footer's speed box shows
+    NextFooterMetric,
+    NextSpeedSource,
+    ToggleCombinedSpeed,
+    /// hosts, connections or destinations
+    NextView,
+    NextConnectionOrder,
+    ReverseConnectionOrder,
+    /// /24, /16 or exact addresses
+    NextDestinationMask,
+    /// every host, then only the active, idle or silent ones
+    NextActivityFilter,
+    Export,
+    ExportReport,
+    /// solo the selected host, or stop soloing
+    ToggleSolo,
+    /// announce when the selected host starts or stops sending, again to stop
+    ToggleWatch,
+    /// save the hosts as a snapshot to compare later
+    SaveSnapshot,
+    /// the hosts against the last snapshot
+    CompareSnapshot,
+}
+
+/// Palette commands with their help, in the order they are suggested.
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("scan", "send ARP requests to every address"),
+    ("clean", "remove the selected host and older ones"),
+    ("label", "label the selected host"),
+    (
+        "solo",
+        "only account the selected host's traffic, again to stop",
+    ),
+    (
+        "watch",
+        "tell when the selected host starts or stops sending, again to stop",
+    ),
+    ("export", "write the hosts as nmap XML"),
+    ("snapshot", "save the hosts to compare them later"),
+    ("compare", "compare the hosts with the last snapshot"),
+    (
+        "report",
+        "write an HTML report of the hosts and the traffic",
+    ),
+    ("theme", "theme dark|light|high-contrast, or the next one"),
+    ("freeze", "freeze or unfreeze the time column"),
+    ("total", "next speed shown in the footer"),
+    (
+        "settings",
+        "change the scan delay, the averaging window and more",
+    ),
+    ("speed", "next source of the speed columns"),
+    (
+        "merge",
+        "show the speeds in one column, again to split them",
+    ),
+    (
+        "activity",
+        "list only active, idle or silent hosts, then all",
+    ),
+    (
+        "connections",
+        "switch between the hosts, the connections and the destinations",
+    ),
+    (
+        "group",
+        "group the destinations by /24, /16 or exact address",
+    ),
+    (
+        "order",
+        "next order of the connections, 'order reverse' flips it",
+    ),
+    ("interface", "show the interface details"),
+    ("status", "show the status view"),
+    ("capture", "show how the capture is keeping up"),
+    ("help", "list every key of the current view"),
+    ("quit", "quit netui"),
+];
+
+/// Reads a command typed in the palette, its name may be abbreviated as long
+/// as only one command starts with it.
+pub fn parse(input: &str) -> Result<Action, String> {
+    let mut words = input.split_whitespace();
+    let Some(word) = words.next() else {
+        return Err("type a command".to_string());
+    };
+    let candidates: Vec<&str> = COMMANDS
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| name.starts_with(word))
+        .collect();
+    let name = match candidates[..] {
+        [name] => name,
+        [] => return Err(format!("unknown command '{}'", word)),
+        _ if candidates.contains(&word) => word,
+        _ => {
+            return Err(format!(
+                "'{}' is ambiguous: {}",
+                word,
+                candidates.join(", ")
+            ))
+        }
+    };
+    let argument = words.next();
+    let action = match (name, argument) {
+        ("scan", None) => Action::Scan,
+        ("clean", None) => Action::Clean,
+        ("label", None) => Action::EditLabel,
+        ("solo", None) => Action::ToggleSolo,
+        ("watch", None) => Action::ToggleWatch,
+        ("export", None) => Action::Export,
+        ("report", None) => Action::ExportReport,
+        ("snapshot", None) => Action::SaveSnapshot,
+        ("compare", None) => Action::CompareSnapshot,
+        ("theme", Some(theme)) => Action::Theme(ThemeName::from_str(theme, true)?),
+        ("theme", None) => Action::NextTheme,
+        ("freeze", None) => Action::FreezeTime,
+        ("total", None) => Action::NextFooterMetric,
+        ("settings", None) => Action::Settings,
+        ("speed", None) => Action::NextSpeedSource,
+        ("merge", None) => Action::ToggleCombinedSpeed,
+        ("connections", None) => Action::NextView,
+        ("group", None) => Action::NextDestinationMask,
+        ("order", None) => Action::NextConnectionOrder,
+        ("order", Some("reverse")) => Action::ReverseConnectionOrder,
+        ("activity", None) => Action::NextActivityFilter,
+        ("interface", None) => Action::InterfaceInfo,
+        ("status", None) => Action::Status,
+        ("capture", None) => Action::CaptureStats,
+        ("help", None) => Action::Help,
+        ("quit", None) => Action::Quit,
+        (name, Some(argument)) => {
+            return Err(format!("{} takes no argument, got '{}'", name, argument))
+        }
+        (name, None) => return Err(format!("unknown command '{}'", name)),
+    };
+    match words.next() {
+        Some(extra) => Err(format!("unexpected '{}'", extra)),
+        None => Ok(action),
+    }
+}
+
+/// Commands whose name contains the typed letters in order, best first:
+/// prefixes, then the tightest matches.
+pub fn suggestions(input: &str) -> Vec<(&'static str, &'static str)> {
+    let word = input.split_whitespace().next().unwrap_or("");
+    let mut matches: Vec<(usize, &(&str, &str))> = COMMANDS
+        .iter()
+        .filter_map(|command| fuzzy_score(command.0, word).map(|score| (score, command)))
+        .collect();
+    matches.sort_by_key(|(score, _)| *score);
+    matches.into_iter().map(|(_, command)| *command).collect()
+}
+
+/// Lower is better, `None` when `pattern` isn't a subsequence of `name`.
+fn fuzzy_score(name: &str, pattern: &str) -> Option<usize> {
+    if name.starts_with(pattern) {
+        return Some(0);
+    }
+    let mut first = None;
+    let mut last = 0;
+    let mut chars = name.char_indices();
+    for wanted in pattern.chars() {
+        let (index, _) = chars.find(|(_, c)| *c == wanted)?;
+        first.get_or_insert(index);
+        last = index;
+    }
+    // the span covering the letters, shorter spans read as better matches
+    Some(1 + last - first.unwrap_or(0))
+}
@@ -0,0 +1,181 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use pnet::util::MacAddr;
+use serde::Deserialize;
+
+use crate::{
+    app::AppResult,
+    format::{parse_rate, parse_size},
+    limits::Limits,
+    logging::get_config_dir,
+    mac_format::parse_mac,
+    resolver::ResolverOptions,
+    watch::WatchOptions,
+};
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Settings read from `config.toml` in the config directory, every key is optional.
+///
+/// ```toml
+/// combined_speed = true
+/// link_speed = "1Gbit"
+/// same_ip = "merge"
+/// vendor_overrides = "vendors.csv"
+///
+/// [budgets]
+/// "aa:bb:cc:dd:ee:ff" = "2GiB"
+/// "AABB.CCDD.EEFF" = "500MB"
+///
+/// [limits]
+/// hosts = 1024
+/// flows = 8192
+///
+/// [resolver]
+/// concurrency = 2
+/// per_second = 5
+///
+/// [watch]
+/// on = "start"
+/// bell = false
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// start with download and upload in a single column
+    pub combined_speed: bool,
+    /// speed of the link when the interface doesn't report it, e.g. `"1Gbit"`
+    link_speed: Option<String>,
+    /// daily traffic budget of hosts, by MAC address
+    budgets: HashMap<String, String>,
+    /// vendor names by MAC prefix over the OUI registry's, a CSV or TOML
+    /// file relative to the config directory
+    vendor_overrides: Option<PathBuf>,
+    /// caps on the collections a long session fills
+    pub limits: Limits,
+    /// limits of the host name lookups
+    pub resolver: ResolverOptions,
+    /// how the hosts watched with `b` are announced
+    pub watch: WatchOptions,
+    /// rows of an address answering with several MACs
+    pub same_ip: SameIpPolicy,
+}
+
+/// How an address seen with several MACs is listed, e.g. a host on bonded
+/// links or a failover pair sharing an address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SameIpPolicy {
+    /// a row per MAC
+    #[default]
+    Separate,
+    /// a single row, showing the last MAC the address was seen with
+    Merge,
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        get_config_dir().join(CONFIG_FILE)
+    }
+
+    /// Reads the config file, a missing file is an empty config.
+    pub fn load() -> AppResult<Self> {
+        let path = Self::path();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+        };
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Daily budgets in bytes.
+    pub fn budgets(&self) -> AppResult<HashMap<MacAddr, u128>> {
+        self.budgets
+            .iter()
+            .map(|(mac, size)| {
+                let mac = parse_mac(mac).map_err(|e| format!("budget: {}", e))?;
+                let bytes = parse_size(size).map_err(|e| format!("budget for '{}': {}", mac, e))?;
+                Ok((mac, bytes))
+            })
+            .collect()
+    }
+
+    /// The vendor overrides file, when one is configured.
+    pub fn vendor_overrides(&self) -> Option<PathBuf> {
+        self.vendor_overrides
+            .as_ref()
+            .map(|path| get_config_dir().join(path))
+    }
+
+    /// The configured link speed in bits per second.
+    pub fn link_speed(&self) -> AppResult<Option<u64>> {
+        self.link_speed
+            .as_deref()
+            .map(|rate| parse_rate(rate).map_err(|e| format!("link_speed: {}", e).into()))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(text: &str) -> Config {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn budgets_are_read_in_any_mac_format() {
+        let budgets = config(
+            r#"
+            [budgets]
+            "aa:bb:cc:dd:ee:ff" = "2GiB"
+            "AABB.CCDD.EE00" = "500MB"
+            "aa-bb-cc-dd-ee-01" = "1.5 K"
+            "#,
+        )
+        .budgets()
+        .unwrap();
+        assert_eq!(
+            budgets,
+            HashMap::from([
+                (MacAddr(0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff), 2 << 30),
+                (MacAddr(0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0), 500_000_000),
+                (MacAddr(0xaa, 0xbb, 0xcc, 0xdd, 0xee, 1), 1536),
+            ])
+        );
+    }
+
+    #[test]
+    fn the_link_speed_override_is_a_rate() {
+        assert_eq!(config("").link_speed().unwrap(), None);
+        assert_eq!(
+            config("link_speed = \"1Gbit\"").link_speed().unwrap(),
+            Some(1_000_000_000)
+        );
+        let error = config("link_speed = \"fast\"")
+            .link_speed()
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "link_speed: 'fast' is not a rate");
+    }
+
+    #[test]
+    fn a_bad_budget_names_its_host() {
+        let error = config("[budgets]\n\"aa:bb:cc:dd:ee:ff\" = \"2 gigs\"")
+            .budgets()
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.starts_with("budget for 'aa:bb:cc:dd:ee:ff'"),
+            "{}",
+            error
+        );
+        let error = config("[budgets]\n\"tv\" = \"2GiB\"")
+            .budgets()
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("budget: "), "{}", error);
+    }
+}
@@ -0,0 +1,129 @@
+use itertools::Itertools;
+use ratatui::{
+    layout::{Constraint, Rect},
+    text::Text,
+    widgets::{Cell, HighlightSpacing, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    format::{format_age, format_bytes},
+    stats_aggregator::{Connection, ConnectionSort, Transport},
+    theme::Theme,
+};
+
+/// Local, remote, protocol, speed, both directions' bytes and duration.
+pub const COLUMNS: usize = 7;
+
+/// The flows the aggregator tracks, one row each.
+pub struct ConnectionsTable<'a> {
+    items: &'a [Connection],
+    theme: &'a Theme,
+    sort: ConnectionSort,
+}
+
+impl<'a> ConnectionsTable<'a> {
+    pub fn new(items: &'a [Connection], theme: &'a Theme) -> Self {
+        Self {
+            items,
+            theme,
+            sort: ConnectionSort::default(),
+        }
+    }
+
+    /// Names the order of the rows in the header.
+    pub fn sort(mut self, sort: ConnectionSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn draw(&self, table_state: &mut TableState, frame: &mut Frame, area: Rect) {
+        self.render_table(table_state, frame, area);
+    }
+
+    fn render_table(&self, table_state: &mut TableState, frame: &mut Frame, area: Rect) {
+        let order = format!(
+            "by {}{}",
+            self.sort.order.label(),
+            if self.sort.descending {
+                ", reversed"
+            } else {
+                ""
+            }
+        );
+        let header = [
+            format!("Local ({})", order),
+            "Remote".to_string(),
+            "Proto".to_string(),
+            "Speed".to_string(),
+            "Client→Server".to_string(),
+            "Server→Client".to_string(),
+            "Duration".to_string(),
+        ]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(self.theme.header)
+        .height(1);
+        // with a single network, naming it on every row says nothing
+        let several_networks = self
+            .items
+            .iter()
+            .filter_map(|connection| connection.network)
+            .unique()
+            .count()
+            > 1;
+        let rows = self.items.iter().enumerate().map(|(i, connection)| {
+            let endpoint = |(ip, port): (std::net::Ipv4Addr, u16)| format!("{}:{}", ip, port);
+            let remote = if connection.broadcast {
+                format!("{} (broadcast)", endpoint(connection.remote))
+            } else {
+                endpoint(connection.remote)
+            };
+            let transport = match connection.transport {
+                Transport::Tcp => "tcp",
+                Transport::Udp => "udp",
+            };
+            let mut local = if connection.server_is_local {
+                format!("{} (server)", endpoint(connection.local))
+            } else {
+                endpoint(connection.local)
+            };
+            if let Some(network) = connection.network.filter(|_| several_networks) {
+                local = format!("{} on {}", local, network);
+            }
+            [
+                local,
+                remote,
+                transport.to_string(),
+                connection.speed.to_string(),
+                format_bytes(connection.requests),
+                format_bytes(connection.responses),
+                format_age(connection.age),
+            ]
+            .into_iter()
+            .map(|content| Cell::from(Text::from(content)))
+            .collect::<Row>()
+            .style(self.theme.row_style(i))
+            .height(1)
+        });
+        let widths = [
+            Constraint::Min(30),
+            Constraint::Min(32),
+            Constraint::Length(5),
+            Constraint::Min(24),
+            Constraint::Min(13),
+            Constraint::Min(13),
+            Constraint::Min(8),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .row_highlight_style(self.theme.selected_row)
+            .column_highlight_style(self.theme.selected_column)
+            .cell_highlight_style(self.theme.selected_cell)
+            .highlight_symbol(Text::from(vec![" ━ ".into()]))
+            .style(self.theme.background)
+            .highlight_spacing(HighlightSpacing::Always);
+        frame.render_stateful_widget(table, area, table_state);
+    }
+}
@@ -0,0 +1,93 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    text::Text,
+    widgets::{Cell, HighlightSpacing, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    format::format_bytes,
+    stats_aggregator::{Destination, DestinationMask},
+    theme::Theme,
+};
+
+/// Destination, down, up and LAN hosts.
+pub const COLUMNS: usize = 4;
+
+/// The internet traffic of the LAN by remote network, busiest first.
+pub struct DestinationsTable<'a> {
+    items: &'a [Destination],
+    theme: &'a Theme,
+    mask: DestinationMask,
+}
+
+impl<'a> DestinationsTable<'a> {
+    pub fn new(items: &'a [Destination], theme: &'a Theme) -> Self {
+        Self {
+            items,
+            theme,
+            mask: DestinationMask::default(),
+        }
+    }
+
+    /// Names the grouping in the header, exact addresses are shown without
+    /// a prefix.
+    pub fn mask(mut self, mask: DestinationMask) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    pub fn draw(&self, table_state: &mut TableState, frame: &mut Frame, area: Rect) {
+        self.render_table(table_state, frame, area);
+    }
+
+    fn render_table(&self, table_state: &mut TableState, frame: &mut Frame, area: Rect) {
+        let grouping = match self.mask {
+            DestinationMask::Exact => "by address".to_string(),
+            mask => format!("by /{}", mask.prefix()),
+        };
+        let header = [
+            format!("Destination ({})", grouping),
+            "Down".to_string(),
+            "Up".to_string(),
+            "LAN hosts".to_string(),
+        ]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(self.theme.header)
+        .height(1);
+        let rows = self.items.iter().enumerate().map(|(i, destination)| {
+            let network = match self.mask {
+                DestinationMask::Exact => destination.network.ip().to_string(),
+                _ => destination.network.to_string(),
+            };
+            [
+                network,
+                format_bytes(destination.down),
+                format_bytes(destination.up),
+                destination.lan_hosts.to_string(),
+            ]
+            .into_iter()
+            .map(|content| Cell::from(Text::from(content)))
+            .collect::<Row>()
+            .style(self.theme.row_style(i))
+            .height(1)
+        });
+        let widths = [
+            Constraint::Min(22),
+            Constraint::Min(12),
+            Constraint::Min(12),
+            Constraint::Min(10),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .row_highlight_style(self.theme.selected_row)
+            .column_highlight_style(self.theme.selected_column)
+            .cell_highlight_style(self.theme.selected_cell)
+            .highlight_symbol(Text::from(vec![" ━ ".into()]))
+            .style(self.theme.background)
+            .highlight_spacing(HighlightSpacing::Always);
+        frame.render_stateful_widget(table, area, table_state);
+    }
+}
@@ -0,0 +1,310 @@
+use std::{fmt::Display, fs};
+
+use chrono::{Datelike, Local};
+use crossterm::style::Stylize;
+use pnet_datalink::NetworkInterface;
+
+use crate::{
+    config::Config,
+    enrich::Oui,
+    instance::{self, InstanceLock},
+    logging::get_data_dir,
+    networks::NetworkPlan,
+    scanner::{Scanner, DEFAULT_CAPTURE_BUFFER},
+    state::State,
+    theme::ColorDepth,
+};
+
+/// a clock before this year is certainly unset (no RTC, no NTP yet)
+const MIN_SANE_YEAR: i32 = 2024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    /// 0 when all is fine, 1 with warnings, 2 with failures.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Status::Ok => 0,
+            Status::Warn => 1,
+            Status::Fail => 2,
+        }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        write!(f, "{:<4}", label)
+    }
+}
+
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    /// what to do about a warning or a failure
+    pub remedy: Option<String>,
+}
+
+impl Check {
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Ok,
+            detail: detail.into(),
+            remedy: None,
+        }
+    }
+
+    fn problem(
+        status: Status,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        remedy: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            remedy: Some(remedy.into()),
+        }
+    }
+}
+
+/// Runs every check, prints the report and returns the worst result.
+pub async fn run(web_port: Option<u16>, color: ColorDepth) -> Status {
+    let mut checks = vec![];
+    let interfaces = Scanner::usable_interfaces();
+    if interfaces.is_empty() {
+        checks.push(Check::problem(
+            Status::Fail,
+            "interfaces",
+            "no interface is up and running",
+            "bring a network interface up",
+        ));
+    }
+    for nif in &interfaces {
+        checks.push(check_capture(nif));
+        checks.push(check_networks(nif));
+        checks.push(check_state(&nif.name));
+    }
+    checks.push(check_config());
+    checks.push(check_vendors());
+    checks.extend(check_instances());
+    checks.push(check_data_dir());
+    checks.push(check_clock());
+    if let Some(port) = web_port {
+        checks.push(check_port(port).await);
+    }
+
+    for check in &checks {
+        let status = check.status.to_string();
+        let status = match (color, check.status) {
+            (ColorDepth::None, _) => status,
+            (_, Status::Ok) => status.green().to_string(),
+            (_, Status::Warn) => status.yellow().to_string(),
+            (_, Status::Fail) => status.red().bold().to_string(),
+        };
+        println!("[{}] {}: {}", status, check.name, check.detail);
+        if let Some(remedy) = &check.remedy {
+            println!("       -> {}", remedy);
+        }
+    }
+    checks
+        .iter()
+        .map(|check| check.status)
+        .max()
+        .unwrap_or(Status::Ok)
+}
+
+fn check_capture(nif: &NetworkInterface) -> Check {
+    let name = format!("{} capture", nif.name);
+    match Scanner::create_datalink_channel(nif.clone(), DEFAULT_CAPTURE_BUFFER) {
+        Ok(_) => Check::ok(name, "an Ethernet channel can be opened"),
+        Err(e) => Check::problem(
+            Status::Fail,
+            name,
+            e.to_string(),
+            "run as root, or: sudo setcap cap_net_raw,cap_net_admin=eip $(which netui)",
+        ),
+    }
+}
+
+fn check_networks(nif: &NetworkInterface) -> Check {
+    let name = format!("{} networks", nif.name);
+    let ipv4 = nif.ips.iter().filter(|ip| ip.is_ipv4()).count();
+    let ipv6 = nif.ips.len() - ipv4;
+    let detail = format!("{} IPv4, {} IPv6", ipv4, ipv6);
+    let plan = NetworkPlan::analyze(&nif.ips);
+    if !plan.warnings.is_empty() {
+        Check::problem(
+            Status::Warn,
+            name,
+            format!("{}, {}", detail, plan.warnings.join("; ")),
+            "remove the leftover addresses, sweeps probe each address once meanwhile",
+        )
+    } else if ipv4 == 0 {
+        Check::problem(
+            Status::Warn,
+            name,
+            detail,
+            "ARP sweeps need an IPv4 network, use --monitor-only on taps and bridges",
+        )
+    } else {
+        Check::ok(name, detail)
+    }
+}
+
+fn check_config() -> Check {
+    let path = Config::path();
+    let name = "config";
+    match Config::load().and_then(|config| config.budgets().map(|_| config)) {
+        Ok(_) if path.exists() => Check::ok(name, format!("{} is valid", path.display())),
+        Ok(_) => Check::ok(name, format!("no {}, defaults are used", path.display())),
+        // toml errors point at the line and column
+        Err(e) => Check::problem(Status::Fail, name, e.to_string(), "fix or remove the file"),
+    }
+}
+
+fn check_state(interface: &str) -> Check {
+    let name = format!("state of {}", interface);
+    match State::load(interface) {
+        Ok(_) => Check::ok(
+            name,
+            format!("{} is readable", State::path(interface).display()),
+        ),
+        Err(e) => Check::problem(
+            Status::Warn,
+            name,
+            e.to_string(),
+            "labels can't be restored, remove the file to start over",
+        ),
+    }
+}
+
+/// Instances running now, and the locks crashed ones left behind.
+fn check_instances() -> Vec<Check> {
+    let locks = instance::held_locks();
+    if locks.is_empty() {
+        return vec![Check::ok("instances", "no other netui is running")];
+    }
+    locks
+        .into_iter()
+        .map(|lock| {
+            let name = format!("instance on {}", lock.interface);
+            match lock.pid {
+                Some(pid) if lock.running => Check::ok(name, format!("running as pid {}", pid)),
+                _ => Check::problem(
+                    Status::Warn,
+                    name,
+                    format!(
+                        "stale lock {}",
+                        InstanceLock::path(&lock.interface).display()
+                    ),
+                    "netui takes it over on start, or remove the file",
+                ),
+            }
+        })
+        .collect()
+}
+
+fn check_data_dir() -> Check {
+    let directory = get_data_dir();
+    let name = "data directory";
+    let probe = directory.join(".doctor");
+    let writable = fs::create_dir_all(&directory)
+        .and_then(|_| fs::write(&probe, b""))
+        .and_then(|_| fs::remove_file(&probe));
+    match writable {
+        Ok(_) => Check::ok(name, format!("{} is writable", directory.display())),
+        Err(e) => Check::problem(
+            Status::Fail,
+            name,
+            format!("{}: {}", directory.display(), e),
+            "make it writable, or point NETUI_DATA to a writable folder",
+        ),
+    }
+}
+
+fn check_vendors() -> Check {
+    let overrides = Config::load()
+        .ok()
+        .and_then(|config| config.vendor_overrides());
+    let oui = match overrides {
+        Some(path) => match Oui::load().with_overrides(&path) {
+            Ok(oui) => oui,
+            Err(e) => {
+                return Check::problem(
+                    Status::Fail,
+                    "vendors",
+                    e.to_string(),
+                    "fix the vendor overrides file or remove vendor_overrides",
+                )
+            }
+        },
+        None => Oui::load(),
+    };
+    match oui.vendor_count() {
+        0 if oui.override_count() > 0 => Check::problem(
+            Status::Warn,
+            "vendors",
+            format!(
+                "no OUI registry found, only {} overrides",
+                oui.override_count()
+            ),
+            "install nmap, ieee-data or hwdata",
+        ),
+        0 => Check::problem(
+            Status::Warn,
+            "vendors",
+            "no OUI registry found, hosts are shown without their vendor",
+            "install nmap, ieee-data or hwdata",
+        ),
+        count if oui.override_count() > 0 => Check::ok(
+            "vendors",
+            format!(
+                "{} vendors known, {} overrides",
+                count,
+                oui.override_count()
+            ),
+        ),
+        count => Check::ok("vendors", format!("{} vendors known", count)),
+    }
+}
+
+fn check_clock() -> Check {
+    let now = Local::now();
+    let detail = now.format("%Y-%m-%d %H:%M:%S %:z").to_string();
+    if now.year() < MIN_SANE_YEAR {
+        Check::problem(
+            Status::Warn,
+            "clock",
+            detail,
+            "set the system clock, daily counters and exports use it",
+        )
+    } else {
+        Check::ok("clock", detail)
+    }
+}
+
+async fn check_port(port: u16) -> Check {
+    let name = format!("web port {}", port);
+    match crate::web::bind(crate::web::DEFAULT_BIND, port).await {
+        Ok(_) => Check::ok(name, "can be bound"),
+        Err(e) => Check::problem(
+            Status::Fail,
+            name,
+            e.to_string(),
+            "pick another port for --web",
+        ),
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    fs, io,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use pnet::packet::{
+    arp::ArpPacket,
+    ethernet::{EtherTypes, EthernetPacket},
+    Packet,
+};
+use pnet_datalink::{DataLinkSender, NetworkInterface};
+
+/// set once from `--dry-run`, before anything is sent or written
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// what the notices start with while nothing is really done
+pub const TAG: &str = "[dry-run]";
+
+/// Simulates what leaves the machine or touches the disk for the rest of
+/// the run: frames and files are logged instead.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// `sender`, or a [`DryRunSender`] around it under `--dry-run`. Every
+/// frame netui sends goes through what this returns.
+pub fn sender(sender: Box<dyn DataLinkSender>) -> Box<dyn DataLinkSender> {
+    if enabled() {
+        Box::new(DryRunSender::new(sender))
+    } else {
+        sender
+    }
+}
+
+/// Logs the frames it is given and drops them, the wrapped sender stays
+/// open but never sees one. Sending succeeds, so the sweeps count and
+/// report as usual.
+pub struct DryRunSender {
+    /// kept so the channel stays as it would be
+    _inner: Box<dyn DataLinkSender>,
+}
+
+impl DryRunSender {
+    pub fn new(inner: Box<dyn DataLinkSender>) -> Self {
+        Self { _inner: inner }
+    }
+
+    fn log(frame: &[u8]) {
+        let arp = EthernetPacket::new(frame)
+            .filter(|ethernet| ethernet.get_ethertype() == EtherTypes::Arp)
+            .and_then(|ethernet| {
+                ArpPacket::new(ethernet.payload()).map(|arp| arp.get_target_proto_addr())
+            });
+        match arp {
+            Some(target) => tracing::info!("{} would send an ARP request for {}", TAG, target),
+            None => tracing::info!("{} would send a {} byte frame", TAG, frame.len()),
+        }
+    }
+}
+
+impl DataLinkSender for DryRunSender {
+    fn build_and_send(
+        &mut self,
+        num_packets: usize,
+        packet_size: usize,
+        func: &mut dyn FnMut(&mut [u8]),
+    ) -> Option<io::Result<()>> {
+        for _ in 0..num_packets {
+            let mut frame = vec![0; packet_size];
+            func(&mut frame);
+            Self::log(&frame);
+        }
+        Some(Ok(()))
+    }
+
+    fn send_to(&mut self, packet: &[u8], _dst: Option<NetworkInterface>) -> Option<io::Result<()>> {
+        Self::log(packet);
+        Some(Ok(()))
+    }
+}
+
+/// Writes `contents` to `path`, creating its directory first, or only logs
+/// it under `--dry-run`. Every file netui saves goes through it.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_unless(enabled(), path, contents.as_ref())
+}
+
+fn write_unless(dry_run: bool, path: &Path, contents: &[u8]) -> io::Result<()> {
+    if dry_run {
+        tracing::info!(
+            "{} would write {} bytes to {}",
+            TAG,
+            contents.len(),
+            path.display()
+        );
+        return Ok(());
+    }
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Counts the frames that would have reached the wire.
+    struct CountingSender(Arc<Mutex<usize>>);
+
+    impl DataLinkSender for CountingSender {
+        fn build_and_send(
+            &mut self,
+            num_packets: usize,
+            _packet_size: usize,
+            _func: &mut dyn FnMut(&mut [u8]),
+        ) -> Option<io::Result<()>> {
+            *self.0.lock().unwrap() += num_packets;
+            Some(Ok(()))
+        }
+
+        fn send_to(
+            &mut self,
+            _packet: &[u8],
+            _dst: Option<NetworkInterface>,
+        ) -> Option<io::Result<()>> {
+            *self.0.lock().unwrap() += 1;
+            Some(Ok(()))
+        }
+    }
+
+    #[test]
+    fn no_frame_reaches_the_wrapped_sender() {
+        let sent = Arc::new(Mutex::new(0));
+        let mut sender = DryRunSender::new(Box::new(CountingSender(sent.clone())));
+        assert!(matches!(sender.send_to(&[0; 60], None), Some(Ok(()))));
+        assert!(matches!(
+            sender.build_and_send(3, 60, &mut |frame| frame[0] = 1),
+            Some(Ok(()))
+        ));
+        assert_eq!(*sent.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn no_file_appears() {
+        let dir = std::env::temp_dir().join(format!("netui-dry-run-{}", std::process::id()));
+        let path = dir.join("nested").join("hosts.xml");
+        write_unless(true, &path, b"<nmaprun/>").unwrap();
+        assert!(!dir.exists());
+        write_unless(false, &path, b"<nmaprun/>").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"<nmaprun/>");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,572 @@
+use std::{collections::BTreeMap, collections::HashMap, fs, path::Path};
+
+use pnet::util::MacAddr;
+
+use crate::{
+    app::{AppResult, Host},
+    state::State,
+};
+
+/// IEEE registries as distributions ship them, nmap's first.
+const OUI_FILES: [&str; 4] = [
+    "/usr/share/nmap/nmap-mac-prefixes",
+    "/usr/share/ieee-data/oui.txt",
+    "/usr/share/hwdata/oui.txt",
+    "/usr/share/misc/oui.txt",
+];
+
+/// What enrichers may read besides the host.
+pub struct EnrichCtx<'a> {
+    pub state: &'a State,
+}
+
+/// What the enrichers found about a host, by enricher then by key: two
+/// sources can name the same thing without clobbering each other.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Annotations(BTreeMap<&'static str, BTreeMap<&'static str, String>>);
+
+impl Annotations {
+    pub fn get(&self, source: &str, key: &str) -> Option<&String> {
+        self.0.get(source)?.get(key)
+    }
+
+    /// Every value, as `(source, key, value)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'static str, &String)> {
+        self.0.iter().flat_map(|(source, notes)| {
+            notes.iter().map(move |(key, value)| (*source, *key, value))
+        })
+    }
+}
+
+/// Annotates hosts after their discovery and again when something they
+/// depend on changes. An enricher only writes under its own `source`, which
+/// is emptied before each run: running it twice changes nothing.
+pub trait HostEnricher {
+    fn source(&self) -> &'static str;
+
+    /// Adds its findings about `host` to `notes`; the findings of the
+    /// enrichers before it are in `host.annotations`.
+    fn enrich(&self, host: &Host, ctx: &EnrichCtx, notes: &mut BTreeMap<&'static str, String>);
+}
+
+/// The enrichers, run in order.
+#[derive(Default)]
+pub struct Pipeline {
+    enrichers: Vec<Box<dyn HostEnricher>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `enricher` after the ones already added.
+    pub fn with(mut self, enricher: impl HostEnricher + 'static) -> Self {
+        self.enrichers.push(Box::new(enricher));
+        self
+    }
+
+    pub fn run(&self, host: &mut Host, ctx: &EnrichCtx) {
+        for enricher in &self.enrichers {
+            let mut notes = BTreeMap::new();
+            enricher.enrich(host, ctx, &mut notes);
+            if notes.is_empty() {
+                host.annotations.0.remove(enricher.source());
+            } else {
+                host.annotations.0.insert(enricher.source(), notes);
+            }
+        }
+    }
+}
+
+/// Vendors by MAC prefix, a level per hex digit: the IEEE assigns blocks of
+/// 24 bits (MA-L), 28 bits (MA-M) and 36 bits (MA-S).
+#[derive(Debug, Default)]
+struct VendorTrie {
+    root: Node,
+    len: usize,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    vendor: Option<String>,
+    children: [Option<Box<Node>>; 16],
+}
+
+impl VendorTrie {
+    /// Names the MACs starting with `prefix`, hex digits, replacing the
+    /// name it had.
+    fn insert(&mut self, prefix: &[u8], vendor: String) {
+        let mut node = &mut self.root;
+        for digit in prefix {
+            node = node.children[*digit as usize].get_or_insert_with(Box::default);
+        }
+        if node.vendor.replace(vendor).is_none() {
+            self.len += 1;
+        }
+    }
+
+    /// The vendor of the longest prefix `mac` starts with, and that
+    /// prefix's length in hex digits.
+    fn lookup(&self, mac: MacAddr) -> Option<(usize, &str)> {
+        let mut node = &self.root;
+        let mut found = None;
+        for (depth, digit) in mac_digits(mac).into_iter().enumerate() {
+            let Some(child) = &node.children[digit as usize] else {
+                break;
+            };
+            node = child;
+            if let Some(vendor) = &node.vendor {
+                found = Some((depth + 1, vendor.as_str()));
+            }
+        }
+        found
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+fn mac_digits(mac: MacAddr) -> [u8; 12] {
+    let mut digits = [0; 12];
+    for (i, byte) in mac.octets().into_iter().enumerate() {
+        digits[2 * i] = byte >> 4;
+        digits[2 * i + 1] = byte & 0x0f;
+    }
+    digits
+}
+
+/// The hex digits of a prefix like `00:1B:63`, `70B3D5F2A` or
+/// `70:B3:D5:F2:A0:00/36`: 6, 7 or 9 digits, or a `/24`, `/28` or `/36`
+/// length cutting a longer address.
+fn parse_prefix(text: &str) -> Result<Vec<u8>, String> {
+    let (digits, length) = match text.trim().split_once('/') {
+        Some((digits, length)) => (digits, Some(length.trim())),
+        None => (text.trim(), None),
+    };
+    let mut nibbles = digits
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| format!("'{}' is not a MAC prefix", text.trim()))?;
+    let wanted = match length {
+        Some("24") => 6,
+        Some("28") => 7,
+        Some("36") => 9,
+        Some(length) => return Err(format!("prefix length /{} isn't /24, /28 or /36", length)),
+        None => nibbles.len(),
+    };
+    if !matches!(wanted, 6 | 7 | 9) || nibbles.len() < wanted {
+        return Err(format!(
+            "'{}' is not a 24, 28 or 36 bit MAC prefix",
+            text.trim()
+        ));
+    }
+    nibbles.truncate(wanted);
+    Ok(nibbles)
+}
+
+/// The maker of the network card, from the start of the MAC.
+pub struct Oui {
+    vendors: VendorTrie,
+    /// the user's names, over the registry's
+    overrides: VendorTrie,
+}
+
+impl Oui {
+    pub const SOURCE: &'static str = "oui";
+
+    /// Reads the first registry found, none only leaves the addresses that
+    /// aren't assigned by a vendor recognized.
+    pub fn load() -> Self {
+        let vendors = OUI_FILES
+            .iter()
+            .find_map(|path| fs::read_to_string(path).ok())
+            .map(|text| parse_oui(&text))
+            .unwrap_or_default();
+        Self {
+            vendors,
+            overrides: VendorTrie::default(),
+        }
+    }
+
+    /// Adds the names of the overrides file, `prefix,name` lines or, for a
+    /// `.toml` file, `"prefix" = "name"` keys.
+    pub fn with_overrides(mut self, path: &Path) -> AppResult<Self> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let entries = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str::<HashMap<String, String>>(&text)
+                .map_err(|e| format!("{}: {}", path.display(), e))?
+                .into_iter()
+                .collect()
+        } else {
+            parse_overrides_csv(&text).map_err(|e| format!("{}: {}", path.display(), e))?
+        };
+        for (prefix, vendor) in entries {
+            let prefix = parse_prefix(&prefix).map_err(|e| format!("{}: {}", path.display(), e))?;
+            self.overrides.insert(&prefix, vendor.trim().to_string());
+        }
+        Ok(self)
+    }
+
+    pub fn vendor_count(&self) -> usize {
+        self.vendors.len()
+    }
+
+    pub fn override_count(&self) -> usize {
+        self.overrides.len()
+    }
+
+    /// The longest prefix wins, an override when both are as long. Locally
+    /// administered MACs are only named by overrides.
+    pub fn vendor(&self, mac: MacAddr) -> Option<&str> {
+        let overridden = self.overrides.lookup(mac);
+        if mac.0 & 0x02 != 0 {
+            return overridden.map(|(_, vendor)| vendor);
+        }
+        match (overridden, self.vendors.lookup(mac)) {
+            (Some((own, vendor)), Some((registry, _))) if own >= registry => Some(vendor),
+            (_, Some((_, vendor))) | (Some((_, vendor)), None) => Some(vendor),
+            (None, None) => None,
+        }
+    }
+}
+
+impl HostEnricher for Oui {
+    fn source(&self) -> &'static str {
+        Self::SOURCE
+    }
+
+    fn enrich(&self, host: &Host, _ctx: &EnrichCtx, notes: &mut BTreeMap<&'static str, String>) {
+        match self.vendor(host.mac) {
+            Some(vendor) => {
+                notes.insert("vendor", vendor.to_string());
+            }
+            // phones and laptops randomize their MAC per network, no vendor then
+            None if host.mac.0 & 0x02 != 0 => {
+                notes.insert("vendor", "locally administered".to_string());
+            }
+            None => {}
+        }
+    }
+}
+
+/// The labels the user gave hosts, remembered in the state file.
+pub struct KnownHosts;
+
+impl KnownHosts {
+    pub const SOURCE: &'static str = "known_hosts";
+}
+
+impl HostEnricher for KnownHosts {
+    fn source(&self) -> &'static str {
+        Self::SOURCE
+    }
+
+    fn enrich(&self, host: &Host, ctx: &EnrichCtx, notes: &mut BTreeMap<&'static str, String>) {
+        if let Some(label) = ctx.state.label(host.ipv4, host.mac) {
+            notes.insert("label", label.clone());
+        }
+    }
+}
+
+/// Vendors by prefix, from nmap's `000000 Name` lines, with longer prefixes
+/// for the MA-M and MA-S blocks, or the IEEE's `00-00-00   (hex)    NAME`
+/// ones, leaving out the IEEE's repeated `(base 16)` lines.
+fn parse_oui(text: &str) -> VendorTrie {
+    let mut vendors = VendorTrie::default();
+    let entries = text.lines().filter_map(|line| {
+        if line.contains("(base 16)") {
+            return None;
+        }
+        let (prefix, vendor) = match line.split_once("(hex)") {
+            Some((prefix, vendor)) => (prefix.trim().replace('-', ""), vendor),
+            None => {
+                let (prefix, vendor) = line.split_once(char::is_whitespace)?;
+                (prefix.to_string(), vendor)
+            }
+        };
+        let vendor = vendor.trim();
+        if vendor.is_empty() {
+            return None;
+        }
+        Some((parse_prefix(&prefix).ok()?, vendor.to_string()))
+    });
+    for (prefix, vendor) in entries {
+        vendors.insert(&prefix, vendor);
+    }
+    vendors
+}
+
+/// `prefix,name` lines, the name may hold commas or be quoted. Blank lines,
+/// `#` comments and a `prefix,name` header are skipped.
+fn parse_overrides_csv(text: &str) -> Result<Vec<(String, String)>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(number, line)| (number, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .filter(|(number, line)| !(*number == 0 && line.eq_ignore_ascii_case("prefix,name")))
+        .map(|(number, line)| {
+            let (prefix, vendor) = line
+                .split_once(',')
+                .ok_or_else(|| format!("line {}: expected prefix,name", number + 1))?;
+            let vendor = vendor.trim().trim_matches('"');
+            if vendor.is_empty() {
+                return Err(format!(
+                    "line {}: no name for {}",
+                    number + 1,
+                    prefix.trim()
+                ));
+            }
+            Ok((prefix.trim().to_string(), vendor.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn host(mac: MacAddr) -> Host {
+        Host::new(Ipv4Addr::new(192, 168, 1, 20), mac, false)
+    }
+
+    fn oui(registry: &str, overrides: &[(&str, &str)]) -> Oui {
+        let mut oui = Oui {
+            vendors: parse_oui(registry),
+            overrides: VendorTrie::default(),
+        };
+        for (prefix, vendor) in overrides {
+            oui.overrides
+                .insert(&parse_prefix(prefix).unwrap(), vendor.to_string());
+        }
+        oui
+    }
+
+    /// Writes `value` under `key`, with what an earlier source wrote when
+    /// there is one.
+    struct Echo {
+        source: &'static str,
+        key: &'static str,
+        value: &'static str,
+        after: Option<&'static str>,
+    }
+
+    impl HostEnricher for Echo {
+        fn source(&self) -> &'static str {
+            self.source
+        }
+
+        fn enrich(
+            &self,
+            host: &Host,
+            _ctx: &EnrichCtx,
+            notes: &mut BTreeMap<&'static str, String>,
+        ) {
+            let earlier = self
+                .after
+                .and_then(|source| host.annotations.get(source, self.key));
+            let value = match earlier {
+                Some(earlier) => format!("{} after {}", self.value, earlier),
+                None => self.value.to_string(),
+            };
+            notes.insert(self.key, value);
+        }
+    }
+
+    #[test]
+    fn enrichers_run_in_order_each_in_its_namespace() {
+        let state = State::default();
+        let ctx = EnrichCtx { state: &state };
+        let pipeline = Pipeline::new()
+            .with(Echo {
+                source: "first",
+                key: "name",
+                value: "a",
+                after: None,
+            })
+            .with(Echo {
+                source: "second",
+                key: "name",
+                value: "b",
+                after: Some("first"),
+            });
+        let mut host = host(MacAddr(0, 0x1b, 0x63, 1, 2, 3));
+        pipeline.run(&mut host, &ctx);
+        assert_eq!(host.annotations.get("first", "name").unwrap(), "a");
+        assert_eq!(host.annotations.get("second", "name").unwrap(), "b after a");
+    }
+
+    #[test]
+    fn running_again_changes_nothing_and_drops_stale_notes() {
+        let mut state = State::default();
+        let mut host = host(MacAddr(0, 0x1b, 0x63, 1, 2, 3));
+        state.set_label(host.ipv4, host.mac, "printer");
+        let oui = oui("001B63 Apple\n", &[]);
+        let pipeline = Pipeline::new().with(oui).with(KnownHosts);
+        pipeline.run(&mut host, &EnrichCtx { state: &state });
+        let once = host.annotations.clone();
+        pipeline.run(&mut host, &EnrichCtx { state: &state });
+        assert_eq!(host.annotations, once);
+        assert_eq!(
+            host.annotations.get(Oui::SOURCE, "vendor").unwrap(),
+            "Apple"
+        );
+        assert_eq!(
+            host.annotations.get(KnownHosts::SOURCE, "label").unwrap(),
+            "printer"
+        );
+
+        state.set_label(host.ipv4, host.mac, "");
+        pipeline.run(&mut host, &EnrichCtx { state: &state });
+        assert_eq!(host.annotations.get(KnownHosts::SOURCE, "label"), None);
+        assert_eq!(host.annotations.iter().count(), 1);
+    }
+
+    #[test]
+    fn oui_reads_both_registry_formats() {
+        let nmap = "# comment\n001B63 Apple\n0050F2 Microsoft\n8C1F6 Too Short\n";
+        let ieee =
+            "OUI/MA-L\n\n00-1B-63   (hex)\t\tApple, Inc.\n001B63     (base 16)\t\tApple, Inc.\n";
+        let vendors = parse_oui(nmap);
+        assert_eq!(vendors.len(), 2);
+        let lookup =
+            |vendors: &VendorTrie, mac| vendors.lookup(mac).map(|(_, vendor)| vendor.to_string());
+        assert_eq!(
+            lookup(&vendors, MacAddr(0x00, 0x50, 0xf2, 1, 2, 3)).unwrap(),
+            "Microsoft"
+        );
+        let vendors = parse_oui(ieee);
+        assert_eq!(
+            lookup(&vendors, MacAddr(0x00, 0x1b, 0x63, 1, 2, 3)).unwrap(),
+            "Apple, Inc."
+        );
+        assert_eq!(vendors.len(), 1);
+    }
+
+    #[test]
+    fn the_longest_prefix_names_the_vendor() {
+        // nmap lists the MA-M and MA-S blocks of a registry block
+        let oui = oui(
+            "70B3D5 IEEE Registration Authority\n70B3D5F Medium\n70B3D5F2A Small\n",
+            &[],
+        );
+        assert_eq!(
+            oui.vendor(MacAddr(0x70, 0xb3, 0xd5, 0x01, 0, 1)),
+            Some("IEEE Registration Authority")
+        );
+        assert_eq!(
+            oui.vendor(MacAddr(0x70, 0xb3, 0xd5, 0xf0, 0, 1)),
+            Some("Medium")
+        );
+        assert_eq!(
+            oui.vendor(MacAddr(0x70, 0xb3, 0xd5, 0xf2, 0xa0, 1)),
+            Some("Small")
+        );
+        assert_eq!(
+            oui.vendor(MacAddr(0x70, 0xb3, 0xd5, 0xf2, 0xb0, 1)),
+            Some("Medium")
+        );
+        assert_eq!(oui.vendor(MacAddr(0x70, 0xb3, 0xd6, 0, 0, 1)), None);
+    }
+
+    #[test]
+    fn overrides_win_at_equal_length_only() {
+        let oui = oui(
+            "001B63 Apple\n70B3D5 IEEE Registration Authority\n70B3D5F2A Small\n",
+            &[
+                ("00:1B:63", "Office Macs"),
+                ("70-B3-D5", "Registry Block"),
+                ("70:B3:D5:F0:00:00/28", "Lab Sensors"),
+                ("02:42:AC", "Docker bridge"),
+            ],
+        );
+        assert_eq!(
+            oui.vendor(MacAddr(0, 0x1b, 0x63, 1, 2, 3)),
+            Some("Office Macs")
+        );
+        assert_eq!(
+            oui.vendor(MacAddr(0x70, 0xb3, 0xd5, 0x01, 0, 1)),
+            Some("Registry Block")
+        );
+        assert_eq!(
+            oui.vendor(MacAddr(0x70, 0xb3, 0xd5, 0xf0, 0, 1)),
+            Some("Lab Sensors")
+        );
+        // the registry's MA-S block is longer than the override's /28
+        assert_eq!(
+            oui.vendor(MacAddr(0x70, 0xb3, 0xd5, 0xf2, 0xa0, 1)),
+            Some("Small")
+        );
+        // overrides name locally administered MACs too
+        assert_eq!(
+            oui.vendor(MacAddr(0x02, 0x42, 0xac, 0x11, 0, 2)),
+            Some("Docker bridge")
+        );
+        assert_eq!(oui.vendor(MacAddr(0x02, 0x42, 0xad, 0x11, 0, 2)), None);
+    }
+
+    #[test]
+    fn prefixes_are_24_28_or_36_bits() {
+        assert_eq!(parse_prefix("00:1b:63").unwrap(), [0, 0, 1, 0xb, 6, 3]);
+        assert_eq!(parse_prefix("001B63A").unwrap().len(), 7);
+        assert_eq!(parse_prefix("70B3.D5F2.A000/36").unwrap().len(), 9);
+        assert_eq!(parse_prefix("00:1B:63:AA:BB:CC/24").unwrap().len(), 6);
+        assert!(parse_prefix("02:42").is_err());
+        assert!(parse_prefix("001B63/32").is_err());
+        assert!(parse_prefix("00:1B:6G").is_err());
+        assert!(parse_prefix("001B63/28").is_err());
+    }
+
+    #[test]
+    fn overrides_read_csv_and_toml() {
+        let csv =
+            "prefix,name\n# lab\n00:1B:63,\"Office Macs, 2nd floor\"\n\n70B3D5F,Lab Sensors\n";
+        assert_eq!(
+            parse_overrides_csv(csv).unwrap(),
+            [
+                ("00:1B:63".to_string(), "Office Macs, 2nd floor".to_string()),
+                ("70B3D5F".to_string(), "Lab Sensors".to_string()),
+            ]
+        );
+        assert!(parse_overrides_csv("00:1B:63\n").is_err());
+
+        let dir = std::env::temp_dir().join(format!("netui-vendors-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("vendors.toml");
+        fs::write(
+            &path,
+            "\"00:1B:63\" = \"Office Macs\"\n\"70B3D5F2A/36\" = \"Tiny\"\n",
+        )
+        .unwrap();
+        let overridden = oui("", &[]).with_overrides(&path).unwrap();
+        assert_eq!(overridden.override_count(), 2);
+        assert_eq!(
+            overridden.vendor(MacAddr(0x70, 0xb3, 0xd5, 0xf2, 0xa0, 1)),
+            Some("Tiny")
+        );
+        fs::write(&path, "\"00:1B\" = \"Short\"\n").unwrap();
+        assert!(oui("", &[]).with_overrides(&path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn randomized_macs_have_no_vendor() {
+        let state = State::default();
+        let oui = oui("021B63 Nobody\n", &[]);
+        let mut notes = BTreeMap::new();
+        oui.enrich(
+            &host(MacAddr(0x02, 0x1b, 0x63, 1, 2, 3)),
+            &EnrichCtx { state: &state },
+            &mut notes,
+        );
+        assert_eq!(notes["vendor"], "locally administered");
+    }
+}
@@ -1,11 +1,17 @@
-use std::time::Duration;
+use std::{
+    net::Ipv4Addr,
+    time::{Duration, Instant},
+};
 
 use crossterm::event::{Event as CrosstermEvent, KeyEvent, MouseEvent};
 use futures::{FutureExt, StreamExt};
+use pnet::ipnetwork::IpNetwork;
 use tokio::sync::mpsc;
 
 use crate::{
     app::{AppResult, Host},
+    interface_info::InterfaceInfo,
+    scanner::SweepSummary,
     stats_aggregator::StatsMap,
 };
 
@@ -26,11 +32,28 @@ pub enum Event {
 
 #[derive(Clone, Debug)]
 pub enum ScannerEvent {
-    HostFound(Host),
-    StatTick(StatsMap),
-    InterfaceName(String),
-    BeginScan,
-    Complete,
+    HostFound(Box<Host>),
+    /// bits counted per flow, the time they were counted over and when they
+    /// were flushed, which can be a while before the app gets to them
+    StatTick(StatsMap, Duration, Instant),
+    Interface(InterfaceInfo),
+    /// a sweep of the network is starting
+    BeginScan(IpNetwork),
+    Complete(SweepSummary),
+    /// a sweep was asked for but the interface has no IPv4 network to sweep
+    NoNetwork,
+    /// something the user should know went wrong, the scanner keeps going
+    Error(String),
+    /// the interface's networks overlap or look misconfigured, sweeps
+    /// probe each address once anyway
+    NetworkWarnings(Vec<String>),
+    /// the resolver found the name of an address
+    Resolved(Ipv4Addr, String),
+    /// a scanner task panicked, what it did is no longer done
+    TaskFailed {
+        task: &'static str,
+        reason: String,
+    },
 }
 
 /// Terminal event handler.
@@ -63,20 +86,30 @@ impl EventHandler {
                     break;
                   }
                   _ = tick_delay => {
-                    sender_clone.send(Event::Tick).unwrap();
+                    if sender_clone.send(Event::Tick).is_err() {
+                      break;
+                    }
                   }
                   Some(Ok(evt)) = crossterm_event => {
                     match evt {
                       CrosstermEvent::Key(key) => {
-                        if key.kind == crossterm::event::KeyEventKind::Press {
-                          sender_clone.send(Event::Key(key)).unwrap();
+                        // releases would trigger every key twice where they
+                        // are reported, repeats are sorted out by the app
+                        if key.kind != crossterm::event::KeyEventKind::Release
+                          && sender_clone.send(Event::Key(key)).is_err()
+                        {
+                          break;
                         }
                       },
                       CrosstermEvent::Mouse(mouse) => {
-                        sender_clone.send(Event::Mouse(mouse)).unwrap();
+                        if sender_clone.send(Event::Mouse(mouse)).is_err() {
+                          break;
+                        }
                       },
                       CrosstermEvent::Resize(x, y) => {
-                        sender_clone.send(Event::Resize(x, y)).unwrap();
+                        if sender_clone.send(Event::Resize(x, y)).is_err() {
+                          break;
+                        }
                       },
                       CrosstermEvent::FocusLost => {
                       },
@@ -96,6 +129,16 @@ impl EventHandler {
         }
     }
 
+    /// Only carries the scanner events, for running without a terminal.
+    pub fn without_terminal() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            sender,
+            receiver,
+            handler: tokio::spawn(async {}),
+        }
+    }
+
     /// Receive the next event from the handler thread.
     ///
     /// This function will always block the current thread if
@@ -104,10 +147,12 @@ impl EventHandler {
         self.receiver
             .recv()
             .await
-            .ok_or(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "This is an IO error",
-            )))
+            .ok_or(Box::new(std::io::Error::other("This is an IO error")))
+    }
+
+    /// Receive an event only if one is already queued.
+    pub fn try_next(&mut self) -> Option<Event> {
+        self.receiver.try_recv().ok()
     }
 
     pub fn get_sender_clone(&self) -> mpsc::UnboundedSender<Event> {
@@ -0,0 +1,172 @@
+use std::{io, path::Path, time::Instant};
+
+use chrono::{DateTime, Local};
+
+use crate::{activity, app::Host, dry_run};
+
+const NMAP_TIME_FORMAT: &str = "%a %b %e %H:%M:%S %Y";
+
+/// Renders the host inventory as an nmap XML document (`-oX` format), so it can be
+/// consumed by tools expecting the result of an `nmap -sn -PR` host discovery.
+/// Hosts silent for a while at `now` are down.
+pub fn nmap_xml(
+    hosts: &[Host],
+    interface: &str,
+    started_at: DateTime<Local>,
+    now: Instant,
+) -> String {
+    let finished_at = Local::now();
+    let up = hosts
+        .iter()
+        .filter(|host| !activity::is_down(host, now))
+        .count();
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        "<!DOCTYPE nmaprun>".to_string(),
+        format!(
+            r#"<nmaprun scanner="netui" args="{}" start="{}" startstr="{}" version="{}" xmloutputversion="1.05">"#,
+            xml_escape(&format!("netui --name {}", interface)),
+            started_at.timestamp(),
+            started_at.format(NMAP_TIME_FORMAT),
+            env!("CARGO_PKG_VERSION"),
+        ),
+    ];
+    for host in hosts {
+        let seen = host.time.timestamp();
+        lines.push(format!(r#"<host starttime="{}" endtime="{}">"#, seen, seen));
+        lines.push(if activity::is_down(host, now) {
+            r#"<status state="down" reason="no-response" reason_ttl="0"/>"#.to_string()
+        } else {
+            r#"<status state="up" reason="arp-response" reason_ttl="0"/>"#.to_string()
+        });
+        lines.push(format!(
+            r#"<address addr="{}" addrtype="ipv4"/>"#,
+            host.ipv4
+        ));
+        lines.push(match host.vendor() {
+            Some(vendor) => format!(
+                r#"<address addr="{}" addrtype="mac" vendor="{}"/>"#,
+                host.mac.to_string().to_uppercase(),
+                xml_escape(vendor)
+            ),
+            None => format!(
+                r#"<address addr="{}" addrtype="mac"/>"#,
+                host.mac.to_string().to_uppercase()
+            ),
+        });
+        lines.push(match &host.hostname {
+            Some(hostname) => format!(
+                r#"<hostnames><hostname name="{}" type="PTR"/></hostnames>"#,
+                xml_escape(hostname)
+            ),
+            None => "<hostnames></hostnames>".to_string(),
+        });
+        if let Some(service) = host.service {
+            lines.push(format!(
+                r#"<hostscript><script id="netui-service" output="{}"/></hostscript>"#,
+                service
+            ));
+        }
+        lines.push("</host>".to_string());
+    }
+    lines.push("<runstats>".to_string());
+    lines.push(format!(
+        r#"<finished time="{}" timestr="{}" elapsed="{}" summary="{}" exit="success"/>"#,
+        finished_at.timestamp(),
+        finished_at.format(NMAP_TIME_FORMAT),
+        (finished_at - started_at).num_seconds(),
+        xml_escape(&format!("netui done; {} hosts up", up)),
+    ));
+    lines.push(format!(
+        r#"<hosts up="{}" down="{}" total="{}"/>"#,
+        up,
+        hosts.len() - up,
+        hosts.len()
+    ));
+    lines.push("</runstats>".to_string());
+    lines.push("</nmaprun>".to_string());
+    lines.join("\n") + "\n"
+}
+
+pub fn write_nmap_xml(
+    path: &Path,
+    hosts: &[Host],
+    interface: &str,
+    started_at: DateTime<Local>,
+    now: Instant,
+) -> io::Result<()> {
+    dry_run::write(path, nmap_xml(hosts, interface, started_at, now))
+}
+
+/// Escapes text for XML and HTML alike.
+pub fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::Ipv4Addr, time::Duration};
+
+    use pnet::util::MacAddr;
+
+    use super::*;
+    use crate::{
+        enrich::{EnrichCtx, Oui, Pipeline},
+        state::State,
+    };
+
+    #[test]
+    fn hosts_carry_their_vendor_and_the_silent_ones_are_down() {
+        let dir = std::env::temp_dir().join(format!("netui-nmap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let overrides = dir.join("vendors.csv");
+        std::fs::write(&overrides, "00:1B:63,Apple & Co\n").unwrap();
+        let pipeline = Pipeline::new().with(Oui::load().with_overrides(&overrides).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let now = Instant::now();
+        let host = |last, quiet: Duration| {
+            let mut host = Host::new(
+                Ipv4Addr::new(192, 168, 1, last),
+                MacAddr(0, 0x1b, 0x63, 0, 0, last),
+                false,
+            );
+            host.seen_at = now - quiet;
+            pipeline.run(
+                &mut host,
+                &EnrichCtx {
+                    state: &State::default(),
+                },
+            );
+            host
+        };
+        let hosts = [
+            host(10, Duration::from_secs(30)),
+            host(20, Duration::from_secs(600)),
+        ];
+        let xml = nmap_xml(&hosts, "eth0", Local::now(), now);
+        assert_eq!(
+            xml.matches(r#"addrtype="mac" vendor="Apple &amp; Co"/>"#)
+                .count(),
+            2
+        );
+        let states: Vec<_> = xml
+            .lines()
+            .filter_map(|line| line.strip_prefix("<status state=\""))
+            .map(|line| line.split('"').next().unwrap())
+            .collect();
+        assert_eq!(states, ["up", "down"]);
+        assert!(xml.contains(r#"<hosts up="1" down="1" total="2"/>"#));
+    }
+}
@@ -0,0 +1,254 @@
+//! How netui writes sizes, speeds and durations, and reads them back from
+//! the config. Scaled values have two decimals, rounded half up, and move to
+//! the next unit once they would round to a full step of the ladder: a
+//! value never shows as `1024.00 KiB`.
+
+use std::time::Duration;
+
+const BYTES: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+const BITS_PER_SEC: [&str; 4] = ["Bit/s", "Kib/s", "Mib/s", "Gib/s"];
+const BYTES_PER_SEC: [&str; 4] = ["B/s", "KiB/s", "MiB/s", "GiB/s"];
+const PACKETS_PER_SEC: [&str; 3] = ["pps", "kpps", "Mpps"];
+
+/// `numerator / denominator` up the ladder of `units`, each `step` times
+/// the one before.
+fn ladder(numerator: u128, mut denominator: u128, step: u128, units: &[&str]) -> String {
+    for (i, unit) in units.iter().enumerate() {
+        let hundredths =
+            numerator.saturating_mul(200).saturating_add(denominator) / (2 * denominator);
+        if hundredths < step * 100 || i + 1 == units.len() {
+            return format!("{}.{:02} {}", hundredths / 100, hundredths % 100, unit);
+        }
+        denominator *= step;
+    }
+    unreachable!("the ladder has units")
+}
+
+/// Compact duration, e.g. `45s`, `3m12s`, `2h05m` or `1d03h`.
+pub fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3_600 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        3_600..86_400 => format!("{}h{:02}m", seconds / 3_600, seconds % 3_600 / 60),
+        _ => format!("{}d{:02}h", seconds / 86_400, seconds % 86_400 / 3_600),
+    }
+}
+
+/// Human readable byte count, e.g. `12.40 MiB`.
+pub fn format_bytes(bytes: u128) -> String {
+    ladder(bytes, 1, 1024, &BYTES)
+}
+
+/// A speed in bits per second as the tables show it, e.g. `1.50 Mib/s`.
+pub fn format_bits_per_sec(bits_per_sec: u128) -> String {
+    ladder(bits_per_sec, 1, 1024, &BITS_PER_SEC)
+}
+
+/// A speed given in bits per second, in bytes, e.g. `192.00 KiB/s`.
+pub fn format_bytes_per_sec(bits_per_sec: u128) -> String {
+    ladder(bits_per_sec, 8, 1024, &BYTES_PER_SEC)
+}
+
+/// A packet rate with decimal units, e.g. `850.00 pps` or `12.50 kpps`.
+pub fn format_pps(packets_per_sec: f64) -> String {
+    let hundredths = (packets_per_sec.max(0.0) * 100.0).round() as u128;
+    ladder(hundredths, 100, 1000, &PACKETS_PER_SEC)
+}
+
+/// A link speed in the unit it is sold in, e.g. `1 Gb/s` or `2.5 Gb/s`:
+/// one decimal, rounded half up, left out when it's 0.
+pub fn format_link_speed(bits_per_sec: u64) -> String {
+    let tenths = |divisor: u128| (u128::from(bits_per_sec) * 20 + divisor) / (2 * divisor);
+    let (tenths, unit) = [(1_000, "kb/s"), (1_000_000, "Mb/s")]
+        .into_iter()
+        .map(|(divisor, unit)| (tenths(divisor), unit))
+        .find(|(tenths, _)| *tenths < 10_000)
+        .unwrap_or((tenths(1_000_000_000), "Gb/s"));
+    match tenths % 10 {
+        0 => format!("{} {}", tenths / 10, unit),
+        decimal => format!("{}.{} {}", tenths / 10, decimal, unit),
+    }
+}
+
+/// The number in front of a unit, and the unit.
+fn split_number(text: &str) -> (&str, &str) {
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    text.split_at(split)
+}
+
+/// Parses a link rate like `1Gbit`, `100 Mbit/s` or `2.5G` into bits per
+/// second, with decimal units as links are sold.
+pub fn parse_rate(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let (number, unit) = split_number(text);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a rate", text))?;
+    let unit = unit.trim().to_ascii_lowercase();
+    let unit = unit
+        .trim_end_matches("/s")
+        .trim_end_matches("ps")
+        .trim_end_matches("bit")
+        .trim_end_matches('b');
+    let multiplier: f64 = match unit {
+        "" => 1.0,
+        "k" => 1e3,
+        "m" => 1e6,
+        "g" => 1e9,
+        "t" => 1e12,
+        unit => return Err(format!("unknown rate unit '{}'", unit)),
+    };
+    let rate = (number * multiplier).round();
+    if rate < 1.0 {
+        return Err(format!("'{}' is not a rate", text));
+    }
+    Ok(rate as u64)
+}
+
+/// Parses a human size like `2GiB`, `500 MB` or `1.5G` into bytes, rounded
+/// to the nearest byte.
+///
+/// Binary and decimal units are both accepted, a bare letter is binary.
+pub fn parse_size(text: &str) -> Result<u128, String> {
+    let text = text.trim();
+    let (number, unit) = split_number(text);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a size", text))?;
+    let multiplier: u128 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        unit => return Err(format!("unknown size unit '{}'", unit)),
+    };
+    Ok((number * multiplier as f64).round() as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+
+    /// `helper<TAB>input<TAB>output` lines, regenerated with
+    /// `NETUI_BLESS=1 cargo test format`.
+    const GOLDEN: &str = include_str!("../testdata/format.golden");
+    const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/format.golden");
+
+    fn run(helper: &str, input: &str) -> String {
+        let number = || input.parse::<u128>().unwrap();
+        match helper {
+            "age" => format_age(Duration::from_secs(number() as u64)),
+            "bytes" => format_bytes(number()),
+            "bits_per_sec" => format_bits_per_sec(number()),
+            "bytes_per_sec" => format_bytes_per_sec(number()),
+            "pps" => format_pps(input.parse().unwrap()),
+            "link_speed" => format_link_speed(number() as u64),
+            "parse_size" => parse_size(input).map_or_else(|e| e, |size| size.to_string()),
+            "parse_rate" => parse_rate(input).map_or_else(|e| e, |rate| rate.to_string()),
+            helper => panic!("unknown helper {}", helper),
+        }
+    }
+
+    #[test]
+    fn the_helpers_match_the_golden_file() {
+        let mut actual = String::new();
+        for line in GOLDEN.lines() {
+            match line.split('\t').collect::<Vec<_>>()[..] {
+                [helper, input, _] if !line.starts_with('#') => {
+                    writeln!(actual, "{}\t{}\t{}", helper, input, run(helper, input)).unwrap();
+                }
+                _ => writeln!(actual, "{}", line).unwrap(),
+            }
+        }
+        if std::env::var_os("NETUI_BLESS").is_some() {
+            std::fs::write(GOLDEN_PATH, &actual).unwrap();
+            return;
+        }
+        let mismatches: Vec<_> = GOLDEN
+            .lines()
+            .zip(actual.lines())
+            .filter(|(expected, actual)| expected != actual)
+            .collect();
+        assert!(mismatches.is_empty(), "{:#?}", mismatches);
+    }
+
+    #[test]
+    fn sizes_read_back_what_they_print() {
+        for unit in 0..BYTES.len() as u32 {
+            let scale = 1024u128.pow(unit);
+            for value in [1, 3, 17, 512, 1000, 1023] {
+                for fraction in [0, 1, 499, 500, 999] {
+                    let bytes = value * scale + fraction * scale / 1000;
+                    let parsed = parse_size(&format_bytes(bytes)).unwrap();
+                    // off by at most half a hundredth of the unit
+                    assert!(
+                        parsed.abs_diff(bytes) * 200 <= scale,
+                        "{} printed as {} read back as {}",
+                        bytes,
+                        format_bytes(bytes),
+                        parsed
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn link_speeds_read_back_what_they_print() {
+        for bits_per_sec in [
+            10_000,
+            100_000_000,
+            1_000_000_000,
+            2_500_000_000,
+            10_000_000_000,
+        ] {
+            assert_eq!(
+                parse_rate(&format_link_speed(bits_per_sec)).unwrap(),
+                bits_per_sec
+            );
+        }
+    }
+
+    #[test]
+    fn speeds_keep_growing_past_u32_max() {
+        let shown = |bits: u128| {
+            let text = format_bits_per_sec(bits);
+            let (number, unit) = text.split_once(' ').unwrap();
+            let scale = BITS_PER_SEC.iter().position(|u| *u == unit).unwrap();
+            number.parse::<f64>().unwrap() * 1024f64.powi(scale as i32)
+        };
+        let around = u128::from(u32::MAX);
+        let mut previous = 0.0;
+        for bits in (around - (1 << 30)..around + (1 << 34)).step_by(1 << 24) {
+            let speed = shown(bits);
+            assert!(
+                speed >= previous,
+                "{} shows as {}",
+                bits,
+                format_bits_per_sec(bits)
+            );
+            previous = speed;
+        }
+        assert_eq!(format_bits_per_sec(around + 1), "4.00 Gib/s");
+        assert_eq!(format_bits_per_sec(around * 3), "12.00 Gib/s");
+    }
+
+    #[test]
+    fn halves_round_up() {
+        // 1.005 KiB and 1.015 KiB, exactly
+        assert_eq!(format_bytes(1024 + 1024 * 5 / 1000 + 1), "1.01 KiB");
+        assert_eq!(ladder(1005, 1000, 1024, &BYTES), "1.01 B");
+        assert_eq!(ladder(1015, 1000, 1024, &BYTES), "1.02 B");
+        assert_eq!(ladder(1025, 1000, 1024, &BYTES), "1.03 B");
+    }
+}
@@ -0,0 +1,486 @@
+use std::{
+    collections::HashMap,
+    io::{self, IsTerminal, Write},
+    net::Ipv4Addr,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use pnet::util::MacAddr;
+use serde::Serialize;
+
+use crate::{
+    app::{App, AppResult, Host},
+    command::Action,
+    event::{Event, EventHandler, ScannerEvent},
+    format::{format_age, format_bits_per_sec, format_bytes, format_bytes_per_sec},
+    scanner::ARP_SWEEP_TASK,
+    snapshot::{to_json, HostSnapshot},
+    stats_aggregator::{Connection, ConnectionSort, Transport},
+    text_table::{Align, TextTable},
+};
+
+/// How `--headless` prints.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// aligned columns, sized to the terminal when there is one
+    #[default]
+    Table,
+    /// one JSON object per line
+    Ndjson,
+}
+
+/// Unit of the printed speeds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Units {
+    /// bits per second, as the TUI shows them
+    #[default]
+    Bits,
+    Bytes,
+}
+
+impl Units {
+    fn rate(self, bits_per_sec: u128) -> u128 {
+        match self {
+            Units::Bits => bits_per_sec,
+            Units::Bytes => bits_per_sec / 8,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Units::Bits => "bit/s",
+            Units::Bytes => "B/s",
+        }
+    }
+
+    /// `12.40 Mib/s` or `1.55 MiB/s`.
+    fn format(self, bits_per_sec: u128) -> String {
+        match self {
+            Units::Bits => format_bits_per_sec(bits_per_sec),
+            Units::Bytes => format_bytes_per_sec(bits_per_sec),
+        }
+    }
+}
+
+/// A line of `--format ndjson`, its `type` names the variant.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Record {
+    Host(HostSnapshot),
+    Connection(ConnectionRecord),
+    Summary {
+        hosts: usize,
+        connections: usize,
+        bytes: u128,
+        seconds: u64,
+    },
+}
+
+/// A connection as `--format ndjson` prints it, speeds in `unit`.
+#[derive(Debug, Serialize)]
+struct ConnectionRecord {
+    time: String,
+    local: String,
+    remote: String,
+    proto: &'static str,
+    broadcast: bool,
+    down: u128,
+    up: u128,
+    unit: &'static str,
+    requests: u128,
+    responses: u128,
+    seconds: u64,
+}
+
+/// What `--headless` prints besides the hosts.
+#[derive(Clone, Copy, Debug)]
+pub struct HeadlessOptions {
+    pub format: OutputFormat,
+    pub units: Units,
+    /// print the connections this often, none to only print the hosts
+    pub watch_connections: Option<Duration>,
+    pub sort: ConnectionSort,
+    /// only the first connections
+    pub top: Option<usize>,
+}
+
+/// Runs without the terminal UI until Ctrl-C, printing the hosts as they
+/// are found and, when watched, the connections every interval. Stops
+/// quietly once stdout is closed, e.g. piped into `head`.
+pub async fn run(
+    app: &mut App,
+    events: &mut EventHandler,
+    options: HeadlessOptions,
+) -> AppResult<()> {
+    let started_at = Instant::now();
+    let mut watch = options.watch_connections.map(|period| {
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        interval
+    });
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+    loop {
+        let output = tokio::select! {
+            _ = &mut interrupted => {
+                let summary = summary(app, started_at.elapsed(), options);
+                return until_stdout_closed(print(&summary));
+            }
+            _ = async { watch.as_mut().unwrap().tick().await }, if watch.is_some() => {
+                let connections = shown_connections(app, options);
+                let now = chrono::Local::now();
+                match options.format {
+                    OutputFormat::Table => {
+                        let header = format!(
+                            "# {} {} connections",
+                            now.format("%H:%M:%S"),
+                            connections.len()
+                        );
+                        let table = connections_table(&connections, options.units)
+                            .render(output_width());
+                        format!("{}\n{}\n", header, table)
+                    }
+                    OutputFormat::Ndjson => connections
+                        .iter()
+                        .map(|connection| connection_json(connection, &now.to_rfc3339(), options.units) + "\n")
+                        .collect(),
+                }
+            }
+            event = events.next() => {
+                let Event::Scanner(worker_event) = event? else {
+                    continue;
+                };
+                let found = match &worker_event {
+                    ScannerEvent::HostFound(host) if !app.hosts.iter().any(|h| *h == **host) => {
+                        Some((host.ipv4, host.mac))
+                    }
+                    _ => None,
+                };
+                app.handle_worker_events(worker_event)?;
+                let Some(host) = found.and_then(|(ip, mac)| {
+                    app.hosts.iter().find(|h| h.ipv4 == ip && h.mac == mac)
+                }) else {
+                    continue;
+                };
+                host_line(app, host, options.format)
+            }
+        };
+        if let Err(e) = print(&output) {
+            return until_stdout_closed(Err(e));
+        }
+    }
+}
+
+/// How long `--oneshot` waits.
+#[derive(Clone, Copy, Debug)]
+pub struct OneshotOptions {
+    /// for the late replies, once the last request is sent
+    pub grace: Duration,
+    /// the whole run, sweep included
+    pub timeout: Duration,
+}
+
+/// when each host was first seen during the sweep
+type FirstSeen = HashMap<(Ipv4Addr, MacAddr), DateTime<Local>>;
+
+/// Sweeps every network of the interface once, waits for the late replies,
+/// then prints the hosts found as a JSON array. Past the timeout the hosts
+/// found so far are printed and it fails.
+pub async fn oneshot(
+    app: &mut App,
+    events: &mut EventHandler,
+    options: OneshotOptions,
+) -> AppResult<()> {
+    let (first_seen, timed_out) = sweep_once(app, events, options).await?;
+    until_stdout_closed(print(&hosts_json(app, &first_seen)))?;
+    if timed_out {
+        return Err(format!(
+            "the sweep didn't end within {}s, the hosts found so far were printed",
+            options.timeout.as_secs()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Runs the sweep and its grace period, true when the timeout cut it.
+async fn sweep_once(
+    app: &mut App,
+    events: &mut EventHandler,
+    options: OneshotOptions,
+) -> AppResult<(FirstSeen, bool)> {
+    let deadline = tokio::time::Instant::now() + options.timeout;
+    let mut sweeps = app.network_plan().scan.len();
+    if sweeps == 0 {
+        return Err(format!("no IPv4 network on {}, nothing to sweep", app.interface).into());
+    }
+    if app.network_plan().needs_confirmation() && !app.force_large_scan {
+        return Err(
+            "the networks are larger than a /20; pass --force-large-scan to sweep them".into(),
+        );
+    }
+    let mut first_seen = FirstSeen::new();
+    let mut grace_ends = None;
+    app.perform(Action::Scan);
+    loop {
+        let until = grace_ends.unwrap_or(deadline).min(deadline);
+        let Ok(event) = tokio::time::timeout_at(until, events.next()).await else {
+            let timed_out = grace_ends.is_none_or(|grace_ends| grace_ends > deadline);
+            return Ok((first_seen, timed_out));
+        };
+        let Event::Scanner(worker_event) = event? else {
+            continue;
+        };
+        match &worker_event {
+            ScannerEvent::HostFound(host) => {
+                first_seen.entry((host.ipv4, host.mac)).or_insert(host.time);
+            }
+            ScannerEvent::Complete(_) => {
+                sweeps = sweeps.saturating_sub(1);
+                if sweeps == 0 {
+                    grace_ends = Some(tokio::time::Instant::now() + options.grace);
+                }
+            }
+            ScannerEvent::TaskFailed { task, reason } if *task == ARP_SWEEP_TASK => {
+                return Err(format!("{} stopped: {}", task, reason).into());
+            }
+            _ => {}
+        }
+        app.handle_worker_events(worker_event)?;
+    }
+}
+
+/// The hosts by address, one JSON object a line inside the array.
+fn hosts_json(app: &App, first_seen: &FirstSeen) -> String {
+    let mut hosts: Vec<&Host> = app.hosts.iter().collect();
+    hosts.sort_by_key(|host| host.ipv4);
+    let objects: Vec<String> = hosts
+        .iter()
+        .map(|host| {
+            let mut snapshot = HostSnapshot::new(host, app.mac_format, &app.daily_usage);
+            snapshot.first_seen =
+                Some(*first_seen.get(&(host.ipv4, host.mac)).unwrap_or(&host.time));
+            format!("  {}", to_json(&snapshot))
+        })
+        .collect();
+    if objects.is_empty() {
+        return "[]\n".to_string();
+    }
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+/// The connections as the connections view lists them, cut to `--top`.
+fn shown_connections(app: &App, options: HeadlessOptions) -> Vec<Connection> {
+    let mut connections = app.stats_aggregator.connections(options.sort);
+    if let Some(top) = options.top {
+        connections.truncate(top);
+    }
+    connections
+}
+
+fn host_line(app: &App, host: &Host, format: OutputFormat) -> String {
+    let mac = app.mac_format.format(host.mac);
+    match format {
+        OutputFormat::Table => {
+            let name = host.label.as_ref().or(host.hostname.as_ref());
+            match name {
+                Some(name) => format!("host {} {} {}\n", host.ipv4, mac, name),
+                None => format!("host {} {}\n", host.ipv4, mac),
+            }
+        }
+        OutputFormat::Ndjson => {
+            let host = HostSnapshot::new(host, app.mac_format, &app.daily_usage);
+            to_json(&Record::Host(host)) + "\n"
+        }
+    }
+}
+
+fn summary(app: &App, elapsed: Duration, options: HeadlessOptions) -> String {
+    let connections = app.stats_aggregator.connections(options.sort);
+    let bytes: u128 = connections.iter().map(|connection| connection.bytes).sum();
+    match options.format {
+        OutputFormat::Table => format!(
+            "# {} hosts, {} connections, {} over {}\n",
+            app.hosts.len(),
+            connections.len(),
+            format_bytes(bytes),
+            format_age(elapsed)
+        ),
+        OutputFormat::Ndjson => {
+            let summary = Record::Summary {
+                hosts: app.hosts.len(),
+                connections: connections.len(),
+                bytes,
+                seconds: elapsed.as_secs(),
+            };
+            to_json(&summary) + "\n"
+        }
+    }
+}
+
+fn endpoint((ip, port): (Ipv4Addr, u16)) -> String {
+    format!("{}:{}", ip, port)
+}
+
+fn transport(connection: &Connection) -> &'static str {
+    match connection.transport {
+        Transport::Tcp => "tcp",
+        Transport::Udp => "udp",
+    }
+}
+
+fn connections_table(connections: &[Connection], units: Units) -> TextTable {
+    let mut table = TextTable::new(&[
+        ("Local", Align::Left, true),
+        ("Remote", Align::Left, true),
+        ("Proto", Align::Left, false),
+        ("Down", Align::Right, false),
+        ("Up", Align::Right, false),
+        ("Client→Server", Align::Right, false),
+        ("Server→Client", Align::Right, false),
+        ("Duration", Align::Right, false),
+    ]);
+    for connection in connections {
+        let remote = if connection.broadcast {
+            format!("{} (broadcast)", endpoint(connection.remote))
+        } else {
+            endpoint(connection.remote)
+        };
+        table.push(vec![
+            endpoint(connection.local),
+            remote,
+            transport(connection).to_string(),
+            units.format(connection.speed.input()),
+            units.format(connection.speed.output()),
+            format_bytes(connection.requests),
+            format_bytes(connection.responses),
+            format_age(connection.age),
+        ]);
+    }
+    table
+}
+
+fn connection_json(connection: &Connection, time: &str, units: Units) -> String {
+    to_json(&Record::Connection(ConnectionRecord {
+        time: time.to_string(),
+        local: endpoint(connection.local),
+        remote: endpoint(connection.remote),
+        proto: transport(connection),
+        broadcast: connection.broadcast,
+        down: units.rate(connection.speed.input()),
+        up: units.rate(connection.speed.output()),
+        unit: units.label(),
+        requests: connection.requests,
+        responses: connection.responses,
+        seconds: connection.age.as_secs(),
+    }))
+}
+
+/// The terminal's width, `COLUMNS` when piped, no limit without either so
+/// `grep` sees whole rows.
+fn output_width() -> Option<usize> {
+    if io::stdout().is_terminal() {
+        if let Ok((columns, _)) = crossterm::terminal::size() {
+            return Some(columns as usize);
+        }
+    }
+    std::env::var("COLUMNS").ok()?.parse().ok()
+}
+
+/// Writes and flushes, every block shows up at once down a pipe.
+fn print(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
+
+/// A closed stdout ends the run like Ctrl-C, other errors are reported.
+fn until_stdout_closed(result: io::Result<()>) -> AppResult<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        smoke::{scanner_on, Lan},
+        stats_aggregator::{FlowScope, Speed},
+    };
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn oneshot_finds_the_lan_and_waits_out_the_grace() {
+        let lan = Arc::new(Lan::default());
+        let mut events = EventHandler::without_terminal();
+        let mut app = App::new(scanner_on(&lan, events.get_sender_clone())).unwrap();
+        app.set_scan_delay(Duration::ZERO);
+        let options = OneshotOptions {
+            grace: Duration::from_millis(200),
+            timeout: Duration::from_secs(5),
+        };
+        let (first_seen, timed_out) = sweep_once(&mut app, &mut events, options).await.unwrap();
+        assert!(!timed_out);
+        let json = hosts_json(&app, &first_seen);
+        assert!(json.starts_with("[\n  {\"ip\":\"192.168.1."), "{}", json);
+        assert_eq!(json.lines().count(), app.hosts.len() + 2);
+        assert!(json.contains(r#""is_my_device":false,"#));
+        assert_eq!(json.matches(r#""first_seen":""#).count(), app.hosts.len());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn oneshot_stops_at_the_timeout() {
+        let lan = Arc::new(Lan::default());
+        let mut events = EventHandler::without_terminal();
+        let mut app = App::new(scanner_on(&lan, events.get_sender_clone())).unwrap();
+        // far too slow to sweep the /29 in time
+        app.set_scan_delay(Duration::from_secs(1));
+        let options = OneshotOptions {
+            grace: Duration::from_secs(2),
+            timeout: Duration::from_millis(300),
+        };
+        let (_, timed_out) = sweep_once(&mut app, &mut events, options).await.unwrap();
+        assert!(timed_out);
+    }
+
+    #[test]
+    fn speeds_follow_the_units() {
+        assert_eq!(Units::Bits.format(1024 * 1024), "1.00 Mib/s");
+        assert_eq!(Units::Bytes.format(8 * 1024), "1.00 KiB/s");
+        assert_eq!(
+            Units::Bytes.format(8 * 1024 * 1024 * 1024 * 2048),
+            "2048.00 GiB/s"
+        );
+        assert_eq!(Units::Bytes.rate(800), 100);
+    }
+
+    #[test]
+    fn a_connection_is_one_json_line() {
+        let connection = Connection {
+            local: (Ipv4Addr::new(192, 168, 1, 20), 51234),
+            remote: (Ipv4Addr::new(93, 184, 216, 34), 443),
+            transport: Transport::Tcp,
+            scope: FlowScope::External,
+            network: None,
+            broadcast: false,
+            speed: Speed::default(),
+            bytes: 3000,
+            server_is_local: false,
+            requests: 1000,
+            responses: 2000,
+            age: Duration::from_secs(75),
+        };
+        let json = connection_json(&connection, "2026-01-02T03:04:05+00:00", Units::Bytes);
+        assert!(!json.contains('\n'));
+        assert_eq!(
+            json,
+            r#"{"type":"connection","time":"2026-01-02T03:04:05+00:00","local":"192.168.1.20:51234","remote":"93.184.216.34:443","proto":"tcp","broadcast":false,"down":0,"up":0,"unit":"B/s","requests":1000,"responses":2000,"seconds":75}"#
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crossterm::event::KeyCode;
+use unicode_width::UnicodeWidthStr;
+
+use crate::strings;
+
+/// hints a view or popup shows at most, `?` lists the rest
+pub const MAX_HINTS: usize = 5;
+
+/// A key and what it does where the user is, for the hint line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hint {
+    /// keys doing the same thing, e.g. `j` and `k`
+    pub keys: &'static [KeyCode],
+    /// strings key of the action
+    pub action: &'static str,
+}
+
+impl Hint {
+    pub const fn new(keys: &'static [KeyCode], action: &'static str) -> Self {
+        Self { keys, action }
+    }
+
+    /// `(Enter) details`.
+    pub fn label(&self) -> String {
+        let keys: Vec<String> = self.keys.iter().map(|key| key_name(*key)).collect();
+        format!("({}) {}", keys.join("/"), strings::text(self.action))
+    }
+}
+
+/// How a key reads in the hints and the help.
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::BackTab => "Shift-Tab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        key => format!("{:?}", key),
+    }
+}
+
+/// The first `MAX_HINTS` of `hints` that fit in `width` columns, ending with
+/// `… ? for more` when some had to be left out.
+pub fn line(hints: &[Hint], width: usize) -> String {
+    let labels: Vec<String> = hints.iter().take(MAX_HINTS).map(Hint::label).collect();
+    let all = labels.join(" | ");
+    if all.width() <= width {
+        return all;
+    }
+    let more = strings::text("hints.more");
+    (0..labels.len())
+        .rev()
+        .map(|shown| match shown {
+            0 => more.to_string(),
+            shown => format!("{} | {}", labels[..shown].join(" | "), more),
+        })
+        .find(|line| line.width() <= width)
+        .unwrap_or_else(|| more.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HINTS: &[Hint] = &[
+        Hint::new(&[KeyCode::Char('s')], "hint.sweep"),
+        Hint::new(&[KeyCode::Enter], "hint.details"),
+        Hint::new(&[KeyCode::Char('j'), KeyCode::Char('k')], "hint.move"),
+    ];
+
+    #[test]
+    fn every_hint_shows_when_it_fits() {
+        assert_eq!(line(HINTS, 80), "(s) sweep | (Enter) details | (j/k) move");
+    }
+
+    #[test]
+    fn narrow_terminals_drop_the_last_hints() {
+        assert_eq!(line(HINTS, 39), "(s) sweep | … ? for more");
+        assert_eq!(line(HINTS, 20), "… ? for more");
+    }
+
+    #[test]
+    fn keys_have_display_names() {
+        assert_eq!(key_name(KeyCode::Char(' ')), "Space");
+        assert_eq!(key_name(KeyCode::Esc), "Esc");
+        assert_eq!(key_name(KeyCode::F(5)), "F5");
+        assert_eq!(key_name(KeyCode::Char('?')), "?");
+    }
+}
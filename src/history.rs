@@ -0,0 +1,318 @@
+use std::{collections::HashMap, net::Ipv4Addr, time::Duration};
+
+use ringbuf::{
+    traits::{Consumer, Observer, RingBuffer},
+    HeapRb,
+};
+
+use crate::stats_aggregator::Speed;
+
+/// one sample per stat tick
+const FINE_RESOLUTION: Duration = Duration::from_secs(1);
+/// 5 minutes at full resolution
+const FINE_SAMPLES: usize = 300;
+/// fine samples averaged into one coarse sample
+const DOWNSAMPLE_FACTOR: usize = 10;
+/// 1 hour at 10s resolution
+const COARSE_SAMPLES: usize = 360;
+/// hosts with their own history unless configured, the least recently
+/// active ones are dropped first
+const MAX_HOST_HISTORIES: usize = 256;
+
+/// Speed samples over a long period: the last minutes at tick resolution and
+/// older ones downsampled, so the memory used by a history is fixed.
+pub struct SpeedHistory {
+    fine: HeapRb<Speed>,
+    coarse: HeapRb<Speed>,
+    /// fine samples not yet folded into a coarse one
+    pending: (Speed, usize),
+}
+
+impl SpeedHistory {
+    pub fn new() -> Self {
+        Self {
+            fine: HeapRb::new(FINE_SAMPLES),
+            coarse: HeapRb::new(COARSE_SAMPLES),
+            pending: (Speed::default(), 0),
+        }
+    }
+
+    pub fn push(&mut self, speed: Speed) {
+        self.fine.push_overwrite(speed);
+        self.pending.0 += speed;
+        self.pending.1 += 1;
+        if self.pending.1 == DOWNSAMPLE_FACTOR {
+            self.coarse
+                .push_overwrite(self.pending.0 / DOWNSAMPLE_FACTOR as u128);
+            self.pending = (Speed::default(), 0);
+        }
+    }
+
+    /// Samples covering the last `duration`, oldest first.
+    ///
+    /// Durations within the fine retention are returned at tick resolution,
+    /// longer ones at the downsampled resolution.
+    pub fn samples(&self, duration: Duration) -> Vec<Speed> {
+        let fine_count = Self::sample_count(duration, FINE_RESOLUTION);
+        if fine_count <= FINE_SAMPLES {
+            let skip = self.fine.occupied_len().saturating_sub(fine_count);
+            return self.fine.iter().skip(skip).copied().collect();
+        }
+        let coarse_count = Self::sample_count(duration, FINE_RESOLUTION * DOWNSAMPLE_FACTOR as u32);
+        let skip = self.coarse.occupied_len().saturating_sub(coarse_count);
+        self.coarse.iter().skip(skip).copied().collect()
+    }
+
+    /// Mean speed over the last `duration`, `None` until the history reaches
+    /// that far back: a partial window would understate the average.
+    pub fn average(&self, duration: Duration) -> Option<Speed> {
+        if self.span() < duration {
+            return None;
+        }
+        let samples = self.samples(duration);
+        if samples.is_empty() {
+            return None;
+        }
+        let sum = samples.iter().fold(Speed::default(), |a, b| a + *b);
+        Some(sum / samples.len() as u128)
+    }
+
+    /// How long back this history reaches.
+    pub fn span(&self) -> Duration {
+        let fine = FINE_RESOLUTION * self.fine.occupied_len() as u32;
+        let coarse = FINE_RESOLUTION
+            * (self.coarse.occupied_len() * DOWNSAMPLE_FACTOR + self.pending.1) as u32;
+        fine.max(coarse)
+    }
+
+    fn sample_count(duration: Duration, resolution: Duration) -> usize {
+        duration.as_millis().div_ceil(resolution.as_millis()) as usize
+    }
+}
+
+impl Default for SpeedHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Long term speed history of the total traffic and of every host.
+pub struct HistoryStore {
+    total: SpeedHistory,
+    hosts: HashMap<Ipv4Addr, HostHistory>,
+    max_hosts: usize,
+    ticks: u64,
+}
+
+impl Default for HistoryStore {
+    fn default() -> Self {
+        Self {
+            total: Default::default(),
+            hosts: Default::default(),
+            max_hosts: MAX_HOST_HISTORIES,
+            ticks: 0,
+        }
+    }
+}
+
+struct HostHistory {
+    history: SpeedHistory,
+    last_active_tick: u64,
+}
+
+impl HistoryStore {
+    /// Records one tick: the total speed and the speed of every host seen in it.
+    ///
+    /// Tracked hosts missing from `per_host` get a zero sample so all histories
+    /// stay aligned on the same timeline.
+    pub fn record(&mut self, total: Speed, per_host: &HashMap<Ipv4Addr, Speed>) {
+        self.ticks += 1;
+        self.total.push(total);
+        for (ip, speed) in per_host {
+            let host = self.hosts.entry(*ip).or_insert_with(|| HostHistory {
+                history: SpeedHistory::new(),
+                last_active_tick: 0,
+            });
+            host.last_active_tick = self.ticks;
+            host.history.push(*speed);
+        }
+        let ticks = self.ticks;
+        self.hosts
+            .values_mut()
+            .filter(|host| host.last_active_tick != ticks)
+            .for_each(|host| host.history.push(Speed::default()));
+        self.evict_idle_hosts();
+    }
+
+    pub fn total(&self) -> &SpeedHistory {
+        &self.total
+    }
+
+    /// Total speed averaged over each of `windows`, like load averages.
+    pub fn averages(&self, windows: &[Duration]) -> Vec<Option<Speed>> {
+        windows
+            .iter()
+            .map(|window| self.total.average(*window))
+            .collect()
+    }
+
+    /// Caps the hosts with a history, the idlest go first.
+    pub fn set_max_hosts(&mut self, max_hosts: usize) {
+        self.max_hosts = max_hosts;
+        self.evict_idle_hosts();
+    }
+
+    pub fn host_count(&self) -> usize {
+        self.hosts.len()
+    }
+
+    pub fn max_hosts(&self) -> usize {
+        self.max_hosts
+    }
+
+    pub fn host(&self, ip: &Ipv4Addr) -> Option<&SpeedHistory> {
+        self.hosts.get(ip).map(|host| &host.history)
+    }
+
+    fn evict_idle_hosts(&mut self) {
+        while self.hosts.len() > self.max_hosts {
+            let Some(idlest) = self
+                .hosts
+                .iter()
+                .min_by_key(|(_, host)| host.last_active_tick)
+                .map(|(ip, _)| *ip)
+            else {
+                return;
+            };
+            self.hosts.remove(&idlest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(samples: &[Speed]) -> Vec<u128> {
+        samples.iter().map(Speed::input).collect()
+    }
+
+    #[test]
+    fn ten_ticks_fold_into_one_coarse_sample() {
+        let mut history = SpeedHistory::new();
+        for i in 1..=25 {
+            history.push(Speed::new(i * 10, i));
+        }
+        // within the fine retention, one sample a tick
+        assert_eq!(
+            inputs(&history.samples(Duration::from_secs(3))),
+            [230, 240, 250]
+        );
+        assert_eq!(history.samples(Duration::from_millis(2500)).len(), 3);
+        // beyond, the mean of each ten ticks, the last five still pending
+        let coarse = history.samples(Duration::from_secs(20 * 60));
+        assert_eq!(inputs(&coarse), [55, 155]);
+        assert_eq!(coarse[1].output(), 15);
+        assert_eq!(history.span(), Duration::from_secs(25));
+    }
+
+    #[test]
+    fn an_average_needs_the_whole_window() {
+        let mut history = SpeedHistory::new();
+        for i in 1..=4 {
+            history.push(Speed::new(i * 100, 0));
+        }
+        assert!(history.average(Duration::from_secs(5)).is_none());
+        assert_eq!(
+            history.average(Duration::from_secs(2)).unwrap().input(),
+            350
+        );
+        assert_eq!(
+            history.average(Duration::from_secs(4)).unwrap().input(),
+            250
+        );
+    }
+
+    #[test]
+    fn a_long_session_keeps_a_bounded_history() {
+        let mut history = SpeedHistory::new();
+        for i in 0..5 * 3600 {
+            history.push(Speed::new(i, 0));
+        }
+        assert_eq!(history.fine.occupied_len(), FINE_SAMPLES);
+        assert_eq!(history.coarse.occupied_len(), COARSE_SAMPLES);
+        assert_eq!(history.span(), Duration::from_secs(3600));
+        let hour = history.samples(Duration::from_secs(3600));
+        assert_eq!(hour.len(), COARSE_SAMPLES);
+        // the newest coarse sample, ticks 17990 to 17999
+        assert_eq!(hour.last().unwrap().input(), 17994);
+        assert_eq!(
+            history.samples(Duration::from_secs(5 * 3600)).len(),
+            COARSE_SAMPLES
+        );
+    }
+
+    #[test]
+    fn load_averages_show_once_their_window_is_covered() {
+        const WINDOWS: [Duration; 3] = [
+            Duration::from_secs(60),
+            Duration::from_secs(5 * 60),
+            Duration::from_secs(15 * 60),
+        ];
+        let averaged = |store: &HistoryStore| {
+            store
+                .averages(&WINDOWS)
+                .iter()
+                .map(|average| average.map(|speed| speed.input()))
+                .collect::<Vec<_>>()
+        };
+        let mut store = HistoryStore::default();
+        let record = |store: &mut HistoryStore, ticks, speed| {
+            for _ in 0..ticks {
+                store.record(speed, &HashMap::new());
+            }
+        };
+        // cold start
+        record(&mut store, 59, Speed::new(8000, 800));
+        assert_eq!(averaged(&store), [None, None, None]);
+        record(&mut store, 1, Speed::new(8000, 800));
+        assert_eq!(averaged(&store), [Some(8000), None, None]);
+        record(&mut store, 240, Speed::new(8000, 800));
+        assert_eq!(averaged(&store), [Some(8000), Some(8000), None]);
+        record(&mut store, 480, Speed::new(8000, 800));
+        assert_eq!(averaged(&store), [Some(8000), Some(8000), None]);
+        // the link goes down for two minutes, its ticks count as idle
+        record(&mut store, 120, Speed::default());
+        assert_eq!(averaged(&store), [Some(0), Some(4800), Some(6933)]);
+        record(&mut store, 60, Speed::default());
+        // 72 of the last 90 coarse samples were busy
+        assert_eq!(averaged(&store), [Some(0), Some(3200), Some(6400)]);
+        // back up, the short window recovers first
+        record(&mut store, 60, Speed::new(8000, 800));
+        assert_eq!(averaged(&store), [Some(8000), Some(3200), Some(6400)]);
+        assert_eq!(store.averages(&WINDOWS)[2].unwrap().output(), 640);
+    }
+
+    #[test]
+    fn the_idlest_hosts_lose_their_history_past_the_cap() {
+        let host = |last| Ipv4Addr::new(10, 0, 0, last);
+        let mut store = HistoryStore::default();
+        store.set_max_hosts(2);
+        for last in 1..=3 {
+            let per_host = HashMap::from([(host(last), Speed::new(8, 8))]);
+            store.record(Speed::new(8, 8), &per_host);
+        }
+        assert_eq!(store.host_count(), 2);
+        assert!(store.host(&host(1)).is_none());
+        // .2 got a zero sample for the tick it was quiet
+        let samples = store
+            .host(&host(2))
+            .unwrap()
+            .samples(Duration::from_secs(2));
+        assert_eq!(inputs(&samples), [8, 0]);
+        assert_eq!(store.total().span(), Duration::from_secs(3));
+        store.set_max_hosts(1);
+        assert_eq!(store.host_count(), 1);
+        assert!(store.host(&host(3)).is_some());
+    }
+}
@@ -13,81 +13,201 @@
 //! [examples]: https://github.com/ratatui/ratatui/blob/main/examples
 //! [examples readme]: https://github.com/ratatui/ratatui/blob/main/examples/README.md
 
-use chrono::{Duration, Local};
+use std::{collections::BTreeMap, net::Ipv4Addr, time::Instant};
+
 use ratatui::{
     layout::{Constraint, Layout, Margin, Rect},
-    style::{self, Color, Modifier, Style, Stylize},
-    text::Text,
+    text::{Line, Span, Text},
     widgets::{
-        Block, BorderType, Cell, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation,
-        ScrollbarState, Table, TableState,
+        Cell, HighlightSpacing, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table,
+        TableState,
     },
     Frame,
 };
-use style::palette::tailwind;
 
-use crate::app::Host;
+use unicode_width::UnicodeWidthStr;
 
-const PALETTES: [tailwind::Palette; 4] = [
-    tailwind::BLUE,
-    tailwind::EMERALD,
-    tailwind::INDIGO,
-    tailwind::RED,
-];
-const INFO_TEXT: [&str; 2] = [
-    "(q) quit | (k) move up | (j) move down | (h) move left | (l) move right",
-    "(s) send ARP requests | (c) clean current and older hosts",
-];
+use crate::{
+    activity::ActivityBucket,
+    app::Host,
+    mac_format::MacFormat,
+    stats_aggregator::{Speed, SpeedSource, Trend},
+    theme::Theme,
+};
+
+/// after the address of active and silent hosts with `Theme::text_markers`
+const ACTIVE_MARKER: &str = " [ON]";
+const SILENT_MARKER: &str = " [OFF]";
+/// before the label of the hosts watched with `b`
+const WATCH_MARKER: &str = "◉ ";
+const WATCH_TEXT_MARKER: &str = "[WATCH] ";
+
+/// speed cells of hosts never heard from
+const NO_DATA: &str = "—";
 
-struct TableColors {
-    buffer_bg: Color,
-    header_bg: Color,
-    header_fg: Color,
-    row_fg: Color,
-    selected_row_style_fg: Color,
-    selected_column_style_fg: Color,
-    selected_cell_style_fg: Color,
-    normal_row_color: Color,
-    alt_row_color: Color,
-    help_border_color: Color,
+/// Rows whose speed columns don't read as a plain host's download/upload.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RowRole {
+    Host,
+    /// columns are from this machine's point of view
+    ThisDevice,
+    /// columns show the traffic the whole LAN exchanges with off-subnet addresses
+    Gateway,
 }
 
-impl TableColors {
-    const fn new(color: &tailwind::Palette) -> Self {
-        Self {
-            buffer_bg: tailwind::SLATE.c950,
-            header_bg: color.c900,
-            header_fg: tailwind::SLATE.c200,
-            row_fg: tailwind::SLATE.c200,
-            selected_row_style_fg: color.c400,
-            selected_column_style_fg: color.c400,
-            selected_cell_style_fg: color.c600,
-            normal_row_color: tailwind::SLATE.c950,
-            alt_row_color: tailwind::SLATE.c900,
-            help_border_color: color.c400,
+impl RowRole {
+    fn speed_headers(self) -> [&'static str; 2] {
+        match self {
+            RowRole::Host => ["Speed ↓", "Speed ↑"],
+            RowRole::ThisDevice => ["Received ↓", "Sent ↑"],
+            RowRole::Gateway => ["WAN→LAN ↓", "LAN→WAN ↑"],
+        }
+    }
+
+    /// Both speed headers in one, for the combined column.
+    fn combined_speed_header(self) -> &'static str {
+        match self {
+            RowRole::Host => "Speed",
+            RowRole::ThisDevice => "Received | Sent",
+            RowRole::Gateway => "WAN→LAN | LAN→WAN",
         }
     }
 }
 
+/// in front of the selected row, the totals row is indented the same
+const HIGHLIGHT_SYMBOL: &str = " ━ ";
+
+/// IP, MAC, vendor, label, activity and time, around the speed columns
+const FIXED_COLUMNS: usize = 6;
+/// the names column widths are saved under, with both speed columns or one
+const COLUMN_KEYS: [&str; 8] = [
+    "ip", "mac", "vendor", "label", "down", "up", "activity", "time",
+];
+const COMBINED_COLUMN_KEYS: [&str; 7] =
+    ["ip", "mac", "vendor", "label", "speed", "activity", "time"];
+/// the vendor column starts this wide at most, `>` widens it
+const MAX_VENDOR_WIDTH: u16 = 20;
+/// in the vendor column, for prefixes no registry knows
+const UNKNOWN_VENDOR: &str = "Unknown";
+/// first cell of the totals row, the IP column is kept that wide
+const TOTAL_LABEL: &str = "Total";
+/// narrowest a column gets, whether the user shrinks it or the others grow
+pub const MIN_COLUMN_WIDTH: u16 = 3;
+
 pub struct HostsTable<'a> {
-    items: &'a Vec<Host>,
-    longest_item_lens: (u16, u16, u16, u16, u16), // order is (name, address, email)
-    colors: TableColors,
-    color_index: usize,
+    items: Vec<&'a Host>,
+    longest_item_lens: (u16, u16, u16, u16, u16, u16, u16, u16, u16), // order is (ip, mac, label, down, up, combined, activity, time, vendor)
+    theme: &'a Theme,
+    /// the "ago" column is relative to this instant
+    now: Instant,
+    time_frozen: bool,
+    gateway: Option<Ipv4Addr>,
+    wan_speed: Option<Speed>,
+    mac_format: MacFormat,
+    speed_source: SpeedSource,
+    /// one speed column instead of two, for narrow terminals
+    combined_speed: bool,
+    /// the bucket `items` were filtered to, named in the header
+    activity_filter: Option<ActivityBucket>,
+    /// widths the user gave columns, by `column_key`
+    column_widths: BTreeMap<String, u16>,
 }
 
 impl<'a> HostsTable<'a> {
-    pub fn new(data_vec: &'a Vec<Host>) -> Self {
+    pub fn new(data_vec: Vec<&'a Host>, theme: &'a Theme, now: Instant, time_frozen: bool) -> Self {
+        let mut longest_item_lens = Self::constraint_len_calculator(&data_vec);
+        longest_item_lens.0 += theme.marker(SILENT_MARKER).len() as u16;
         Self {
-            longest_item_lens: Self::constraint_len_calculator(data_vec),
-            colors: TableColors::new(&PALETTES[0]),
-            color_index: 0,
+            longest_item_lens,
+            theme,
             items: data_vec,
+            now,
+            time_frozen,
+            gateway: None,
+            wan_speed: None,
+            mac_format: MacFormat::default(),
+            speed_source: SpeedSource::default(),
+            combined_speed: false,
+            activity_filter: None,
+            column_widths: BTreeMap::new(),
+        }
+    }
+
+    pub fn mac_format(mut self, mac_format: MacFormat) -> Self {
+        self.mac_format = mac_format;
+        self.longest_item_lens.1 = self
+            .items
+            .iter()
+            .map(|h| mac_format.format(h.mac).len())
+            .max()
+            .unwrap_or(0) as u16;
+        self
+    }
+
+    /// Shows the LAN's off-subnet traffic on the gateway's row.
+    pub fn gateway(mut self, gateway: Option<Ipv4Addr>, wan_speed: Option<Speed>) -> Self {
+        self.gateway = gateway;
+        self.wan_speed = wan_speed;
+        self
+    }
+
+    /// Which per-host traffic the speed columns show.
+    pub fn speed_source(mut self, speed_source: SpeedSource) -> Self {
+        self.speed_source = speed_source;
+        self
+    }
+
+    /// Shows download and upload in a single column.
+    pub fn combined_speed(mut self, combined_speed: bool) -> Self {
+        self.combined_speed = combined_speed;
+        self
+    }
+
+    /// Names the bucket the hosts were filtered to.
+    pub fn activity_filter(mut self, filter: Option<ActivityBucket>) -> Self {
+        self.activity_filter = filter;
+        self
+    }
+
+    /// Uses these widths instead of the computed ones for their columns.
+    pub fn column_widths(mut self, widths: &BTreeMap<String, u16>) -> Self {
+        self.column_widths = widths.clone();
+        self
+    }
+
+    /// Number of columns the table has, the speeds take one or two.
+    pub fn column_count(combined_speed: bool) -> usize {
+        FIXED_COLUMNS + if combined_speed { 1 } else { 2 }
+    }
+
+    /// The name the width of `column` is saved under.
+    pub fn column_key(column: usize, combined_speed: bool) -> Option<&'static str> {
+        if combined_speed {
+            COMBINED_COLUMN_KEYS.get(column).copied()
+        } else {
+            COLUMN_KEYS.get(column).copied()
         }
     }
 
-    pub fn set_colors(&mut self) {
-        self.colors = TableColors::new(&PALETTES[self.color_index]);
+    /// The width of each column once laid out `width` cells wide.
+    pub fn laid_out(&self, width: u16) -> Vec<u16> {
+        let bar = HIGHLIGHT_SYMBOL.width() as u16;
+        Layout::horizontal(self.widths(width))
+            .spacing(1)
+            .split(Rect::new(0, 0, width.saturating_sub(bar), 1))
+            .iter()
+            .map(|column| column.width)
+            .collect()
+    }
+
+    fn row_role(&self, host: &Host) -> RowRole {
+        if host.is_my_device_mac {
+            RowRole::ThisDevice
+        } else if Some(host.ipv4) == self.gateway {
+            RowRole::Gateway
+        } else {
+            RowRole::Host
+        }
     }
 
     pub fn draw(
@@ -97,99 +217,304 @@ impl<'a> HostsTable<'a> {
         frame: &mut Frame,
         area: Rect,
     ) {
-        let vertical = &Layout::vertical([Constraint::Min(5), Constraint::Length(4)]);
+        let vertical = &Layout::vertical([Constraint::Min(5), Constraint::Length(1)]);
         let rects = vertical.split(area);
 
-        self.set_colors();
-
         self.render_table(table_state, frame, rects[0]);
         self.render_scrollbar(scroll_state, frame, rects[0]);
-        self.render_help(frame, rects[1]);
+        self.render_totals(frame, rects[1]);
     }
 
-    fn render_table(&mut self, table_state: &mut TableState, frame: &mut Frame, area: Rect) {
-        let header_style = Style::default()
-            .fg(self.colors.header_fg)
-            .bg(self.colors.header_bg);
-        let selected_row_style = Style::default()
-            .add_modifier(Modifier::REVERSED)
-            .fg(self.colors.selected_row_style_fg);
-        let selected_col_style = Style::default().fg(self.colors.selected_column_style_fg);
-        let selected_cell_style = Style::default()
-            .add_modifier(Modifier::REVERSED)
-            .fg(self.colors.selected_cell_style_fg);
-
-        let header = ["IP Address", "Mac Address", "Speed ↓", "Speed ↑", "Time"]
+    /// The computed constraints, or the user's width for the columns that
+    /// have one, shrunk so the table fits `width`.
+    fn widths(&self, width: u16) -> Vec<Constraint> {
+        let computed = self.computed_widths();
+        let overrides: Vec<Option<u16>> = (0..computed.len())
+            .map(|column| {
+                Self::column_key(column, self.combined_speed)
+                    .and_then(|key| self.column_widths.get(key).copied())
+            })
+            .collect();
+        let spacing = computed.len() as u16 - 1;
+        let available = width.saturating_sub(HIGHLIGHT_SYMBOL.width() as u16 + spacing);
+        clamp_widths(&overrides, available)
             .into_iter()
+            .zip(computed)
+            .map(|(width, computed)| width.map_or(computed, Constraint::Length))
+            .collect()
+    }
+
+    fn computed_widths(&self) -> Vec<Constraint> {
+        let speed_widths = if self.combined_speed {
+            vec![Constraint::Min(self.longest_item_lens.5)]
+        } else {
+            vec![
+                Constraint::Min(self.longest_item_lens.3),
+                Constraint::Min(self.longest_item_lens.4),
+            ]
+        };
+        [
+            // + 1 is for padding.
+            Constraint::Length(self.longest_item_lens.0.max(TOTAL_LABEL.width() as u16) + 1),
+            Constraint::Min(self.longest_item_lens.1 + 4),
+            Constraint::Min(self.longest_item_lens.8.min(MAX_VENDOR_WIDTH)),
+            Constraint::Min(self.longest_item_lens.2),
+        ]
+        .into_iter()
+        .chain(speed_widths)
+        .chain([
+            Constraint::Min(self.longest_item_lens.6),
+            Constraint::Min(self.longest_item_lens.7),
+        ])
+        .collect()
+    }
+
+    /// The speeds of the listed hosts added up, under the table and out of
+    /// the way of the selection.
+    fn render_totals(&self, frame: &mut Frame, area: Rect) {
+        let total = self
+            .items
+            .iter()
+            .filter_map(|host| self.speed_source.select(host.speed, host.local_speed))
+            .fold(Speed::default(), |total, speed| total + speed);
+        let speed_cells = if self.combined_speed {
+            vec![total.to_string()]
+        } else {
+            vec![total.to_string_input(), total.to_string_output()]
+        };
+        let hosts = match self.items.len() {
+            1 => "1 host".to_string(),
+            n => format!("{} hosts", n),
+        };
+        let row = [TOTAL_LABEL.to_string(), hosts, String::new(), String::new()]
+            .into_iter()
+            .chain(speed_cells)
+            .chain([String::new(), String::new()])
             .map(Cell::from)
             .collect::<Row>()
-            .style(header_style)
-            .height(1);
-        let rows = self.items.iter().enumerate().map(|(i, host)| {
-            let color = match i % 2 {
-                0 => self.colors.normal_row_color,
-                _ => self.colors.alt_row_color,
-            };
-            let row = [
-                host.ipv4.to_string(),
-                {
-                    if host.is_my_device_mac {
-                        host.mac.to_string() + " (*)"
-                    } else {
-                        host.mac.to_string()
-                    }
-                },
-                {
-                    if let Some(speed) = host.speed {
-                        speed.to_string_input()
-                    } else {
-                        String::from("")
+            .style(self.theme.header);
+        let table = Table::new([row], self.widths(area.width))
+            .highlight_symbol(Text::from(vec![HIGHLIGHT_SYMBOL.into()]))
+            .style(self.theme.background)
+            .highlight_spacing(HighlightSpacing::Always);
+        frame.render_widget(table, area);
+    }
+
+    fn render_table(&mut self, table_state: &mut TableState, frame: &mut Frame, area: Rect) {
+        let time_header = if self.time_frozen {
+            "Time (frozen)"
+        } else {
+            "Time"
+        };
+        // the speed headers follow the selected row, so they always describe it
+        let selected_role = table_state
+            .selected()
+            .and_then(|i| self.items.get(i).copied())
+            .map_or(RowRole::Host, |host| self.row_role(host));
+        let speed_header = |header: &str| match self.speed_source {
+            SpeedSource::External => header.to_string(),
+            source => format!("{} ({})", header, source.label()),
+        };
+        let speed_headers = if self.combined_speed {
+            vec![speed_header(selected_role.combined_speed_header())]
+        } else {
+            selected_role.speed_headers().map(speed_header).to_vec()
+        };
+        let ip_header = match self.activity_filter {
+            Some(bucket) => format!("IP Address ({})", bucket),
+            None => "IP Address".to_string(),
+        };
+        let header = [
+            ip_header,
+            "Mac Address".to_string(),
+            "Vendor".to_string(),
+            "Label".to_string(),
+        ]
+        .into_iter()
+        .chain(speed_headers)
+        .chain(["Activity".to_string(), time_header.to_string()])
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(self.theme.header)
+        .height(1);
+        // only the rows that fit are built, the table is handed that slice
+        // and a state relative to it
+        let (offset, visible) = Self::visible_window(table_state, area, self.items.len());
+        *table_state.offset_mut() = offset;
+        let mut slice_state = TableState::new()
+            .with_selected(table_state.selected().map(|i| i - offset))
+            .with_selected_column(table_state.selected_column());
+        let widths = self.widths(area.width);
+        let bar = HIGHLIGHT_SYMBOL;
+        // the same split the table does, to shorten MACs on group boundaries
+        // rather than let the cell cut them anywhere
+        let mac_width = self.laid_out(area.width)[1] as usize;
+        let rows = self.items[offset..offset + visible]
+            .iter()
+            .enumerate()
+            .map(|(i, host)| {
+                let i = offset + i;
+                let role = self.row_role(host);
+                let speed = self.speed_source.select(host.speed, host.local_speed);
+                let wan_speed = self
+                    .wan_speed
+                    .filter(|_| self.speed_source != SpeedSource::Local);
+                let speed_cells = match (role, wan_speed, speed) {
+                    (RowRole::Gateway, Some(wan), _) if self.combined_speed => {
+                        vec![Line::from(wan.to_string())]
                     }
-                },
-                {
-                    if let Some(speed) = host.speed {
-                        speed.to_string_output()
-                    } else {
-                        String::from("")
+                    (RowRole::Gateway, Some(wan), _) => vec![
+                        Line::from(format!("WAN→LAN {}", wan.to_string_input())),
+                        Line::from(format!("LAN→WAN {}", wan.to_string_output())),
+                    ],
+                    (_, _, Some(speed)) if host.has_traffic => {
+                        // trends follow the external speed, the other sources have none
+                        let trend = host
+                            .trend
+                            .filter(|_| self.speed_source == SpeedSource::External);
+                        let down = self.speed_line(
+                            speed.input(),
+                            speed.to_string_input(),
+                            trend.map(|t| t.input),
+                        );
+                        let up = self.speed_line(
+                            speed.output(),
+                            speed.to_string_output(),
+                            trend.map(|t| t.output),
+                        );
+                        if self.combined_speed {
+                            // laid out like `Speed`'s Display, with the trends
+                            let mut spans = vec![Span::raw("↓ ")];
+                            spans.extend(down.spans);
+                            spans.push(Span::raw(" | ↑ "));
+                            spans.extend(up.spans);
+                            vec![Line::from(spans)]
+                        } else {
+                            vec![down, up]
+                        }
                     }
-                },
-                {
-                    let diff = Local::now().timestamp_millis() - host.time.timestamp_millis();
-                    let durr =
-                        Duration::new(diff / 1000, (diff % 1000) as u32 * 1000).unwrap_or_default();
+                    // never had traffic, unlike an idle host's zeros
+                    _ if self.combined_speed => vec![Line::from(NO_DATA)],
+                    _ => vec![Line::from(NO_DATA), Line::from(NO_DATA)],
+                };
+                let bucket = ActivityBucket::of(host, self.now);
+                let cells = [
                     format!(
-                        "{:2} min {:2} sec ago",
-                        durr.num_minutes(),
-                        durr.num_seconds() - (durr.num_minutes() * 60)
-                    )
-                }, // data.time.to_string(),
-            ];
-            row.into_iter()
-                .map(|content| Cell::from(Text::from(content)))
-                .collect::<Row>()
-                .style(Style::new().fg(self.colors.row_fg).bg(color))
-                .height(1)
-        });
-        let bar = " ━ ";
-        let table = Table::new(
-            rows,
-            [
-                // + 1 is for padding.
-                Constraint::Length(self.longest_item_lens.0 + 1),
-                Constraint::Min(self.longest_item_lens.1 + 4),
-                Constraint::Min(self.longest_item_lens.2),
-                Constraint::Min(self.longest_item_lens.3),
-                Constraint::Min(self.longest_item_lens.4),
-            ],
-        )
-        .header(header)
-        .row_highlight_style(selected_row_style)
-        .column_highlight_style(selected_col_style)
-        .cell_highlight_style(selected_cell_style)
-        .highlight_symbol(Text::from(vec![bar.into()]))
-        .bg(self.colors.buffer_bg)
-        .highlight_spacing(HighlightSpacing::Always);
-        frame.render_stateful_widget(table, area, table_state);
+                        "{}{}",
+                        host.ipv4,
+                        match bucket {
+                            ActivityBucket::Active => self.theme.marker(ACTIVE_MARKER),
+                            ActivityBucket::Idle => "",
+                            ActivityBucket::Silent => self.theme.marker(SILENT_MARKER),
+                        }
+                    ),
+                    {
+                        let markers = self.theme.text_markers;
+                        let marker = if host.is_my_device_mac {
+                            if markers {
+                                " [SELF]"
+                            } else {
+                                " (*)"
+                            }
+                        } else if role == RowRole::Gateway {
+                            if markers {
+                                " [GATEWAY]"
+                            } else {
+                                " (gateway)"
+                            }
+                        } else if host.arp.flagged {
+                            if markers {
+                                " [ALERT] arp scan"
+                            } else {
+                                " (arp scan)"
+                            }
+                        } else {
+                            ""
+                        };
+                        let mac = self
+                            .mac_format
+                            .truncate(host.mac, mac_width.saturating_sub(marker.len()));
+                        mac + marker
+                    },
+                    host.vendor().unwrap_or(UNKNOWN_VENDOR).to_string(),
+                    {
+                        let label = host.label.clone().unwrap_or_default();
+                        match (host.watch, self.theme.text_markers) {
+                            (None, _) => label,
+                            (Some(_), false) => format!("{}{}", WATCH_MARKER, label),
+                            (Some(_), true) => format!("{}{}", WATCH_TEXT_MARKER, label),
+                        }
+                    },
+                    host.category
+                        .map(|category| category.to_string())
+                        .unwrap_or_default(),
+                    {
+                        // saturating: hosts seen after a freeze would otherwise show a negative age
+                        let age = self.now.saturating_duration_since(host.seen_at).as_secs();
+                        format!("{:2} min {:2} sec ago", age / 60, age % 60)
+                    }, // data.time.to_string(),
+                ];
+                let [ip, mac, vendor, label, category, time] =
+                    cells.map(|content| Cell::from(Text::from(content)));
+                let ip = match bucket {
+                    ActivityBucket::Active => ip.style(self.theme.active),
+                    ActivityBucket::Idle => ip,
+                    ActivityBucket::Silent => ip.style(self.theme.silent),
+                };
+                [ip, mac, vendor, label]
+                    .into_iter()
+                    .chain(speed_cells.into_iter().map(Cell::from))
+                    .chain([category, time])
+                    .collect::<Row>()
+                    .style(self.theme.row_style(i))
+                    .height(1)
+            });
+        let table = Table::new(rows, widths)
+            .header(header)
+            .row_highlight_style(self.theme.selected_row)
+            .column_highlight_style(self.theme.selected_column)
+            .cell_highlight_style(self.theme.selected_cell)
+            .highlight_symbol(Text::from(vec![bar.into()]))
+            .style(self.theme.background)
+            .highlight_spacing(HighlightSpacing::Always);
+        frame.render_stateful_widget(table, area, &mut slice_state);
+    }
+
+    /// A speed followed by its trend glyph, if it has one.
+    /// The formatted speed with its trend, a dimmed `0` while idle.
+    fn speed_line(&self, bits: u128, speed: String, trend: Option<Trend>) -> Line<'static> {
+        if bits == 0 {
+            return Line::from(Span::styled("0", self.theme.silent));
+        }
+        let Some(trend) = trend else {
+            return Line::from(speed);
+        };
+        let style = match trend {
+            Trend::Rising => self.theme.rising,
+            Trend::Falling => self.theme.falling,
+            Trend::Steady => self.theme.text,
+        };
+        Line::from(vec![
+            Span::raw(speed),
+            Span::raw(" "),
+            Span::styled(trend.glyph(), style),
+        ])
+    }
+
+    /// First row and number of rows to draw so the selection stays in view,
+    /// scrolling as little as possible from the previous offset.
+    fn visible_window(table_state: &TableState, area: Rect, len: usize) -> (usize, usize) {
+        // the header takes one line
+        let height = (area.height as usize).saturating_sub(1).max(1);
+        let mut offset = table_state.offset().min(len.saturating_sub(height));
+        if let Some(selected) = table_state.selected().filter(|i| *i < len) {
+            if selected < offset {
+                offset = selected;
+            } else if selected >= offset + height {
+                offset = selected + 1 - height;
+            }
+        }
+        (offset, height.min(len - offset))
     }
 
     fn render_scrollbar(
@@ -211,23 +536,7 @@ impl<'a> HostsTable<'a> {
         );
     }
 
-    fn render_help(&self, frame: &mut Frame, area: Rect) {
-        let info_help = Paragraph::new(Text::from_iter(INFO_TEXT))
-            .style(
-                Style::new()
-                    .fg(self.colors.row_fg)
-                    .bg(self.colors.buffer_bg),
-            )
-            .centered()
-            .block(
-                Block::bordered()
-                    .border_type(BorderType::Double)
-                    .border_style(Style::new().fg(self.colors.help_border_color)),
-            );
-        frame.render_widget(info_help, area);
-    }
-
-    fn constraint_len_calculator(items: &[Host]) -> (u16, u16, u16, u16, u16) {
+    fn constraint_len_calculator(items: &[&Host]) -> (u16, u16, u16, u16, u16, u16, u16, u16, u16) {
         let ip_len = items
             .iter()
             .map(|h| h.ipv4.to_string().len())
@@ -238,14 +547,32 @@ impl<'a> HostsTable<'a> {
             .map(|h| h.mac.to_string().len())
             .max()
             .unwrap_or(0);
+        let label_len = items
+            .iter()
+            .map(|h| {
+                let marker = h.watch.map_or(0, |_| WATCH_TEXT_MARKER.width());
+                h.label.as_ref().map_or(0, |l| l.width()) + marker
+            })
+            .max()
+            .unwrap_or(0);
         let speed_down_len = items
             .iter()
-            .map(|h| h.speed.map(|s| s.to_string_input().len()).unwrap_or(0))
+            .map(|h| h.speed.map(|s| s.to_string_input().len() + 2).unwrap_or(0))
             .max()
             .unwrap_or(0);
         let speed_up_len = items
             .iter()
-            .map(|h| h.speed.map(|s| s.to_string_output().len()).unwrap_or(0))
+            .map(|h| h.speed.map(|s| s.to_string_output().len() + 2).unwrap_or(0))
+            .max()
+            .unwrap_or(0);
+        let speed_combined_len = items
+            .iter()
+            .map(|h| h.speed.map(|s| s.to_string().width() + 4).unwrap_or(0))
+            .max()
+            .unwrap_or(0);
+        let category_len = items
+            .iter()
+            .map(|h| h.category.map(|c| c.to_string().len()).unwrap_or(0))
             .max()
             .unwrap_or(0);
         let time_len = items
@@ -253,14 +580,74 @@ impl<'a> HostsTable<'a> {
             .map(|h| h.time.to_string().len())
             .max()
             .unwrap_or(0);
+        let vendor_len = items
+            .iter()
+            .map(|h| h.vendor().unwrap_or(UNKNOWN_VENDOR).width())
+            .max()
+            .unwrap_or(0);
 
         #[allow(clippy::cast_possible_truncation)]
         (
             ip_len as u16,
             mac_len as u16,
+            label_len as u16,
             speed_down_len as u16,
             speed_up_len as u16,
+            speed_combined_len as u16,
+            category_len as u16,
             time_len as u16,
+            vendor_len as u16,
         )
     }
 }
+
+/// The user's widths cut down to fit `available` cells, the columns without
+/// one keeping `MIN_COLUMN_WIDTH`; the first columns keep theirs first.
+fn clamp_widths(overrides: &[Option<u16>], available: u16) -> Vec<Option<u16>> {
+    let computed = overrides.iter().filter(|width| width.is_none()).count() as u16;
+    let mut left = available.saturating_sub(computed * MIN_COLUMN_WIDTH);
+    overrides
+        .iter()
+        .map(|width| {
+            width.map(|width| {
+                let width = width.min(left);
+                left -= width;
+                width
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widths_that_fit_are_kept() {
+        assert_eq!(
+            clamp_widths(&[None, Some(10), None, Some(20)], 80),
+            [None, Some(10), None, Some(20)]
+        );
+    }
+
+    #[test]
+    fn widths_are_cut_to_leave_room_for_the_others() {
+        // 2 computed columns keep 3 cells each
+        assert_eq!(
+            clamp_widths(&[Some(30), None, Some(30), None], 40),
+            [Some(30), None, Some(4), None]
+        );
+        assert_eq!(clamp_widths(&[Some(30), None], 2), [Some(0), None]);
+    }
+
+    #[test]
+    fn columns_have_a_key_in_both_layouts() {
+        for combined in [false, true] {
+            let count = HostsTable::column_count(combined);
+            assert!((0..count).all(|column| HostsTable::column_key(column, combined).is_some()));
+            assert_eq!(HostsTable::column_key(count, combined), None);
+        }
+        assert_eq!(HostsTable::column_key(2, false), Some("vendor"));
+        assert_eq!(HostsTable::column_key(4, true), Some("speed"));
+    }
+}
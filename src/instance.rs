@@ -0,0 +1,203 @@
+use std::{fmt::Display, fs, io, path::PathBuf, process};
+
+use crate::logging::get_data_dir;
+
+const LOCK_PREFIX: &str = "netui-";
+const LOCK_EXTENSION: &str = "lock";
+
+/// Held while netui watches an interface: a file in the data directory with
+/// the PID, removed on exit. Instances on other interfaces don't share it.
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    /// another instance watches the interface, with this PID
+    Held(String, u32),
+    Io(io::Error),
+}
+
+impl Display for LockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::Held(interface, pid) => {
+                write!(f, "netui already watches {} (pid {})", interface, pid)
+            }
+            LockError::Io(e) => write!(f, "no instance lock: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for LockError {
+    fn from(e: io::Error) -> Self {
+        LockError::Io(e)
+    }
+}
+
+impl InstanceLock {
+    pub fn path(interface: &str) -> PathBuf {
+        get_data_dir().join(format!("{}{}.{}", LOCK_PREFIX, interface, LOCK_EXTENSION))
+    }
+
+    /// Takes the lock of `interface`, over a stale one left by a crashed instance.
+    pub fn acquire(interface: &str) -> Result<Self, LockError> {
+        Self::acquire_at(Self::path(interface), interface)
+    }
+
+    fn acquire_at(path: PathBuf, interface: &str) -> Result<Self, LockError> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        // the PID is written aside then linked into place, so the lock never
+        // shows without it and a starting instance isn't taken for a crashed one
+        let mut pending = path.clone().into_os_string();
+        pending.push(format!(".{}.tmp", process::id()));
+        let pending = PathBuf::from(pending);
+        fs::write(&pending, process::id().to_string())?;
+        let linked = Self::link(&pending, &path, interface);
+        let _ = fs::remove_file(&pending);
+        linked?;
+        Ok(Self { path })
+    }
+
+    /// Links the written lock `pending` as `path`, over a stale one.
+    fn link(
+        pending: &std::path::Path,
+        path: &std::path::Path,
+        interface: &str,
+    ) -> Result<(), LockError> {
+        // a second attempt once a stale lock is removed
+        for _ in 0..2 {
+            match fs::hard_link(pending, path) {
+                Ok(()) => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => match read_pid(path) {
+                    Some(pid) if is_running(pid) => {
+                        return Err(LockError::Held(interface.to_string(), pid))
+                    }
+                    _ => match fs::remove_file(path) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                        _ => {}
+                    },
+                },
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(io::Error::other(format!("{} keeps being recreated", path.display())).into())
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A lock file found in the data directory, for the doctor.
+pub struct HeldLock {
+    pub interface: String,
+    /// none when the file doesn't hold one
+    pub pid: Option<u32>,
+    pub running: bool,
+}
+
+/// Every instance lock in the data directory, the stale ones included.
+pub fn held_locks() -> Vec<HeldLock> {
+    let Ok(entries) = fs::read_dir(get_data_dir()) else {
+        return vec![];
+    };
+    let mut locks = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != LOCK_EXTENSION {
+                return None;
+            }
+            let stem = path.file_stem()?.to_str()?;
+            let interface = stem.strip_prefix(LOCK_PREFIX)?.to_string();
+            let pid = read_pid(&path);
+            Some(HeldLock {
+                interface,
+                pid,
+                running: pid.is_some_and(is_running),
+            })
+        })
+        .collect::<Vec<_>>();
+    locks.sort_by(|a, b| a.interface.cmp(&b.interface));
+    locks
+}
+
+fn read_pid(path: &std::path::Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Whether a process with `pid` exists; where that can't be told the lock
+/// is taken as held.
+fn is_running(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        PathBuf::from("/proc").join(pid.to_string()).exists()
+    } else if cfg!(unix) {
+        process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    } else {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("netui-instance-{}-{}", name, process::id()))
+            .join(format!("{}{}.{}", LOCK_PREFIX, name, LOCK_EXTENSION))
+    }
+
+    #[test]
+    fn a_second_instance_finds_the_lock_held() {
+        let path = lock_path("eth0");
+        let lock = InstanceLock::acquire_at(path.clone(), "eth0").unwrap();
+        assert_eq!(read_pid(&path), Some(process::id()));
+        match InstanceLock::acquire_at(path.clone(), "eth0") {
+            Err(LockError::Held(interface, pid)) => {
+                assert_eq!((interface.as_str(), pid), ("eth0", process::id()))
+            }
+            other => panic!("the lock was taken twice: {:?}", other),
+        }
+        // the PID written aside for the link is cleaned up either way
+        let files = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(files, 1);
+        assert_eq!(read_pid(&path), Some(process::id()));
+        drop(lock);
+        assert!(!path.exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn the_lock_of_a_crashed_instance_is_taken_over() {
+        // the PID of a process that has exited
+        let mut child = process::Command::new("true").spawn().unwrap();
+        let dead = child.id();
+        child.wait().unwrap();
+        assert!(!is_running(dead));
+
+        let path = lock_path("wlan0");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, dead.to_string()).unwrap();
+        let lock = InstanceLock::acquire_at(path.clone(), "wlan0").unwrap();
+        assert_eq!(read_pid(&path), Some(process::id()));
+        drop(lock);
+
+        // linked whole, a lock without a PID is never a starting instance
+        fs::write(&path, "").unwrap();
+        let lock = InstanceLock::acquire_at(path.clone(), "wlan0").unwrap();
+        assert_eq!(read_pid(&path), Some(process::id()));
+        drop(lock);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,290 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use pnet::ipnetwork::IpNetwork;
+use pnet_datalink::{MacAddr, NetworkInterface};
+
+use crate::{
+    mac_format::MacFormat,
+    text_table::{Align, TextTable},
+};
+
+use crate::format::format_link_speed;
+
+/// Everything known about the watched interface, as shown in the interface popup.
+#[derive(Clone, Debug)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub description: String,
+    pub index: u32,
+    pub mac: Option<MacAddr>,
+    pub mtu: Option<u32>,
+    /// negotiated speed of the link in bits per second, when the platform tells
+    pub link_speed: Option<u64>,
+    pub flags: Vec<&'static str>,
+    pub networks: Vec<NetworkInfo>,
+    pub gateway: Option<Ipv4Addr>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NetworkInfo {
+    pub network: IpNetwork,
+    /// only meaningful for IPv4
+    pub broadcast: Option<Ipv4Addr>,
+    /// number of addresses an ARP sweep can target
+    pub scannable: Option<u64>,
+}
+
+impl InterfaceInfo {
+    pub fn from_interface(nif: &NetworkInterface) -> Self {
+        Self {
+            name: nif.name.clone(),
+            description: nif.description.clone(),
+            index: nif.index,
+            mac: nif.mac,
+            mtu: read_mtu(&nif.name),
+            link_speed: read_link_speed(&nif.name),
+            flags: flags(nif),
+            networks: nif.ips.iter().map(NetworkInfo::from_network).collect(),
+            gateway: default_gateway(&nif.name),
+        }
+    }
+
+    /// The address ARP requests are sent from.
+    pub fn primary_ipv4(&self) -> Option<Ipv4Addr> {
+        self.networks.iter().find_map(|n| match n.network.ip() {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        })
+    }
+}
+
+fn flags(nif: &NetworkInterface) -> Vec<&'static str> {
+    [
+        (nif.is_up(), "up"),
+        (nif.is_running(), "running"),
+        (nif.is_broadcast(), "broadcast"),
+        (nif.is_multicast(), "multicast"),
+        (nif.is_loopback(), "loopback"),
+        (nif.is_point_to_point(), "point-to-point"),
+    ]
+    .into_iter()
+    .filter_map(|(set, name)| set.then_some(name))
+    .collect()
+}
+
+/// A line per interface for `--list-interfaces`: name, MAC, networks, flags
+/// and whether it's `default`. Empty cells are `-` and lists have no spaces,
+/// so every line splits into the same fields.
+pub fn list(
+    interfaces: &[NetworkInterface],
+    default: Option<&str>,
+    mac_format: MacFormat,
+) -> String {
+    let mut table = TextTable::new(&[
+        ("NAME", Align::Left, false),
+        ("MAC", Align::Left, false),
+        ("NETWORKS", Align::Left, false),
+        ("FLAGS", Align::Left, false),
+        ("DEFAULT", Align::Left, false),
+    ]);
+    let or_dash = |text: String| {
+        if text.is_empty() {
+            "-".to_string()
+        } else {
+            text
+        }
+    };
+    for nif in interfaces {
+        let networks: Vec<String> = nif.ips.iter().map(|ip| ip.to_string()).collect();
+        table.push(
+            vec![
+                nif.name.clone(),
+                nif.mac
+                    .filter(|mac| *mac != MacAddr::zero())
+                    .map_or_else(String::new, |mac| mac_format.format(mac)),
+                networks.join(","),
+                flags(nif).join(","),
+                if Some(nif.name.as_str()) == default {
+                    "default"
+                } else {
+                    ""
+                }
+                .to_string(),
+            ]
+            .into_iter()
+            .map(or_dash)
+            .collect(),
+        );
+    }
+    table.render(None)
+}
+
+impl NetworkInfo {
+    fn from_network(network: &IpNetwork) -> Self {
+        match network {
+            IpNetwork::V4(v4) => {
+                let prefix = v4.prefix();
+                let scannable = match prefix {
+                    32 => 1,
+                    31 => 2,
+                    _ => (1u64 << (32 - prefix)) - 2,
+                };
+                Self {
+                    network: *network,
+                    broadcast: Some(v4.broadcast()),
+                    scannable: Some(scannable),
+                }
+            }
+            IpNetwork::V6(_) => Self {
+                network: *network,
+                broadcast: None,
+                scannable: None,
+            },
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_mtu(interface_name: &str) -> Option<u32> {
+    std::fs::read_to_string(format!("/sys/class/net/{}/mtu", interface_name))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_mtu(_interface_name: &str) -> Option<u32> {
+    None
+}
+
+/// Reads the link speed sysfs reports in Mbit/s; virtual and disconnected
+/// interfaces report -1 or fail to read.
+#[cfg(target_os = "linux")]
+fn read_link_speed(interface_name: &str) -> Option<u64> {
+    let speed = std::fs::read_to_string(format!("/sys/class/net/{}/speed", interface_name)).ok()?;
+    parse_sysfs_speed(&speed)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_link_speed(_interface_name: &str) -> Option<u64> {
+    None
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_sysfs_speed(text: &str) -> Option<u64> {
+    let mbits: i64 = text.trim().parse().ok()?;
+    u64::try_from(mbits)
+        .ok()
+        .filter(|mbits| *mbits > 0)
+        .map(|mbits| mbits * 1_000_000)
+}
+
+/// `14% of 1 Gb/s`, from the busier direction of `bits_per_sec` since links
+/// are full duplex.
+pub fn utilization_str(busiest_bits_per_sec: u128, link_speed: u64) -> String {
+    let percent = busiest_bits_per_sec as f64 * 100.0 / link_speed.max(1) as f64;
+    let percent = if percent > 0.0 && percent < 1.0 {
+        "<1".to_string()
+    } else {
+        format!("{:.0}", percent)
+    };
+    format!("{}% of {}", percent, format_link_speed(link_speed))
+}
+
+/// Default route's gateway for this interface, read from the kernel routing table.
+#[cfg(target_os = "linux")]
+fn default_gateway(interface_name: &str) -> Option<Ipv4Addr> {
+    let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+    routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [iface, "00000000", gateway, ..] if *iface == interface_name => {
+                let gateway = u32::from_str_radix(gateway, 16).ok()?;
+                // the kernel prints the network-order address as a native integer
+                Some(Ipv4Addr::from(u32::from_be(gateway)))
+            }
+            _ => None,
+        }
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_gateway(_interface_name: &str) -> Option<Ipv4Addr> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use pnet_datalink::MacAddr;
+
+    use super::*;
+
+    fn nif(name: &str, mac: Option<MacAddr>, ips: &[&str], flags: u32) -> NetworkInterface {
+        NetworkInterface {
+            name: name.to_string(),
+            description: String::new(),
+            index: 1,
+            mac,
+            ips: ips.iter().map(|ip| ip.parse().unwrap()).collect(),
+            flags,
+        }
+    }
+
+    #[test]
+    fn sysfs_speeds_are_read_in_mbits_and_unknown_ones_ignored() {
+        assert_eq!(parse_sysfs_speed("1000\n"), Some(1_000_000_000));
+        assert_eq!(parse_sysfs_speed("2500"), Some(2_500_000_000));
+        // disconnected, or a virtual interface
+        assert_eq!(parse_sysfs_speed("-1\n"), None);
+        assert_eq!(parse_sysfs_speed("0"), None);
+        assert_eq!(parse_sysfs_speed(""), None);
+    }
+
+    #[test]
+    fn utilization_is_a_whole_percentage_of_the_link() {
+        let gigabit = 1_000_000_000;
+        assert_eq!(utilization_str(140_000_000, gigabit), "14% of 1 Gb/s");
+        assert_eq!(utilization_str(0, gigabit), "0% of 1 Gb/s");
+        // some traffic never shows as none
+        assert_eq!(utilization_str(1_000, gigabit), "<1% of 1 Gb/s");
+        assert_eq!(utilization_str(10_000_000, gigabit), "1% of 1 Gb/s");
+        assert_eq!(utilization_str(50_000_000, 100_000_000), "50% of 100 Mb/s");
+        // a wrong override or a bonded link can read past the link speed
+        assert_eq!(utilization_str(1_500_000_000, gigabit), "150% of 1 Gb/s");
+        assert_eq!(
+            utilization_str(1_250_000_000, 2_500_000_000),
+            "50% of 2.5 Gb/s"
+        );
+    }
+
+    #[test]
+    fn every_line_has_the_same_fields() {
+        let interfaces = [
+            nif("lo", None, &[], 0),
+            nif(
+                "eth0",
+                Some(MacAddr(0, 0x1b, 0x63, 0, 0, 1)),
+                &["192.168.1.20/24", "fe80::1/64"],
+                0,
+            ),
+        ];
+        let listing = list(&interfaces, Some("eth0"), MacFormat::default());
+        let lines: Vec<Vec<&str>> = listing
+            .lines()
+            .skip(1)
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(lines[0], ["lo", "-", "-", "-", "-"]);
+        assert_eq!(
+            lines[1],
+            [
+                "eth0",
+                "00:1b:63:00:00:01",
+                "192.168.1.20/24,fe80::1/64",
+                "-",
+                "default"
+            ]
+        );
+    }
+}
@@ -0,0 +1,377 @@
+use std::{fs, net::Ipv4Addr, path::PathBuf};
+
+use crossterm::style::Stylize;
+use pnet::util::MacAddr;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{AppResult, Host},
+    dry_run,
+    logging::get_data_dir,
+    text_table::{Align, TextTable},
+    theme::ColorDepth,
+};
+
+/// A host as a snapshot remembers it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InventoryHost {
+    pub ip: Ipv4Addr,
+    pub mac: String,
+    pub hostname: Option<String>,
+    pub label: Option<String>,
+}
+
+impl InventoryHost {
+    /// The label when the user gave one, else the hostname.
+    pub fn name(&self) -> Option<&str> {
+        self.label.as_deref().or(self.hostname.as_deref())
+    }
+
+    /// Hosts seen through a router or from the OS cache may have no MAC,
+    /// they are matched by IP only.
+    fn has_mac(&self) -> bool {
+        self.mac != MacAddr::zero().to_string()
+    }
+}
+
+/// The hosts of an interface at one point in time, saved with `w` in the
+/// `snapshots` folder of the data directory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub interface: String,
+    /// RFC 3339
+    pub taken_at: String,
+    pub hosts: Vec<InventoryHost>,
+}
+
+impl Snapshot {
+    pub fn of(interface: &str, hosts: &[Host]) -> Self {
+        Self {
+            interface: interface.to_string(),
+            taken_at: chrono::Local::now().to_rfc3339(),
+            hosts: hosts
+                .iter()
+                .map(|host| InventoryHost {
+                    ip: host.ipv4,
+                    mac: host.mac.to_string(),
+                    hostname: host.hostname.clone(),
+                    label: host.label.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn dir() -> PathBuf {
+        get_data_dir().join("snapshots")
+    }
+
+    /// Saves it as `<interface>-<date>-<time>`, returns that name.
+    pub fn save(&self) -> AppResult<String> {
+        let name = format!(
+            "{}-{}",
+            self.interface,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        dry_run::write(&Self::path(&name), toml::to_string(self)?)?;
+        Ok(name)
+    }
+
+    fn path(name: &str) -> PathBuf {
+        Self::dir().join(format!("{}.toml", name))
+    }
+
+    /// Reads the snapshot saved as `name`, or the file `name` points to.
+    pub fn load(name: &str) -> AppResult<Self> {
+        let path = match PathBuf::from(name) {
+            path if path.is_file() => path,
+            _ => Self::path(name),
+        };
+        let text = fs::read_to_string(&path).map_err(|e| match Self::names() {
+            names if names.is_empty() => {
+                format!("{}: {}, no snapshot saved yet", path.display(), e)
+            }
+            names => format!("{}: {}, saved: {}", path.display(), e, names.join(", ")),
+        })?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// The names of the saved snapshots, oldest first.
+    pub fn names() -> Vec<String> {
+        let Ok(entries) = fs::read_dir(Self::dir()) else {
+            return vec![];
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                (path.extension()? == "toml").then_some(())?;
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .collect();
+        // by the `<date>-<time>` the names end with
+        names.sort_by(|a, b| {
+            let taken = |name: &String| name[name.len().saturating_sub(15)..].to_string();
+            taken(a).cmp(&taken(b)).then_with(|| a.cmp(b))
+        });
+        names
+    }
+
+    /// The last snapshot of `interface` with its name.
+    pub fn latest(interface: &str) -> Option<(String, Self)> {
+        Self::names()
+            .into_iter()
+            .rev()
+            .filter(|name| name.starts_with(&format!("{}-", interface)))
+            .find_map(|name| {
+                let snapshot = Self::load(&name).ok()?;
+                (snapshot.interface == interface).then_some((name, snapshot))
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// the same host with other details
+    Changed {
+        ip: bool,
+        mac: bool,
+        name: bool,
+    },
+}
+
+impl ChangeKind {
+    /// `added`, `removed` or what changed, e.g. `ip, name`.
+    pub fn label(self) -> String {
+        match self {
+            ChangeKind::Added => "added".to_string(),
+            ChangeKind::Removed => "removed".to_string(),
+            ChangeKind::Changed { ip, mac, name } => [(ip, "ip"), (mac, "mac"), (name, "name")]
+                .iter()
+                .filter(|(changed, _)| *changed)
+                .map(|(_, what)| *what)
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// none when added
+    pub old: Option<InventoryHost>,
+    /// none when removed
+    pub new: Option<InventoryHost>,
+}
+
+impl Change {
+    /// `old → new` when the value changed, else the value.
+    pub fn describe(&self, value: impl Fn(&InventoryHost) -> String) -> String {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) if value(old) != value(new) => {
+                format!("{} → {}", value(old), value(new))
+            }
+            (_, Some(host)) | (Some(host), None) => value(host),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// What changed from `old` to `new`, by IP. A host is the same one when its
+/// MAC is, else when its IP is: a new address from DHCP is an IP change, a
+/// replaced card behind a fixed address a MAC change.
+pub fn diff(old: &[InventoryHost], new: &[InventoryHost]) -> Vec<Change> {
+    let mut old_left: Vec<Option<&InventoryHost>> = old.iter().map(Some).collect();
+    let mut pairs: Vec<(Option<&InventoryHost>, &InventoryHost)> =
+        new.iter().map(|host| (None, host)).collect();
+    let same_mac = |a: &InventoryHost, b: &InventoryHost| a.has_mac() && a.mac == b.mac;
+    let same_ip = |a: &InventoryHost, b: &InventoryHost| a.ip == b.ip;
+    for matches in [&same_mac as &dyn Fn(&_, &_) -> bool, &same_ip] {
+        for (matched, host) in pairs.iter_mut().filter(|(matched, _)| matched.is_none()) {
+            let found = old_left
+                .iter_mut()
+                .find(|old| old.is_some_and(|old| matches(old, host)));
+            if let Some(found) = found {
+                *matched = found.take();
+            }
+        }
+    }
+    let mut changes: Vec<Change> = pairs
+        .into_iter()
+        .filter_map(|(old, new)| {
+            let kind = match old {
+                None => ChangeKind::Added,
+                Some(old) => {
+                    let ip = old.ip != new.ip;
+                    let mac = old.mac != new.mac;
+                    let name = old.hostname != new.hostname || old.label != new.label;
+                    if !(ip || mac || name) {
+                        return None;
+                    }
+                    ChangeKind::Changed { ip, mac, name }
+                }
+            };
+            Some(Change {
+                kind,
+                old: old.cloned(),
+                new: Some(new.clone()),
+            })
+        })
+        .collect();
+    changes.extend(old_left.into_iter().flatten().map(|old| Change {
+        kind: ChangeKind::Removed,
+        old: Some(old.clone()),
+        new: None,
+    }));
+    changes.sort_by_key(|change| {
+        change
+            .new
+            .as_ref()
+            .or(change.old.as_ref())
+            .map(|host| host.ip)
+    });
+    changes
+}
+
+/// Prints what changed from the snapshot `a` to `b`, a row per host.
+pub fn print_diff(a: &str, b: &str, color: ColorDepth) -> AppResult<()> {
+    let (old, new) = (Snapshot::load(a)?, Snapshot::load(b)?);
+    let changes = diff(&old.hosts, &new.hosts);
+    println!("{} ({}) → {} ({})", a, old.taken_at, b, new.taken_at);
+    if changes.is_empty() {
+        println!("no change");
+        return Ok(());
+    }
+    let mut table = TextTable::new(&[
+        ("Change", Align::Left, false),
+        ("IP", Align::Left, false),
+        ("MAC", Align::Left, false),
+        ("Name", Align::Left, true),
+    ]);
+    for change in &changes {
+        table.push(vec![
+            change.kind.label(),
+            change.describe(|host| host.ip.to_string()),
+            change.describe(|host| host.mac.clone()),
+            change.describe(|host| host.name().unwrap_or("").to_string()),
+        ]);
+    }
+    let text = table.render(None);
+    let mut lines = text.lines();
+    println!("{}", lines.next().unwrap_or_default());
+    for (line, change) in lines.zip(&changes) {
+        let line = match (color, change.kind) {
+            (ColorDepth::None, _) => line.to_string(),
+            (_, ChangeKind::Added) => line.green().to_string(),
+            (_, ChangeKind::Removed) => line.red().to_string(),
+            (_, ChangeKind::Changed { .. }) => line.yellow().to_string(),
+        };
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(ip: u8, mac: u8, hostname: Option<&str>) -> InventoryHost {
+        InventoryHost {
+            ip: Ipv4Addr::new(192, 168, 1, ip),
+            mac: MacAddr(0, 0x1b, 0x63, 0, 0, mac).to_string(),
+            hostname: hostname.map(str::to_string),
+            label: None,
+        }
+    }
+
+    fn kinds(changes: &[Change]) -> Vec<(Ipv4Addr, String)> {
+        changes
+            .iter()
+            .map(|change| {
+                let host = change.new.as_ref().or(change.old.as_ref()).unwrap();
+                (host.ip, change.kind.label())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_same_inventory_has_no_change() {
+        let hosts = [host(1, 1, Some("router")), host(2, 2, None)];
+        assert!(diff(&hosts, &hosts).is_empty());
+    }
+
+    #[test]
+    fn hosts_are_added_and_removed() {
+        let old = [host(1, 1, None), host(2, 2, None)];
+        let new = [host(1, 1, None), host(3, 3, None)];
+        let changes = diff(&old, &new);
+        assert_eq!(
+            kinds(&changes),
+            [
+                (Ipv4Addr::new(192, 168, 1, 2), "removed".to_string()),
+                (Ipv4Addr::new(192, 168, 1, 3), "added".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_host_is_followed_by_mac_then_by_ip() {
+        // a new lease, a replaced card and a renamed host
+        let old = [
+            host(10, 1, None),
+            host(20, 2, None),
+            host(30, 3, Some("nas")),
+        ];
+        let new = [
+            host(11, 1, None),
+            host(20, 9, None),
+            host(30, 3, Some("backup")),
+        ];
+        let changes = diff(&old, &new);
+        assert_eq!(
+            kinds(&changes),
+            [
+                (Ipv4Addr::new(192, 168, 1, 11), "ip".to_string()),
+                (Ipv4Addr::new(192, 168, 1, 20), "mac".to_string()),
+                (Ipv4Addr::new(192, 168, 1, 30), "name".to_string()),
+            ]
+        );
+        assert_eq!(
+            changes[0].describe(|host| host.ip.to_string()),
+            "192.168.1.10 → 192.168.1.11"
+        );
+        assert_eq!(
+            changes[2].describe(|host| host.name().unwrap_or("").to_string()),
+            "nas → backup"
+        );
+    }
+
+    #[test]
+    fn a_mac_match_wins_over_an_ip_match() {
+        // two hosts swapped their addresses
+        let old = [host(1, 1, None), host(2, 2, None)];
+        let new = [host(2, 1, None), host(1, 2, None)];
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|change| change.kind
+            == ChangeKind::Changed {
+                ip: true,
+                mac: false,
+                name: false
+            }));
+    }
+
+    #[test]
+    fn hosts_without_a_mac_are_matched_by_ip() {
+        let mut old = host(1, 0, None);
+        old.mac = MacAddr::zero().to_string();
+        let mut new = old.clone();
+        new.label = Some("printer".to_string());
+        let changes = diff(&[old], &[new]);
+        assert_eq!(
+            kinds(&changes),
+            [(Ipv4Addr::new(192, 168, 1, 1), "name".to_string())]
+        );
+    }
+}
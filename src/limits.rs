@@ -0,0 +1,48 @@
+use serde::Deserialize;
+
+/// Caps on what a long session accumulates, the `[limits]` table of
+/// `config.toml`. Past its cap a collection drops its least recently seen
+/// entries first.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// hosts in the list
+    pub hosts: usize,
+    /// hosts counted as seen this session, later ones are not counted
+    pub seen_hosts: usize,
+    /// flows of the connections view
+    pub flows: usize,
+    /// internet addresses of the destinations view
+    pub remotes: usize,
+    /// hosts with their own speed history
+    pub host_histories: usize,
+    /// pairs with a total since the start
+    pub pair_totals: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            hosts: 4096,
+            seen_hosts: 65536,
+            flows: 4096,
+            remotes: 4096,
+            host_histories: 256,
+            pair_totals: 16384,
+        }
+    }
+}
+
+/// How full a bounded collection is, for the capture stats popup.
+#[derive(Clone, Copy, Debug)]
+pub struct CollectionSize {
+    pub name: &'static str,
+    pub len: usize,
+    pub limit: usize,
+}
+
+impl CollectionSize {
+    pub fn new(name: &'static str, len: usize, limit: usize) -> Self {
+        Self { name, len, limit }
+    }
+}
@@ -1,17 +1,26 @@
-use std::path::PathBuf;
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
 
-use color_eyre::eyre::Result;
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
+use crate::syslog::SyslogLayer;
+
 lazy_static! {
     pub static ref PROJECT_NAME: String = env!("CARGO_CRATE_NAME").to_uppercase().to_string();
     pub static ref DATA_FOLDER: Option<PathBuf> =
         std::env::var(format!("{}_DATA", PROJECT_NAME.clone()))
             .ok()
             .map(PathBuf::from);
+    pub static ref CONFIG_FOLDER: Option<PathBuf> =
+        std::env::var(format!("{}_CONFIG", PROJECT_NAME.clone()))
+            .ok()
+            .map(PathBuf::from);
     pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
     pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
@@ -31,11 +40,57 @@ pub fn get_data_dir() -> PathBuf {
     directory
 }
 
-pub fn initialize_logging() -> Result<()> {
-    let directory = get_data_dir();
-    std::fs::create_dir_all(directory.clone())?;
-    let log_path = directory.join(LOG_FILE.clone());
-    let log_file = std::fs::File::create(log_path)?;
+pub fn get_config_dir() -> PathBuf {
+    let directory = if let Some(s) = CONFIG_FOLDER.clone() {
+        s
+    } else if let Some(proj_dirs) = project_directory() {
+        proj_dirs.config_local_dir().to_path_buf()
+    } else {
+        PathBuf::from(".").join(".config")
+    };
+    directory
+}
+
+/// Where the log goes: `--log-file`, else `NETUI_DATA`, else the XDG state
+/// directory where the platform has one, else the data directory.
+pub fn log_file_path(log_file: Option<PathBuf>) -> PathBuf {
+    select_log_path(
+        log_file,
+        DATA_FOLDER.clone(),
+        project_directory().and_then(|dirs| dirs.state_dir().map(PathBuf::from)),
+        get_data_dir(),
+    )
+}
+
+fn select_log_path(
+    log_file: Option<PathBuf>,
+    data_folder: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    data_dir: PathBuf,
+) -> PathBuf {
+    log_file.unwrap_or_else(|| {
+        data_folder
+            .or(state_dir)
+            .unwrap_or(data_dir)
+            .join(LOG_FILE.clone())
+    })
+}
+
+/// Logs to `path`, and the host and alert events to `syslog` when given.
+/// When the file can't be created netui runs without a log file, the reason
+/// is returned for the app to show.
+pub fn initialize_logging(path: &Path, syslog: Option<SyslogLayer>) -> Result<(), String> {
+    let log_file = match open_log_file(path) {
+        Ok(log_file) => log_file,
+        Err(e) => {
+            // still needed for the span traces of error reports
+            tracing_subscriber::registry()
+                .with(syslog)
+                .with(ErrorLayer::default())
+                .init();
+            return Err(format!("{}: {}", path.display(), e));
+        }
+    };
     std::env::set_var(
         "RUST_LOG",
         std::env::var("RUST_LOG")
@@ -51,11 +106,19 @@ pub fn initialize_logging() -> Result<()> {
         .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
     tracing_subscriber::registry()
         .with(file_subscriber)
+        .with(syslog)
         .with(ErrorLayer::default())
         .init();
     Ok(())
 }
 
+fn open_log_file(path: &Path) -> io::Result<File> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    File::create(path)
+}
+
 /// Similar to the `std::dbg!` macro, but generates `tracing` events rather
 /// than printing to stdout.
 ///
@@ -81,3 +144,42 @@ macro_rules! trace_dbg {
         trace_dbg!(level: tracing::Level::DEBUG, $ex)
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_log_path_prefers_the_option_then_the_variable_then_xdg() {
+        let select =
+            |log_file: Option<&str>, data_folder: Option<&str>, state_dir: Option<&str>| {
+                select_log_path(
+                    log_file.map(PathBuf::from),
+                    data_folder.map(PathBuf::from),
+                    state_dir.map(PathBuf::from),
+                    PathBuf::from("/data"),
+                )
+            };
+        let log = |dir: &str| Path::new(dir).join(LOG_FILE.as_str());
+        assert_eq!(
+            select(Some("/tmp/x.log"), Some("/env"), Some("/state")),
+            Path::new("/tmp/x.log")
+        );
+        assert_eq!(select(None, Some("/env"), Some("/state")), log("/env"));
+        assert_eq!(select(None, None, Some("/state")), log("/state"));
+        // macOS and Windows have no state directory
+        assert_eq!(select(None, None, None), log("/data"));
+    }
+
+    #[test]
+    fn the_log_directory_is_created_or_the_reason_returned() {
+        let dir = std::env::temp_dir().join(format!("netui-logging-{}", std::process::id()));
+        let path = dir.join("state").join("netui.log");
+        assert!(open_log_file(&path).is_ok());
+        assert!(path.is_file());
+        // a read-only home is alike: the directory can't be made
+        let blocked = path.join("netui.log");
+        assert!(open_log_file(&blocked).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,213 @@
+use clap::ValueEnum;
+use pnet::util::MacAddr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum MacSeparator {
+    /// aa:bb:cc:dd:ee:ff
+    #[default]
+    Colon,
+    /// aa-bb-cc-dd-ee-ff
+    Dash,
+    /// aabb.ccdd.eeff
+    Dot,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum MacCase {
+    #[default]
+    Lower,
+    Upper,
+}
+
+/// How MAC addresses are shown, the one place hardware addresses get formatted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MacFormat {
+    pub separator: MacSeparator,
+    pub case: MacCase,
+}
+
+impl MacFormat {
+    pub fn new(separator: MacSeparator, case: MacCase) -> Self {
+        Self { separator, case }
+    }
+
+    pub fn format(&self, mac: MacAddr) -> String {
+        self.format_bytes(&mac.octets())
+    }
+
+    /// Any hardware address, MAC-48 or EUI-64 alike.
+    pub fn format_bytes(&self, bytes: &[u8]) -> String {
+        self.groups(bytes).join(self.separator_str())
+    }
+
+    /// Fits a formatted address in `width` columns, cutting between groups
+    /// and marking the cut with an ellipsis, never in the middle of an octet.
+    pub fn truncate(&self, mac: MacAddr, width: usize) -> String {
+        let formatted = self.format(mac);
+        if formatted.len() <= width {
+            return formatted;
+        }
+        let mut truncated = String::new();
+        for group in self.groups(&mac.octets()) {
+            let separator = if truncated.is_empty() {
+                ""
+            } else {
+                self.separator_str()
+            };
+            // room is kept for the separator and the ellipsis
+            if truncated.len() + separator.len() + group.len() + 2 > width {
+                break;
+            }
+            truncated.push_str(separator);
+            truncated.push_str(&group);
+        }
+        if !truncated.is_empty() {
+            truncated.push_str(self.separator_str());
+        }
+        truncated.push('…');
+        truncated
+    }
+
+    fn groups(&self, bytes: &[u8]) -> Vec<String> {
+        let hex = |byte: &u8| match self.case {
+            MacCase::Lower => format!("{:02x}", byte),
+            MacCase::Upper => format!("{:02X}", byte),
+        };
+        match self.separator {
+            MacSeparator::Colon | MacSeparator::Dash => bytes.iter().map(hex).collect(),
+            MacSeparator::Dot => bytes
+                .chunks(2)
+                .map(|pair| pair.iter().map(hex).collect())
+                .collect(),
+        }
+    }
+
+    fn separator_str(&self) -> &'static str {
+        match self.separator {
+            MacSeparator::Colon => ":",
+            MacSeparator::Dash => "-",
+            MacSeparator::Dot => ".",
+        }
+    }
+}
+
+/// Reads a MAC in any of the displayed formats, in either case, or as bare hex.
+///
+/// EUI-64 identifiers built from a MAC-48 (`ff:fe` in the middle) are turned
+/// back into that MAC, other EUI-64 identifiers are rejected.
+pub fn parse_mac(text: &str) -> Result<MacAddr, String> {
+    let hex: String = text
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if !hex.len().is_multiple_of(2) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not a MAC address", text));
+    }
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("'{}' is not a MAC address", text))?;
+    match bytes[..] {
+        [a, b, c, d, e, f] => Ok(MacAddr::new(a, b, c, d, e, f)),
+        [a, b, c, 0xff, 0xfe, f, g, h] => Ok(MacAddr::new(a, b, c, f, g, h)),
+        [_, _, _, _, _, _, _, _] => Err(format!(
+            "'{}' is an EUI-64 identifier not derived from a MAC address",
+            text
+        )),
+        _ => Err(format!("'{}' is not a MAC address", text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MACS: [MacAddr; 3] = [
+        MacAddr(0x00, 0x1b, 0x63, 0x0a, 0xbc, 0xde),
+        MacAddr(0xff, 0xff, 0xff, 0xff, 0xff, 0xff),
+        MacAddr(0, 0, 0, 0, 0, 0),
+    ];
+
+    fn formats() -> impl Iterator<Item = MacFormat> {
+        MacSeparator::value_variants().iter().flat_map(|separator| {
+            MacCase::value_variants()
+                .iter()
+                .map(|case| MacFormat::new(*separator, *case))
+        })
+    }
+
+    #[test]
+    fn every_format_reads_back() {
+        for format in formats() {
+            for mac in MACS {
+                assert_eq!(parse_mac(&format.format(mac)), Ok(mac), "{:?}", format);
+            }
+        }
+        let mac = MACS[0];
+        let shown = |separator, case| MacFormat::new(separator, case).format(mac);
+        assert_eq!(
+            shown(MacSeparator::Colon, MacCase::Lower),
+            "00:1b:63:0a:bc:de"
+        );
+        assert_eq!(
+            shown(MacSeparator::Dash, MacCase::Upper),
+            "00-1B-63-0A-BC-DE"
+        );
+        assert_eq!(shown(MacSeparator::Dot, MacCase::Lower), "001b.630a.bcde");
+        assert_eq!(parse_mac(" 001B630ABCDE "), Ok(mac));
+    }
+
+    #[test]
+    fn eui_64_identifiers_read_back_as_their_mac() {
+        let eui_64 = [0x00, 0x1b, 0x63, 0xff, 0xfe, 0x0a, 0xbc, 0xde];
+        for format in formats() {
+            assert_eq!(parse_mac(&format.format_bytes(&eui_64)), Ok(MACS[0]));
+        }
+        assert_eq!(
+            MacFormat::default().format_bytes(&eui_64),
+            "00:1b:63:ff:fe:0a:bc:de"
+        );
+        assert!(parse_mac("00:1b:63:12:34:0a:bc:de")
+            .unwrap_err()
+            .contains("EUI-64"));
+    }
+
+    #[test]
+    fn malformed_addresses_are_rejected() {
+        for text in [
+            "",
+            "00:1b:63:0a:bc",
+            "00:1b:63:0a:bc:d",
+            "00:1b:63:0a:bc:zz",
+        ] {
+            assert!(parse_mac(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn truncation_cuts_between_octets() {
+        let colon = MacFormat::default();
+        let mac = MACS[0];
+        assert_eq!(colon.truncate(mac, 17), "00:1b:63:0a:bc:de");
+        assert_eq!(colon.truncate(mac, 16), "00:1b:63:0a:bc:…");
+        assert_eq!(colon.truncate(mac, 10), "00:1b:63:…");
+        assert_eq!(colon.truncate(mac, 1), "…");
+        let dot = MacFormat::new(MacSeparator::Dot, MacCase::Upper);
+        assert_eq!(dot.truncate(mac, 11), "001B.630A.…");
+        for format in formats() {
+            for width in 1..=17 {
+                let truncated = format.truncate(mac, width);
+                assert!(truncated.chars().count() <= width);
+                let kept = truncated.trim_end_matches('…');
+                assert!(format.format(mac).starts_with(kept));
+                assert!(
+                    kept.is_empty()
+                        || kept.ends_with(format.separator_str())
+                        || kept == format.format(mac)
+                );
+            }
+        }
+    }
+}
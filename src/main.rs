@@ -1,63 +1,562 @@
-use std::io;
+use std::{
+    io::{self, IsTerminal, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use logging::initialize_logging;
+use budget::DailyUsage;
+use command::Action;
+use config::Config;
+use crossterm::event::KeyEventKind;
+use enrich::{KnownHosts, Oui, Pipeline};
+use format::parse_size;
+use headless::{HeadlessOptions, OneshotOptions, OutputFormat, Units};
+use instance::InstanceLock;
+use logging::{initialize_logging, log_file_path};
+use mac_format::{MacCase, MacFormat, MacSeparator};
+use picker::{InterfacePicker, Picked};
 use ratatui::{backend::CrosstermBackend, Terminal};
-use scanner::Scanner;
+use regex::Regex;
+use resolver::{Resolver, SystemBackend};
+use scanner::{InterfaceSelector, Scanner, StatInterval, MIN_CAPTURE_BUFFER, MIN_STAT_INTERVAL};
+use state::State;
+use stats_aggregator::{ConnectionOrder, ConnectionSort};
+use strings::Lang;
+use syslog::SyslogLayer;
+use theme::{ColorChoice, Theme, ThemeName};
+use web::WebSnapshot;
 
 use crate::{
-    app::{App, AppResult},
+    app::{App, AppResult, SwitchedNetworkHint, DEFAULT_ARP_SCAN_THRESHOLD},
     event::{Event, EventHandler},
     tui::Tui,
 };
 
+pub mod activity;
 pub mod app;
+pub mod audit;
+pub mod budget;
+pub mod capture_stats;
+pub mod clipboard;
+pub mod command;
+pub mod config;
+pub mod connections_table;
+pub mod destinations_table;
+pub mod doctor;
+pub mod dry_run;
+pub mod enrich;
 pub mod event;
+pub mod export;
+pub mod format;
+pub mod headless;
+pub mod hints;
+pub mod history;
 pub mod hosts_table;
+pub mod instance;
+pub mod interface_info;
+pub mod inventory;
+pub mod limits;
 pub mod logging;
+pub mod mac_format;
+pub mod networks;
+pub mod picker;
+pub mod report;
+pub mod resolver;
 pub mod scanner;
+pub mod service_hint;
+pub mod settings;
+#[cfg(test)]
+mod smoke;
+pub mod snapshot;
+pub mod state;
 pub mod stats_aggregator;
+pub mod strings;
+pub mod sweep_report;
+pub mod syslog;
+pub mod text_table;
+pub mod theme;
+pub mod traffic_category;
 pub mod tui;
 pub mod ui;
+pub mod watch;
+pub mod web;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
-    /// Name of the interface to watch
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Name of the interface to watch, picked from a list when left out
     #[arg(short, long)]
-    name: String,
+    name: Option<String>,
+
+    /// Watch the interface whose name matches this regex, e.g. '^en[0-9]+$'
+    #[arg(long, value_name = "REGEX", conflicts_with = "name")]
+    interface_regex: Option<Regex>,
+
+    /// Only listen: for tap/bridge interfaces without a local IP, hosts are discovered passively
+    #[arg(long)]
+    monitor_only: bool,
+
+    /// Never transmit anything: no ARP sweeps, hosts are only discovered from observed traffic
+    #[arg(long)]
+    passive: bool,
+
+    /// Write the discovered hosts as nmap XML to this file on exit (and on `x`)
+    #[arg(long, value_name = "PATH")]
+    export_nmap: Option<PathBuf>,
+
+    /// Write a self-contained HTML report to this file on exit (and on `R`)
+    #[arg(long, value_name = "PATH")]
+    export_html: Option<PathBuf>,
+
+    /// How often captured traffic is turned into speeds, in milliseconds (at least 200)
+    #[arg(long, value_name = "MS", default_value_t = 1000, value_parser = parse_stat_interval)]
+    stat_interval_ms: u64,
+
+    /// Flush traffic less often (2 to 5 seconds) while the network is quiet
+    #[arg(long)]
+    adaptive_stat_interval: bool,
+
+    /// Only look at the frames tagged with this VLAN id, on a trunk port
+    #[arg(long, value_name = "ID", value_parser = clap::value_parser!(u16).range(1..4095))]
+    vlan: Option<u16>,
+
+    /// Bytes of each frame read, e.g. 16KiB; longer frames are cut short
+    #[arg(long, value_name = "SIZE", default_value = "64KiB", value_parser = parse_capture_buffer)]
+    capture_buffer: usize,
+
+    /// Run without root: no capture nor ARP sweeps, hosts come from the OS ARP cache
+    #[arg(long, conflicts_with_all = ["monitor_only", "passive"])]
+    no_capture: bool,
+
+    /// Write the log there instead of the state or data directory
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Also send host discoveries, hosts going offline and alerts to the local syslog
+    #[arg(long)]
+    syslog: bool,
+
+    /// Send ARP requests to every address as soon as the capture is up
+    #[arg(long)]
+    scan_on_start: bool,
+
+    /// Sweep networks larger than a /20 without asking first
+    #[arg(long)]
+    force_large_scan: bool,
+
+    /// Print the interfaces, marking the one netui would watch as default, and exit
+    #[arg(long)]
+    list_interfaces: bool,
+
+    /// Only log the frames and files netui would send and write
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Color theme
+    #[arg(long, value_enum, default_value_t = ThemeName::Dark)]
+    theme: ThemeName,
+
+    /// Colors of the UI and of `doctor`, auto reads NO_COLOR, COLORTERM and TERM
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto, global = true)]
+    color: ColorChoice,
+
+    /// Language of the interface, from LC_ALL, LC_MESSAGES or LANG by default
+    #[arg(long, value_enum)]
+    lang: Option<Lang>,
+
+    /// ARP requests per minute above which a host is flagged as scanning the network
+    #[arg(long, value_name = "REQUESTS", default_value_t = DEFAULT_ARP_SCAN_THRESHOLD)]
+    arp_scan_threshold: u32,
+
+    /// How MAC addresses are written
+    #[arg(long, value_enum, default_value_t = MacSeparator::Colon)]
+    mac_separator: MacSeparator,
+
+    /// Case of the hex digits of MAC addresses
+    #[arg(long, value_enum, default_value_t = MacCase::Lower)]
+    mac_case: MacCase,
+
+    /// Never explain why other hosts' traffic isn't visible on a switched network
+    #[arg(long)]
+    no_switch_hint: bool,
+
+    /// Only account the traffic of this host, `F` toggles it on the selected host
+    #[arg(long, value_name = "IP")]
+    solo: Option<Ipv4Addr>,
+
+    /// Also serve a live dashboard of the hosts over HTTP on this port
+    #[arg(long, value_name = "PORT")]
+    web: Option<u16>,
+
+    /// Address the web dashboard listens on, it has no authentication
+    #[arg(long, value_name = "IP", default_value_t = web::DEFAULT_BIND, requires = "web")]
+    web_bind: IpAddr,
+
+    /// Run without the terminal UI, printing the hosts as they are found until Ctrl-C
+    #[arg(long)]
+    headless: bool,
+
+    /// Sweep the networks once, print the hosts found as JSON and exit
+    #[arg(long, conflicts_with_all = ["headless", "passive", "monitor_only", "no_capture", "scan_on_start"])]
+    oneshot: bool,
+
+    /// Seconds --oneshot waits for late replies once the sweep is sent
+    #[arg(long, value_name = "SECS", default_value_t = 2, requires = "oneshot")]
+    grace: u64,
+
+    /// Seconds --oneshot may run in all, it fails past them
+    #[arg(long, value_name = "SECS", default_value_t = 60, requires = "oneshot",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: u64,
+
+    /// With --headless, also print the connections every --interval seconds
+    #[arg(long, requires = "headless")]
+    watch_connections: bool,
+
+    /// Seconds between two connection listings
+    #[arg(long, value_name = "SECS", default_value_t = 5, requires = "watch_connections",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    interval: u64,
+
+    /// How --headless prints
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, requires = "headless")]
+    format: OutputFormat,
+
+    /// Order of the listed connections, bandwidth and bytes list the busiest first
+    #[arg(long, value_enum, default_value_t = ConnectionOrder::Bandwidth, requires = "watch_connections")]
+    sort: ConnectionOrder,
+
+    /// List the connections in the opposite order
+    #[arg(long, requires = "watch_connections")]
+    reverse: bool,
+
+    /// Only list the first connections
+    #[arg(long, value_name = "N", requires = "watch_connections")]
+    top: Option<usize>,
+
+    /// Unit of the speeds --headless prints
+    #[arg(long, value_enum, default_value_t = Units::Bits, requires = "headless")]
+    units: Units,
 }
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check the environment (capture permissions, config, data directory...) and exit
+    Doctor {
+        /// Also check that the web dashboard can listen on this port
+        #[arg(long, value_name = "PORT")]
+        web: Option<u16>,
+    },
+    /// Print the addresses each MAC had on an interface, as recorded while watching it
+    Audit {
+        /// The interface, as netui named it
+        interface: String,
+        /// Print CSV instead of a table
+        #[arg(long)]
+        csv: bool,
+    },
+    /// Show the hosts added, removed and changed between two snapshots saved with `w`
+    Diff {
+        /// The older snapshot, by name or path
+        a: String,
+        /// The newer one
+        b: String,
+    },
+}
+
+const TICK_RATE_MS: u64 = 250;
+/// upper bound of queued events handled between two frames, so a flood of
+/// scanner events can't starve the redraw
+const MAX_EVENTS_PER_FRAME: usize = 1024;
+/// how long operations in flight get to finish once the user quit
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[tokio::main]
 async fn main() -> AppResult<()> {
     let args = Args::parse();
-    let interface_name = args.name;
+    let color_depth = args.color.depth(io::stdout().is_terminal());
+    theme::set_color_depth(color_depth);
+    match args.command {
+        Some(Command::Doctor { web }) => {
+            let status = doctor::run(web, color_depth).await;
+            std::process::exit(status.exit_code());
+        }
+        Some(Command::Diff { a, b }) => return inventory::print_diff(&a, &b, color_depth),
+        Some(Command::Audit { interface, csv }) => return audit::print(&interface, csv),
+        None => {}
+    }
+    if args.list_interfaces {
+        let default = Scanner::default_interface(args.name.clone());
+        println!(
+            "{}",
+            interface_info::list(
+                &pnet_datalink::interfaces(),
+                default.as_deref(),
+                MacFormat::new(args.mac_separator, args.mac_case)
+            )
+        );
+        return Ok(());
+    }
+    if (args.headless || args.oneshot) && args.name.is_none() && args.interface_regex.is_none() {
+        let mode = if args.oneshot {
+            "--oneshot"
+        } else {
+            "--headless"
+        };
+        return Err(format!("{} needs --name or --interface-regex", mode).into());
+    }
 
-    initialize_logging()?;
+    strings::set_lang(args.lang.unwrap_or_else(Lang::from_env));
+    if args.dry_run {
+        dry_run::enable();
+    }
+    let log_path = log_file_path(args.log_file);
+    let (syslog, syslog_error) = match args.syslog.then(SyslogLayer::connect) {
+        Some(Ok(layer)) => (Some(layer), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    let logging = initialize_logging(&log_path, syslog);
+    let config = Config::load()?;
 
-    // Initialize the terminal user interface.
-    let backend = CrosstermBackend::new(io::stdout());
-    let terminal = Terminal::new(backend)?;
-    let mut tui = Tui::new(terminal);
-    let mut events = EventHandler::new(250);
-    let scanner = Scanner::new(events.get_sender_clone(), interface_name)?;
+    let mut events = if args.headless || args.oneshot {
+        EventHandler::without_terminal()
+    } else {
+        EventHandler::new(TICK_RATE_MS)
+    };
+    let interface_selector = match (args.interface_regex, args.name) {
+        (Some(regex), _) => InterfaceSelector::Regex(regex),
+        (None, Some(name)) => InterfaceSelector::Name(name),
+        (None, None) => {
+            let picker = InterfacePicker::new(Scanner::usable_interfaces())
+                .theme(Theme::new(args.theme))
+                .mac_format(MacFormat::new(args.mac_separator, args.mac_case));
+            match pick_interface(picker, &mut events).await? {
+                // the exact name, another interface may contain it
+                Picked::Interface(name) => {
+                    InterfaceSelector::Regex(Regex::new(&format!("^{}$", regex::escape(&name)))?)
+                }
+                Picked::Nothing => return Ok(()),
+            }
+        }
+    };
+    let stat_interval = StatInterval {
+        base: Duration::from_millis(args.stat_interval_ms),
+        adaptive: args.adaptive_stat_interval,
+    };
+    let scanner = if args.no_capture {
+        Scanner::without_capture(events.get_sender_clone(), interface_selector, stat_interval)?
+    } else {
+        Scanner::new(
+            events.get_sender_clone(),
+            interface_selector,
+            args.monitor_only,
+            args.passive,
+            stat_interval,
+            args.vlan,
+            args.capture_buffer,
+        )?
+    };
 
     // Create an application.
     let mut app = App::new(scanner)?;
+    app.export_nmap_path = args.export_nmap;
+    app.export_html_path = args.export_html;
+    app.theme = Theme::new(args.theme);
+    app.mac_format = MacFormat::new(args.mac_separator, args.mac_case);
+    app.arp_scan_threshold = args.arp_scan_threshold;
+    app.force_large_scan = args.force_large_scan;
+    app.daily_usage = DailyUsage::new(config.budgets()?);
+    app.combined_speed = config.combined_speed;
+    app.limits = config.limits;
+    app.stats_aggregator.set_limits(config.limits);
+    app.link_speed_override = config.link_speed()?;
+    app.watch_options = config.watch;
+    app.same_ip = config.same_ip;
+    let oui = match config.vendor_overrides() {
+        Some(path) => Oui::load().with_overrides(&path)?,
+        None => Oui::load(),
+    };
+    app.enrichers = Pipeline::new().with(oui).with(KnownHosts);
+    // reverse lookups send DNS queries
+    if !app.passive {
+        app.resolver = Some(Resolver::new(
+            SystemBackend,
+            config.resolver,
+            events.get_sender_clone(),
+        ));
+    }
+    // a second instance on the interface runs on, without saving anything
+    let _instance_lock = match InstanceLock::acquire(&app.interface) {
+        Ok(lock) => {
+            app.state = State::load(&app.interface)?;
+            Some(lock)
+        }
+        Err(e) => {
+            app.notify(format!("{}, labels won't be saved", e));
+            app.state_error = Some(e.to_string());
+            None
+        }
+    };
+    app.switched_network_hint = SwitchedNetworkHint::new(!args.no_switch_hint);
+    app.set_solo(args.solo);
+    match logging {
+        Ok(()) => app.log_file = Some(log_path),
+        Err(reason) => {
+            app.notify(format!("not logging, {}", reason));
+            app.logging_error = Some(reason);
+        }
+    }
+    if let Some(e) = syslog_error {
+        app.notify(format!("not sending to syslog: {}", e));
+    }
+    if let Some(port) = args.web {
+        let listener = web::bind(args.web_bind, port).await?;
+        // until the first stat tick, serve an empty table rather than nothing
+        let snapshot: WebSnapshot = Arc::new(Mutex::new(app.snapshot(None).to_json()));
+        app.web_snapshot = Some(snapshot.clone());
+        app.web_address = Some(SocketAddr::from((args.web_bind, port)));
+        tokio::spawn(web::serve(listener, snapshot));
+    }
+
+    if args.scan_on_start {
+        // the sweep waits for the capture loop, replies can't be missed
+        if app.passive || app.monitor_only || app.no_capture {
+            tracing::warn!("--scan-on-start ignored, this mode sends no ARP requests");
+            app.notify("--scan-on-start ignored, this mode sends no ARP requests".to_string());
+        } else if args.headless && app.network_plan().needs_confirmation() && !app.force_large_scan
+        {
+            // nobody is there to confirm it
+            tracing::warn!("--scan-on-start skipped, the networks are larger than a /20");
+            eprintln!("--scan-on-start skipped, the networks are larger than a /20; pass --force-large-scan to sweep them");
+        } else {
+            app.perform(Action::Scan);
+        }
+    }
+
+    if args.oneshot {
+        let options = OneshotOptions {
+            grace: Duration::from_secs(args.grace),
+            timeout: Duration::from_secs(args.timeout),
+        };
+        headless::oneshot(&mut app, &mut events, options).await?;
+    } else if args.headless {
+        let options = HeadlessOptions {
+            format: args.format,
+            units: args.units,
+            watch_connections: args
+                .watch_connections
+                .then(|| Duration::from_secs(args.interval)),
+            sort: ConnectionSort {
+                order: args.sort,
+                descending: matches!(
+                    args.sort,
+                    ConnectionOrder::Bandwidth | ConnectionOrder::Bytes
+                ) != args.reverse,
+            },
+            top: args.top,
+        };
+        headless::run(&mut app, &mut events, options).await?;
+    } else {
+        run_tui(&mut app, &mut events).await?;
+    }
+
+    // Give the operations in flight a chance to wind down cleanly.
+    app.stop_operations();
+    let deadline = tokio::time::Instant::now() + SHUTDOWN_TIMEOUT;
+    while !app.operations.is_empty() {
+        match tokio::time::timeout_at(deadline, events.next()).await {
+            Ok(Ok(Event::Scanner(worker_event))) => app.handle_worker_events(worker_event)?,
+            Ok(Ok(_)) => {}
+            Ok(Err(_)) | Err(_) => break,
+        }
+    }
+
+    if let Some(path) = &app.export_nmap_path {
+        app.export_nmap(path)?;
+    }
+    if let Some(path) = &app.export_html_path {
+        app.export_html(path)?;
+    }
+    Ok(())
+}
 
+/// Lets the user pick the interface to watch, the terminal is restored
+/// whatever they do.
+async fn pick_interface(
+    mut picker: InterfacePicker,
+    events: &mut EventHandler,
+) -> AppResult<Picked> {
+    if picker.interfaces.is_empty() {
+        return Err("no interface is up, pass --name to pick one anyway".into());
+    }
+    let backend = CrosstermBackend::new(io::stdout());
+    let mut tui = Tui::new(Terminal::new(backend)?);
+    tui.init()?;
+    let picked = loop {
+        if let Err(e) = tui.draw_picker(&mut picker) {
+            break Err(e);
+        }
+        match events.next().await {
+            Ok(Event::Key(key_event)) if key_event.kind != KeyEventKind::Release => {
+                if let Some(picked) = picker.handle_key(key_event) {
+                    break Ok(picked);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    tui.exit()?;
+    picked
+}
+
+/// The terminal UI, until the user quits.
+async fn run_tui(app: &mut App, events: &mut EventHandler) -> AppResult<()> {
+    // Initialize the terminal user interface.
+    let backend = CrosstermBackend::new(io::stdout());
+    let terminal = Terminal::new(backend)?;
+    let mut tui = Tui::new(terminal);
     tui.init()?;
+    tui.draw(app)?;
+    let frame_interval = Duration::from_millis(TICK_RATE_MS);
+    let mut last_draw = Instant::now();
     // Start the main loop.
     while app.running {
-        // Render the user interface.
-        tui.draw(&mut app)?;
-        // Handle events.
-        match events.next().await? {
-            Event::Tick => app.tick(),
-            Event::Key(key_event) => app.handle_key_events(key_event)?,
-            Event::Mouse(_) => {}
-            Event::Resize(_, _) => {}
-            Event::Scanner(worker_event) => app.handle_worker_events(worker_event)?,
+        // Handle the next event and everything already queued behind it.
+        let mut redraw_now = false;
+        let mut next_event = Some(events.next().await?);
+        let mut handled = 0;
+        // the selection and the scrollbar follow the whole batch at once
+        app.begin_bulk_update();
+        while let Some(event) = next_event {
+            // keys must feel instant, everything else waits for the frame clock
+            redraw_now |= matches!(event, Event::Key(_) | Event::Resize(_, _) | Event::Tick);
+            handle_event(app, event)?;
+            handled += 1;
+            next_event = if app.running && handled < MAX_EVENTS_PER_FRAME {
+                events.try_next()
+            } else {
+                None
+            };
+        }
+        app.end_bulk_update();
+        if std::mem::take(&mut app.ring_bell) {
+            io::stdout().write_all(b"\x07")?;
+            io::stdout().flush()?;
+        }
+        // Render the user interface, when something shown changed.
+        if app.running && app.dirty && (redraw_now || last_draw.elapsed() >= frame_interval) {
+            tui.draw(app)?;
+            app.mark_drawn();
+            last_draw = Instant::now();
         }
     }
 
@@ -65,3 +564,30 @@ async fn main() -> AppResult<()> {
     tui.exit()?;
     Ok(())
 }
+
+fn parse_stat_interval(text: &str) -> Result<u64, String> {
+    let ms: u64 = text.parse().map_err(|e| format!("{}", e))?;
+    if u128::from(ms) < MIN_STAT_INTERVAL.as_millis() {
+        return Err(format!("at least {} ms", MIN_STAT_INTERVAL.as_millis()));
+    }
+    Ok(ms)
+}
+
+fn parse_capture_buffer(text: &str) -> Result<usize, String> {
+    let bytes = usize::try_from(parse_size(text)?).map_err(|e| format!("{}", e))?;
+    if bytes < MIN_CAPTURE_BUFFER {
+        return Err(format!("at least {} bytes", MIN_CAPTURE_BUFFER));
+    }
+    Ok(bytes)
+}
+
+fn handle_event(app: &mut App, event: Event) -> AppResult<()> {
+    match event {
+        Event::Tick => app.tick(),
+        Event::Key(key_event) => app.handle_key_events(key_event)?,
+        Event::Mouse(_) => {}
+        Event::Resize(_, _) => app.dirty = true,
+        Event::Scanner(worker_event) => app.handle_worker_events(worker_event)?,
+    }
+    Ok(())
+}
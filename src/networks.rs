@@ -0,0 +1,157 @@
+use pnet::ipnetwork::{IpNetwork, Ipv4Network};
+
+/// sweeps of networks larger than this prefix are only sent once confirmed
+pub const LARGE_SWEEP_PREFIX: u8 = 20;
+/// shorter prefixes are rarely a real LAN, a leftover or mistyped address more likely
+const SUSPICIOUS_PREFIX: u8 = 16;
+
+/// The IPv4 networks of an interface as sweeps should see them: each
+/// address once, and what looked wrong on the way.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NetworkPlan {
+    /// the networks to sweep, in the interface's order, none inside another
+    pub scan: Vec<Ipv4Network>,
+    /// duplicated, overlapping or suspiciously large networks
+    pub warnings: Vec<String>,
+}
+
+impl NetworkPlan {
+    /// Plans the sweeps of the IPv4 networks among `networks`. A network
+    /// configured twice, or inside another one, is left out: VPN clients and
+    /// secondary addresses often leave such networks behind.
+    pub fn analyze(networks: &[IpNetwork]) -> Self {
+        let networks: Vec<Ipv4Network> = networks
+            .iter()
+            .filter_map(|network| match network {
+                IpNetwork::V4(network) => Some(*network),
+                IpNetwork::V6(_) => None,
+            })
+            .collect();
+        // the largest networks first, what comes later can only be inside them
+        let mut order: Vec<usize> = (0..networks.len()).collect();
+        order.sort_by_key(|i| (networks[*i].prefix(), networks[*i].network(), *i));
+        let mut kept: Vec<usize> = vec![];
+        let mut warnings = vec![];
+        for i in order {
+            let network = networks[i];
+            let outer = kept.iter().map(|k| networks[*k]).find(|outer| {
+                outer.contains(network.network()) && outer.prefix() <= network.prefix()
+            });
+            match outer {
+                Some(outer) if outer.prefix() == network.prefix() => warnings.push(format!(
+                    "{} and {} are the same network {}/{}, it is swept once",
+                    outer,
+                    network,
+                    network.network(),
+                    network.prefix()
+                )),
+                Some(outer) => warnings.push(format!(
+                    "{} lies inside {}, its addresses are swept once",
+                    network, outer
+                )),
+                None => {
+                    if network.prefix() < SUSPICIOUS_PREFIX {
+                        warnings.push(format!(
+                            "{} spans {} addresses, check it isn't a leftover or mistyped address",
+                            network,
+                            network.size()
+                        ));
+                    }
+                    kept.push(i);
+                }
+            }
+        }
+        kept.sort_unstable();
+        Self {
+            scan: kept.into_iter().map(|i| networks[i]).collect(),
+            warnings,
+        }
+    }
+
+    /// The networks larger than a /20, a sweep asks before probing them.
+    pub fn large(&self) -> impl Iterator<Item = &Ipv4Network> {
+        self.scan
+            .iter()
+            .filter(|network| network.prefix() < LARGE_SWEEP_PREFIX)
+    }
+
+    pub fn needs_confirmation(&self) -> bool {
+        self.large().next().is_some()
+    }
+
+    /// The addresses a sweep probes.
+    pub fn addresses(&self) -> u64 {
+        self.scan.iter().map(|network| network.size() as u64).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn network(text: &str) -> IpNetwork {
+        text.parse().unwrap()
+    }
+
+    fn plan(networks: &[&str]) -> NetworkPlan {
+        let networks: Vec<IpNetwork> = networks.iter().map(|text| network(text)).collect();
+        NetworkPlan::analyze(&networks)
+    }
+
+    fn scanned(plan: &NetworkPlan) -> Vec<String> {
+        plan.scan
+            .iter()
+            .map(|network| network.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn distinct_networks_are_all_swept_quietly() {
+        let plan = plan(&["192.168.1.20/24", "10.8.0.2/24", "fe80::1/64"]);
+        assert_eq!(scanned(&plan), ["192.168.1.20/24", "10.8.0.2/24"]);
+        assert!(plan.warnings.is_empty());
+        assert!(!plan.needs_confirmation());
+        assert_eq!(plan.addresses(), 512);
+    }
+
+    #[test]
+    fn a_network_configured_twice_is_swept_once() {
+        let plan = plan(&["192.168.1.20/24", "192.168.1.77/24"]);
+        assert_eq!(scanned(&plan), ["192.168.1.20/24"]);
+        assert_eq!(
+            plan.warnings,
+            ["192.168.1.20/24 and 192.168.1.77/24 are the same network 192.168.1.0/24, it is swept once"]
+        );
+    }
+
+    #[test]
+    fn a_network_inside_another_is_left_out() {
+        // the VPN's /24 lies in the office /22 configured after it
+        let plan = plan(&["10.1.2.5/24", "192.168.1.20/24", "10.1.0.9/22"]);
+        assert_eq!(scanned(&plan), ["192.168.1.20/24", "10.1.0.9/22"]);
+        assert_eq!(
+            plan.warnings,
+            ["10.1.2.5/24 lies inside 10.1.0.9/22, its addresses are swept once"]
+        );
+    }
+
+    #[test]
+    fn large_networks_warn_and_need_confirmation() {
+        let plan = plan(&["10.0.0.5/8", "172.16.4.1/20", "192.168.0.1/19"]);
+        assert_eq!(plan.warnings.len(), 1);
+        assert!(plan.warnings[0].starts_with("10.0.0.5/8 spans 16777216 addresses"));
+        let large: Vec<String> = plan.large().map(|network| network.to_string()).collect();
+        assert_eq!(large, ["10.0.0.5/8", "192.168.0.1/19"]);
+        assert!(plan.needs_confirmation());
+    }
+
+    #[test]
+    fn host_routes_are_kept() {
+        let plan = plan(&["192.168.1.20/32", "192.168.1.21/32", "192.168.1.20/32"]);
+        assert_eq!(scanned(&plan), ["192.168.1.20/32", "192.168.1.21/32"]);
+        assert_eq!(plan.warnings.len(), 1);
+        assert_eq!(plan.scan[1].ip(), Ipv4Addr::new(192, 168, 1, 21));
+    }
+}
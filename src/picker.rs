@@ -0,0 +1,154 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use pnet_datalink::NetworkInterface;
+use ratatui::widgets::TableState;
+
+use crate::{mac_format::MacFormat, theme::Theme};
+
+/// What the picker was left with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Picked {
+    Interface(String),
+    /// Esc, q or Ctrl-C, netui exits
+    Nothing,
+}
+
+/// The interface list shown when netui starts without `--name`: the
+/// scanner needs an interface before the app can exist.
+#[derive(Clone, Debug)]
+pub struct InterfacePicker {
+    pub interfaces: Vec<NetworkInterface>,
+    pub table_state: TableState,
+    pub mac_format: MacFormat,
+    pub theme: Theme,
+}
+
+impl InterfacePicker {
+    /// Lists `interfaces`, the first one selected.
+    pub fn new(interfaces: Vec<NetworkInterface>) -> Self {
+        Self {
+            interfaces,
+            table_state: TableState::default().with_selected(Some(0)),
+            mac_format: MacFormat::default(),
+            theme: Theme::default(),
+        }
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn mac_format(mut self, mac_format: MacFormat) -> Self {
+        self.mac_format = mac_format;
+        self
+    }
+
+    /// The name, addresses and MAC of each interface.
+    pub fn rows(&self) -> Vec<[String; 3]> {
+        self.interfaces
+            .iter()
+            .map(|nif| {
+                let ips: Vec<String> = nif.ips.iter().map(|ip| ip.to_string()).collect();
+                [
+                    nif.name.clone(),
+                    ips.join(", "),
+                    nif.mac
+                        .map_or_else(String::new, |mac| self.mac_format.format(mac)),
+                ]
+            })
+            .collect()
+    }
+
+    /// Moves the selection or picks, `Some` once the user is done.
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> Option<Picked> {
+        let last = self.interfaces.len().saturating_sub(1);
+        let selected = self.table_state.selected().unwrap_or(0);
+        match key_event.code {
+            KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => {
+                return Some(Picked::Nothing)
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Some(Picked::Nothing),
+            KeyCode::Enter => {
+                return self
+                    .interfaces
+                    .get(selected)
+                    .map(|nif| Picked::Interface(nif.name.clone()))
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.table_state.select(Some((selected + 1).min(last)))
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.table_state.select(Some(selected.saturating_sub(1)))
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pnet::ipnetwork::IpNetwork;
+    use pnet_datalink::MacAddr;
+
+    use super::*;
+
+    fn nif(name: &str, ip: &str) -> NetworkInterface {
+        NetworkInterface {
+            name: name.to_string(),
+            description: String::new(),
+            index: 1,
+            mac: Some(MacAddr(0, 0x1b, 0x63, 0, 0, 1)),
+            ips: vec![ip.parse::<IpNetwork>().unwrap()],
+            flags: 0,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::from(code)
+    }
+
+    #[test]
+    fn enter_picks_the_selected_interface() {
+        let mut picker = InterfacePicker::new(vec![
+            nif("eth0", "192.168.1.20/24"),
+            nif("wlan0", "10.0.0.5/24"),
+        ]);
+        assert_eq!(picker.handle_key(key(KeyCode::Char('j'))), None);
+        // the selection stops at the last one
+        picker.handle_key(key(KeyCode::Down));
+        assert_eq!(
+            picker.handle_key(key(KeyCode::Enter)),
+            Some(Picked::Interface("wlan0".to_string()))
+        );
+        picker.handle_key(key(KeyCode::Char('k')));
+        picker.handle_key(key(KeyCode::Up));
+        assert_eq!(
+            picker.handle_key(key(KeyCode::Enter)),
+            Some(Picked::Interface("eth0".to_string()))
+        );
+    }
+
+    #[test]
+    fn esc_picks_nothing() {
+        let mut picker = InterfacePicker::new(vec![nif("eth0", "192.168.1.20/24")]);
+        assert_eq!(picker.handle_key(key(KeyCode::Esc)), Some(Picked::Nothing));
+        assert_eq!(
+            picker.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Picked::Nothing)
+        );
+    }
+
+    #[test]
+    fn rows_show_the_addresses_and_mac() {
+        let picker = InterfacePicker::new(vec![nif("eth0", "192.168.1.20/24")]);
+        assert_eq!(
+            picker.rows(),
+            [[
+                "eth0".to_string(),
+                "192.168.1.20/24".to_string(),
+                "00:1b:63:00:00:01".to_string()
+            ]]
+        );
+    }
+}
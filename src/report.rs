@@ -0,0 +1,323 @@
+use std::{io, path::Path};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    dry_run,
+    export::xml_escape,
+    format::format_bytes,
+    history::SpeedHistory,
+    snapshot::HostsSnapshot,
+    stats_aggregator::{Destination, Speed},
+};
+
+const SVG_WIDTH: f64 = 600.0;
+const SVG_HEIGHT: f64 = 120.0;
+
+/// Everything a report shows, gathered by the app at export time.
+pub struct Report<'a> {
+    /// the hosts and the total speed
+    pub snapshot: HostsSnapshot,
+    pub history: &'a SpeedHistory,
+    pub alerts: Vec<String>,
+    /// internet traffic by remote network
+    pub destinations: Vec<Destination>,
+    pub generated_at: DateTime<Local>,
+}
+
+/// A single HTML file with no external resource: the hosts table (click a
+/// header to sort), the usage summary, the alerts and the total throughput
+/// history as an inline SVG.
+pub fn html_report(report: &Report) -> String {
+    let snapshot = &report.snapshot;
+    let title = format!("netui report for {}", snapshot.interface);
+    let mut html = vec![
+        "<!DOCTYPE html>".to_string(),
+        r#"<html lang="en">"#.to_string(),
+        "<head>".to_string(),
+        r#"<meta charset="utf-8">"#.to_string(),
+        format!("<title>{}</title>", xml_escape(&title)),
+        STYLE.to_string(),
+        "</head>".to_string(),
+        "<body>".to_string(),
+        format!("<h1>{}</h1>", xml_escape(&title)),
+        format!(
+            "<p>Generated {}</p>",
+            report.generated_at.format("%Y-%m-%d %H:%M:%S %:z")
+        ),
+    ];
+
+    html.push("<h2>Summary</h2>".to_string());
+    let used_today: u128 = snapshot.hosts.iter().map(|host| host.used_today).sum();
+    html.push("<ul>".to_string());
+    html.push(format!("<li>{} hosts</li>", snapshot.hosts.len()));
+    html.push(format!(
+        "<li>Total speed: ↓ {} ↑ {}</li>",
+        snapshot.total.input, snapshot.total.output
+    ));
+    html.push(format!("<li>Used today: {}</li>", format_bytes(used_today)));
+    html.push("</ul>".to_string());
+
+    html.push("<h2>Throughput</h2>".to_string());
+    html.push(throughput_svg(report.history));
+
+    html.push("<h2>Alerts</h2>".to_string());
+    if report.alerts.is_empty() {
+        html.push("<p>None.</p>".to_string());
+    } else {
+        html.push("<ul>".to_string());
+        for alert in &report.alerts {
+            html.push(format!("<li>{}</li>", xml_escape(alert)));
+        }
+        html.push("</ul>".to_string());
+    }
+
+    html.push("<h2>Hosts</h2>".to_string());
+    html.push(r#"<table id="hosts">"#.to_string());
+    html.push(
+        "<thead><tr><th>IP</th><th>MAC</th><th>Name</th><th>Speed ↓ (bit/s)</th>\
+         <th>Speed ↑ (bit/s)</th><th>Today (bytes)</th><th>Service</th><th>Last seen</th></tr></thead>"
+            .to_string(),
+    );
+    html.push("<tbody>".to_string());
+    for host in &snapshot.hosts {
+        let speed = host
+            .speed
+            .clone()
+            .unwrap_or_else(|| Speed::default().into());
+        // numbers sort by their raw value, shown in human units
+        html.push(format!(
+            r#"<tr><td data-sort="{}">{}</td><td>{}</td><td>{}</td><td data-sort="{}">{}</td><td data-sort="{}">{}</td><td data-sort="{}">{}</td><td>{}</td><td>{}</td></tr>"#,
+            u32::from(host.ip),
+            host.ip,
+            xml_escape(&host.mac),
+            host.name().map_or_else(String::new, xml_escape),
+            speed.input_bps,
+            speed.input,
+            speed.output_bps,
+            speed.output,
+            host.used_today,
+            format_bytes(host.used_today),
+            host.service.as_deref().map_or_else(String::new, xml_escape),
+            host.last_seen.format("%Y-%m-%d %H:%M:%S"),
+        ));
+    }
+    html.push("</tbody>".to_string());
+    html.push("</table>".to_string());
+
+    html.push("<h2>Destinations</h2>".to_string());
+    if report.destinations.is_empty() {
+        html.push("<p>No internet traffic yet.</p>".to_string());
+    } else {
+        html.push("<table>".to_string());
+        html.push(
+            "<thead><tr><th>Destination</th><th>Down (bytes)</th><th>Up (bytes)</th>\
+             <th>LAN hosts</th></tr></thead>"
+                .to_string(),
+        );
+        html.push("<tbody>".to_string());
+        for destination in &report.destinations {
+            html.push(format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                destination.network,
+                format_bytes(destination.down),
+                format_bytes(destination.up),
+                destination.lan_hosts,
+            ));
+        }
+        html.push("</tbody>".to_string());
+        html.push("</table>".to_string());
+    }
+    html.push(SORT_SCRIPT.to_string());
+    html.push("</body>".to_string());
+    html.push("</html>".to_string());
+    html.join("\n") + "\n"
+}
+
+pub fn write_html_report(path: &Path, report: &Report) -> io::Result<()> {
+    dry_run::write(path, html_report(report))
+}
+
+/// Download and upload lines over the whole history, scaled to the busiest sample.
+fn throughput_svg(history: &SpeedHistory) -> String {
+    let samples = history.samples(history.span());
+    if samples.len() < 2 {
+        return "<p>Not enough history yet.</p>".to_string();
+    }
+    let max = samples
+        .iter()
+        .map(|s| s.input().max(s.output()))
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let step = SVG_WIDTH / (samples.len() - 1) as f64;
+    let points = |value: fn(&Speed) -> u128| {
+        samples
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let y = SVG_HEIGHT - value(s) as f64 / max * SVG_HEIGHT;
+                format!("{:.1},{:.1}", i as f64 * step, y)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" role="img" aria-label="total throughput">
+<rect width="{w}" height="{h}" fill="#f8fafc" stroke="#cbd5e1"/>
+<polyline fill="none" stroke="#2563eb" stroke-width="1.5" points="{down}"/>
+<polyline fill="none" stroke="#16a34a" stroke-width="1.5" points="{up}"/>
+</svg>
+<p><span style="color:#2563eb">download</span> / <span style="color:#16a34a">upload</span>, peak {peak} bit/s</p>"##,
+        w = SVG_WIDTH,
+        h = SVG_HEIGHT,
+        down = points(Speed::input),
+        up = points(Speed::output),
+        peak = max,
+    )
+}
+
+const STYLE: &str = r#"<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #cbd5e1; padding: 4px 8px; text-align: left; }
+th { background: #1e3a8a; color: white; cursor: pointer; }
+tr:nth-child(even) { background: #f1f5f9; }
+</style>"#;
+
+const SORT_SCRIPT: &str = r##"<script>
+document.querySelectorAll("#hosts th").forEach((th, column) => {
+  let ascending = true;
+  th.addEventListener("click", () => {
+    const body = document.querySelector("#hosts tbody");
+    const key = (row) => {
+      const cell = row.children[column];
+      const sort = cell.dataset.sort;
+      return sort === undefined ? cell.textContent : Number(sort);
+    };
+    const rows = Array.from(body.rows).sort((a, b) => {
+      const [x, y] = [key(a), key(b)];
+      return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1);
+    });
+    ascending = !ascending;
+    body.replaceChildren(...rows);
+  });
+});
+</script>"##;
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use chrono::NaiveDate;
+    use pnet::util::MacAddr;
+
+    use super::*;
+    use crate::{app::Host, budget::DailyUsage, mac_format::MacFormat};
+
+    /// Raw text elements, skipped up to their end tag.
+    const RAW_TEXT: [&str; 2] = ["script", "style"];
+    const VOID: [&str; 2] = ["meta", "br"];
+
+    /// Every element closed in the order it was opened, and no stray `&` in
+    /// the text or the attributes.
+    fn assert_well_formed(html: &str) {
+        let entity = |text: &str| {
+            text.match_indices('&').all(|(i, _)| {
+                ["&amp;", "&lt;", "&gt;", "&quot;", "&apos;"]
+                    .iter()
+                    .any(|entity| text[i..].starts_with(entity))
+            })
+        };
+        let mut open: Vec<&str> = vec![];
+        let mut rest = html;
+        while let Some(start) = rest.find('<') {
+            assert!(entity(&rest[..start]), "{}", &rest[..start]);
+            let end = start + rest[start..].find('>').expect("an unclosed tag");
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            assert!(!tag.contains('<'), "<{}>", tag);
+            if tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop(), Some(name), "</{}>", name);
+                continue;
+            }
+            assert!(entity(tag), "<{}>", tag);
+            let name = tag.split_whitespace().next().unwrap().trim_end_matches('/');
+            if RAW_TEXT.contains(&name) {
+                let close = format!("</{}>", name);
+                rest = &rest[rest.find(&close).expect("an unclosed raw text") + close.len()..];
+            } else if !tag.ends_with('/') && !VOID.contains(&name) {
+                open.push(name);
+            }
+        }
+        assert!(open.is_empty(), "unclosed {:?}", open);
+    }
+
+    /// The rows of the table following `heading`.
+    fn rows_after(html: &str, heading: &str) -> usize {
+        let table = &html[html.find(heading).unwrap()..];
+        let table = &table[..table.find("</table>").unwrap()];
+        table.matches("<tr>").count() - 1
+    }
+
+    fn report(hosts: &[Host], history: &SpeedHistory) -> String {
+        let mut daily_usage = DailyUsage::default();
+        let today = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        daily_usage.record(today, [(hosts[0].mac, 3 * 1024 * 1024)]);
+        html_report(&Report {
+            snapshot: HostsSnapshot::new("eth<0>", None, hosts, MacFormat::default(), &daily_usage),
+            history,
+            alerts: vec!["10.0.0.1 sends <many> ARP requests & more".to_string()],
+            destinations: vec![Destination {
+                network: "93.184.216.0/24".parse().unwrap(),
+                down: 4096,
+                up: 1024,
+                lan_hosts: 1,
+            }],
+            generated_at: Local::now(),
+        })
+    }
+
+    #[test]
+    fn the_report_is_well_formed_with_a_row_per_host() {
+        let mut hosts: Vec<Host> = (1..=3)
+            .map(|last| {
+                Host::new(
+                    Ipv4Addr::new(10, 0, 0, last),
+                    MacAddr(0, 0, 0, 0, 0, last),
+                    false,
+                )
+            })
+            .collect();
+        hosts[1].hostname = Some("<b>nas</b> & \"co\"".to_string());
+        let mut history = SpeedHistory::new();
+        for _ in 0..3 {
+            history.push(Speed::default());
+        }
+        let html = report(&hosts, &history);
+        assert_well_formed(&html);
+        assert_eq!(rows_after(&html, "<h2>Hosts</h2>"), 3);
+        assert_eq!(rows_after(&html, "<h2>Destinations</h2>"), 1);
+        assert!(html.contains("<li>3 hosts</li>"));
+        assert!(html.contains("<li>Used today: 3.00 MiB</li>"));
+        assert!(html.contains("&lt;b&gt;nas&lt;/b&gt; &amp; &quot;co&quot;"));
+        assert!(html.contains("<title>netui report for eth&lt;0&gt;</title>"));
+        assert!(html.contains("<polyline"));
+    }
+
+    #[test]
+    fn a_report_without_history_is_well_formed() {
+        let hosts = [Host::new(
+            Ipv4Addr::new(10, 0, 0, 1),
+            MacAddr(0, 0, 0, 0, 0, 1),
+            false,
+        )];
+        let html = report(&hosts, &SpeedHistory::new());
+        assert_well_formed(&html);
+        assert_eq!(rows_after(&html, "<h2>Hosts</h2>"), 1);
+        assert!(html.contains("<p>Not enough history yet.</p>"));
+    }
+}
@@ -0,0 +1,423 @@
+use std::{
+    collections::HashSet,
+    net::Ipv4Addr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use serde::Deserialize;
+use tokio::{
+    sync::{
+        mpsc::{self, error::TrySendError, UnboundedSender},
+        Notify, Semaphore,
+    },
+    time::{self, MissedTickBehavior},
+};
+
+use crate::event::{Event, ScannerEvent};
+
+/// Limits of the name lookups, the `[resolver]` table of `config.toml`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResolverOptions {
+    /// lookups running at once
+    pub concurrency: usize,
+    /// lookups started per second, 0 for no limit
+    pub per_second: u32,
+    /// addresses waiting for a lookup, later ones are dropped
+    pub queue: usize,
+}
+
+impl Default for ResolverOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            per_second: 10,
+            queue: 256,
+        }
+    }
+}
+
+/// Finds the name of an address, blocking: it runs on tokio's blocking pool.
+pub trait ResolveBackend: Send + Sync + 'static {
+    fn resolve(&self, ip: Ipv4Addr) -> Option<String>;
+}
+
+/// Reverse lookups (PTR) through the system resolver, so `/etc/hosts` and
+/// whatever `nsswitch.conf` lists apply.
+pub struct SystemBackend;
+
+impl ResolveBackend for SystemBackend {
+    #[cfg(unix)]
+    fn resolve(&self, ip: Ipv4Addr) -> Option<String> {
+        use std::{ffi::CStr, mem};
+
+        // SAFETY: all zeros is a valid sockaddr_in, the fields that matter
+        // are set right after
+        let mut addr: libc::sockaddr_in = unsafe { mem::zeroed() };
+        addr.sin_family = libc::AF_INET as libc::sa_family_t;
+        addr.sin_addr.s_addr = u32::from(ip).to_be();
+        let mut host = [0 as libc::c_char; 1025];
+        // SAFETY: both buffers outlive the call and their lengths are passed
+        let status = unsafe {
+            libc::getnameinfo(
+                &addr as *const libc::sockaddr_in as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+                host.as_mut_ptr(),
+                host.len() as libc::socklen_t,
+                std::ptr::null_mut(),
+                0,
+                libc::NI_NAMEREQD,
+            )
+        };
+        if status != 0 {
+            return None;
+        }
+        // SAFETY: on success getnameinfo wrote a nul terminated name
+        let name = unsafe { CStr::from_ptr(host.as_ptr()) };
+        name.to_str().ok().map(str::to_string)
+    }
+
+    #[cfg(not(unix))]
+    fn resolve(&self, _ip: Ipv4Addr) -> Option<String> {
+        None
+    }
+}
+
+/// Counters of the lookups, for the capture stats popup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResolverMetrics {
+    /// waiting for a permit or the rate limit
+    pub queued: u64,
+    pub in_flight: u64,
+    /// lookups that found a name
+    pub completed: u64,
+    /// lookups that found none
+    pub failed: u64,
+    /// addresses dropped because the queue was full
+    pub dropped: u64,
+}
+
+#[derive(Default)]
+struct Shared {
+    /// queued or in flight, an address is only looked up once at a time
+    pending: Mutex<HashSet<Ipv4Addr>>,
+    queued: AtomicU64,
+    in_flight: AtomicU64,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+    cancelled: AtomicBool,
+    cancel: Notify,
+}
+
+/// The one way to look up host names: every name source enqueues here, the
+/// lookups run at most `concurrency` at once and `per_second` per second,
+/// and the names come back as `ScannerEvent::Resolved`.
+pub struct Resolver {
+    queue: mpsc::Sender<Ipv4Addr>,
+    shared: Arc<Shared>,
+}
+
+impl Resolver {
+    /// Starts the dispatcher, it needs a tokio runtime.
+    pub fn new(
+        backend: impl ResolveBackend,
+        options: ResolverOptions,
+        outputs: UnboundedSender<Event>,
+    ) -> Self {
+        let (queue, requests) = mpsc::channel(options.queue.max(1));
+        let shared = Arc::new(Shared::default());
+        tokio::spawn(dispatch(
+            requests,
+            Arc::new(backend),
+            options,
+            shared.clone(),
+            outputs,
+        ));
+        Self { queue, shared }
+    }
+
+    /// Queues a lookup of `ip`, false when it's already pending, the queue
+    /// is full or the resolver was cancelled.
+    pub fn enqueue(&self, ip: Ipv4Addr) -> bool {
+        if self.shared.cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+        let mut pending = self.shared.pending.lock().unwrap();
+        if pending.contains(&ip) {
+            return false;
+        }
+        // counted before it's sent, the dispatcher may take it right away
+        self.shared.queued.fetch_add(1, Ordering::Relaxed);
+        match self.queue.try_send(ip) {
+            Ok(()) => {
+                pending.insert(ip);
+                true
+            }
+            Err(e) => {
+                self.shared.queued.fetch_sub(1, Ordering::Relaxed);
+                if let TrySendError::Full(_) = e {
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                false
+            }
+        }
+    }
+
+    /// Stops starting lookups, the queued ones are dropped and the names of
+    /// those still running are thrown away.
+    pub fn cancel(&self) {
+        self.shared.cancelled.store(true, Ordering::Relaxed);
+        self.shared.cancel.notify_one();
+    }
+
+    pub fn metrics(&self) -> ResolverMetrics {
+        let shared = &self.shared;
+        ResolverMetrics {
+            queued: shared.queued.load(Ordering::Relaxed),
+            in_flight: shared.in_flight.load(Ordering::Relaxed),
+            completed: shared.completed.load(Ordering::Relaxed),
+            failed: shared.failed.load(Ordering::Relaxed),
+            dropped: shared.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for Resolver {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+async fn dispatch(
+    mut requests: mpsc::Receiver<Ipv4Addr>,
+    backend: Arc<dyn ResolveBackend>,
+    options: ResolverOptions,
+    shared: Arc<Shared>,
+    outputs: UnboundedSender<Event>,
+) {
+    let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let mut pace = (options.per_second > 0).then(|| {
+        let mut pace = time::interval(Duration::from_secs(1) / options.per_second);
+        pace.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        pace
+    });
+    loop {
+        let ip = tokio::select! {
+            _ = shared.cancel.notified() => break,
+            ip = requests.recv() => match ip {
+                Some(ip) => ip,
+                None => break,
+            },
+        };
+        let permit = tokio::select! {
+            _ = shared.cancel.notified() => break,
+            permit = permits.clone().acquire_owned() => match permit {
+                Ok(permit) => permit,
+                Err(_) => break,
+            },
+        };
+        if let Some(pace) = &mut pace {
+            tokio::select! {
+                _ = shared.cancel.notified() => break,
+                _ = pace.tick() => {}
+            }
+        }
+        if shared.cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        shared.queued.fetch_sub(1, Ordering::Relaxed);
+        shared.in_flight.fetch_add(1, Ordering::Relaxed);
+        let backend = backend.clone();
+        let shared = shared.clone();
+        let outputs = outputs.clone();
+        tokio::spawn(async move {
+            let name = tokio::task::spawn_blocking(move || backend.resolve(ip))
+                .await
+                .ok()
+                .flatten();
+            drop(permit);
+            shared.in_flight.fetch_sub(1, Ordering::Relaxed);
+            shared.pending.lock().unwrap().remove(&ip);
+            if shared.cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            match name {
+                Some(name) => {
+                    shared.completed.fetch_add(1, Ordering::Relaxed);
+                    let _ = outputs.send(Event::Scanner(ScannerEvent::Resolved(ip, name)));
+                }
+                None => {
+                    shared.failed.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+    }
+    // what's still queued won't be looked up
+    requests.close();
+    while requests.try_recv().is_ok() {}
+    shared.queued.store(0, Ordering::Relaxed);
+    shared.pending.lock().unwrap().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::AtomicUsize,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    use super::*;
+
+    /// Names every address after itself, after `delay`, and records how many
+    /// lookups ran at once and when they started.
+    #[derive(Clone, Default)]
+    struct MockBackend {
+        delay: Duration,
+        running: Arc<AtomicUsize>,
+        max_running: Arc<AtomicUsize>,
+        calls: Arc<Mutex<Vec<(Ipv4Addr, Instant)>>>,
+    }
+
+    impl MockBackend {
+        fn with_delay(delay: Duration) -> Self {
+            Self {
+                delay,
+                ..Default::default()
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.lock().unwrap().len()
+        }
+    }
+
+    impl ResolveBackend for MockBackend {
+        fn resolve(&self, ip: Ipv4Addr) -> Option<String> {
+            self.calls.lock().unwrap().push((ip, Instant::now()));
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            thread::sleep(self.delay);
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            (ip.octets()[3] != 0).then(|| format!("host-{}", ip.octets()[3]))
+        }
+    }
+
+    fn options(concurrency: usize, per_second: u32) -> ResolverOptions {
+        ResolverOptions {
+            concurrency,
+            per_second,
+            queue: 64,
+        }
+    }
+
+    fn ip(last: u8) -> Ipv4Addr {
+        Ipv4Addr::new(192, 168, 1, last)
+    }
+
+    async fn next_name(outputs: &mut UnboundedReceiver<Event>) -> (Ipv4Addr, String) {
+        match time::timeout(Duration::from_secs(5), outputs.recv()).await {
+            Ok(Some(Event::Scanner(ScannerEvent::Resolved(ip, name)))) => (ip, name),
+            other => panic!("expected a name, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_at_most_concurrency_lookups_at_once() {
+        let backend = MockBackend::with_delay(Duration::from_millis(20));
+        let (tx, mut rx) = unbounded_channel();
+        let resolver = Resolver::new(backend.clone(), options(3, 0), tx);
+        for last in 1..=12 {
+            assert!(resolver.enqueue(ip(last)));
+        }
+        let mut names = Vec::new();
+        for _ in 0..12 {
+            names.push(next_name(&mut rx).await);
+        }
+        assert_eq!(names.len(), 12);
+        assert!(backend.max_running.load(Ordering::SeqCst) <= 3);
+        let metrics = resolver.metrics();
+        assert_eq!(metrics.completed, 12);
+        assert_eq!((metrics.queued, metrics.in_flight), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn spaces_lookups_by_the_rate_limit() {
+        let backend = MockBackend::default();
+        let (tx, mut rx) = unbounded_channel();
+        let resolver = Resolver::new(backend.clone(), options(8, 20), tx);
+        for last in 1..=5 {
+            resolver.enqueue(ip(last));
+        }
+        for _ in 0..5 {
+            next_name(&mut rx).await;
+        }
+        let calls = backend.calls.lock().unwrap();
+        let spread = calls[4].1.duration_since(calls[0].1);
+        // 4 gaps of 50 ms, with some slack for the timer
+        assert!(spread >= Duration::from_millis(180), "{:?}", spread);
+    }
+
+    #[tokio::test]
+    async fn looks_up_an_address_once_while_pending() {
+        let backend = MockBackend::with_delay(Duration::from_millis(20));
+        let (tx, mut rx) = unbounded_channel();
+        let resolver = Resolver::new(backend.clone(), options(1, 0), tx);
+        assert!(resolver.enqueue(ip(7)));
+        assert!(!resolver.enqueue(ip(7)));
+        assert_eq!(next_name(&mut rx).await, (ip(7), "host-7".to_string()));
+        assert_eq!(backend.calls(), 1);
+        // done, it can be looked up again
+        assert!(resolver.enqueue(ip(7)));
+    }
+
+    #[tokio::test]
+    async fn counts_failures_and_drops_past_the_queue() {
+        let backend = MockBackend::with_delay(Duration::from_millis(50));
+        let (tx, mut rx) = unbounded_channel();
+        let options = ResolverOptions {
+            concurrency: 1,
+            per_second: 0,
+            queue: 1,
+        };
+        let resolver = Resolver::new(backend.clone(), options, tx);
+        assert!(resolver.enqueue(ip(0)));
+        // wait for the dispatcher to take it, freeing the queue
+        while backend.calls() == 0 {
+            time::sleep(Duration::from_millis(5)).await;
+        }
+        assert!(resolver.enqueue(ip(1)));
+        assert!(!resolver.enqueue(ip(2)));
+        next_name(&mut rx).await;
+        let metrics = resolver.metrics();
+        assert_eq!((metrics.completed, metrics.failed), (1, 1));
+        assert_eq!(metrics.dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_the_queued_lookups() {
+        let backend = MockBackend::with_delay(Duration::from_millis(50));
+        let (tx, mut rx) = unbounded_channel();
+        let resolver = Resolver::new(backend.clone(), options(1, 0), tx);
+        for last in 1..=5 {
+            resolver.enqueue(ip(last));
+        }
+        while backend.calls() == 0 {
+            time::sleep(Duration::from_millis(5)).await;
+        }
+        resolver.cancel();
+        assert!(!resolver.enqueue(ip(9)));
+        time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(backend.calls(), 1);
+        // the lookup running when cancelled is thrown away
+        assert!(rx.try_recv().is_err());
+        assert_eq!(resolver.metrics().queued, 0);
+    }
+}
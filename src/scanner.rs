@@ -1,13 +1,19 @@
+use itertools::Itertools;
 use pnet::packet::{
     ip::IpNextHeaderProtocols, ipv4::Ipv4Packet, tcp::TcpPacket, udp::UdpPacket, MutablePacket,
     Packet,
 };
+use regex::Regex;
 use std::{
     collections::HashMap,
+    io,
     net::{IpAddr, Ipv4Addr},
     process,
-    sync::{Arc, Mutex},
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 use tracing::Level;
 
@@ -15,22 +21,122 @@ use pnet::{
     ipnetwork,
     packet::{
         arp::{ArpHardwareTypes, ArpOperations, ArpPacket, MutableArpPacket},
-        ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket},
+        ethernet::{EtherType, EtherTypes, EthernetPacket, MutableEthernetPacket},
     },
 };
 use pnet_datalink::{DataLinkReceiver, DataLinkSender, MacAddr, NetworkInterface};
 use tokio::{
-    sync::mpsc::{self, unbounded_channel, UnboundedReceiver, UnboundedSender},
-    time::{self, sleep},
+    sync::{
+        mpsc::{self, error::SendError, unbounded_channel, UnboundedReceiver, UnboundedSender},
+        Notify,
+    },
+    task::JoinHandle,
+    time::{self, sleep, Interval, MissedTickBehavior},
 };
 
 use crate::{
-    app::{AppResult, Host},
+    app::{AppResult, ArpActivity, Host},
+    capture_stats::{CaptureCounters, CaptureStats, Ignored, Load, Sampling},
+    dry_run,
     event::{Event, ScannerEvent},
+    interface_info::InterfaceInfo,
+    networks::NetworkPlan,
     stats_aggregator::{self, StatsMap},
     trace_dbg,
 };
 
+/// pause before each ARP request of a sweep unless changed in the settings
+pub const DEFAULT_SCAN_DELAY: Duration = Duration::from_millis(37);
+/// how often a passively observed host gets its "last seen" time refreshed
+const OBSERVED_HOST_REFRESH: Duration = Duration::from_secs(1);
+/// past this many, the observed hosts not due for a refresh are forgotten
+const MAX_OBSERVED_HOSTS: usize = 1024;
+/// shortest Ethernet frame without the FCS, some virtual interfaces drop
+/// shorter ones instead of padding them
+const MIN_ETHERNET_FRAME_LEN: usize = 60;
+/// an 802.1Q or 802.1ad tag: the tag control then the inner EtherType
+const VLAN_TAG_LEN: usize = 4;
+/// sends failing in a row before the sweep reports an error
+const SEND_FAILURES_BEFORE_ERROR: usize = 8;
+/// bytes of a frame the channel reads unless `--capture-buffer` says
+/// otherwise: a jumbo frame, or a segment coalesced by the NIC, fits whole
+pub const DEFAULT_CAPTURE_BUFFER: usize = 64 * 1024;
+/// the longest standard frame with a VLAN tag, a smaller buffer would cut
+/// ordinary traffic
+pub const MIN_CAPTURE_BUFFER: usize = 1522;
+
+/// shortest stat tick, below it the flushes cost more than they tell
+pub const MIN_STAT_INTERVAL: Duration = Duration::from_millis(200);
+/// traffic below this many bits per second counts as nothing happening
+const QUIET_BITS_PER_SEC: u128 = 8 * 1024;
+/// how long it has to stay quiet before the adaptive ticks slow down
+const QUIET_BEFORE_SLOWDOWN: Duration = Duration::from_secs(10);
+/// the adaptive ticks of a quiet network, doubling from the first to the last
+const QUIET_INTERVALS: (Duration, Duration) = (Duration::from_secs(2), Duration::from_secs(5));
+
+/// How often the captured traffic is flushed to the app.
+#[derive(Clone, Copy, Debug)]
+pub struct StatInterval {
+    pub base: Duration,
+    /// slow down while the network is quiet, back to `base` once it isn't
+    pub adaptive: bool,
+}
+
+impl StatInterval {
+    /// The period after a tick of `bits` over `elapsed`; `quiet` is how long
+    /// traffic has been under the quiet threshold.
+    fn next_period(
+        &self,
+        period: Duration,
+        quiet: &mut Duration,
+        bits: u128,
+        elapsed: Duration,
+    ) -> Duration {
+        if !self.adaptive {
+            return period;
+        }
+        let bits_per_sec = bits * 1000 / elapsed.as_millis().max(1);
+        if bits_per_sec >= QUIET_BITS_PER_SEC {
+            *quiet = Duration::ZERO;
+            return self.base;
+        }
+        *quiet += elapsed;
+        if *quiet < QUIET_BEFORE_SLOWDOWN {
+            return period;
+        }
+        let (slowest_first, slowest) = QUIET_INTERVALS;
+        (period * 2).clamp(slowest_first.max(self.base), slowest.max(self.base))
+    }
+}
+
+impl Default for StatInterval {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_secs(1),
+            adaptive: false,
+        }
+    }
+}
+
+/// name the ARP sweep worker is reported under when it fails
+pub const ARP_SWEEP_TASK: &str = "ARP sweeps";
+
+/// What an ARP sweep over one network did.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SweepSummary {
+    pub sent: usize,
+    pub failed: usize,
+}
+
+/// How the interface to watch is picked.
+#[derive(Clone, Debug)]
+pub enum InterfaceSelector {
+    /// first interface whose name contains this, or the default one
+    Name(String),
+    /// the only interface whose full name matches
+    Regex(Regex),
+}
+
 enum ScannerInputEvent {
     StartScanning,
 }
@@ -38,52 +144,229 @@ enum ScannerInputEvent {
 pub struct Scanner {
     scanner_input_tx: UnboundedSender<ScannerInputEvent>,
     scanner_outputs: UnboundedSender<Event>,
+    /// the interface has no usable local address (tap/bridge), nothing is sent
+    monitor_only: bool,
+    /// never transmit, hosts are only discovered from the traffic they send
+    passive: bool,
+    /// the raw channel is open; without it nothing is captured nor sent
+    capturing: bool,
+    stat_interval: StatInterval,
+    /// asks a running sweep to stop after the current request
+    scan_cancelled: Arc<AtomicBool>,
+    /// the only host whose flows are accounted, `UNSPECIFIED` for all of them
+    solo: Arc<AtomicU32>,
+    /// the only VLAN whose frames are looked at, untagged ones included without it
+    vlan: Option<u16>,
+    /// pause before each ARP request of a sweep, in milliseconds
+    scan_delay_ms: Arc<AtomicU64>,
+    /// when the sweep sent its request to each address, matched with the replies
+    pending_requests: PendingRequests,
+    /// how the capture loop is keeping up
+    capture_counters: Arc<CaptureCounters>,
+    /// notified once the capture loop runs, sweeps wait for it
+    capture_ready: Arc<Notify>,
+    /// the interface's networks without overlaps, what sweeps probe
+    network_plan: NetworkPlan,
+}
+
+type PendingRequests = Arc<Mutex<HashMap<Ipv4Addr, Instant>>>;
+
+/// What the sweeps share with the scanner.
+struct SweepState {
+    cancelled: Arc<AtomicBool>,
+    pending_requests: PendingRequests,
+    /// notified once the capture loop reads, sweeps wait for it
+    capture_ready: Arc<Notify>,
+    delay_ms: Arc<AtomicU64>,
 }
 
 impl Scanner {
     /// Creates a new [`Scanner`].
     pub fn new(
         scanner_outputs: mpsc::UnboundedSender<Event>,
-        interface_name: String,
+        interface_selector: InterfaceSelector,
+        monitor_only: bool,
+        passive: bool,
+        stat_interval: StatInterval,
+        vlan: Option<u16>,
+        capture_buffer: usize,
+    ) -> AppResult<Self> {
+        let nif = Self::select_interface(interface_selector)?;
+        let channel = Self::create_datalink_channel(nif.clone(), capture_buffer)?;
+        Self::with_channel(
+            scanner_outputs,
+            nif,
+            channel,
+            monitor_only,
+            passive,
+            stat_interval,
+            vlan,
+        )
+    }
+
+    /// A [`Scanner`] on a channel already opened, a simulated one in tests.
+    pub fn with_channel(
+        scanner_outputs: mpsc::UnboundedSender<Event>,
+        nif: NetworkInterface,
+        (datalink_tx, datalink_rx): (Box<dyn DataLinkSender>, Box<dyn DataLinkReceiver>),
+        monitor_only: bool,
+        passive: bool,
+        stat_interval: StatInterval,
+        vlan: Option<u16>,
+    ) -> AppResult<Self> {
+        let (mut scanner, scanner_input_rx) = Self::prepare(
+            scanner_outputs,
+            &nif,
+            monitor_only,
+            passive,
+            stat_interval,
+            true,
+        )?;
+        scanner.vlan = vlan;
+        scanner.start_listening(datalink_rx, nif.clone())?;
+        // without the worker no frame can ever be built or sent
+        if scanner.transmits() {
+            scanner.start_tx_worker(scanner_input_rx, datalink_tx, nif)?;
+        }
+
+        Ok(scanner)
+    }
+
+    /// A [`Scanner`] that opens no raw channel, for running without root:
+    /// the hosts come from the OS ARP cache, nothing is captured nor sent.
+    pub fn without_capture(
+        scanner_outputs: mpsc::UnboundedSender<Event>,
+        interface_selector: InterfaceSelector,
+        stat_interval: StatInterval,
     ) -> AppResult<Self> {
-        let nif = Self::find_interface_or_get_default(interface_name)?;
+        let nif = Self::select_interface(interface_selector)?;
+        let (scanner, _) =
+            Self::prepare(scanner_outputs, &nif, false, false, stat_interval, false)?;
+        for host in Self::arp_cache_hosts(&nif) {
+            scanner
+                .scanner_outputs
+                .send(Event::Scanner(ScannerEvent::HostFound(Box::new(host))))
+                .map_err(|_| "events channel closed")?;
+        }
+        Ok(scanner)
+    }
+
+    fn select_interface(interface_selector: InterfaceSelector) -> AppResult<NetworkInterface> {
+        match interface_selector {
+            InterfaceSelector::Name(interface_name) => {
+                Self::find_interface_or_get_default(interface_name)
+            }
+            InterfaceSelector::Regex(regex) => Self::find_interface_matching(&regex),
+        }
+    }
+
+    /// Announces the interface, the channel is left to the caller.
+    fn prepare(
+        scanner_outputs: mpsc::UnboundedSender<Event>,
+        nif: &NetworkInterface,
+        monitor_only: bool,
+        passive: bool,
+        stat_interval: StatInterval,
+        capturing: bool,
+    ) -> AppResult<(Self, UnboundedReceiver<ScannerInputEvent>)> {
         scanner_outputs
-            .send(Event::Scanner(ScannerEvent::InterfaceName(
-                nif.name.clone(),
+            .send(Event::Scanner(ScannerEvent::Interface(
+                InterfaceInfo::from_interface(nif),
             )))
-            .unwrap();
+            .map_err(|_| "events channel closed")?;
+        let network_plan = NetworkPlan::analyze(&nif.ips);
+        if !network_plan.warnings.is_empty() {
+            scanner_outputs
+                .send(Event::Scanner(ScannerEvent::NetworkWarnings(
+                    network_plan.warnings.clone(),
+                )))
+                .map_err(|_| "events channel closed")?;
+        }
 
         let (scanner_input_tx, scanner_input_rx) = unbounded_channel::<ScannerInputEvent>();
 
-        let mut scanner = Self {
+        let scanner = Self {
             scanner_outputs,
             scanner_input_tx,
+            monitor_only,
+            passive,
+            capturing,
+            stat_interval,
+            scan_cancelled: Arc::new(AtomicBool::new(false)),
+            solo: Arc::new(AtomicU32::new(u32::from(Ipv4Addr::UNSPECIFIED))),
+            vlan: None,
+            scan_delay_ms: Arc::new(AtomicU64::new(DEFAULT_SCAN_DELAY.as_millis() as u64)),
+            pending_requests: Default::default(),
+            capture_counters: Default::default(),
+            capture_ready: Default::default(),
+            network_plan,
         };
+        Ok((scanner, scanner_input_rx))
+    }
 
-        let (datalink_tx, datalink_rx) = Self::create_datalink_channel(nif.clone())?;
-        scanner.start_listening(datalink_rx, nif.clone())?;
-        scanner.start_tx_worker(scanner_input_rx, datalink_tx, nif)?;
+    /// The complete entries of the kernel's ARP cache on `nif`, readable
+    /// without root.
+    #[cfg(target_os = "linux")]
+    fn arp_cache_hosts(nif: &NetworkInterface) -> Vec<Host> {
+        // IP address, HW type, Flags, HW address, Mask, Device
+        const COMPLETE: &str = "0x2";
+        let Ok(table) = std::fs::read_to_string("/proc/net/arp") else {
+            return vec![];
+        };
+        table
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let [ip, _, flags, mac, _, device] = fields[..] else {
+                    return None;
+                };
+                if device != nif.name || flags != COMPLETE {
+                    return None;
+                }
+                let mac: MacAddr = mac.parse().ok()?;
+                Some(Host::new(ip.parse().ok()?, mac, Some(mac) == nif.mac))
+            })
+            .collect()
+    }
 
-        Ok(scanner)
+    #[cfg(not(target_os = "linux"))]
+    fn arp_cache_hosts(_nif: &NetworkInterface) -> Vec<Host> {
+        vec![]
     }
 
-    fn create_datalink_channel(
+    /// Opens the raw Ethernet channel of `nif`, the check `netui doctor`
+    /// runs on every interface. Frames longer than `buffer_size` are read
+    /// cut short.
+    pub fn create_datalink_channel(
         nif: NetworkInterface,
+        buffer_size: usize,
     ) -> AppResult<(Box<dyn DataLinkSender>, Box<dyn DataLinkReceiver>)> {
         let channel_config = pnet_datalink::Config {
             read_timeout: Some(Duration::from_millis(500)),
+            read_buffer_size: buffer_size,
+            write_buffer_size: buffer_size,
             ..pnet_datalink::Config::default()
         };
-        let pair = match pnet_datalink::channel(&nif, channel_config) {
-            Ok(pnet_datalink::Channel::Ethernet(tx, rx)) => (tx, rx),
-            Ok(_) => {
-                process::exit(1);
-            }
-            Err(_error) => {
-                process::exit(1);
-            }
-        };
-        Ok(pair)
+        match pnet_datalink::channel(&nif, channel_config) {
+            Ok(pnet_datalink::Channel::Ethernet(tx, rx)) => Ok((tx, rx)),
+            Ok(_) => Err(format!("{}: not an Ethernet channel", nif.name).into()),
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => Err(format!(
+                "{}: {} (capturing needs root or the CAP_NET_RAW capability, --no-capture runs without it)",
+                nif.name, e
+            )
+            .into()),
+            Err(e) => Err(format!("{}: {}", nif.name, e).into()),
+        }
+    }
+
+    /// Interfaces netui can pick, in the order the default one is chosen from.
+    pub fn usable_interfaces() -> Vec<NetworkInterface> {
+        pnet_datalink::interfaces()
+            .into_iter()
+            .rev()
+            .filter(|nif| nif.is_up() && nif.is_running() && !nif.is_loopback())
+            .collect()
     }
 
     fn start_listening(
@@ -93,10 +376,20 @@ impl Scanner {
     ) -> AppResult<()> {
         let scanner_outputs: UnboundedSender<Event> = self.scanner_outputs.clone();
         let scanner_outputs_clone = scanner_outputs.clone();
+        let monitor_only = self.monitor_only;
+        // nothing answers our sweeps, IPv4 senders are the hosts we get to know
+        let observe_hosts = self.monitor_only || self.passive;
+        let pending_requests = self.pending_requests.clone();
+        let solo = self.solo.clone();
+        let vlan = self.vlan;
         let agg: Arc<Mutex<StatsMap>> = Arc::new(Mutex::new(HashMap::new()));
         let agg_clone = agg.clone();
-        tokio::spawn(async move {
-            let mut interval = time::interval(Duration::from_secs(1));
+        let stat_interval = self.stat_interval;
+        let stat_ticks = tokio::spawn(async move {
+            let mut period = stat_interval.base;
+            let mut interval = Self::tick_interval(period);
+            let mut last_flush = Instant::now();
+            let mut quiet = Duration::ZERO;
             loop {
                 interval.tick().await;
                 let data_clone;
@@ -105,105 +398,345 @@ impl Scanner {
                     data_clone = data.clone();
                     *data = HashMap::new();
                 }
-                scanner_outputs_clone
-                    .send(Event::Scanner(ScannerEvent::StatTick(data_clone)))
-                    .unwrap();
+                // the real length, ticks can be late and periods change
+                let flushed_at = Instant::now();
+                let elapsed = flushed_at.saturating_duration_since(last_flush);
+                last_flush = flushed_at;
+                let bits: u128 = data_clone.values().map(|value| value.total()).sum();
+                let next_period = stat_interval.next_period(period, &mut quiet, bits, elapsed);
+                if scanner_outputs_clone
+                    .send(Event::Scanner(ScannerEvent::StatTick(
+                        data_clone, elapsed, flushed_at,
+                    )))
+                    .is_err()
+                {
+                    Self::log_channel_closed("stat ticks");
+                    break;
+                }
+                if next_period != period {
+                    period = next_period;
+                    interval = Self::tick_interval(period);
+                }
             }
         });
+        self.supervise("stat ticks", stat_ticks);
 
-        tokio::spawn(async move {
+        let counters = self.capture_counters.clone();
+        let capture_ready = self.capture_ready.clone();
+        let capture = tokio::spawn(async move {
+            let mut observed_hosts: HashMap<(Ipv4Addr, MacAddr), Instant> = HashMap::new();
+            let mut load = Load::new(Instant::now());
+            let mut sampling = Sampling::default();
+            capture_ready.notify_one();
             loop {
-                if let Ok(buffer) = datalink_rx.next() {
-                    let ethernet_packet = match EthernetPacket::new(buffer) {
-                        Some(packet) => packet,
-                        None => continue,
+                let waiting_since = Instant::now();
+                let next = datalink_rx.next();
+                if let Some(busy) = load.waited(waiting_since.elapsed(), Instant::now()) {
+                    if let Some(factor) = sampling.adapt(busy) {
+                        if factor > 1 {
+                            tracing::warn!(
+                                "capture can't keep up ({:.0}% busy), accounting 1 IPv4 frame in {}",
+                                busy * 100.0,
+                                factor
+                            );
+                        } else {
+                            tracing::info!("capture keeps up again, accounting every frame");
+                        }
+                    }
+                    counters.load(busy, sampling.factor());
+                }
+                let buffer = match next {
+                    Ok(buffer) => buffer,
+                    // the read timeout only keeps the loop alive, until the app is gone
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                        if scanner_outputs.is_closed() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(_) => {
+                        counters.read_error();
+                        continue;
+                    }
+                };
+                counters.frame(buffer.len());
+                let (ethernet_packet, (ethertype, frame_vlan, payload)) =
+                    match EthernetPacket::new(buffer).zip(Self::untag(buffer)) {
+                        Some(frame) => frame,
+                        None => {
+                            counters.ignored(Ignored::Malformed);
+                            continue;
+                        }
                     };
+                if vlan.is_some() && frame_vlan != vlan {
+                    counters.ignored(Ignored::OtherVlan);
+                    continue;
+                }
 
-                    match ethernet_packet.get_ethertype() {
-                        EtherTypes::Arp => {
-                            if let Some(host) = Self::get_host_infos(buffer, &def_nif) {
-                                match scanner_outputs.send(Event::Scanner(
-                                    crate::event::ScannerEvent::HostFound(host),
-                                )) {
-                                    Ok(_) => {}
-                                    Err(e) => {
-                                        trace_dbg!(level: Level::ERROR, e);
+                match ethertype {
+                    EtherTypes::Arp => {
+                        counters.arp();
+                        if let Some(mut host) = Self::get_host_infos(payload, &def_nif) {
+                            if host.arp.replies > 0 {
+                                host.arp_rtt = pending_requests
+                                    .lock()
+                                    .unwrap()
+                                    .remove(&host.ipv4)
+                                    .map(|sent| sent.elapsed());
+                            }
+                            if scanner_outputs
+                                .send(Event::Scanner(ScannerEvent::HostFound(Box::new(host))))
+                                .is_err()
+                            {
+                                Self::log_channel_closed("capture");
+                                break;
+                            }
+                        }
+                    }
+                    EtherTypes::Ipv4 => {
+                        if observe_hosts {
+                            if let Some(host) = Self::get_observed_host(
+                                ethernet_packet.get_source(),
+                                payload,
+                                &def_nif,
+                            ) {
+                                let key = (host.ipv4, host.mac);
+                                let is_due = observed_hosts
+                                    .get(&key)
+                                    .is_none_or(|last| last.elapsed() >= OBSERVED_HOST_REFRESH);
+                                if is_due {
+                                    observed_hosts.insert(key, Instant::now());
+                                    if observed_hosts.len() > MAX_OBSERVED_HOSTS {
+                                        observed_hosts.retain(|_, last| {
+                                            last.elapsed() < OBSERVED_HOST_REFRESH
+                                        });
+                                    }
+                                    if scanner_outputs
+                                        .send(Event::Scanner(ScannerEvent::HostFound(Box::new(
+                                            host,
+                                        ))))
+                                        .is_err()
+                                    {
+                                        Self::log_channel_closed("capture");
+                                        break;
                                     }
                                 }
                             }
                         }
-                        EtherTypes::Ipv4 => {
-                            if let Some(stat) = Self::get_stats(ethernet_packet, &def_nif) {
-                                {
-                                    let mut agg_data = agg.lock().unwrap();
-
-                                    agg_data
-                                        .entry(stat.key.clone())
-                                        .and_modify(|v| v.size += stat.value.size)
-                                        .or_insert(stats_aggregator::StatValues { size: 0 });
-                                }
+                        // parsing and accounting is what can't keep up, not reading
+                        if !sampling.admit() {
+                            counters.sampled_out();
+                            continue;
+                        }
+                        let stat = match Self::get_stats(payload, &def_nif, monitor_only, &counters)
+                        {
+                            Ok(stat) => stat,
+                            Err(reason) => {
+                                counters.ignored(reason);
+                                continue;
                             }
+                        };
+                        let solo = Ipv4Addr::from(solo.load(Ordering::Relaxed));
+                        if !solo.is_unspecified() && stat.key.a_ip != solo && stat.key.b_ip != solo
+                        {
+                            counters.ignored(Ignored::Solo);
+                            continue;
                         }
-                        _ => continue,
-                    };
-                }
+                        counters.accounted();
+                        *agg.lock().unwrap().entry(stat.key).or_default() +=
+                            sampling.scale(stat.value);
+                    }
+                    _ => counters.ignored(Ignored::OtherEtherType),
+                };
             }
         });
+        self.supervise("capture", capture);
         Ok(())
     }
 
     fn start_tx_worker(
         &mut self,
+        scanner_input_rx: UnboundedReceiver<ScannerInputEvent>,
+        datalink_channel_tx: Box<dyn DataLinkSender>,
+        nif: NetworkInterface,
+    ) -> AppResult<()> {
+        let tx_worker = tokio::spawn(Self::run_sweeps(
+            scanner_input_rx,
+            dry_run::sender(datalink_channel_tx),
+            nif,
+            self.network_plan.scan.clone(),
+            self.scanner_outputs.clone(),
+            SweepState {
+                cancelled: self.scan_cancelled.clone(),
+                pending_requests: self.pending_requests.clone(),
+                capture_ready: self.capture_ready.clone(),
+                delay_ms: self.scan_delay_ms.clone(),
+            },
+        ));
+        self.supervise(ARP_SWEEP_TASK, tx_worker);
+        Ok(())
+    }
+
+    /// Sweeps `networks`, those of `nif` without overlaps, on each request,
+    /// whoever asked: a key, the palette or `--scan-on-start`.
+    async fn run_sweeps(
         mut scanner_input_rx: UnboundedReceiver<ScannerInputEvent>,
         mut datalink_channel_tx: Box<dyn DataLinkSender>,
         nif: NetworkInterface,
-    ) -> AppResult<()> {
-        let scanner_outputs_clone = self.scanner_outputs.clone();
-        tokio::spawn(async move {
-            while let Some(event) = scanner_input_rx.recv().await {
-                if !matches!(event, ScannerInputEvent::StartScanning) {
-                    continue;
-                }
+        networks: Vec<ipnetwork::Ipv4Network>,
+        scanner_outputs: UnboundedSender<Event>,
+        state: SweepState,
+    ) {
+        // replies sent before the capture loop reads would be missed
+        state.capture_ready.notified().await;
+        while let Some(event) = scanner_input_rx.recv().await {
+            if !matches!(event, ScannerInputEvent::StartScanning) {
+                continue;
+            }
+            state.cancelled.store(false, Ordering::Relaxed);
+            // replies to a previous sweep arriving now would get a bogus RTT
+            state.pending_requests.lock().unwrap().clear();
 
-                let nif = nif.clone();
-                for ip_network in nif
-                    .clone()
-                    .ips
-                    .into_iter()
-                    .filter(|&ip_network| ip_network.is_ipv4())
+            if networks.is_empty() {
+                if scanner_outputs
+                    .send(Event::Scanner(ScannerEvent::NoNetwork))
+                    .is_err()
                 {
-                    Self::scan_range(
-                        &nif,
-                        ip_network,
-                        scanner_outputs_clone.clone(),
-                        &mut datalink_channel_tx,
-                    )
-                    .await;
+                    Self::log_channel_closed("ARP sweeps");
+                    return;
                 }
+                continue;
             }
-        });
-        Ok(())
+            for ip_network in &networks {
+                if state.cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
+                if Self::scan_range(
+                    &nif,
+                    ipnetwork::IpNetwork::V4(*ip_network),
+                    scanner_outputs.clone(),
+                    &mut datalink_channel_tx,
+                    &state.cancelled,
+                    &state.pending_requests,
+                    &state.delay_ms,
+                )
+                .await
+                .is_err()
+                {
+                    Self::log_channel_closed("ARP sweeps");
+                    return;
+                }
+            }
+        }
     }
     async fn scan_range(
         nif: &NetworkInterface,
         ip_network: ipnetwork::IpNetwork,
         scanner_outputs: mpsc::UnboundedSender<Event>,
         datalink_channel_tx: &mut Box<dyn DataLinkSender>,
-    ) {
-        scanner_outputs
-            .send(Event::Scanner(crate::event::ScannerEvent::BeginScan))
-            .unwrap();
+        scan_cancelled: &AtomicBool,
+        pending_requests: &PendingRequests,
+        scan_delay_ms: &AtomicU64,
+    ) -> Result<(), SendError<Event>> {
+        scanner_outputs.send(Event::Scanner(crate::event::ScannerEvent::BeginScan(
+            ip_network,
+        )))?;
         let sender_clone = scanner_outputs.clone();
         let sender = sender_clone;
+        let mut summary = SweepSummary::default();
+        let mut failures_in_a_row = 0;
         for ip_addr in ip_network.iter() {
+            if scan_cancelled.load(Ordering::Relaxed) {
+                break;
+            }
             if let IpAddr::V4(ipv4_address) = ip_addr {
-                sleep(Duration::from_millis(37)).await;
-                Self::send_arp_request(datalink_channel_tx, nif, ipv4_address);
+                if Self::is_network_or_broadcast(ip_network, ipv4_address) {
+                    continue;
+                }
+                sleep(Duration::from_millis(scan_delay_ms.load(Ordering::Relaxed))).await;
+                // nobody is left to read the replies
+                if sender.is_closed() {
+                    return Err(SendError(Event::Scanner(ScannerEvent::Complete(summary))));
+                }
+                pending_requests
+                    .lock()
+                    .unwrap()
+                    .insert(ipv4_address, Instant::now());
+                match Self::send_arp_request(datalink_channel_tx, nif, ipv4_address) {
+                    Ok(()) => {
+                        summary.sent += 1;
+                        failures_in_a_row = 0;
+                    }
+                    Err(e) => {
+                        summary.failed += 1;
+                        failures_in_a_row += 1;
+                        // once per streak, the user doesn't need every failure
+                        if failures_in_a_row == SEND_FAILURES_BEFORE_ERROR {
+                            sender.send(Event::Scanner(ScannerEvent::Error(format!(
+                                "{}: ARP requests can't be sent: {}",
+                                nif.name, e
+                            ))))?;
+                        }
+                    }
+                }
+            }
+        }
+        sender.send(Event::Scanner(crate::event::ScannerEvent::Complete(
+            summary,
+        )))
+    }
+
+    fn tick_interval(period: Duration) -> Interval {
+        let mut interval = time::interval_at(time::Instant::now() + period, period);
+        // after a stall (suspend, overloaded runtime) don't burst catch-up ticks,
+        // each would flush an empty map and drag the averages down
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        interval
+    }
+
+    /// Tells the app when `task` panics, tokio would otherwise swallow the
+    /// panic and leave the UI running on a dead task.
+    fn supervise(&self, task: &'static str, handle: JoinHandle<()>) {
+        let scanner_outputs = self.scanner_outputs.clone();
+        tokio::spawn(async move {
+            let Err(e) = handle.await else {
+                return;
+            };
+            if !e.is_panic() {
+                return;
+            }
+            let payload = e.into_panic();
+            let reason = payload
+                .downcast_ref::<&str>()
+                .map(|reason| reason.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            let msg = format!("{} task panicked: {}", task, reason);
+            trace_dbg!(level: Level::ERROR, msg);
+            if scanner_outputs
+                .send(Event::Scanner(ScannerEvent::TaskFailed { task, reason }))
+                .is_err()
+            {
+                Self::log_channel_closed("task supervision");
+            }
+        });
+    }
+
+    /// A closed events channel means the app is shutting down, producers
+    /// stop and drop what they hold instead of panicking.
+    fn log_channel_closed(producer: &str) {
+        let msg = format!("events channel closed, stopping {}", producer);
+        trace_dbg!(level: Level::DEBUG, msg);
+    }
+
+    /// No host answers on these, except on /31 and /32 where every address is a host.
+    fn is_network_or_broadcast(ip_network: ipnetwork::IpNetwork, ip: Ipv4Addr) -> bool {
+        match ip_network {
+            ipnetwork::IpNetwork::V4(network) if network.prefix() < 31 => {
+                ip == network.network() || ip == network.broadcast()
             }
+            _ => false,
         }
-        sender
-            .send(Event::Scanner(crate::event::ScannerEvent::Complete))
-            .unwrap();
     }
 
     fn find_interface(interface_name: String) -> AppResult<pnet_datalink::NetworkInterface> {
@@ -222,54 +755,79 @@ impl Scanner {
             .ok_or("interface not found")?)
     }
 
+    /// Unlike the name lookup there is no fallback: the pattern has to single
+    /// out exactly one usable interface.
+    fn find_interface_matching(regex: &Regex) -> AppResult<pnet_datalink::NetworkInterface> {
+        let mut candidates: Vec<NetworkInterface> = pnet_datalink::interfaces()
+            .into_iter()
+            .filter(|nif| {
+                nif.is_up() && nif.is_running() && !nif.is_loopback() && regex.is_match(&nif.name)
+            })
+            .collect();
+        match candidates.len() {
+            0 => Err(format!("no interface matches /{}/", regex).into()),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(format!(
+                "several interfaces match /{}/: {}",
+                regex,
+                candidates.iter().map(|nif| nif.name.as_str()).join(", ")
+            )
+            .into()),
+        }
+    }
+
+    /// The name of the interface `--name` would pick, the first usable one
+    /// without it.
+    pub fn default_interface(interface_name: Option<String>) -> Option<String> {
+        Self::find_interface_or_get_default(interface_name.unwrap_or_default())
+            .ok()
+            .map(|nif| nif.name)
+    }
+
     fn find_interface_or_get_default(
         interface_name: String,
     ) -> AppResult<pnet_datalink::NetworkInterface> {
-        let interfaces = pnet_datalink::interfaces();
-
         let nif = if let Ok(c_nif) = Self::find_interface(interface_name) {
             c_nif
         } else {
-            interfaces
+            Self::usable_interfaces()
                 .into_iter()
-                .rev()
-                .find(|nif| nif.is_up() && nif.is_running() && !nif.is_loopback())
+                .next()
                 .ok_or("interface not found")?
         };
         Ok(nif)
     }
 
+    /// Broadcasts a request for `target_ip`, padded to the minimum frame length.
     fn send_arp_request(
         tx: &mut Box<dyn DataLinkSender>,
         interface: &NetworkInterface,
         target_ip: Ipv4Addr,
-    ) {
-        let mut ethernet_buffer = vec![0u8; 42];
-        let mut ethernet_packet =
-            MutableEthernetPacket::new(&mut ethernet_buffer).unwrap_or_else(|| {
-                // eprintln!("Could not build Ethernet packet");
-                process::exit(1);
-            });
+    ) -> io::Result<()> {
+        let source_mac = interface
+            .mac
+            .ok_or_else(|| io::Error::other("the interface has no MAC address"))?;
+        let frame = Self::arp_request_frame(source_mac, Self::find_source_ip(interface), target_ip);
+        match tx.send_to(&frame, Some(interface.clone())) {
+            Some(result) => result,
+            None => Err(io::Error::other("the interface can't send frames")),
+        }
+    }
 
-        let target_mac_broadcast = MacAddr::broadcast();
-        let source_mac = interface.mac.unwrap_or_else(|| {
-            // eprintln!("Interface should have a MAC address");
-            process::exit(1);
-        });
+    fn arp_request_frame(source_mac: MacAddr, source_ip: Ipv4Addr, target_ip: Ipv4Addr) -> Vec<u8> {
+        // the bytes after the ARP packet stay zero, that is the padding
+        let mut ethernet_buffer = vec![0u8; MIN_ETHERNET_FRAME_LEN];
+        let mut ethernet_packet = MutableEthernetPacket::new(&mut ethernet_buffer)
+            .expect("the buffer is longer than an Ethernet header");
 
+        let target_mac_broadcast = MacAddr::broadcast();
         ethernet_packet.set_destination(target_mac_broadcast);
         ethernet_packet.set_source(source_mac);
-
-        let selected_ethertype = EtherTypes::Arp;
-        ethernet_packet.set_ethertype(selected_ethertype);
+        ethernet_packet.set_ethertype(EtherTypes::Arp);
 
         let mut arp_buffer = [0u8; 28];
-        let mut arp_packet = MutableArpPacket::new(&mut arp_buffer).unwrap_or_else(|| {
-            // eprintln!("Could not build ARP packet");
-            process::exit(1);
-        });
-
-        let source_ip = Self::find_source_ip(interface);
+        let mut arp_packet =
+            MutableArpPacket::new(&mut arp_buffer).expect("the buffer fits an ARP packet");
 
         arp_packet.set_hardware_type(ArpHardwareTypes::Ethernet);
         arp_packet.set_protocol_type(EtherTypes::Ipv4);
@@ -282,11 +840,7 @@ impl Scanner {
         arp_packet.set_target_proto_addr(target_ip);
 
         ethernet_packet.set_payload(arp_packet.packet_mut());
-
-        tx.send_to(
-            ethernet_packet.to_immutable().packet(),
-            Some(interface.clone()),
-        );
+        ethernet_buffer
     }
 
     fn find_source_ip(network_interface: &NetworkInterface) -> Ipv4Addr {
@@ -303,91 +857,877 @@ impl Scanner {
         }
     }
 
+    pub fn is_monitor_only(&self) -> bool {
+        self.monitor_only
+    }
+
+    pub fn is_passive(&self) -> bool {
+        self.passive
+    }
+
+    pub fn vlan(&self) -> Option<u16> {
+        self.vlan
+    }
+
+    /// Whether the traffic is captured, see [`Scanner::without_capture`].
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    /// Whether ARP requests may be sent at all.
+    fn transmits(&self) -> bool {
+        self.capturing && !self.monitor_only && !self.passive
+    }
+
+    /// Only accounts the flows of `ip` from now on, or every flow again.
+    pub fn set_solo(&self, ip: Option<Ipv4Addr>) {
+        let ip = ip.unwrap_or(Ipv4Addr::UNSPECIFIED);
+        self.solo.store(u32::from(ip), Ordering::Relaxed);
+    }
+
+    /// Paces the ARP requests of the sweeps, the running one included.
+    pub fn set_scan_delay(&self, delay: Duration) {
+        self.scan_delay_ms
+            .store(delay.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn scan_delay(&self) -> Duration {
+        Duration::from_millis(self.scan_delay_ms.load(Ordering::Relaxed))
+    }
+
+    /// What the capture loop has processed so far.
+    pub fn capture_stats(&self) -> CaptureStats {
+        self.capture_counters.snapshot()
+    }
+
+    /// Stops the running sweep, if any; it still ends with a `Complete` event.
+    pub fn cancel_scan(&self) {
+        self.scan_cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// The networks sweeps probe and what looked wrong with the interface's.
+    pub fn network_plan(&self) -> &NetworkPlan {
+        &self.network_plan
+    }
+
     pub fn send_arp_packets(&self) {
-        self.scanner_input_tx
+        if !self.transmits() {
+            return;
+        }
+        if self
+            .scanner_input_tx
             .send(ScannerInputEvent::StartScanning)
-            .unwrap();
+            .is_err()
+        {
+            Self::log_channel_closed("ARP sweep requests");
+        }
+    }
+
+    /// The EtherType and payload of a frame past its 802.1Q and 802.1ad
+    /// tags, with the outermost VLAN id; `None` for runt frames.
+    fn untag(buffer: &[u8]) -> Option<(EtherType, Option<u16>, &[u8])> {
+        let mut offset = MutableEthernetPacket::minimum_packet_size();
+        let read_u16 = |at: usize| {
+            buffer
+                .get(at..at + 2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        };
+        let mut ethertype = EtherType(read_u16(offset - 2)?);
+        let mut vlan = None;
+        // 802.1ad's outer tag, or the older Q-in-Q one, then 802.1Q's
+        while [EtherTypes::PBridge, EtherTypes::QinQ, EtherTypes::Vlan].contains(&ethertype) {
+            // tag control: priority, drop eligible, then the 12 bits of the id
+            let tci = read_u16(offset)?;
+            vlan = vlan.or(Some(tci & 0x0fff));
+            ethertype = EtherType(read_u16(offset + 2)?);
+            offset += VLAN_TAG_LEN;
+        }
+        Some((ethertype, vlan, buffer.get(offset..)?))
     }
 
-    fn get_host_infos(buffer: &[u8], def_nif: &NetworkInterface) -> Option<Host> {
-        let arp_packet = ArpPacket::new(&buffer[MutableEthernetPacket::minimum_packet_size()..]);
+    fn get_host_infos(payload: &[u8], def_nif: &NetworkInterface) -> Option<Host> {
+        let arp_packet = ArpPacket::new(payload);
         if let Some(arp) = arp_packet {
             let sender_ipv4 = arp.get_sender_proto_addr();
             let sender_mac = arp.get_sender_hw_addr();
+            // an address conflict probe (RFC 5227), the sender has no address yet
+            if sender_ipv4.is_unspecified() {
+                return None;
+            }
 
-            let host = Host {
-                hostname: None,
-                time: chrono::Local::now(),
-                mac: sender_mac,
-                ipv4: sender_ipv4,
-                is_my_device_mac: sender_mac == def_nif.mac.unwrap_or_default(),
-                speed: None,
-            };
+            let mut host = Host::new(
+                sender_ipv4,
+                sender_mac,
+                sender_mac == def_nif.mac.unwrap_or_default(),
+            );
+            host.arp = ArpActivity::from_operation(arp.get_operation());
             Some(host)
         } else {
             None
         }
     }
 
+    /// Passive discovery used in monitor-only and passive modes: the sender of an IPv4 frame
+    /// with a private source address is taken as a host of the monitored segment
+    fn get_observed_host(
+        src_mac: MacAddr,
+        payload: &[u8],
+        def_nif: &NetworkInterface,
+    ) -> Option<Host> {
+        let ipv4_packet = Ipv4Packet::new(payload)?;
+        let src_ip = ipv4_packet.get_source();
+        if !Self::is_segment_address(src_ip) {
+            return None;
+        }
+        Some(Host::new(
+            src_ip,
+            src_mac,
+            src_mac == def_nif.mac.unwrap_or_default(),
+        ))
+    }
+
+    /// Without a local address on the interface there is nothing to compare
+    /// against, so private and link-local ranges stand in for "our" side
+    fn is_segment_address(ip: Ipv4Addr) -> bool {
+        ip.is_private() || ip.is_link_local()
+    }
+
+    /// The IPv4 payload, once the header length is known to be sane: at least
+    /// the 20 fixed bytes and within both the captured and the total length.
+    /// pnet trusts the IHL, a bogus one would have the ports read from inside
+    /// the options or the data.
+    fn ipv4_payload<'p>(ipv4_packet: &'p Ipv4Packet) -> Option<&'p [u8]> {
+        let header_len = ipv4_packet.get_header_length() as usize * 4;
+        let is_sane = ipv4_packet.get_version() == 4
+            && header_len >= Ipv4Packet::minimum_packet_size()
+            && header_len <= ipv4_packet.packet().len()
+            && header_len <= ipv4_packet.get_total_length() as usize;
+        is_sane.then(|| ipv4_packet.payload())
+    }
+
     fn get_stats(
-        ethernet_packet: EthernetPacket,
+        payload: &[u8],
         def_nif: &NetworkInterface,
-    ) -> Option<stats_aggregator::StatItem> {
-        let ipv4_packet = Ipv4Packet::new(ethernet_packet.payload())?;
+        monitor_only: bool,
+        counters: &CaptureCounters,
+    ) -> Result<stats_aggregator::StatItem, Ignored> {
+        let ipv4_packet = Ipv4Packet::new(payload).ok_or(Ignored::Malformed)?;
+        let payload = Self::ipv4_payload(&ipv4_packet).ok_or(Ignored::Malformed)?;
+        // a frame longer than the capture buffer was cut short: what the
+        // header declares is accounted, the ports come from what is left
+        let missing =
+            (ipv4_packet.get_total_length() as usize).saturating_sub(ipv4_packet.packet().len());
+        if missing > 0 {
+            counters.truncated();
+        }
         let src_ip = ipv4_packet.get_source();
         let dst_ip = ipv4_packet.get_destination();
         let next_level_protocol = ipv4_packet.get_next_level_protocol();
-        let ips: Vec<IpAddr> = def_nif
-            .ips
-            .iter()
-            .filter(|ipn| ipn.is_ipv4())
-            .flatten()
-            .collect();
+        let direction = if monitor_only {
+            let direction = match (
+                Self::is_segment_address(src_ip),
+                Self::is_segment_address(dst_ip),
+            ) {
+                (true, true) => stats_aggregator::Direction::Local,
+                (true, false) => stats_aggregator::Direction::Outgoing,
+                (false, true) => stats_aggregator::Direction::Incomming,
+                (false, false) => stats_aggregator::Direction::None,
+            };
+            (direction, None)
+        } else {
+            stats_aggregator::Direction::classify(src_ip, dst_ip, &def_nif.ips)
+        };
 
-        let direction = match (
-            ips.contains(&IpAddr::from(src_ip)),
-            ips.contains(&IpAddr::from(dst_ip)),
-        ) {
-            (true, true) => stats_aggregator::Direction::Local,
-            (true, false) => stats_aggregator::Direction::Outgoing,
-            (false, true) => stats_aggregator::Direction::Incomming,
-            (false, false) => stats_aggregator::Direction::None,
+        // a truncated or odd TCP/UDP header still carried bytes: they are
+        // accounted for the IP pair, with ports zeroed. Cut by the capture
+        // buffer, the header is among the missing bytes: its minimum length
+        // is left out like a parsed one would be
+        let unparsed = |transport, header_len: usize| {
+            counters.unparsed(transport);
+            let header_len = if missing > 0 { header_len } else { 0 };
+            (
+                transport,
+                0,
+                0,
+                (payload.len() + missing).saturating_sub(header_len),
+            )
         };
+        let (transport, src_port, dst_port, accounted_len) = match next_level_protocol {
+            IpNextHeaderProtocols::Tcp => match TcpPacket::new(payload) {
+                Some(message) => (
+                    stats_aggregator::Transport::Tcp,
+                    message.get_source(),
+                    message.get_destination(),
+                    message.payload().len() + missing,
+                ),
+                None => unparsed(
+                    stats_aggregator::Transport::Tcp,
+                    TcpPacket::minimum_packet_size(),
+                ),
+            },
+            IpNextHeaderProtocols::Udp => match UdpPacket::new(payload) {
+                Some(datagram) => (
+                    stats_aggregator::Transport::Udp,
+                    datagram.get_source(),
+                    datagram.get_destination(),
+                    datagram.payload().len() + missing,
+                ),
+                None => unparsed(
+                    stats_aggregator::Transport::Udp,
+                    UdpPacket::minimum_packet_size(),
+                ),
+            },
+            _ => return Err(Ignored::OtherProtocol),
+        };
+        // both directions of a connection land in the same entry
+        let (key, sent_by_a) = stats_aggregator::StatKey::of_frame(
+            transport,
+            (src_ip, src_port),
+            (dst_ip, dst_port),
+            direction,
+        );
+        Ok(stats_aggregator::StatItem {
+            key,
+            value: stats_aggregator::StatValues::sent(sent_by_a, 8 * accounted_len as u128),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dry_run::DryRunSender;
+    use pnet::{
+        ipnetwork::{IpNetwork, Ipv4Network},
+        packet::{
+            arp::ArpOperation, ip::IpNextHeaderProtocol, ipv4::MutableIpv4Packet,
+            tcp::MutableTcpPacket, udp::MutableUdpPacket,
+        },
+    };
+    use stats_aggregator::{FlowScope, StatItem, Transport};
+
+    const OUR_MAC: MacAddr = MacAddr(0x02, 0, 0, 0, 0, 0x01);
+    const OTHER_MAC: MacAddr = MacAddr(0x02, 0, 0, 0, 0, 0x02);
+    const US: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 10);
+    const LAN_HOST: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 20);
+    const REMOTE: Ipv4Addr = Ipv4Addr::new(93, 184, 216, 34);
+    const OTHER_REMOTE: Ipv4Addr = Ipv4Addr::new(1, 1, 1, 1);
+
+    fn nif() -> NetworkInterface {
+        NetworkInterface {
+            name: "eth0".to_string(),
+            description: String::new(),
+            index: 1,
+            mac: Some(OUR_MAC),
+            ips: vec![IpNetwork::V4(Ipv4Network::new(US, 24).unwrap())],
+            flags: 0,
+        }
+    }
+
+    fn ipv4(src: Ipv4Addr, dst: Ipv4Addr, protocol: IpNextHeaderProtocol, l4: &[u8]) -> Vec<u8> {
+        let mut buffer = vec![0; Ipv4Packet::minimum_packet_size() + l4.len()];
+        let total_length = buffer.len() as u16;
+        let mut packet = MutableIpv4Packet::new(&mut buffer).unwrap();
+        packet.set_version(4);
+        packet.set_header_length(5);
+        packet.set_total_length(total_length);
+        packet.set_ttl(64);
+        packet.set_next_level_protocol(protocol);
+        packet.set_source(src);
+        packet.set_destination(dst);
+        packet.set_payload(l4);
+        buffer
+    }
+
+    fn tcp(src_port: u16, dst_port: u16, data_len: usize) -> Vec<u8> {
+        let mut buffer = vec![0; TcpPacket::minimum_packet_size() + data_len];
+        let mut segment = MutableTcpPacket::new(&mut buffer).unwrap();
+        segment.set_source(src_port);
+        segment.set_destination(dst_port);
+        segment.set_data_offset(5);
+        buffer
+    }
+
+    fn udp(src_port: u16, dst_port: u16, data_len: usize) -> Vec<u8> {
+        let mut buffer = vec![0; UdpPacket::minimum_packet_size() + data_len];
+        let length = buffer.len() as u16;
+        let mut datagram = MutableUdpPacket::new(&mut buffer).unwrap();
+        datagram.set_source(src_port);
+        datagram.set_destination(dst_port);
+        datagram.set_length(length);
+        buffer
+    }
+
+    fn tcp_packet(src: (Ipv4Addr, u16), dst: (Ipv4Addr, u16), data_len: usize) -> Vec<u8> {
+        ipv4(
+            src.0,
+            dst.0,
+            IpNextHeaderProtocols::Tcp,
+            &tcp(src.1, dst.1, data_len),
+        )
+    }
+
+    /// An Ethernet frame from `OTHER_MAC`, padded to the 60 bytes minimum.
+    fn ethernet(tags: &[(EtherType, u16)], ethertype: EtherType, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0; 12];
+        frame[..6].copy_from_slice(&OUR_MAC.octets());
+        frame[6..12].copy_from_slice(&OTHER_MAC.octets());
+        for (tpid, vlan) in tags {
+            frame.extend(tpid.0.to_be_bytes());
+            frame.extend(vlan.to_be_bytes());
+        }
+        frame.extend(ethertype.0.to_be_bytes());
+        frame.extend(payload);
+        frame.resize(frame.len().max(MIN_ETHERNET_FRAME_LEN), 0);
+        frame
+    }
+
+    fn arp(operation: ArpOperation, sender: (MacAddr, Ipv4Addr), target_ip: Ipv4Addr) -> Vec<u8> {
+        let mut buffer = vec![0; MutableArpPacket::minimum_packet_size()];
+        let mut packet = MutableArpPacket::new(&mut buffer).unwrap();
+        packet.set_hardware_type(ArpHardwareTypes::Ethernet);
+        packet.set_protocol_type(EtherTypes::Ipv4);
+        packet.set_hw_addr_len(6);
+        packet.set_proto_addr_len(4);
+        packet.set_operation(operation);
+        packet.set_sender_hw_addr(sender.0);
+        packet.set_sender_proto_addr(sender.1);
+        packet.set_target_proto_addr(target_ip);
+        buffer
+    }
+
+    fn stats(packet: &[u8], monitor_only: bool) -> Result<StatItem, Ignored> {
+        Scanner::get_stats(packet, &nif(), monitor_only, &CaptureCounters::new())
+    }
+
+    #[test]
+    fn outgoing_frames_are_sent_by_the_lan_end() {
+        let item = stats(&tcp_packet((LAN_HOST, 50000), (REMOTE, 443), 100), false).unwrap();
+        assert_eq!(item.key.scope, FlowScope::External);
+        assert_eq!((item.key.a_ip, item.key.a_port), (LAN_HOST, 50000));
+        assert_eq!((item.key.b_ip, item.key.b_port), (REMOTE, 443));
+        assert_eq!((item.value.a_sent, item.value.b_sent), (800, 0));
+    }
+
+    #[test]
+    fn incoming_frames_are_sent_by_the_remote_end() {
+        let item = stats(&tcp_packet((REMOTE, 443), (LAN_HOST, 50000), 100), false).unwrap();
+        assert_eq!(item.key.scope, FlowScope::External);
+        assert_eq!((item.key.a_ip, item.key.a_port), (LAN_HOST, 50000));
+        assert_eq!((item.value.a_sent, item.value.b_sent), (0, 800));
+    }
+
+    #[test]
+    fn both_directions_of_a_local_flow_share_a_key() {
+        let there = stats(&tcp_packet((US, 22), (LAN_HOST, 40000), 10), false).unwrap();
+        let back = stats(&tcp_packet((LAN_HOST, 40000), (US, 22), 30), false).unwrap();
+        assert_eq!(there.key.scope, FlowScope::Local);
+        assert_eq!(there.key, back.key);
+        // the lowest endpoint is `a`
+        assert_eq!(there.key.a_ip, US);
+        assert_eq!((there.value.a_sent, there.value.b_sent), (80, 0));
+        assert_eq!((back.value.a_sent, back.value.b_sent), (0, 240));
+    }
+
+    #[test]
+    fn frames_between_two_remotes_are_foreign() {
+        let item = stats(&tcp_packet((REMOTE, 443), (OTHER_REMOTE, 53), 10), false).unwrap();
+        assert_eq!(item.key.scope, FlowScope::Foreign);
+    }
+
+    #[test]
+    fn the_subnet_broadcast_is_local() {
+        let broadcast = Ipv4Addr::new(192, 168, 1, 255);
+        let packet = ipv4(
+            LAN_HOST,
+            broadcast,
+            IpNextHeaderProtocols::Udp,
+            &udp(5000, 5000, 4),
+        );
+        assert_eq!(stats(&packet, false).unwrap().key.scope, FlowScope::Local);
+    }
+
+    #[test]
+    fn monitor_only_takes_private_addresses_as_local() {
+        let other_segment = Ipv4Addr::new(10, 0, 0, 5);
+        let item = stats(&tcp_packet((other_segment, 50000), (REMOTE, 80), 1), true).unwrap();
+        assert_eq!(item.key.scope, FlowScope::External);
+        assert_eq!(item.key.a_ip, other_segment);
+        let item = stats(&tcp_packet((other_segment, 50000), (LAN_HOST, 80), 1), true).unwrap();
+        assert_eq!(item.key.scope, FlowScope::Local);
+        let item = stats(&tcp_packet((REMOTE, 443), (OTHER_REMOTE, 53), 1), true).unwrap();
+        assert_eq!(item.key.scope, FlowScope::Foreign);
+    }
+
+    #[test]
+    fn udp_ports_and_payload_are_read() {
+        let packet = ipv4(
+            LAN_HOST,
+            REMOTE,
+            IpNextHeaderProtocols::Udp,
+            &udp(5353, 53, 12),
+        );
+        let item = stats(&packet, false).unwrap();
+        assert_eq!(item.key.transport, Transport::Udp);
+        assert_eq!((item.key.a_port, item.key.b_port), (5353, 53));
+        assert_eq!(item.value.a_sent, 8 * 12);
+    }
+
+    #[test]
+    fn ethernet_padding_is_not_traffic() {
+        let packet = tcp_packet((LAN_HOST, 50000), (REMOTE, 443), 0);
+        let frame = ethernet(&[], EtherTypes::Ipv4, &packet);
+        let (_, _, payload) = Scanner::untag(&frame).unwrap();
+        assert!(payload.len() > packet.len());
+        assert_eq!(stats(payload, false).unwrap().value.total(), 0);
+    }
+
+    #[test]
+    fn a_truncated_tcp_header_is_accounted_without_ports() {
+        let packet = ipv4(LAN_HOST, REMOTE, IpNextHeaderProtocols::Tcp, &[0; 12]);
+        let counters = CaptureCounters::new();
+        let item = Scanner::get_stats(&packet, &nif(), false, &counters).unwrap();
+        assert_eq!((item.key.a_port, item.key.b_port), (0, 0));
+        assert_eq!(item.value.a_sent, 8 * 12);
+        assert_eq!(counters.snapshot().tcp_unparsed, 1);
+    }
+
+    #[test]
+    fn a_frame_cut_by_the_capture_buffer_accounts_its_declared_length() {
+        // a 9000 bytes jumbo frame read into a 1522 bytes buffer
+        let mut packet = tcp_packet((LAN_HOST, 50000), (REMOTE, 443), 9000 - 40);
+        let declared = packet.len();
+        packet.truncate(MIN_CAPTURE_BUFFER - 14);
+        assert_eq!(declared, 9000);
+        let counters = CaptureCounters::new();
+        let item = Scanner::get_stats(&packet, &nif(), false, &counters).unwrap();
+        assert_eq!((item.key.a_port, item.key.b_port), (50000, 443));
+        assert_eq!(item.value.a_sent, 8 * (9000 - 40));
+        assert_eq!(counters.snapshot().truncated, 1);
 
-        let stat = match next_level_protocol {
-            IpNextHeaderProtocols::Tcp => {
-                let message = TcpPacket::new(ipv4_packet.payload())?;
-                Some(stats_aggregator::StatItem {
-                    key: stats_aggregator::StatKey {
-                        direction,
-                        src_port: message.get_source(),
-                        sdt_port: message.get_destination(),
-                        src_ip,
-                        dst_ip,
-                    },
-                    value: stats_aggregator::StatValues {
-                        size: 8 * message.payload().len() as u128,
-                    },
-                })
+        // down to the IP header, still the declared bytes but the TCP
+        // header, without ports
+        packet.truncate(24);
+        let item = Scanner::get_stats(&packet, &nif(), false, &counters).unwrap();
+        assert_eq!((item.key.a_port, item.key.b_port), (0, 0));
+        assert_eq!(item.value.a_sent, 8 * (9000 - 40));
+        assert_eq!(counters.snapshot().truncated, 2);
+    }
+
+    #[test]
+    fn a_whole_jumbo_frame_is_not_truncated() {
+        let packet = tcp_packet((LAN_HOST, 50000), (REMOTE, 443), 9000 - 40);
+        let counters = CaptureCounters::new();
+        let item = Scanner::get_stats(&packet, &nif(), false, &counters).unwrap();
+        assert_eq!(item.value.a_sent, 8 * (9000 - 40));
+        assert_eq!(counters.snapshot().truncated, 0);
+    }
+
+    #[test]
+    fn a_tcp_data_offset_past_the_segment_counts_nothing() {
+        let mut segment = tcp(50000, 443, 0);
+        MutableTcpPacket::new(&mut segment)
+            .unwrap()
+            .set_data_offset(15);
+        let packet = ipv4(LAN_HOST, REMOTE, IpNextHeaderProtocols::Tcp, &segment);
+        assert_eq!(stats(&packet, false).unwrap().value.total(), 0);
+    }
+
+    #[test]
+    fn other_protocols_and_bad_headers_are_ignored() {
+        let icmp = ipv4(LAN_HOST, REMOTE, IpNextHeaderProtocols::Icmp, &[0; 8]);
+        assert!(matches!(stats(&icmp, false), Err(Ignored::OtherProtocol)));
+        let mut bad_ihl = tcp_packet((LAN_HOST, 50000), (REMOTE, 443), 0);
+        MutableIpv4Packet::new(&mut bad_ihl)
+            .unwrap()
+            .set_header_length(15);
+        assert!(matches!(stats(&bad_ihl, false), Err(Ignored::Malformed)));
+        assert!(matches!(stats(&[0x45; 10], false), Err(Ignored::Malformed)));
+    }
+
+    /// `packet` with `options` between its IPv4 header and the payload.
+    fn with_ip_options(packet: &[u8], options: &[u8]) -> Vec<u8> {
+        let header_len = Ipv4Packet::minimum_packet_size();
+        let mut buffer = packet[..header_len].to_vec();
+        buffer.extend(options);
+        buffer.extend(&packet[header_len..]);
+        let total_length = buffer.len() as u16;
+        let mut ipv4 = MutableIpv4Packet::new(&mut buffer).unwrap();
+        ipv4.set_header_length(((header_len + options.len()) / 4) as u8);
+        ipv4.set_total_length(total_length);
+        buffer
+    }
+
+    #[test]
+    fn ports_are_read_past_the_ip_options() {
+        // record route with room for two addresses, padded to 12 bytes
+        let record_route = [7, 11, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let packet = with_ip_options(
+            &tcp_packet((LAN_HOST, 50000), (REMOTE, 443), 100),
+            &record_route,
+        );
+        let item = stats(&packet, false).unwrap();
+        assert_eq!((item.key.a_port, item.key.b_port), (50000, 443));
+        assert_eq!(item.value.a_sent, 8 * 100);
+
+        let packet = with_ip_options(
+            &ipv4(
+                LAN_HOST,
+                REMOTE,
+                IpNextHeaderProtocols::Udp,
+                &udp(5353, 53, 30),
+            ),
+            &[1; 40],
+        );
+        let item = stats(&packet, false).unwrap();
+        assert_eq!((item.key.a_port, item.key.b_port), (5353, 53));
+        assert_eq!(item.value.a_sent, 8 * 30);
+    }
+
+    #[test]
+    fn an_ihl_out_of_bounds_is_malformed() {
+        let packet = tcp_packet((LAN_HOST, 50000), (REMOTE, 443), 100);
+        // shorter than the fixed header, or past the total length though
+        // within the captured bytes, e.g. Ethernet padding
+        for (ihl, total_length) in [(4, packet.len()), (6, 22), (15, 40)] {
+            let mut bad = packet.clone();
+            let mut ipv4 = MutableIpv4Packet::new(&mut bad).unwrap();
+            ipv4.set_header_length(ihl);
+            ipv4.set_total_length(total_length as u16);
+            assert!(
+                matches!(stats(&bad, false), Err(Ignored::Malformed)),
+                "IHL {}",
+                ihl
+            );
+        }
+    }
+
+    #[test]
+    fn arp_requests_and_replies_make_hosts() {
+        let request = arp(ArpOperations::Request, (OTHER_MAC, LAN_HOST), US);
+        let host = Scanner::get_host_infos(&request, &nif()).unwrap();
+        assert_eq!((host.ipv4, host.mac), (LAN_HOST, OTHER_MAC));
+        assert!(!host.is_my_device_mac);
+        assert_eq!((host.arp.requests, host.arp.replies), (1, 0));
+
+        let reply = arp(ArpOperations::Reply, (OUR_MAC, US), LAN_HOST);
+        let host = Scanner::get_host_infos(&reply, &nif()).unwrap();
+        assert!(host.is_my_device_mac);
+        assert_eq!((host.arp.requests, host.arp.replies), (0, 1));
+    }
+
+    #[test]
+    fn arp_probes_and_short_payloads_make_no_host() {
+        let probe = arp(
+            ArpOperations::Request,
+            (OTHER_MAC, Ipv4Addr::UNSPECIFIED),
+            LAN_HOST,
+        );
+        assert!(Scanner::get_host_infos(&probe, &nif()).is_none());
+        assert!(Scanner::get_host_infos(&[0; 10], &nif()).is_none());
+    }
+
+    #[test]
+    fn frames_are_read_past_their_vlan_tags() {
+        let packet = tcp_packet((LAN_HOST, 50000), (REMOTE, 443), 0);
+        let untagged = ethernet(&[], EtherTypes::Ipv4, &packet);
+        assert_eq!(Scanner::untag(&untagged).unwrap().1, None);
+
+        let tagged = ethernet(&[(EtherTypes::Vlan, 20)], EtherTypes::Ipv4, &packet);
+        let (ethertype, vlan, payload) = Scanner::untag(&tagged).unwrap();
+        assert_eq!((ethertype, vlan), (EtherTypes::Ipv4, Some(20)));
+        assert_eq!(&payload[..packet.len()], &packet[..]);
+
+        // the priority bits aren't part of the id, the outer tag wins
+        let stacked = ethernet(
+            &[(EtherTypes::PBridge, 0xe000 | 100), (EtherTypes::Vlan, 20)],
+            EtherTypes::Arp,
+            &[],
+        );
+        let (ethertype, vlan, _) = Scanner::untag(&stacked).unwrap();
+        assert_eq!((ethertype, vlan), (EtherTypes::Arp, Some(100)));
+
+        assert!(Scanner::untag(&[0; 10]).is_none());
+        assert!(Scanner::untag(&untagged[..14 + 2]).is_some());
+        assert!(Scanner::untag(&tagged[..16]).is_none());
+    }
+
+    /// Keeps the frames a sweep sends instead of putting them on a wire.
+    struct MockSender(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl DataLinkSender for MockSender {
+        fn build_and_send(
+            &mut self,
+            num_packets: usize,
+            packet_size: usize,
+            func: &mut dyn FnMut(&mut [u8]),
+        ) -> Option<io::Result<()>> {
+            for _ in 0..num_packets {
+                let mut frame = vec![0; packet_size];
+                func(&mut frame);
+                self.0.lock().unwrap().push(frame);
+            }
+            Some(Ok(()))
+        }
+
+        fn send_to(
+            &mut self,
+            packet: &[u8],
+            _dst: Option<NetworkInterface>,
+        ) -> Option<io::Result<()>> {
+            self.0.lock().unwrap().push(packet.to_vec());
+            Some(Ok(()))
+        }
+    }
+
+    /// A sweep task of the network `US/prefix`, waiting for its capture.
+    struct Sweeps {
+        requests: UnboundedSender<ScannerInputEvent>,
+        events: UnboundedReceiver<Event>,
+        capture_ready: Arc<Notify>,
+        delay_ms: Arc<AtomicU64>,
+        task: JoinHandle<()>,
+    }
+
+    impl Sweeps {
+        fn spawn(prefix: u8, sender: Box<dyn DataLinkSender>) -> Self {
+            let mut nif = nif();
+            nif.ips = vec![IpNetwork::V4(Ipv4Network::new(US, prefix).unwrap())];
+            let networks = NetworkPlan::analyze(&nif.ips).scan;
+            let (requests, input_rx) = unbounded_channel();
+            let (outputs, events) = unbounded_channel();
+            let state = SweepState {
+                cancelled: Default::default(),
+                pending_requests: Default::default(),
+                capture_ready: Default::default(),
+                delay_ms: Default::default(),
+            };
+            let capture_ready = state.capture_ready.clone();
+            let delay_ms = state.delay_ms.clone();
+            let task = tokio::spawn(Scanner::run_sweeps(
+                input_rx, sender, nif, networks, outputs, state,
+            ));
+            Self {
+                requests,
+                events,
+                capture_ready,
+                delay_ms,
+                task,
             }
-            IpNextHeaderProtocols::Udp => {
-                let datagram = UdpPacket::new(ipv4_packet.payload())?;
-                Some(stats_aggregator::StatItem {
-                    key: stats_aggregator::StatKey {
-                        direction,
-                        src_port: datagram.get_source(),
-                        sdt_port: datagram.get_destination(),
-                        src_ip,
-                        dst_ip,
-                    },
-                    value: stats_aggregator::StatValues {
-                        size: 8 * datagram.payload().len() as u128,
-                    },
-                })
+        }
+
+        /// Spawned with the capture already reading.
+        fn ready(prefix: u8, sender: Box<dyn DataLinkSender>) -> Self {
+            let sweeps = Self::spawn(prefix, sender);
+            sweeps.capture_ready.notify_one();
+            sweeps
+        }
+
+        fn start(&self) {
+            self.requests
+                .send(ScannerInputEvent::StartScanning)
+                .unwrap();
+        }
+
+        /// The summary of the sweep, with the errors reported on the way.
+        async fn complete(&mut self) -> (SweepSummary, Vec<String>) {
+            let mut errors = vec![];
+            loop {
+                match self.events.recv().await {
+                    Some(Event::Scanner(ScannerEvent::Complete(summary))) => {
+                        return (summary, errors)
+                    }
+                    Some(Event::Scanner(ScannerEvent::Error(error))) => errors.push(error),
+                    Some(_) => {}
+                    None => panic!("the sweep ended without completing"),
+                }
             }
-            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn a_startup_sweep_sends_once_the_capture_is_ready() {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let mut sweeps = Sweeps::spawn(29, Box::new(MockSender(frames.clone())));
+        // what --scan-on-start sends, before the capture loop is up
+        sweeps.start();
+        tokio::task::yield_now().await;
+        assert!(frames.lock().unwrap().is_empty());
+
+        sweeps.capture_ready.notify_one();
+        let (summary, _) = sweeps.complete().await;
+        assert_eq!((summary.sent, summary.failed), (6, 0));
+        let targets = frames
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|frame| {
+                let ethernet = EthernetPacket::new(frame).unwrap();
+                assert_eq!(ethernet.get_destination(), MacAddr::broadcast());
+                let arp = ArpPacket::new(ethernet.payload()).unwrap();
+                assert_eq!(arp.get_operation(), ArpOperations::Request);
+                assert_eq!(arp.get_sender_proto_addr(), US);
+                arp.get_target_proto_addr()
+            })
+            .collect::<Vec<_>>();
+        // 192.168.1.8/29, without its network and broadcast addresses
+        let expected = (9..=14)
+            .map(|last| Ipv4Addr::new(192, 168, 1, last))
+            .collect::<Vec<_>>();
+        assert_eq!(targets, expected);
+    }
+
+    #[tokio::test]
+    async fn a_dry_run_sweep_completes_without_sending() {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let mut sweeps = Sweeps::ready(
+            29,
+            Box::new(DryRunSender::new(Box::new(MockSender(frames.clone())))),
+        );
+        sweeps.start();
+        let (summary, _) = sweeps.complete().await;
+        // reported as sent, as without --dry-run
+        assert_eq!((summary.sent, summary.failed), (6, 0));
+        assert!(frames.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn adaptive_ticks_slow_down_on_a_quiet_network_and_snap_back() {
+        let second = Duration::from_secs(1);
+        let interval = StatInterval {
+            base: second,
+            adaptive: true,
+        };
+        let mut period = interval.base;
+        let mut quiet = Duration::ZERO;
+        let mut periods = vec![];
+        for _ in 0..14 {
+            // a few hundred bits a second, nothing happening
+            let bits = 400 * period.as_secs() as u128;
+            period = interval.next_period(period, &mut quiet, bits, period);
+            periods.push(period.as_secs());
+        }
+        assert_eq!(periods, [1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 4, 5, 5, 5]);
+        // traffic again, measured over a quiet 5 s tick
+        let bits = QUIET_BITS_PER_SEC * 5;
+        period = interval.next_period(period, &mut quiet, bits, period);
+        assert_eq!((period, quiet), (second, Duration::ZERO));
+
+        // never faster than the base
+        let slow = StatInterval {
+            base: Duration::from_secs(3),
+            adaptive: true,
         };
+        let mut quiet = QUIET_BEFORE_SLOWDOWN;
+        assert_eq!(
+            slow.next_period(slow.base, &mut quiet, 0, slow.base),
+            Duration::from_secs(5)
+        );
+        let fixed = StatInterval {
+            base: MIN_STAT_INTERVAL,
+            adaptive: false,
+        };
+        let mut quiet = QUIET_BEFORE_SLOWDOWN;
+        assert_eq!(
+            fixed.next_period(fixed.base, &mut quiet, 0, fixed.base),
+            MIN_STAT_INTERVAL
+        );
+    }
+
+    #[test]
+    fn arp_requests_are_padded_to_the_ethernet_minimum() {
+        let frame = Scanner::arp_request_frame(OUR_MAC, US, LAN_HOST);
+        assert_eq!(frame.len(), MIN_ETHERNET_FRAME_LEN);
+        // 14 bytes of Ethernet header and 28 of ARP, then zeros
+        assert!(frame[14 + 28..].iter().all(|byte| *byte == 0));
+        let ethernet = EthernetPacket::new(&frame).unwrap();
+        assert_eq!(ethernet.get_destination(), MacAddr::broadcast());
+        assert_eq!(ethernet.get_source(), OUR_MAC);
+        assert_eq!(ethernet.get_ethertype(), EtherTypes::Arp);
+        let arp = ArpPacket::new(ethernet.payload()).unwrap();
+        assert_eq!(arp.get_operation(), ArpOperations::Request);
+        assert_eq!(arp.get_sender_hw_addr(), OUR_MAC);
+        assert_eq!(arp.get_sender_proto_addr(), US);
+        assert_eq!(arp.get_target_proto_addr(), LAN_HOST);
+    }
+
+    /// Fails the first `failures` frames it is given, sends the others.
+    struct FailingSender {
+        failures: usize,
+        attempts: usize,
+    }
+
+    impl DataLinkSender for FailingSender {
+        fn build_and_send(
+            &mut self,
+            _num_packets: usize,
+            _packet_size: usize,
+            _func: &mut dyn FnMut(&mut [u8]),
+        ) -> Option<io::Result<()>> {
+            // sweeps send whole frames
+            None
+        }
+
+        fn send_to(
+            &mut self,
+            _packet: &[u8],
+            _dst: Option<NetworkInterface>,
+        ) -> Option<io::Result<()>> {
+            self.attempts += 1;
+            if self.attempts > self.failures {
+                Some(Ok(()))
+            } else if self.attempts.is_multiple_of(2) {
+                // a sender that can't send at all fails the same
+                None
+            } else {
+                Some(Err(io::Error::other("no buffer space available")))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_sends_are_counted_and_reported_once_a_streak() {
+        // 14 addresses
+        let mut sweeps = Sweeps::ready(
+            28,
+            Box::new(FailingSender {
+                // past SEND_FAILURES_BEFORE_ERROR in a row
+                failures: 10,
+                attempts: 0,
+            }),
+        );
+        sweeps.start();
+        let (summary, errors) = sweeps.complete().await;
+        assert_eq!((summary.sent, summary.failed), (4, 10));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("eth0: ARP requests can't be sent"));
+    }
 
-        stat
+    #[tokio::test]
+    async fn a_sweep_stops_quietly_when_the_app_drops_its_events() {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let Sweeps {
+            requests,
+            mut events,
+            delay_ms,
+            task,
+            ..
+        } = Sweeps::ready(29, Box::new(MockSender(frames.clone())));
+        delay_ms.store(20, Ordering::Relaxed);
+        requests.send(ScannerInputEvent::StartScanning).unwrap();
+        assert!(matches!(
+            events.recv().await,
+            Some(Event::Scanner(ScannerEvent::BeginScan(_)))
+        ));
+        drop(events);
+        // the requests channel is still open, only the closed events end it
+        time::timeout(Duration::from_secs(5), task)
+            .await
+            .expect("the sweep task didn't stop")
+            .expect("the sweep task panicked");
+        // the rest of the network wasn't probed for nobody
+        assert!(frames.lock().unwrap().len() < 6);
+        // and the task dropped what it held
+        assert!(requests.is_closed());
     }
 }
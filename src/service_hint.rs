@@ -0,0 +1,96 @@
+use std::fmt::Display;
+
+use crate::stats_aggregator::Transport;
+
+/// Short "what is it doing" hint of a host, the service carrying most of its
+/// traffic, guessed from transport and port.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ServiceHint {
+    Https,
+    Http,
+    Quic,
+    Ssh,
+    Dns,
+    Smb,
+    Ntp,
+    Mdns,
+    Dhcp,
+    Rdp,
+    /// no port in the table
+    Unknown,
+    /// no service carries enough of the traffic to speak for it
+    Mixed,
+}
+
+/// share of the host's bytes the busiest service needs to be its hint
+pub const MIN_DOMINANT_SHARE: f64 = 0.5;
+
+/// start of the dynamic range, two ports in it say nothing of who serves
+const EPHEMERAL_PORTS: u16 = 49152;
+
+/// Well-known ports, `None` matches both transports; the first match wins.
+const SERVICE_PORTS: &[(Option<Transport>, u16, ServiceHint)] = &[
+    (Some(Transport::Udp), 443, ServiceHint::Quic),
+    (Some(Transport::Tcp), 443, ServiceHint::Https),
+    (None, 8443, ServiceHint::Https),
+    (None, 80, ServiceHint::Http),
+    (None, 8080, ServiceHint::Http),
+    (None, 22, ServiceHint::Ssh),
+    (None, 53, ServiceHint::Dns),
+    (None, 853, ServiceHint::Dns),
+    (None, 445, ServiceHint::Smb),
+    (None, 139, ServiceHint::Smb),
+    (None, 123, ServiceHint::Ntp),
+    (None, 5353, ServiceHint::Mdns),
+    (None, 67, ServiceHint::Dhcp),
+    (None, 68, ServiceHint::Dhcp),
+    (None, 3389, ServiceHint::Rdp),
+];
+
+impl ServiceHint {
+    /// Service of a flow from its two ports, the well-known one decides.
+    pub fn from_flow(transport: Transport, src_port: u16, dst_port: u16) -> Self {
+        Self::from_port(transport, src_port)
+            .or_else(|| Self::from_port(transport, dst_port))
+            .unwrap_or(ServiceHint::Unknown)
+    }
+
+    /// Whether the `first` end of a flow is its server: the one on a
+    /// well-known port, else the lower port unless both are ephemeral.
+    pub fn first_is_server(transport: Transport, first: u16, second: u16) -> Option<bool> {
+        let known = |port| Self::from_port(transport, port).is_some();
+        match (known(first), known(second)) {
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            _ if first == second || first.min(second) >= EPHEMERAL_PORTS => None,
+            _ => Some(first < second),
+        }
+    }
+
+    fn from_port(transport: Transport, port: u16) -> Option<Self> {
+        SERVICE_PORTS
+            .iter()
+            .find(|(t, p, _)| *p == port && t.is_none_or(|t| t == transport))
+            .map(|(_, _, hint)| *hint)
+    }
+}
+
+impl Display for ServiceHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ServiceHint::Https => "https",
+            ServiceHint::Http => "http",
+            ServiceHint::Quic => "quic",
+            ServiceHint::Ssh => "ssh",
+            ServiceHint::Dns => "dns",
+            ServiceHint::Smb => "smb",
+            ServiceHint::Ntp => "ntp",
+            ServiceHint::Mdns => "mdns",
+            ServiceHint::Dhcp => "dhcp",
+            ServiceHint::Rdp => "rdp",
+            ServiceHint::Unknown => "unknown",
+            ServiceHint::Mixed => "mixed",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::{
+    app::App,
+    format::{format_link_speed, parse_rate},
+    theme::{Theme, ThemeName},
+};
+
+/// longest pause between two ARP requests of a sweep
+const MAX_SCAN_DELAY: Duration = Duration::from_secs(5);
+/// most ticks the speeds can be averaged over
+const MAX_WINDOW: usize = 120;
+
+/// Options the settings popup (`,`) changes while running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    ScanDelay,
+    AveragingWindow,
+    ArpScanThreshold,
+    LinkSpeed,
+    Theme,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 5] = [
+        Setting::ScanDelay,
+        Setting::AveragingWindow,
+        Setting::ArpScanThreshold,
+        Setting::LinkSpeed,
+        Setting::Theme,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::ScanDelay => "Scan delay (ms)",
+            Setting::AveragingWindow => "Averaging window (ticks)",
+            Setting::ArpScanThreshold => "ARP scan threshold (/min)",
+            Setting::LinkSpeed => "Link speed",
+            Setting::Theme => "Theme",
+        }
+    }
+
+    /// The current value, as it's typed to change it.
+    pub fn value(self, app: &App) -> String {
+        match self {
+            Setting::ScanDelay => app.scan_delay().as_millis().to_string(),
+            Setting::AveragingWindow => app.stats_aggregator.window().to_string(),
+            Setting::ArpScanThreshold => app.arp_scan_threshold.to_string(),
+            Setting::LinkSpeed => app
+                .link_speed_override
+                .map_or_else(String::new, format_link_speed),
+            Setting::Theme => app
+                .theme
+                .name
+                .to_possible_value()
+                .map_or_else(String::new, |value| value.get_name().to_string()),
+        }
+    }
+
+    /// Validates `input` and applies it right away.
+    pub fn apply(self, app: &mut App, input: &str) -> Result<(), String> {
+        let input = input.trim();
+        match self {
+            Setting::ScanDelay => {
+                let delay = Duration::from_millis(parse_number(input)?);
+                if delay > MAX_SCAN_DELAY {
+                    return Err(format!("at most {} ms", MAX_SCAN_DELAY.as_millis()));
+                }
+                app.set_scan_delay(delay);
+            }
+            Setting::AveragingWindow => match parse_number(input)? as usize {
+                window @ 1..=MAX_WINDOW => app.stats_aggregator.set_window(window),
+                _ => return Err(format!("between 1 and {} ticks", MAX_WINDOW)),
+            },
+            Setting::ArpScanThreshold => {
+                app.arp_scan_threshold =
+                    u32::try_from(parse_number(input)?).map_err(|_| "too large".to_string())?;
+            }
+            // empty goes back to what the interface reports
+            Setting::LinkSpeed if input.is_empty() => app.link_speed_override = None,
+            Setting::LinkSpeed => app.link_speed_override = Some(parse_rate(input)?),
+            Setting::Theme => {
+                let name = ThemeName::from_str(input, true).map_err(|_| {
+                    let names = ThemeName::value_variants()
+                        .iter()
+                        .filter_map(|name| name.to_possible_value())
+                        .map(|value| value.get_name().to_string())
+                        .collect::<Vec<_>>();
+                    format!("one of {}", names.join(", "))
+                })?;
+                app.theme = Theme::new(name);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(input: &str) -> Result<u64, String> {
+    input
+        .parse()
+        .map_err(|_| format!("'{}' is not a whole number", input))
+}
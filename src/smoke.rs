@@ -0,0 +1,378 @@
+//! The whole stack on a simulated LAN: scanner tasks, app, UI on a test
+//! backend, driven by keys as a user would, without a terminal or a network.
+
+use std::{
+    collections::VecDeque,
+    io,
+    net::Ipv4Addr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use pnet::{
+    ipnetwork::{IpNetwork, Ipv4Network},
+    packet::{
+        arp::{ArpHardwareTypes, ArpOperations, ArpPacket, MutableArpPacket},
+        ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket},
+        ip::IpNextHeaderProtocols,
+        ipv4::MutableIpv4Packet,
+        udp::MutableUdpPacket,
+        FromPacket, Packet,
+    },
+    util::MacAddr,
+};
+use pnet_datalink::{DataLinkReceiver, DataLinkSender, NetworkInterface};
+use ratatui::{backend::TestBackend, Terminal};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::{
+    app::{App, Popup, View},
+    event::Event,
+    handle_event,
+    scanner::{Scanner, StatInterval, MIN_STAT_INTERVAL},
+    ui,
+};
+
+const OUR_MAC: MacAddr = MacAddr(0x02, 0, 0, 0, 0, 0x10);
+const US: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 10);
+const HOSTS: [(Ipv4Addr, MacAddr); 3] = [
+    (
+        Ipv4Addr::new(192, 168, 1, 9),
+        MacAddr(0, 0x1b, 0x63, 0, 0, 0x09),
+    ),
+    (
+        Ipv4Addr::new(192, 168, 1, 11),
+        MacAddr(0, 0x1b, 0x63, 0, 0, 0x0b),
+    ),
+    (
+        Ipv4Addr::new(192, 168, 1, 12),
+        MacAddr(0, 0x1b, 0x63, 0, 0, 0x0c),
+    ),
+];
+/// streams to the internet while the test runs
+const TALKER: usize = 1;
+const REMOTE: Ipv4Addr = Ipv4Addr::new(93, 184, 216, 34);
+const TRAFFIC_EVERY: Duration = Duration::from_millis(20);
+
+fn nif() -> NetworkInterface {
+    NetworkInterface {
+        name: "sim0".to_string(),
+        description: String::new(),
+        index: 1,
+        mac: Some(OUR_MAC),
+        ips: vec![IpNetwork::V4(Ipv4Network::new(US, 29).unwrap())],
+        flags: 0,
+    }
+}
+
+/// The hosts of the LAN: they answer our ARP requests, one of them streams.
+#[derive(Default)]
+pub(crate) struct Lan {
+    inbox: Mutex<VecDeque<Vec<u8>>>,
+    sender_dropped: AtomicBool,
+    receiver_dropped: AtomicBool,
+}
+
+impl Lan {
+    fn channel(self: &Arc<Self>) -> (Box<dyn DataLinkSender>, Box<dyn DataLinkReceiver>) {
+        let receiver = LanReceiver {
+            lan: self.clone(),
+            frame: vec![],
+            last_traffic: Instant::now(),
+        };
+        (Box::new(LanSender(self.clone())), Box::new(receiver))
+    }
+}
+
+struct LanSender(Arc<Lan>);
+
+impl DataLinkSender for LanSender {
+    fn build_and_send(
+        &mut self,
+        num_packets: usize,
+        packet_size: usize,
+        func: &mut dyn FnMut(&mut [u8]),
+    ) -> Option<io::Result<()>> {
+        for _ in 0..num_packets {
+            let mut frame = vec![0; packet_size];
+            func(&mut frame);
+            self.send_to(&frame, None)?.ok()?;
+        }
+        Some(Ok(()))
+    }
+
+    fn send_to(&mut self, packet: &[u8], _dst: Option<NetworkInterface>) -> Option<io::Result<()>> {
+        let request = EthernetPacket::new(packet)
+            .filter(|ethernet| ethernet.get_ethertype() == EtherTypes::Arp)
+            .and_then(|ethernet| ArpPacket::new(ethernet.payload()).map(|arp| arp.from_packet()));
+        if let Some(request) = request {
+            let target = request.target_proto_addr;
+            if let Some((ip, mac)) = HOSTS.iter().find(|(ip, _)| *ip == target) {
+                let reply = arp_reply(
+                    (*ip, *mac),
+                    (request.sender_proto_addr, request.sender_hw_addr),
+                );
+                self.0.inbox.lock().unwrap().push_back(reply);
+            }
+        }
+        Some(Ok(()))
+    }
+}
+
+impl Drop for LanSender {
+    fn drop(&mut self) {
+        self.0.sender_dropped.store(true, Ordering::SeqCst);
+    }
+}
+
+struct LanReceiver {
+    lan: Arc<Lan>,
+    frame: Vec<u8>,
+    last_traffic: Instant,
+}
+
+impl DataLinkReceiver for LanReceiver {
+    fn next(&mut self) -> io::Result<&[u8]> {
+        if self.last_traffic.elapsed() >= TRAFFIC_EVERY {
+            self.last_traffic = Instant::now();
+            let (ip, mac) = HOSTS[TALKER];
+            self.lan
+                .inbox
+                .lock()
+                .unwrap()
+                .push_back(udp_frame((ip, mac), REMOTE, 1200));
+        }
+        let next = self.lan.inbox.lock().unwrap().pop_front();
+        match next {
+            Some(frame) => {
+                self.frame = frame;
+                Ok(&self.frame)
+            }
+            None => {
+                // like the real read timeout, shorter
+                std::thread::sleep(Duration::from_millis(5));
+                Err(io::ErrorKind::TimedOut.into())
+            }
+        }
+    }
+}
+
+impl Drop for LanReceiver {
+    fn drop(&mut self) {
+        self.lan.receiver_dropped.store(true, Ordering::SeqCst);
+    }
+}
+
+fn ethernet(
+    src: MacAddr,
+    dst: MacAddr,
+    ethertype: pnet::packet::ethernet::EtherType,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut buffer = vec![0; EthernetPacket::minimum_packet_size() + payload.len()];
+    let mut ethernet = MutableEthernetPacket::new(&mut buffer).unwrap();
+    ethernet.set_source(src);
+    ethernet.set_destination(dst);
+    ethernet.set_ethertype(ethertype);
+    ethernet.set_payload(payload);
+    buffer
+}
+
+fn arp_reply((ip, mac): (Ipv4Addr, MacAddr), (to_ip, to_mac): (Ipv4Addr, MacAddr)) -> Vec<u8> {
+    let mut buffer = [0; 28];
+    let mut arp = MutableArpPacket::new(&mut buffer).unwrap();
+    arp.set_hardware_type(ArpHardwareTypes::Ethernet);
+    arp.set_protocol_type(EtherTypes::Ipv4);
+    arp.set_hw_addr_len(6);
+    arp.set_proto_addr_len(4);
+    arp.set_operation(ArpOperations::Reply);
+    arp.set_sender_hw_addr(mac);
+    arp.set_sender_proto_addr(ip);
+    arp.set_target_hw_addr(to_mac);
+    arp.set_target_proto_addr(to_ip);
+    ethernet(mac, to_mac, EtherTypes::Arp, &buffer)
+}
+
+fn udp_frame((ip, mac): (Ipv4Addr, MacAddr), to: Ipv4Addr, len: usize) -> Vec<u8> {
+    let mut udp = vec![0; MutableUdpPacket::minimum_packet_size() + len];
+    let udp_len = udp.len() as u16;
+    let mut datagram = MutableUdpPacket::new(&mut udp).unwrap();
+    datagram.set_source(50000);
+    datagram.set_destination(443);
+    datagram.set_length(udp_len);
+    let mut buffer = vec![0; MutableIpv4Packet::minimum_packet_size() + udp.len()];
+    let total_length = buffer.len() as u16;
+    let mut ipv4 = MutableIpv4Packet::new(&mut buffer).unwrap();
+    ipv4.set_version(4);
+    ipv4.set_header_length(5);
+    ipv4.set_total_length(total_length);
+    ipv4.set_ttl(64);
+    ipv4.set_next_level_protocol(IpNextHeaderProtocols::Udp);
+    ipv4.set_source(ip);
+    ipv4.set_destination(to);
+    ipv4.set_payload(&udp);
+    ethernet(mac, OUR_MAC, EtherTypes::Ipv4, &buffer)
+}
+
+/// The app and its screen, fed the way `main` feeds them.
+struct Session {
+    app: App,
+    events: UnboundedReceiver<Event>,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Session {
+    fn press(&mut self, code: KeyCode) {
+        let key = KeyEvent::new(code, KeyModifiers::NONE);
+        handle_event(&mut self.app, Event::Key(key)).unwrap();
+        self.draw();
+    }
+
+    fn draw(&mut self) {
+        self.terminal
+            .draw(|frame| ui::render(&mut self.app, frame))
+            .unwrap();
+        self.app.mark_drawn();
+    }
+
+    /// Handles events until `done` or the timeout, false on timeout.
+    async fn run_until(&mut self, timeout: Duration, done: impl Fn(&App) -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if done(&self.app) {
+                return true;
+            }
+            let event = tokio::time::timeout(Duration::from_millis(50), self.events.recv()).await;
+            if let Ok(Some(event)) = event {
+                handle_event(&mut self.app, event).unwrap();
+            }
+            handle_event(&mut self.app, Event::Tick).unwrap();
+            if self.app.dirty {
+                self.draw();
+            }
+        }
+        done(&self.app)
+    }
+
+    fn screen(&self) -> String {
+        self.terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+}
+
+/// An app whose scanner runs on `lan`, it needs a tokio runtime.
+pub(crate) fn app_on(lan: &Arc<Lan>) -> (App, UnboundedReceiver<Event>) {
+    let (outputs, events) = unbounded_channel();
+    (App::new(scanner_on(lan, outputs)).unwrap(), events)
+}
+
+/// A scanner on `lan` sending its events to `outputs`.
+pub(crate) fn scanner_on(lan: &Arc<Lan>, outputs: UnboundedSender<Event>) -> Scanner {
+    let stat_interval = StatInterval {
+        base: MIN_STAT_INTERVAL,
+        adaptive: false,
+    };
+    Scanner::with_channel(
+        outputs,
+        nif(),
+        lan.channel(),
+        false,
+        false,
+        stat_interval,
+        None,
+    )
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn the_whole_stack_runs_on_a_simulated_lan() {
+    let export_dir = std::env::temp_dir().join(format!("netui-smoke-{}", std::process::id()));
+    let lan = Arc::new(Lan::default());
+    let (mut app, events) = app_on(&lan);
+    app.set_scan_delay(Duration::ZERO);
+    app.export_nmap_path = Some(export_dir.join("hosts.xml"));
+    let mut session = Session {
+        app,
+        events,
+        terminal: Terminal::new(TestBackend::new(220, 60)).unwrap(),
+    };
+    session.draw();
+
+    // a sweep finds every host
+    session.press(KeyCode::Char('s'));
+    let found = session
+        .run_until(Duration::from_secs(5), |app| {
+            app.hosts.len() == HOSTS.len() && !app.sending_arps
+        })
+        .await;
+    assert!(found, "found {:?}", session.app.hosts);
+    // and reports them as new
+    assert_eq!(session.app.popup, Some(Popup::SweepReport));
+    assert!(session.screen().contains("Sweep of 192.168.1.8/29"));
+    session.press(KeyCode::Esc);
+    // and the talker's traffic is measured
+    let measured = session
+        .run_until(Duration::from_secs(5), |app| {
+            app.hosts
+                .iter()
+                .any(|host| host.ipv4 == HOSTS[TALKER].0 && host.has_traffic)
+        })
+        .await;
+    assert!(measured);
+    assert!(session.screen().contains("192.168.1.11"));
+
+    // navigate, sort and filter through every view
+    for code in [KeyCode::Char('j'), KeyCode::Char('j'), KeyCode::Char('k')] {
+        session.press(code);
+    }
+    session.press(KeyCode::Tab);
+    assert_eq!(session.app.view, View::Connections);
+    session.press(KeyCode::Char('o'));
+    session.press(KeyCode::Char('O'));
+    session.press(KeyCode::Tab);
+    session.press(KeyCode::Char('p'));
+    session.press(KeyCode::Tab);
+    assert_eq!(session.app.view, View::Hosts);
+    for _ in 0..4 {
+        session.press(KeyCode::Char('a'));
+    }
+    assert_eq!(session.app.activity_filter, None);
+
+    // details of the selected host, the filter cleared the selection
+    session.press(KeyCode::Char('j'));
+    session.press(KeyCode::Enter);
+    assert!(matches!(session.app.popup, Some(Popup::HostDetails(_))));
+    assert!(session.screen().contains("Vendor:"));
+    session.press(KeyCode::Esc);
+    assert_eq!(session.app.popup, None);
+
+    // export
+    session.press(KeyCode::Char('x'));
+    let export = std::fs::read_to_string(export_dir.join("hosts.xml")).unwrap();
+    for (ip, _) in HOSTS {
+        assert!(export.contains(&format!(r#"<address addr="{}""#, ip)));
+    }
+    let _ = std::fs::remove_dir_all(&export_dir);
+
+    // quitting stops every scanner task
+    session.press(KeyCode::Char('q'));
+    assert!(!session.app.running);
+    session.app.stop_operations();
+    drop(session);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !(lan.sender_dropped.load(Ordering::SeqCst)
+        && lan.receiver_dropped.load(Ordering::SeqCst))
+    {
+        assert!(Instant::now() < deadline, "scanner tasks still running");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
@@ -0,0 +1,160 @@
+use std::net::Ipv4Addr;
+
+use chrono::{DateTime, Local};
+use serde::{Serialize, Serializer};
+
+use crate::{app::Host, budget::DailyUsage, mac_format::MacFormat, stats_aggregator::Speed};
+
+/// The hosts table at one moment, what the exports are made of: the web
+/// dashboard serves it as JSON, the HTML report renders it and the headless
+/// output prints its hosts.
+#[derive(Clone, Debug, Serialize)]
+pub struct HostsSnapshot {
+    pub interface: String,
+    pub total: SpeedSnapshot,
+    pub hosts: Vec<HostSnapshot>,
+}
+
+/// A host of the [`HostsSnapshot`].
+#[derive(Clone, Debug, Serialize)]
+pub struct HostSnapshot {
+    pub ip: Ipv4Addr,
+    pub mac: String,
+    pub hostname: Option<String>,
+    pub label: Option<String>,
+    pub vendor: Option<String>,
+    pub is_my_device: bool,
+    pub speed: Option<SpeedSnapshot>,
+    pub category: Option<String>,
+    pub service: Option<String>,
+    /// bytes counted against the daily budget
+    pub used_today: u128,
+    /// only known to the one-shot sweep
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "rfc3339_option"
+    )]
+    pub first_seen: Option<DateTime<Local>>,
+    #[serde(serialize_with = "rfc3339")]
+    pub last_seen: DateTime<Local>,
+}
+
+/// A speed readable as is, and in bits per second.
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeedSnapshot {
+    pub input: String,
+    pub output: String,
+    pub input_bps: u128,
+    pub output_bps: u128,
+}
+
+impl HostsSnapshot {
+    pub fn new(
+        interface: &str,
+        total: Option<Speed>,
+        hosts: &[Host],
+        mac_format: MacFormat,
+        daily_usage: &DailyUsage,
+    ) -> Self {
+        Self {
+            interface: interface.to_string(),
+            total: total.unwrap_or_default().into(),
+            hosts: hosts
+                .iter()
+                .map(|host| HostSnapshot::new(host, mac_format, daily_usage))
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        to_json(self)
+    }
+}
+
+impl HostSnapshot {
+    pub fn new(host: &Host, mac_format: MacFormat, daily_usage: &DailyUsage) -> Self {
+        Self {
+            ip: host.ipv4,
+            mac: mac_format.format(host.mac),
+            hostname: host.hostname.clone(),
+            label: host.label.clone(),
+            vendor: host.vendor().map(str::to_string),
+            is_my_device: host.is_my_device_mac,
+            speed: host.speed.map(SpeedSnapshot::from),
+            category: host.category.map(|category| category.to_string()),
+            service: host.service.map(|service| service.to_string()),
+            used_today: daily_usage.used(&host.mac),
+            first_seen: None,
+            last_seen: host.time,
+        }
+    }
+
+    /// The label the user gave, else the resolved name.
+    pub fn name(&self) -> Option<&str> {
+        self.label.as_deref().or(self.hostname.as_deref())
+    }
+}
+
+impl From<Speed> for SpeedSnapshot {
+    fn from(speed: Speed) -> Self {
+        Self {
+            input: speed.to_string_input(),
+            output: speed.to_string_output(),
+            input_bps: speed.input(),
+            output_bps: speed.output(),
+        }
+    }
+}
+
+/// `value` on a single line.
+pub fn to_json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).expect("snapshots only have string keys")
+}
+
+fn rfc3339<S: Serializer>(time: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.to_rfc3339())
+}
+
+fn rfc3339_option<S: Serializer>(
+    time: &Option<DateTime<Local>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => rfc3339(time, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pnet::util::MacAddr;
+
+    use super::*;
+
+    #[test]
+    fn the_snapshot_is_json_with_the_names_escaped() {
+        let mut host = Host::new(
+            Ipv4Addr::new(192, 168, 1, 20),
+            MacAddr(0, 0x1b, 0x63, 0, 0, 20),
+            true,
+        );
+        host.hostname = Some("nas \"2\"\\\u{7}".to_string());
+        let snapshot = HostsSnapshot::new(
+            "eth0",
+            None,
+            &[host],
+            MacFormat::default(),
+            &DailyUsage::default(),
+        );
+        let json: serde_json::Value = serde_json::from_str(&snapshot.to_json()).unwrap();
+        assert_eq!(json["interface"], "eth0");
+        assert_eq!(json["total"]["input_bps"], 0);
+        let host = &json["hosts"][0];
+        assert_eq!(host["ip"], "192.168.1.20");
+        assert_eq!(host["hostname"], "nas \"2\"\\\u{7}");
+        assert_eq!(host["is_my_device"], true);
+        assert!(host["speed"].is_null());
+        assert!(host.get("first_seen").is_none());
+        assert!(!snapshot.to_json().contains('\n'));
+    }
+}
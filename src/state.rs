@@ -0,0 +1,133 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+};
+
+use pnet::util::MacAddr;
+use serde::{Deserialize, Serialize};
+
+use chrono::{DateTime, Local};
+
+use crate::{app::AppResult, audit::AuditTrail, dry_run, logging::get_data_dir};
+
+/// shared by every interface before states were kept per interface
+const LEGACY_STATE_FILE: &str = "state.toml";
+
+/// What netui remembers between runs, saved in the data directory, one file
+/// per interface so instances on different interfaces don't overwrite each
+/// other.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    /// user labels by MAC address, or by IP for hosts without a usable MAC
+    labels: HashMap<String, String>,
+    /// widths given to hosts table columns with `<` and `>`, by column
+    columns: BTreeMap<String, u16>,
+    /// the addresses each MAC had
+    audit: AuditTrail,
+    /// where it's saved, none when it's not
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl State {
+    pub fn path(interface: &str) -> PathBuf {
+        get_data_dir().join(format!("state-{}.toml", interface))
+    }
+
+    /// Reads the state of `interface`, or the shared state file of older
+    /// versions when it has none yet; with neither the state is empty.
+    pub fn load(interface: &str) -> AppResult<Self> {
+        let path = Self::path(interface);
+        let state = match Self::read(&path)? {
+            Some(state) => state,
+            None => Self::read(&get_data_dir().join(LEGACY_STATE_FILE))?.unwrap_or_default(),
+        };
+        Ok(Self {
+            path: Some(path),
+            ..state
+        })
+    }
+
+    /// The state saved at `path`, saved back there.
+    #[cfg(test)]
+    pub fn at(path: PathBuf) -> AppResult<Self> {
+        let state = Self::read(&path)?.unwrap_or_default();
+        Ok(Self {
+            path: Some(path),
+            ..state
+        })
+    }
+
+    fn read(path: &Path) -> AppResult<Option<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+        };
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Writes the file it was loaded for, a state that wasn't loaded isn't saved.
+    pub fn save(&self) -> AppResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        dry_run::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn saved_to(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn label(&self, ip: Ipv4Addr, mac: MacAddr) -> Option<&String> {
+        self.labels.get(&Self::label_key(ip, mac))
+    }
+
+    /// Sets the label of a host, an empty label removes it.
+    pub fn set_label(&mut self, ip: Ipv4Addr, mac: MacAddr, label: &str) {
+        let key = Self::label_key(ip, mac);
+        match label.trim() {
+            "" => self.labels.remove(&key),
+            label => self.labels.insert(key, label.to_string()),
+        };
+    }
+
+    pub fn audit(&self) -> &AuditTrail {
+        &self.audit
+    }
+
+    /// See [`AuditTrail::record`].
+    pub fn record_binding(&mut self, ip: Ipv4Addr, mac: MacAddr, at: DateTime<Local>) -> bool {
+        self.audit.record(ip, mac, at)
+    }
+
+    pub fn column_widths(&self) -> &BTreeMap<String, u16> {
+        &self.columns
+    }
+
+    pub fn column_width(&self, column: &str) -> Option<u16> {
+        self.columns.get(column).copied()
+    }
+
+    /// Sets the width of a hosts table column, none goes back to the
+    /// computed one.
+    pub fn set_column_width(&mut self, column: &str, width: Option<u16>) {
+        match width {
+            Some(width) => self.columns.insert(column.to_string(), width),
+            None => self.columns.remove(column),
+        };
+    }
+
+    /// Labels follow the MAC so they survive DHCP changes.
+    fn label_key(ip: Ipv4Addr, mac: MacAddr) -> String {
+        if mac == MacAddr::zero() {
+            ip.to_string()
+        } else {
+            mac.to_string()
+        }
+    }
+}
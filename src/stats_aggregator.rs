@@ -1,19 +1,32 @@
 use std::{
-    collections::HashMap,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     fmt::Display,
     net::Ipv4Addr,
-    ops::{Add, AddAssign, Div},
+    ops::{Add, AddAssign, Div, Mul},
+    time::{Duration, Instant},
 };
 
+use clap::ValueEnum;
 use itertools::Itertools;
+use pnet::ipnetwork::{IpNetwork, Ipv4Network};
 use ringbuf::{
     traits::{Consumer, Observer, RingBuffer},
     HeapRb,
 };
 use tracing::Level;
 
-use crate::trace_dbg;
+use crate::{
+    format::format_bits_per_sec,
+    history::HistoryStore,
+    limits::{CollectionSize, Limits},
+    service_hint::{ServiceHint, MIN_DOMINANT_SHARE},
+    trace_dbg,
+    traffic_category::TrafficCategory,
+};
 
+/// Keeps a short window of stat ticks to compute averaged speeds, plus a
+/// longer downsampled history for everything looking further back.
 pub struct StatsAggregator {
     /// down, up, local, "other"
     speed_buffer_: HeapRb<Vec<u128>>,
@@ -22,12 +35,133 @@ pub struct StatsAggregator {
     stats_buffer: HeapRb<StatsMap>,
     pairs_buffer: HeapRb<PairStatMap>,
     hosts_buffer: HeapRb<HashMap<Ipv4Addr, Speed>>,
+    /// per-host averages of the last ticks, the oldest is the previous window's
+    averages_buffer: HeapRb<HashMap<Ipv4Addr, Speed>>,
     total_speed_buffer: HeapRb<Speed>,
+
+    history: HistoryStore,
+    /// speeds of the ticks not yet worth a whole history sample, weighted by
+    /// their length
+    history_pending: HistoryPending,
+    last_tick: Option<Instant>,
+    /// length of the last tick, sizes are per second once in the buffers
+    last_elapsed: Duration,
+
+    /// bits exchanged by every pair since the start, least recently seen
+    /// evicted first
+    pair_totals: HashMap<IpPair, PairTotal>,
+    /// used to tell internet, LAN and broadcast counterparts apart
+    gateway: Option<Ipv4Addr>,
+    lan: Vec<Ipv4Network>,
+    /// when every flow was first and last seen and the bits it carried,
+    /// least recently seen evicted first
+    flows: HashMap<StatKey, FlowStats>,
+    /// bits exchanged with every off-subnet address since the start, least
+    /// recently seen evicted first
+    remotes: HashMap<Ipv4Addr, RemoteStats>,
+    limits: Limits,
+    /// busiest tick of the session, external traffic
+    session_peak: Option<Speed>,
+    /// external bits and the time they were counted over, for the session average
+    session_bits: Speed,
+    session_time: Duration,
+}
+
+/// ticks the speeds are averaged over unless changed in the settings
+pub const DEFAULT_WINDOW: usize = 10;
+/// a flow silent for longer starts over with a new age and byte count
+const FLOW_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Clone, Debug)]
+struct RemoteStats {
+    last_seen: Instant,
+    /// `a` is the LAN side
+    bits: StatValues,
+    lan_hosts: HashSet<Ipv4Addr>,
+}
+
+#[derive(Clone, Debug)]
+struct FlowStats {
+    first_seen: Instant,
+    last_seen: Instant,
+    /// since `first_seen`
+    bits: StatValues,
+}
+
+#[derive(Clone, Debug)]
+struct PairTotal {
+    last_seen: Instant,
+    bits: Speed,
 }
 
+/// Speeds of the ticks since the last history sample, summed as bits (speed
+/// times milliseconds, then divided back).
+#[derive(Default)]
+struct HistoryPending {
+    total: Speed,
+    per_host: HashMap<Ipv4Addr, Speed>,
+    elapsed: Duration,
+}
+
+impl HistoryPending {
+    fn add(&mut self, total: Speed, per_host: &HashMap<Ipv4Addr, Speed>, elapsed: Duration) {
+        let ms = elapsed.as_millis();
+        self.total += total * ms;
+        for (ip, speed) in per_host {
+            *self.per_host.entry(*ip).or_default() += *speed * ms;
+        }
+        self.elapsed += elapsed;
+    }
+
+    fn average(&self) -> (Speed, HashMap<Ipv4Addr, Speed>) {
+        let ms = self.elapsed.as_millis().max(1);
+        let per_host = self
+            .per_host
+            .iter()
+            .map(|(ip, speed)| (*ip, *speed / ms))
+            .collect();
+        (self.total / ms, per_host)
+    }
+
+    /// Drops the part of the pending time worth `samples` history samples,
+    /// the rest keeps the same average.
+    fn keep_remainder(&mut self, samples: u64) {
+        if samples == 0 {
+            return;
+        }
+        let remainder = self.elapsed - Duration::from_secs(samples);
+        let (total, per_host) = self.average();
+        *self = Self::default();
+        if !remainder.is_zero() {
+            self.add(total, &per_host, remainder);
+        }
+    }
+}
+
+/// a longer silence between two stat ticks means the process was stalled or
+/// suspended, the window then describes traffic from before the gap
+const MAX_TICK_GAP: Duration = Duration::from_secs(5);
+
 impl StatsAggregator {
     fn new() -> Self {
-        Self::new_with_window_size(10)
+        Self::new_with_window_size(DEFAULT_WINDOW)
+    }
+
+    /// Ticks the speeds are averaged over.
+    pub fn window(&self) -> usize {
+        self.stats_buffer.capacity().get()
+    }
+
+    /// Averages over `window` ticks from now on, keeping the newest ticks of
+    /// the current window; the history is untouched.
+    pub fn set_window(&mut self, window: usize) {
+        let window = window.max(1);
+        self.speed_buffer_ = resized(&self.speed_buffer_, window);
+        self.stats_buffer = resized(&self.stats_buffer, window);
+        self.pairs_buffer = resized(&self.pairs_buffer, window);
+        self.hosts_buffer = resized(&self.hosts_buffer, window);
+        self.averages_buffer = resized(&self.averages_buffer, window + 1);
+        self.total_speed_buffer = resized(&self.total_speed_buffer, window);
     }
 
     fn new_with_window_size(window: usize) -> Self {
@@ -37,29 +171,60 @@ impl StatsAggregator {
             stats_buffer: HeapRb::new(window),
             pairs_buffer: HeapRb::new(window),
             hosts_buffer: HeapRb::new(window),
+            averages_buffer: HeapRb::new(window + 1),
             total_speed_buffer: HeapRb::new(window),
+            history: Default::default(),
+            history_pending: Default::default(),
+            last_tick: None,
+            last_elapsed: Duration::from_secs(1),
+            pair_totals: Default::default(),
+            gateway: None,
+            lan: vec![],
+            flows: Default::default(),
+            remotes: Default::default(),
+            limits: Limits::default(),
+            session_peak: None,
+            session_bits: Speed::default(),
+            session_time: Duration::ZERO,
         }
     }
 
-    pub fn tick(&mut self, hash_map: StatsMap) {
+    /// Adds the bits counted over `elapsed`, whatever its length speeds come
+    /// out per second. `flushed_at` dates them, however long they waited in
+    /// the channel.
+    pub fn tick(&mut self, mut hash_map: StatsMap, elapsed: Duration, flushed_at: Instant) {
+        // a tick the app was slow to handle isn't a gap in the capture
+        let now = flushed_at;
+        if self
+            .last_tick
+            .is_some_and(|last_tick| Self::is_tick_gap(last_tick, now, elapsed))
+        {
+            self.clear_window();
+        }
+        self.last_tick = Some(now);
+        // before the sizes become speeds, the flows add up bits
+        self.update_flows(&hash_map, now);
+        self.update_remotes(&hash_map, now);
+        let elapsed_ms = elapsed.as_millis().max(1);
+        self.last_elapsed = elapsed;
+        for value in hash_map.values_mut() {
+            value.a_sent = value.a_sent * 1000 / elapsed_ms;
+            value.b_sent = value.b_sent * 1000 / elapsed_ms;
+        }
+
         let init = vec![0, 0, 0, 0];
-        let sum = hash_map.iter().map(|(k, v)| (&k.direction, v.size)).fold(
-            init.clone(),
-            |acc, (di, si)| match di {
-                Direction::Outgoing => {
-                    vec![acc[0] + si, acc[1], acc[2], acc[3]]
-                }
-                Direction::Incomming => {
-                    vec![acc[0], acc[1] + si, acc[2], acc[3]]
-                }
-                Direction::Local => {
-                    vec![acc[0], acc[1], acc[2] + si, acc[3]]
-                }
-                Direction::None => {
-                    vec![acc[0], acc[1], acc[2], acc[3] + si]
-                }
-            },
-        );
+        let sum = hash_map.iter().fold(init, |acc, (k, v)| match k.scope {
+            // the LAN end is `a`
+            FlowScope::External => {
+                vec![acc[0] + v.a_sent, acc[1] + v.b_sent, acc[2], acc[3]]
+            }
+            FlowScope::Local => {
+                vec![acc[0], acc[1], acc[2] + v.total(), acc[3]]
+            }
+            FlowScope::Foreign => {
+                vec![acc[0], acc[1], acc[2], acc[3] + v.total()]
+            }
+        });
         self.speed_buffer_.push_overwrite(sum);
         hash_map.keys().for_each(|k| {
             self.stat_keys_buffer_.push_overwrite(k.clone());
@@ -69,7 +234,258 @@ impl StatsAggregator {
 
         self.update_pairs_stats_buffer();
         self.update_hosts_stats_buffer();
+        self.averages_buffer.push_overwrite(self.speed_per_host());
         self.update_total_speed();
+        self.update_session(elapsed);
+        self.update_history(elapsed);
+        self.update_pair_totals(elapsed, now);
+    }
+
+    /// Adds the bits of the tick to their flows; when over capacity the least
+    /// recently seen are dropped, the flows of this tick always survive.
+    fn update_flows(&mut self, hash_map: &StatsMap, now: Instant) {
+        self.flows
+            .retain(|_, flow| now.saturating_duration_since(flow.last_seen) <= FLOW_IDLE_TIMEOUT);
+        for (key, value) in hash_map {
+            self.flows
+                .entry(key.clone())
+                .and_modify(|flow| {
+                    flow.last_seen = now;
+                    flow.bits += value.clone();
+                })
+                .or_insert(FlowStats {
+                    first_seen: now,
+                    last_seen: now,
+                    bits: value.clone(),
+                });
+        }
+        if self.flows.len() > self.limits.flows {
+            let overflow = self.flows.len() - self.limits.flows;
+            let evicted = self
+                .flows
+                .iter()
+                .filter(|(key, _)| !hash_map.contains_key(*key))
+                .sorted_by_key(|(_, flow)| flow.last_seen)
+                .take(overflow)
+                .map(|(key, _)| key.clone())
+                .collect_vec();
+            for key in evicted {
+                self.flows.remove(&key);
+            }
+        }
+    }
+
+    /// Adds the bits of the tick's internet flows to their remote address.
+    fn update_remotes(&mut self, hash_map: &StatsMap, now: Instant) {
+        for (key, value) in hash_map {
+            if key.scope != FlowScope::External || self.peer_kind(key.b_ip) != PeerKind::Internet {
+                continue;
+            }
+            let remote = self.remotes.entry(key.b_ip).or_insert(RemoteStats {
+                last_seen: now,
+                bits: StatValues::default(),
+                lan_hosts: HashSet::new(),
+            });
+            remote.last_seen = now;
+            remote.bits += value.clone();
+            remote.lan_hosts.insert(key.a_ip);
+        }
+        if self.remotes.len() > self.limits.remotes {
+            let overflow = self.remotes.len() - self.limits.remotes;
+            let evicted = self
+                .remotes
+                .iter()
+                .sorted_by_key(|(_, remote)| remote.last_seen)
+                .take(overflow)
+                .map(|(ip, _)| *ip)
+                .collect_vec();
+            for ip in evicted {
+                self.remotes.remove(&ip);
+            }
+        }
+    }
+
+    /// The tick came much later than its own length says it should have.
+    fn is_tick_gap(last_tick: Instant, now: Instant, elapsed: Duration) -> bool {
+        now.saturating_duration_since(last_tick) > elapsed + MAX_TICK_GAP
+    }
+
+    /// Forgets the averaging window, the long term history is kept.
+    fn clear_window(&mut self) {
+        self.speed_buffer_.clear();
+        self.stats_buffer.clear();
+        self.pairs_buffer.clear();
+        self.hosts_buffer.clear();
+        self.averages_buffer.clear();
+        self.total_speed_buffer.clear();
+    }
+
+    /// Adds the tick just added to the session peak and average.
+    fn update_session(&mut self, elapsed: Duration) {
+        let Some(last) = self.total_speed_buffer.last().copied() else {
+            return;
+        };
+        self.session_peak = Some(self.session_peak.map_or(last, |peak| peak.max(last)));
+        self.session_bits += last.over(elapsed);
+        self.session_time += elapsed;
+    }
+
+    /// Busiest tick of external traffic since the start, each direction on its own.
+    pub fn session_peak(&self) -> Option<Speed> {
+        self.session_peak
+    }
+
+    /// External traffic averaged over the whole session.
+    pub fn session_average(&self) -> Option<Speed> {
+        let millis = self.session_time.as_millis();
+        (millis > 0).then(|| self.session_bits * 1000 / millis)
+    }
+
+    /// Feeds the speeds of the tick just added to the long term history,
+    /// which takes one sample a second: short ticks are merged, long ones
+    /// repeated.
+    fn update_history(&mut self, elapsed: Duration) {
+        let total = self.total_speed_buffer.last().copied().unwrap_or_default();
+        let empty = HashMap::new();
+        let per_host = self.hosts_buffer.last().unwrap_or(&empty);
+        let pending = &mut self.history_pending;
+        pending.add(total, per_host, elapsed);
+        for _ in 0..pending.elapsed.as_secs() {
+            let (total, per_host) = pending.average();
+            self.history.record(total, &per_host);
+        }
+        let samples = pending.elapsed.as_secs();
+        pending.keep_remainder(samples);
+    }
+
+    /// Adds the last tick to the totals of its pairs; when over capacity the
+    /// least recently seen are dropped, the pairs of this tick always survive.
+    fn update_pair_totals(&mut self, elapsed: Duration, now: Instant) {
+        let empty = PairStatMap::new();
+        let pairs = self.pairs_buffer.last().unwrap_or(&empty);
+        for (pair, speed) in pairs {
+            let total = self.pair_totals.entry(pair.clone()).or_insert(PairTotal {
+                last_seen: now,
+                bits: Speed::default(),
+            });
+            total.last_seen = now;
+            total.bits += speed.over(elapsed);
+        }
+        if self.pair_totals.len() > self.limits.pair_totals {
+            let overflow = self.pair_totals.len() - self.limits.pair_totals;
+            let evicted = self
+                .pair_totals
+                .iter()
+                .filter(|(pair, _)| !pairs.contains_key(*pair))
+                .sorted_by_key(|(_, total)| total.last_seen)
+                .take(overflow)
+                .map(|(pair, _)| pair.clone())
+                .collect_vec();
+            for pair in evicted {
+                self.pair_totals.remove(&pair);
+            }
+        }
+    }
+
+    /// Caps the flows, the destinations, the pair totals and the host
+    /// histories.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        self.history.set_max_hosts(limits.host_histories);
+    }
+
+    /// How full the bounded collections are.
+    pub fn sizes(&self) -> Vec<CollectionSize> {
+        vec![
+            CollectionSize::new("Flows", self.flows.len(), self.limits.flows),
+            CollectionSize::new("Destinations", self.remotes.len(), self.limits.remotes),
+            CollectionSize::new(
+                "Pair totals",
+                self.pair_totals.len(),
+                self.limits.pair_totals,
+            ),
+            CollectionSize::new(
+                "Host histories",
+                self.history.host_count(),
+                self.history.max_hosts(),
+            ),
+        ]
+    }
+
+    /// The gateway and the networks of the interface, for `host_breakdown`.
+    pub fn set_lan(&mut self, gateway: Option<Ipv4Addr>, lan: Vec<Ipv4Network>) {
+        self.gateway = gateway;
+        self.lan = lan;
+    }
+
+    /// Traffic of `ip` split by the kind of its counterparts.
+    pub fn host_breakdown(&self, ip: Ipv4Addr) -> HostBreakdown {
+        let mut breakdown = HostBreakdown::default();
+        let window = self.pairs_buffer.occupied_len().max(1) as u128;
+        for pairs in self.pairs_buffer.iter() {
+            for (pair, speed) in pairs {
+                if let Some((peer, speed)) = pair.seen_from(ip, *speed) {
+                    breakdown.bucket_mut(self.peer_kind(peer)).speed += speed / window;
+                }
+            }
+        }
+        for (pair, total) in &self.pair_totals {
+            if let Some((peer, bits)) = pair.seen_from(ip, total.bits) {
+                breakdown.bucket_mut(self.peer_kind(peer)).bytes += bits.total() / 8;
+            }
+        }
+        breakdown
+    }
+
+    /// The `limit` counterparts `ip` exchanges the most with, busiest first
+    /// over the window, then by bytes since the start.
+    pub fn top_peers(&self, ip: Ipv4Addr, limit: usize) -> Vec<PeerTraffic> {
+        let mut peers: HashMap<Ipv4Addr, PeerTraffic> = Default::default();
+        let window = self.pairs_buffer.occupied_len().max(1) as u128;
+        for pairs in self.pairs_buffer.iter() {
+            for (pair, speed) in pairs {
+                if let Some((other, speed)) = pair.seen_from(ip, *speed) {
+                    peers
+                        .entry(other)
+                        .or_insert_with(|| PeerTraffic::new(other))
+                        .speed += speed / window;
+                }
+            }
+        }
+        for (pair, total) in &self.pair_totals {
+            if let Some((other, bits)) = pair.seen_from(ip, total.bits) {
+                peers
+                    .entry(other)
+                    .or_insert_with(|| PeerTraffic::new(other))
+                    .bytes += bits.total() / 8;
+            }
+        }
+        peers
+            .into_values()
+            .sorted_by(|a, b| {
+                (b.speed.total(), b.bytes, a.peer).cmp(&(a.speed.total(), a.bytes, b.peer))
+            })
+            .take(limit)
+            .collect()
+    }
+
+    fn peer_kind(&self, peer: Ipv4Addr) -> PeerKind {
+        let is_lan_broadcast = self.lan.iter().any(|network| {
+            network.prefix() < 31 && (peer == network.broadcast() || peer == network.network())
+        });
+        if peer.is_broadcast() || peer.is_multicast() || is_lan_broadcast {
+            PeerKind::Broadcast
+        } else if Some(peer) != self.gateway
+            && self.lan.iter().any(|network| network.contains(peer))
+        {
+            PeerKind::Local
+        } else {
+            PeerKind::Internet
+        }
+    }
+
+    pub fn history(&self) -> &HistoryStore {
+        &self.history
     }
 
     fn update_pairs_stats_buffer(&mut self) {
@@ -77,108 +493,264 @@ impl StatsAggregator {
         self.stats_buffer.iter().for_each(|item| {
             let mut pairs: PairStatMap = Default::default();
             item.iter().for_each(|(k, v)| {
-                let (mut src, mut dst) = (k.src_ip, k.dst_ip);
-                let is_local = k.direction == Direction::Local;
-                if Direction::Incomming == k.direction || (is_local && src > dst) {
-                    (src, dst) = (dst, src);
+                let pair = IpPair::of(k);
+                if k.scope == FlowScope::Foreign {
+                    let msg = format!("{} {}", pair.src_ip, pair.dst_ip);
+                    trace_dbg!(level: Level::TRACE, msg);
                 }
-                let pair = IpPair {
-                    src_ip: src,
-                    dst_ip: dst,
-                    is_local,
-                };
-                let mut speed_pair_to_add: Speed = Default::default();
-                match k.direction {
-                    Direction::Outgoing => {
-                        speed_pair_to_add.output += v.size;
-                    }
-                    Direction::Incomming => {
-                        speed_pair_to_add.input += v.size;
-                    }
-                    Direction::Local => {
-                        if src != k.src_ip {
-                            speed_pair_to_add.output += v.size;
-                        } else {
-                            speed_pair_to_add.input += v.size;
-                        }
-                    }
-                    Direction::None => {
-                        let msg = format!("{} {}", src, dst);
-                        trace_dbg!(level: Level::ERROR, msg);
-                    }
-                }
-                pairs
-                    .entry(pair)
-                    .and_modify(|speed_pair| {
-                        *speed_pair += speed_pair_to_add;
-                    })
-                    .or_insert(speed_pair_to_add);
+                *pairs.entry(pair).or_default() += v.pair_speed(k.scope);
             });
             self.pairs_buffer.push_overwrite(pairs);
         });
     }
 
+    /// Bits exchanged by every host during the last tick.
+    pub fn last_tick_per_host(&self) -> Option<HashMap<Ipv4Addr, Speed>> {
+        let speeds = self.hosts_buffer.last()?;
+        Some(
+            speeds
+                .iter()
+                .map(|(ip, speed)| (*ip, speed.over(self.last_elapsed)))
+                .collect(),
+        )
+    }
+
+    /// Averaged external traffic of every host over the whole window, the
+    /// ticks without its traffic count as zero so an idle host decays to it.
     pub fn speed_per_host(&self) -> HashMap<Ipv4Addr, Speed> {
-        let mut map_sn: HashMap<Ipv4Addr, (Speed, u8)> = Default::default();
-        let mut map: HashMap<Ipv4Addr, Speed> = Default::default();
-
-        self.hosts_buffer.iter().for_each(|pair| {
-            pair.iter().for_each(|(ip, speed)| {
-                map_sn
-                    .entry(*ip)
-                    .and_modify(|(s, n)| {
-                        *s += *speed;
-                        *n += 1
-                    })
-                    .or_insert((*speed, 1));
-            });
+        let mut sums: HashMap<Ipv4Addr, Speed> = Default::default();
+        for per_host in self.hosts_buffer.iter() {
+            for (ip, speed) in per_host {
+                *sums.entry(*ip).or_default() += *speed;
+            }
+        }
+        let window = self.hosts_buffer.occupied_len().max(1) as u128;
+        sums.into_iter()
+            .map(|(ip, speed)| (ip, speed / window))
+            .collect()
+    }
+
+    /// How the averaged speed of every host moved since the previous window,
+    /// hosts that went silent are falling.
+    pub fn trend_per_host(&self) -> HashMap<Ipv4Addr, HostTrend> {
+        if self.averages_buffer.occupied_len() < 2 {
+            return Default::default();
+        }
+        let (Some(previous), Some(current)) = (
+            self.averages_buffer.iter().next(),
+            self.averages_buffer.last(),
+        ) else {
+            return Default::default();
+        };
+        previous
+            .keys()
+            .chain(current.keys())
+            .map(|ip| {
+                let before = previous.get(ip).copied().unwrap_or_default();
+                let now = current.get(ip).copied().unwrap_or_default();
+                let trend = HostTrend {
+                    input: Trend::between(before.input, now.input),
+                    output: Trend::between(before.output, now.output),
+                };
+                (*ip, trend)
+            })
+            .collect()
+    }
+
+    /// Averaged LAN to LAN traffic of every host, both ends of a local pair
+    /// get its speed from their own point of view.
+    pub fn local_speed_per_host(&self) -> HashMap<Ipv4Addr, Speed> {
+        let mut sums: HashMap<Ipv4Addr, Speed> = Default::default();
+        for pairs in self.pairs_buffer.iter() {
+            for (pair, speed) in pairs.iter().filter(|(pair, _)| pair.is_local) {
+                for ip in [pair.src_ip, pair.dst_ip] {
+                    if let Some((_, speed)) = pair.seen_from(ip, *speed) {
+                        *sums.entry(ip).or_default() += speed;
+                    }
+                }
+            }
+        }
+        let window = self.pairs_buffer.occupied_len().max(1) as u128;
+        sums.into_iter()
+            .map(|(ip, speed)| (ip, speed / window))
+            .collect()
+    }
+
+    /// The category carrying the most bytes of every host over the window.
+    pub fn dominant_category_per_host(&self) -> HashMap<Ipv4Addr, TrafficCategory> {
+        let mut bytes: HashMap<(Ipv4Addr, TrafficCategory), u128> = Default::default();
+        self.stats_buffer.iter().flatten().for_each(|(k, v)| {
+            let category = TrafficCategory::from_ports(k.a_port, k.b_port);
+            for ip in k.lan_ips() {
+                *bytes.entry((ip, category)).or_default() += v.total();
+            }
         });
-        map_sn.iter().for_each(|(ip, (speed, n))| {
-            map.insert(*ip, *speed / (*n as u128));
+        let mut dominant: HashMap<Ipv4Addr, (TrafficCategory, u128)> = Default::default();
+        for ((ip, category), size) in bytes {
+            let best = dominant.entry(ip).or_insert((category, size));
+            if (size, std::cmp::Reverse(category)) > (best.1, std::cmp::Reverse(best.0)) {
+                *best = (category, size);
+            }
+        }
+        dominant
+            .into_iter()
+            .map(|(ip, (category, _))| (ip, category))
+            .collect()
+    }
+
+    /// The service of every host carrying at least `MIN_DOMINANT_SHARE` of
+    /// its bytes over the window, `Mixed` when none does.
+    pub fn dominant_service_per_host(&self) -> HashMap<Ipv4Addr, ServiceHint> {
+        let mut bytes: HashMap<Ipv4Addr, HashMap<ServiceHint, u128>> = Default::default();
+        self.stats_buffer.iter().flatten().for_each(|(k, v)| {
+            let service = ServiceHint::from_flow(k.transport, k.a_port, k.b_port);
+            for ip in k.lan_ips() {
+                *bytes.entry(ip).or_default().entry(service).or_default() += v.total();
+            }
+        });
+        bytes
+            .into_iter()
+            .filter_map(|(ip, services)| {
+                let total: u128 = services.values().sum();
+                let (service, size) = services
+                    .into_iter()
+                    .max_by_key(|(service, size)| (*size, std::cmp::Reverse(*service)))?;
+                if total == 0 {
+                    return None;
+                }
+                let hint = if size as f64 / total as f64 >= MIN_DOMINANT_SHARE {
+                    service
+                } else {
+                    ServiceHint::Mixed
+                };
+                Some((ip, hint))
+            })
+            .collect()
+    }
+
+    /// What `ip` is mostly doing, `None` without traffic in the window.
+    pub fn dominant_service(&self, ip: Ipv4Addr) -> Option<ServiceHint> {
+        self.dominant_service_per_host().remove(&ip)
+    }
+
+    /// Averaged total of the chosen scope.
+    pub fn total(&self, scope: TotalScope) -> Option<Speed> {
+        match scope {
+            TotalScope::External => self.wan_speed(),
+            TotalScope::WithLan => self.total_with_lan(),
+            TotalScope::Everything => self.total_observed(),
+        }
+    }
+
+    /// Traffic of all the pairs, LAN to LAN transfers included.
+    pub fn total_with_lan(&self) -> Option<Speed> {
+        if self.pairs_buffer.is_empty() {
+            return None;
+        }
+        let sum = self
+            .pairs_buffer
+            .iter()
+            .flat_map(|pairs| pairs.values())
+            .fold(Speed::default(), |a, b| a + *b);
+        Some(sum / (self.pairs_buffer.occupied_len() as u128))
+    }
+
+    /// Everything captured on the interface. Traffic neither sent nor
+    /// received by a local address counts as input, it was received by the
+    /// capture.
+    pub fn total_observed(&self) -> Option<Speed> {
+        if self.speed_buffer_.is_empty() {
+            return None;
+        }
+        let sum = self.speed_buffer_.iter().fold(Speed::default(), |a, sums| {
+            a + Speed {
+                output: sums[0],
+                input: sums[1] + sums[2] + sums[3],
+            }
         });
-        map
+        Some(sum / (self.speed_buffer_.occupied_len() as u128))
     }
 
-    pub fn speed_str(&self) -> String {
+    /// Averaged traffic between the LAN and off-subnet addresses, `input` is
+    /// WAN→LAN and `output` LAN→WAN.
+    pub fn wan_speed(&self) -> Option<Speed> {
         if self.total_speed_buffer.is_empty() {
-            return "".to_string();
+            return None;
         }
-        let avg: Speed = self.total_speed_buffer.iter().fold(
-            Speed {
-                output: 0,
-                input: 0,
-            },
-            |a, b| a + *b,
-        ) / (self.total_speed_buffer.occupied_len() as u128);
-        avg.to_string()
-    }
-
-    pub fn connections_strs(&self) -> Vec<String> {
-        let mut pairs_avg: HashMap<IpPair, (Speed, u8)> = Default::default();
-        self.pairs_buffer.iter().for_each(|map| {
-            map.iter().for_each(|(pair, speed)| {
-                pairs_avg
-                    .entry(pair.to_owned())
-                    .and_modify(|pair_and_num| {
-                        pair_and_num.0 += *speed;
-                        pair_and_num.1 += 1;
-                    })
-                    .or_insert((*speed, 1));
-            });
-        });
-        let mut keys = pairs_avg.keys().collect_vec();
-        keys.sort();
-        keys.iter()
-            .map(|a| {
-                let (speeds_sum, n) = pairs_avg.get(a).unwrap();
-                let speed_avg = *speeds_sum / *n as u128;
-                let sep = match (speed_avg.input != 0, speed_avg.output != 0) {
-                    (true, true) => "<->",
-                    (true, false) => "-->",
-                    (false, true) => "<--",
-                    (false, false) => "---",
+        let sum = self
+            .total_speed_buffer
+            .iter()
+            .fold(Speed::default(), |a, b| a + *b);
+        Some(sum / (self.total_speed_buffer.occupied_len() as u128))
+    }
+
+    /// The tracked flows with their speed averaged over the window, the
+    /// bytes they carried and their age.
+    pub fn connections(&self, sort: ConnectionSort) -> Vec<Connection> {
+        let mut window_bits: HashMap<&StatKey, StatValues> = Default::default();
+        for (key, value) in self.stats_buffer.iter().flatten() {
+            *window_bits.entry(key).or_default() += value.clone();
+        }
+        let window = self.stats_buffer.occupied_len().max(1) as u128;
+        let now = Instant::now();
+        let mut connections = self
+            .flows
+            .iter()
+            .map(|(key, flow)| {
+                let rate = window_bits.get(key).cloned().unwrap_or_default();
+                // without a clue, the local end is taken as the client
+                let server_is_local =
+                    ServiceHint::first_is_server(key.transport, key.a_port, key.b_port)
+                        .unwrap_or(false);
+                let (requests, responses) = if server_is_local {
+                    (flow.bits.b_sent / 8, flow.bits.a_sent / 8)
+                } else {
+                    (flow.bits.a_sent / 8, flow.bits.b_sent / 8)
                 };
-                format!("{} {} {} \t ({})", a.src_ip, sep, a.dst_ip, speed_avg)
+                Connection {
+                    local: (key.a_ip, key.a_port),
+                    remote: (key.b_ip, key.b_port),
+                    transport: key.transport,
+                    scope: key.scope,
+                    network: key.network,
+                    broadcast: self.peer_kind(key.b_ip) == PeerKind::Broadcast,
+                    speed: Speed {
+                        output: rate.a_sent / window,
+                        input: rate.b_sent / window,
+                    },
+                    bytes: flow.bits.total() / 8,
+                    server_is_local,
+                    requests,
+                    responses,
+                    age: now.saturating_duration_since(flow.first_seen),
+                }
+            })
+            .collect_vec();
+        connections.sort_by(|a, b| sort.compare(a, b));
+        connections
+    }
+
+    /// The internet addresses the LAN talked to, grouped by `mask`, busiest first.
+    pub fn destinations(&self, mask: DestinationMask) -> Vec<Destination> {
+        let mut destinations: HashMap<Ipv4Network, (StatValues, HashSet<Ipv4Addr>)> =
+            Default::default();
+        for (ip, remote) in &self.remotes {
+            let (bits, lan_hosts) = destinations.entry(mask.network(*ip)).or_default();
+            *bits += remote.bits.clone();
+            lan_hosts.extend(&remote.lan_hosts);
+        }
+        destinations
+            .into_iter()
+            .map(|(network, (bits, lan_hosts))| Destination {
+                network,
+                down: bits.b_sent / 8,
+                up: bits.a_sent / 8,
+                lan_hosts: lan_hosts.len(),
+            })
+            .sorted_by(|a, b| {
+                (b.down + b.up)
+                    .cmp(&(a.down + a.up))
+                    .then_with(|| a.network.cmp(&b.network))
             })
             .collect()
     }
@@ -214,6 +786,15 @@ impl StatsAggregator {
     }
 }
 
+/// A copy of `buffer` holding `capacity` items, the oldest dropped first.
+fn resized<T: Clone>(buffer: &HeapRb<T>, capacity: usize) -> HeapRb<T> {
+    let mut resized = HeapRb::new(capacity);
+    for item in buffer.iter() {
+        resized.push_overwrite(item.clone());
+    }
+    resized
+}
+
 impl Default for StatsAggregator {
     fn default() -> Self {
         Self::new()
@@ -229,12 +810,334 @@ pub struct StatItem {
 }
 
 type PairStatMap = HashMap<IpPair, Speed>;
-#[derive(Hash, PartialEq, Eq, Debug, Clone, PartialOrd, Ord)]
+#[derive(Hash, PartialEq, Eq, Debug, Clone)]
 struct IpPair {
     pub src_ip: Ipv4Addr,
     pub dst_ip: Ipv4Addr,
     is_local: bool,
 }
+
+impl IpPair {
+    /// The pair a flow belongs to, its ends in the order of the key: the LAN
+    /// end first, the lowest address first between two LAN hosts.
+    fn of(key: &StatKey) -> Self {
+        IpPair {
+            src_ip: key.a_ip,
+            dst_ip: key.b_ip,
+            is_local: key.scope == FlowScope::Local,
+        }
+    }
+
+    /// The other end of the pair and `speed` seen from `ip`, if `ip` is one
+    /// of its ends.
+    fn seen_from(&self, ip: Ipv4Addr, speed: Speed) -> Option<(Ipv4Addr, Speed)> {
+        if self.src_ip == ip {
+            Some((self.dst_ip, speed))
+        } else if self.dst_ip == ip {
+            Some((
+                self.src_ip,
+                Speed {
+                    input: speed.output,
+                    output: speed.input,
+                },
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Direction a speed took between two windows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+/// smaller relative changes are noise
+const TREND_MIN_RATIO: f64 = 0.1;
+/// and so are smaller absolute ones, in bits per second
+const TREND_MIN_BITS: u128 = 1024;
+
+impl Trend {
+    pub fn between(before: u128, now: u128) -> Self {
+        let change = now.abs_diff(before);
+        if change < TREND_MIN_BITS || (change as f64) < before as f64 * TREND_MIN_RATIO {
+            Trend::Steady
+        } else if now > before {
+            Trend::Rising
+        } else {
+            Trend::Falling
+        }
+    }
+
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Trend::Rising => "▲",
+            Trend::Falling => "▼",
+            Trend::Steady => "=",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HostTrend {
+    pub input: Trend,
+    pub output: Trend,
+}
+
+/// A row of the connections view, one flow seen from its local end.
+#[derive(Debug, Clone)]
+pub struct Connection {
+    /// the LAN end, the lowest one between two LAN hosts
+    pub local: (Ipv4Addr, u16),
+    pub remote: (Ipv4Addr, u16),
+    pub transport: Transport,
+    pub scope: FlowScope,
+    /// the interface network the flow was classified under
+    pub network: Option<Ipv4Network>,
+    /// the remote end is a broadcast or multicast address
+    pub broadcast: bool,
+    /// averaged over the window, from the local end
+    pub speed: Speed,
+    /// since the flow was first seen
+    pub bytes: u128,
+    /// the local end serves the flow, guessed from the ports
+    pub server_is_local: bool,
+    /// bytes from the client to the server, and back
+    pub requests: u128,
+    pub responses: u128,
+    pub age: Duration,
+}
+
+/// A row of the destinations view: off-subnet traffic to one network.
+#[derive(Debug, Clone)]
+pub struct Destination {
+    pub network: Ipv4Network,
+    /// bytes received from it since the start
+    pub down: u128,
+    pub up: u128,
+    /// how many LAN hosts talked to it
+    pub lan_hosts: usize,
+}
+
+/// How finely the destinations are grouped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DestinationMask {
+    #[default]
+    Slash24,
+    Slash16,
+    Exact,
+}
+
+impl DestinationMask {
+    pub fn next(self) -> Self {
+        match self {
+            DestinationMask::Slash24 => DestinationMask::Slash16,
+            DestinationMask::Slash16 => DestinationMask::Exact,
+            DestinationMask::Exact => DestinationMask::Slash24,
+        }
+    }
+
+    pub fn prefix(self) -> u8 {
+        match self {
+            DestinationMask::Slash24 => 24,
+            DestinationMask::Slash16 => 16,
+            DestinationMask::Exact => 32,
+        }
+    }
+
+    /// The network `ip` is grouped in.
+    pub fn network(self, ip: Ipv4Addr) -> Ipv4Network {
+        let network = Ipv4Network::new(ip, self.prefix()).expect("prefix is at most 32");
+        Ipv4Network::new(network.network(), self.prefix()).expect("prefix is at most 32")
+    }
+}
+
+/// Criteria the connections can be listed by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ConnectionOrder {
+    #[default]
+    Endpoint,
+    Bandwidth,
+    Bytes,
+    /// oldest flows first, or last when descending
+    Age,
+    /// flows between LAN hosts first
+    Locality,
+}
+
+impl ConnectionOrder {
+    pub fn next(self) -> Self {
+        match self {
+            ConnectionOrder::Endpoint => ConnectionOrder::Bandwidth,
+            ConnectionOrder::Bandwidth => ConnectionOrder::Bytes,
+            ConnectionOrder::Bytes => ConnectionOrder::Age,
+            ConnectionOrder::Age => ConnectionOrder::Locality,
+            ConnectionOrder::Locality => ConnectionOrder::Endpoint,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConnectionOrder::Endpoint => "endpoints",
+            ConnectionOrder::Bandwidth => "bandwidth",
+            ConnectionOrder::Bytes => "bytes",
+            ConnectionOrder::Age => "age",
+            ConnectionOrder::Locality => "LAN first",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionSort {
+    pub order: ConnectionOrder,
+    pub descending: bool,
+}
+
+impl ConnectionSort {
+    /// Ties are always broken by endpoints so the listing is stable between ticks.
+    fn compare(&self, a: &Connection, b: &Connection) -> Ordering {
+        let endpoints = |c: &Connection| (c.local, c.remote, c.transport);
+        let ordering = match self.order {
+            ConnectionOrder::Endpoint => endpoints(a).cmp(&endpoints(b)),
+            ConnectionOrder::Bandwidth => a.speed.total().cmp(&b.speed.total()),
+            ConnectionOrder::Bytes => a.bytes.cmp(&b.bytes),
+            ConnectionOrder::Age => b.age.cmp(&a.age),
+            ConnectionOrder::Locality => {
+                (b.scope == FlowScope::Local).cmp(&(a.scope == FlowScope::Local))
+            }
+        };
+        let ordering = if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then_with(|| endpoints(a).cmp(&endpoints(b)))
+    }
+}
+/// What the footer's speed box shows, `g` goes through them in turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FooterMetric {
+    Total(TotalScope),
+    /// busiest tick of the session
+    Peak,
+    /// over the whole session
+    Average,
+    /// external speed as a share of the link speed
+    Utilization,
+}
+
+impl Default for FooterMetric {
+    fn default() -> Self {
+        FooterMetric::Total(TotalScope::default())
+    }
+}
+
+impl FooterMetric {
+    pub fn next(self) -> Self {
+        match self {
+            FooterMetric::Total(scope) if scope.next() != TotalScope::default() => {
+                FooterMetric::Total(scope.next())
+            }
+            FooterMetric::Total(_) => FooterMetric::Peak,
+            FooterMetric::Peak => FooterMetric::Average,
+            FooterMetric::Average => FooterMetric::Utilization,
+            FooterMetric::Utilization => FooterMetric::default(),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FooterMetric::Total(scope) => scope.label(),
+            FooterMetric::Peak => "session peak",
+            FooterMetric::Average => "session average",
+            FooterMetric::Utilization => "link utilization",
+        }
+    }
+
+    /// The traffic it's about, external but for the wider totals.
+    pub fn scope(self) -> TotalScope {
+        match self {
+            FooterMetric::Total(scope) => scope,
+            _ => TotalScope::External,
+        }
+    }
+}
+
+/// Which traffic the per-host speed columns show.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpeedSource {
+    /// with off-subnet addresses
+    #[default]
+    External,
+    /// with other LAN hosts
+    Local,
+    /// both
+    Combined,
+}
+
+impl SpeedSource {
+    pub fn next(self) -> Self {
+        match self {
+            SpeedSource::External => SpeedSource::Local,
+            SpeedSource::Local => SpeedSource::Combined,
+            SpeedSource::Combined => SpeedSource::External,
+        }
+    }
+
+    /// Speed of a host for this source, from its external and local speeds.
+    pub fn select(self, external: Option<Speed>, local: Option<Speed>) -> Option<Speed> {
+        match self {
+            SpeedSource::External => external,
+            SpeedSource::Local => local,
+            SpeedSource::Combined => match (external, local) {
+                (None, None) => None,
+                (external, local) => Some(external.unwrap_or_default() + local.unwrap_or_default()),
+            },
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SpeedSource::External => "external",
+            SpeedSource::Local => "LAN",
+            SpeedSource::Combined => "all",
+        }
+    }
+}
+
+/// Which traffic the footer total adds up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TotalScope {
+    /// between the LAN and off-subnet addresses
+    #[default]
+    External,
+    /// external plus LAN to LAN
+    WithLan,
+    /// every captured frame, whoever it is for
+    Everything,
+}
+
+impl TotalScope {
+    pub fn next(self) -> Self {
+        match self {
+            TotalScope::External => TotalScope::WithLan,
+            TotalScope::WithLan => TotalScope::Everything,
+            TotalScope::Everything => TotalScope::External,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TotalScope::External => "external",
+            TotalScope::WithLan => "with LAN",
+            TotalScope::Everything => "everything",
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Speed {
     output: u128,
@@ -258,6 +1161,16 @@ impl AddAssign for Speed {
         self.output += rhs.output;
     }
 }
+impl Mul<u128> for Speed {
+    type Output = Speed;
+
+    fn mul(self, rhs: u128) -> Self::Output {
+        Speed {
+            input: self.input * rhs,
+            output: self.output * rhs,
+        }
+    }
+}
 impl Div<u128> for Speed {
     type Output = Speed;
 
@@ -273,29 +1186,166 @@ impl Display for Speed {
         write!(
             f,
             "↓ {} | ↑ {}",
-            format_size(self.input),
-            format_size(self.output)
+            format_bits_per_sec(self.input),
+            format_bits_per_sec(self.output)
         )
     }
 }
 impl Speed {
+    /// A speed of `input` bits per second down and `output` up.
+    #[cfg(test)]
+    pub fn new(input: u128, output: u128) -> Self {
+        Speed { output, input }
+    }
+
+    /// Downloaded bits per second.
+    pub fn input(&self) -> u128 {
+        self.input
+    }
+
+    /// Uploaded bits per second.
+    pub fn output(&self) -> u128 {
+        self.output
+    }
+
+    /// Both directions together.
+    pub fn total(&self) -> u128 {
+        self.input + self.output
+    }
+
+    /// Bits carried at this speed during `duration`.
+    pub fn over(self, duration: Duration) -> Speed {
+        self * duration.as_millis() / 1000
+    }
+
+    /// The highest rate of each direction.
+    pub fn max(self, other: Speed) -> Speed {
+        Speed {
+            input: self.input.max(other.input),
+            output: self.output.max(other.output),
+        }
+    }
     pub fn to_string_input(&self) -> String {
-        format_size(self.input)
+        format_bits_per_sec(self.input)
     }
     pub fn to_string_output(&self) -> String {
-        format_size(self.output)
+        format_bits_per_sec(self.output)
+    }
+}
+
+#[derive(PartialEq, Eq)]
+enum PeerKind {
+    Internet,
+    Local,
+    Broadcast,
+}
+
+/// Speed over the window and bytes since the start of one kind of counterpart.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct BreakdownBucket {
+    pub speed: Speed,
+    pub bytes: u128,
+}
+
+/// What a host exchanged with one counterpart.
+#[derive(Debug, Clone, Copy)]
+pub struct PeerTraffic {
+    pub peer: Ipv4Addr,
+    /// averaged over the window, from the host's point of view
+    pub speed: Speed,
+    /// since the start
+    pub bytes: u128,
+}
+
+impl PeerTraffic {
+    fn new(peer: Ipv4Addr) -> Self {
+        Self {
+            peer,
+            speed: Speed::default(),
+            bytes: 0,
+        }
     }
 }
 
+/// A host's traffic by counterpart: off-subnet addresses through the
+/// gateway, other LAN hosts, broadcast and multicast groups.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct HostBreakdown {
+    pub internet: BreakdownBucket,
+    pub local: BreakdownBucket,
+    pub broadcast: BreakdownBucket,
+}
+
+impl HostBreakdown {
+    fn bucket_mut(&mut self, kind: PeerKind) -> &mut BreakdownBucket {
+        match kind {
+            PeerKind::Internet => &mut self.internet,
+            PeerKind::Local => &mut self.local,
+            PeerKind::Broadcast => &mut self.broadcast,
+        }
+    }
+}
+
+/// A flow with its ends in a canonical order, so both directions of a
+/// connection are counted in the same entry: the LAN end first in an
+/// external flow, otherwise the lowest address and port first.
 #[derive(Hash, PartialEq, Eq, Debug, Clone)]
 pub struct StatKey {
-    pub src_port: u16,
-    pub sdt_port: u16,
-    pub src_ip: Ipv4Addr,
-    pub dst_ip: Ipv4Addr,
-    pub direction: Direction,
+    pub a_ip: Ipv4Addr,
+    pub a_port: u16,
+    pub b_ip: Ipv4Addr,
+    pub b_port: u16,
+    pub scope: FlowScope,
+    pub transport: Transport,
+    /// the interface network the flow was classified under, the `a` end's
+    pub network: Option<Ipv4Network>,
+}
+
+impl StatKey {
+    /// The key of a frame sent from `src` to `dst`, and whether `src` is its
+    /// `a` end.
+    pub fn of_frame(
+        transport: Transport,
+        src: (Ipv4Addr, u16),
+        dst: (Ipv4Addr, u16),
+        (direction, network): (Direction, Option<Ipv4Network>),
+    ) -> (Self, bool) {
+        let (scope, src_is_a) = match direction {
+            Direction::Outgoing => (FlowScope::External, true),
+            Direction::Incomming => (FlowScope::External, false),
+            Direction::Local => (FlowScope::Local, src <= dst),
+            Direction::None => (FlowScope::Foreign, src <= dst),
+        };
+        let ((a_ip, a_port), (b_ip, b_port)) = if src_is_a { (src, dst) } else { (dst, src) };
+        let key = StatKey {
+            a_ip,
+            a_port,
+            b_ip,
+            b_port,
+            scope,
+            transport,
+            network,
+        };
+        (key, src_is_a)
+    }
+
+    /// The ends of the flow that are hosts of the LAN.
+    fn lan_ips(&self) -> Vec<Ipv4Addr> {
+        match self.scope {
+            FlowScope::External => vec![self.a_ip],
+            FlowScope::Local => vec![self.a_ip, self.b_ip],
+            FlowScope::Foreign => vec![],
+        }
+    }
 }
 
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum Transport {
+    Tcp,
+    Udp,
+}
+
+/// Which ends of a frame are local addresses.
 #[derive(Hash, PartialEq, Eq, Debug, Clone)]
 pub enum Direction {
     None,
@@ -304,25 +1354,647 @@ pub enum Direction {
     Local,
 }
 
-#[derive(Debug, Clone)]
+impl Direction {
+    /// How a frame from `src` to `dst` relates to the interface's networks,
+    /// and the network it was classified under.
+    ///
+    /// Two addresses in different networks of the interface are both hosts
+    /// of the LAN: the frame is local, so each end gets its share, and is
+    /// classified under the lowest address's network so both directions land
+    /// in the same flow.
+    pub fn classify(
+        src: Ipv4Addr,
+        dst: Ipv4Addr,
+        networks: &[IpNetwork],
+    ) -> (Self, Option<Ipv4Network>) {
+        // the most specific network of an address, for nested ones
+        let network_of = |ip: Ipv4Addr| {
+            networks
+                .iter()
+                .filter_map(|network| match network {
+                    IpNetwork::V4(network) if network.contains(ip) => Some(*network),
+                    _ => None,
+                })
+                .max_by_key(|network| network.prefix())
+        };
+        let shared = networks
+            .iter()
+            .filter_map(|network| match network {
+                IpNetwork::V4(network) if network.contains(src) && network.contains(dst) => {
+                    Some(*network)
+                }
+                _ => None,
+            })
+            .max_by_key(|network| network.prefix());
+        if shared.is_some() {
+            return (Direction::Local, shared);
+        }
+        match (network_of(src), network_of(dst)) {
+            (Some(network), None) => (Direction::Outgoing, Some(network)),
+            (None, Some(network)) => (Direction::Incomming, Some(network)),
+            (Some(network), Some(_)) if src <= dst => (Direction::Local, Some(network)),
+            (Some(_), Some(network)) => (Direction::Local, Some(network)),
+            (None, None) => (Direction::None, None),
+        }
+    }
+}
+
+/// Which ends of a flow are hosts of the LAN.
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FlowScope {
+    /// `a` is, `b` is off-subnet
+    External,
+    /// both are
+    Local,
+    /// neither is, traffic merely seen on the segment
+    Foreign,
+}
+
+/// Bits sent by each end of a flow.
+#[derive(Debug, Clone, Default)]
 pub struct StatValues {
-    pub size: u128,
+    pub a_sent: u128,
+    pub b_sent: u128,
 }
 
-const B_1024: f64 = 1024f64;
-fn format_size(bits: u128) -> String {
-    let bits = f64::from(bits as u32);
-    let kbits = if bits < B_1024 {
-        return format!("{:.2} Bit/s", bits);
-    } else {
-        bits / B_1024
-    };
+impl StatValues {
+    /// `bits` sent by the `a` end, or by the `b` end.
+    pub fn sent(by_a: bool, bits: u128) -> Self {
+        if by_a {
+            StatValues {
+                a_sent: bits,
+                b_sent: 0,
+            }
+        } else {
+            StatValues {
+                a_sent: 0,
+                b_sent: bits,
+            }
+        }
+    }
 
-    let mbits = if kbits < B_1024 {
-        return format!("{:.2} Kib/s", kbits);
-    } else {
-        kbits / B_1024
-    };
+    pub fn total(&self) -> u128 {
+        self.a_sent + self.b_sent
+    }
 
-    format!("{:.2} Mib/s", mbits)
+    /// The speed of the flow's pair, from `a`'s side: what `a` sends is
+    /// its output, whether `b` is off-subnet or another LAN host.
+    fn pair_speed(&self, scope: FlowScope) -> Speed {
+        match scope {
+            FlowScope::External | FlowScope::Local => Speed {
+                output: self.a_sent,
+                input: self.b_sent,
+            },
+            FlowScope::Foreign => Speed::default(),
+        }
+    }
+}
+
+impl AddAssign for StatValues {
+    fn add_assign(&mut self, rhs: Self) {
+        self.a_sent += rhs.a_sent;
+        self.b_sent += rhs.b_sent;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A LAN address and a point-to-point link, as with a VPN on the same
+    /// interface.
+    fn dual_subnet() -> Vec<IpNetwork> {
+        vec![
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 10), 24).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 8, 0, 1), 30).unwrap()),
+        ]
+    }
+
+    fn lan() -> Option<Ipv4Network> {
+        Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 10), 24).ok()
+    }
+
+    fn link() -> Option<Ipv4Network> {
+        Ipv4Network::new(Ipv4Addr::new(10, 8, 0, 1), 30).ok()
+    }
+
+    const LAN_A: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 20);
+    const LAN_B: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 30);
+    const PEER: Ipv4Addr = Ipv4Addr::new(10, 8, 0, 2);
+    const US_ON_LINK: Ipv4Addr = Ipv4Addr::new(10, 8, 0, 1);
+    const REMOTE: Ipv4Addr = Ipv4Addr::new(93, 184, 216, 34);
+
+    /// Download then upload.
+    fn in_out(speed: Speed) -> (u128, u128) {
+        (speed.input(), speed.output())
+    }
+
+    /// One tick of a second in which each `src` sent `bits` to its `dst`.
+    fn tick(aggregator: &mut StatsAggregator, flows: &[(Ipv4Addr, Ipv4Addr, u128)]) {
+        tick_at(aggregator, flows, Instant::now());
+    }
+
+    /// [`tick`], flushed at `flushed_at`.
+    fn tick_at(
+        aggregator: &mut StatsAggregator,
+        flows: &[(Ipv4Addr, Ipv4Addr, u128)],
+        flushed_at: Instant,
+    ) {
+        tick_over(aggregator, flows, Duration::from_secs(1), flushed_at);
+    }
+
+    /// [`tick_at`], the bits counted over `elapsed`.
+    fn tick_over(
+        aggregator: &mut StatsAggregator,
+        flows: &[(Ipv4Addr, Ipv4Addr, u128)],
+        elapsed: Duration,
+        flushed_at: Instant,
+    ) {
+        let networks = dual_subnet();
+        let mut stats = StatsMap::new();
+        for &(src, dst, bits) in flows {
+            let direction = Direction::classify(src, dst, &networks);
+            let (key, by_a) =
+                StatKey::of_frame(Transport::Tcp, (src, 50000), (dst, 445), direction);
+            *stats.entry(key).or_default() += StatValues::sent(by_a, bits);
+        }
+        aggregator.tick(stats, elapsed, flushed_at);
+    }
+
+    #[test]
+    fn only_a_gap_past_the_tick_and_the_margin_clears_the_window() {
+        let last = Instant::now();
+        let second = Duration::from_secs(1);
+        let gap = |after| StatsAggregator::is_tick_gap(last, last + after, second);
+        assert!(!gap(second));
+        // a late tick, or a runtime busy for a few seconds
+        assert!(!gap(second + MAX_TICK_GAP));
+        assert!(gap(second + MAX_TICK_GAP + Duration::from_millis(1)));
+        assert!(gap(Duration::from_secs(3600)));
+        // a longer tick is allowed as much more
+        assert!(!StatsAggregator::is_tick_gap(
+            last,
+            last + Duration::from_secs(10),
+            Duration::from_secs(5)
+        ));
+        // a clock going backwards isn't a gap
+        assert!(!StatsAggregator::is_tick_gap(last + second, last, second));
+    }
+
+    #[test]
+    fn a_suspend_clears_the_window_but_not_the_history() {
+        let start = Instant::now();
+        let mut aggregator = StatsAggregator::new();
+        let second = |n| start + Duration::from_secs(n);
+        tick_at(&mut aggregator, &[(LAN_A, LAN_B, 8000)], second(0));
+        tick_at(&mut aggregator, &[], second(1));
+        assert_eq!(in_out(aggregator.local_speed_per_host()[&LAN_A]), (0, 4000));
+
+        // the traffic from before the gap doesn't drag the speed after it
+        tick_at(&mut aggregator, &[(LAN_A, LAN_B, 2000)], second(60));
+        assert_eq!(in_out(aggregator.local_speed_per_host()[&LAN_A]), (0, 2000));
+        assert_eq!(aggregator.history().total().span(), Duration::from_secs(3));
+    }
+
+    fn remote(last: u8) -> Ipv4Addr {
+        Ipv4Addr::new(93, 184, 216, last)
+    }
+
+    /// When the flow from LAN_A to `remote` was first seen, and its bits.
+    fn flow_to(aggregator: &StatsAggregator, remote: Ipv4Addr) -> Option<(Instant, u128)> {
+        aggregator
+            .flows
+            .iter()
+            .find(|(key, _)| key.b_ip == remote)
+            .map(|(_, flow)| (flow.first_seen, flow.bits.total()))
+    }
+
+    #[test]
+    fn a_flow_keeps_its_age_across_ticks_until_it_idles_out() {
+        let start = Instant::now();
+        let second = |n| start + Duration::from_secs(n);
+        let mut aggregator = StatsAggregator::new();
+        for n in 0..30 {
+            let mut flows = vec![(LAN_A, remote(1), 800)];
+            if n >= 10 {
+                flows.push((LAN_A, remote(2), 8000));
+            }
+            tick_at(&mut aggregator, &flows, second(n));
+            // quiet ticks in between don't restart them
+            tick_at(&mut aggregator, &[], second(n) + Duration::from_millis(500));
+        }
+        assert_eq!(flow_to(&aggregator, remote(1)), Some((second(0), 30 * 800)));
+        assert_eq!(
+            flow_to(&aggregator, remote(2)),
+            Some((second(10), 20 * 8000))
+        );
+
+        // back after longer than the idle timeout, a new flow
+        let back = 29 + FLOW_IDLE_TIMEOUT.as_secs() + 1;
+        tick_at(&mut aggregator, &[(LAN_A, remote(1), 800)], second(back));
+        assert_eq!(flow_to(&aggregator, remote(1)), Some((second(back), 800)));
+    }
+
+    #[test]
+    fn eviction_drops_the_least_recently_seen_flows_not_the_active_ones() {
+        let start = Instant::now();
+        let second = |n| start + Duration::from_secs(n);
+        let mut aggregator = StatsAggregator::new();
+        aggregator.set_limits(Limits {
+            flows: 3,
+            ..Default::default()
+        });
+        let to = |lasts: &[u8]| {
+            lasts
+                .iter()
+                .map(|last| (LAN_A, remote(*last), 800))
+                .collect_vec()
+        };
+        tick_at(&mut aggregator, &to(&[1, 2, 3]), second(0));
+        tick_at(&mut aggregator, &to(&[1]), second(1));
+        tick_at(&mut aggregator, &to(&[4, 5]), second(2));
+        assert_eq!(aggregator.flows.len(), 3);
+        assert!(flow_to(&aggregator, remote(2)).is_none());
+        assert!(flow_to(&aggregator, remote(3)).is_none());
+        // still active, its age goes on
+        assert_eq!(flow_to(&aggregator, remote(1)), Some((second(0), 1600)));
+
+        // a tick busier than the cap keeps all its flows
+        tick_at(&mut aggregator, &to(&[6, 7, 8, 9]), second(3));
+        let mut tracked = aggregator.flows.keys().map(|key| key.b_ip).collect_vec();
+        tracked.sort();
+        assert_eq!(tracked, [remote(6), remote(7), remote(8), remote(9)]);
+
+        // under pressure, a flow seen every tick never restarts
+        for n in 4..64 {
+            let busy = [1, 10 + n as u8, 11 + n as u8];
+            tick_at(&mut aggregator, &to(&busy), second(n));
+            assert_eq!(aggregator.flows.len(), 3);
+        }
+        assert_eq!(flow_to(&aggregator, remote(1)).unwrap().0, second(4));
+    }
+
+    #[test]
+    fn pair_totals_drop_the_least_recently_seen_pairs_not_the_new_ones() {
+        let start = Instant::now();
+        let second = |n| start + Duration::from_secs(n);
+        let mut aggregator = StatsAggregator::new();
+        aggregator.set_limits(Limits {
+            pair_totals: 3,
+            ..Default::default()
+        });
+        let totalled = |aggregator: &StatsAggregator| {
+            let mut remotes = aggregator
+                .pair_totals
+                .keys()
+                .map(|pair| pair.src_ip.min(pair.dst_ip))
+                .collect_vec();
+            remotes.sort();
+            remotes
+        };
+        // a big download, then small ones
+        tick_at(&mut aggregator, &[(remote(1), LAN_A, 8_000_000)], second(0));
+        tick_at(&mut aggregator, &[(LAN_A, remote(2), 800)], second(1));
+        tick_at(&mut aggregator, &[(LAN_A, remote(3), 800)], second(2));
+        tick_at(&mut aggregator, &[(LAN_A, remote(4), 800)], second(3));
+        // the small new conversation shows, the big one heard from first goes
+        assert_eq!(totalled(&aggregator), [remote(2), remote(3), remote(4)]);
+
+        // a pair seen every tick keeps its total, the new ones show up
+        for n in 4..16 {
+            let flows = [(LAN_A, remote(2), 800), (LAN_A, remote(n as u8 + 1), 8)];
+            tick_at(&mut aggregator, &flows, second(n));
+            assert!(totalled(&aggregator).contains(&remote(n as u8 + 1)));
+        }
+        assert_eq!(totalled(&aggregator), [remote(2), remote(15), remote(16)]);
+        assert_eq!(aggregator.top_peers(LAN_A, 1)[0].peer, remote(2));
+    }
+
+    #[test]
+    fn speeds_are_per_second_whatever_the_tick_length() {
+        let start = Instant::now();
+        let mut aggregator = StatsAggregator::new();
+        let mut flushed_at = start;
+        // 8 kb/s up and 80 kb/s down, flushed at the interval's bounds and
+        // by the adaptive ticks of a quiet network
+        for ms in [200, 1000, 200, 2000, 2500, 5000, 1000, 200] {
+            let elapsed = Duration::from_millis(ms);
+            flushed_at += elapsed;
+            let bits = |per_sec: u128| per_sec * ms as u128 / 1000;
+            tick_over(
+                &mut aggregator,
+                &[(LAN_A, REMOTE, bits(8000)), (REMOTE, LAN_A, bits(80_000))],
+                elapsed,
+                flushed_at,
+            );
+            assert_eq!(in_out(aggregator.speed_per_host()[&LAN_A]), (80_000, 8000));
+            assert_eq!(
+                in_out(aggregator.total(TotalScope::External).unwrap()),
+                (80_000, 8000)
+            );
+        }
+        assert_eq!(
+            in_out(aggregator.session_average().unwrap()),
+            (80_000, 8000)
+        );
+        // one history sample a second, 12.1 seconds so far
+        let total = aggregator.history().total();
+        assert_eq!(total.span(), Duration::from_secs(12));
+        let samples = total.samples(Duration::from_secs(12));
+        assert!(samples.iter().all(|speed| in_out(*speed) == (80_000, 8000)));
+    }
+
+    #[test]
+    fn rates_use_the_time_a_tick_covers_not_when_it_is_handled() {
+        let start = Instant::now();
+        let mut aggregator = StatsAggregator::new();
+        let mut flushed_at = start;
+        // (bits, claimed length): on time, a stalled flush, a short one
+        for (bits, ms) in [(8000, 1000), (24_000, 3000), (4000, 500), (8000, 1000)] {
+            let elapsed = Duration::from_millis(ms);
+            flushed_at += elapsed;
+            tick_over(
+                &mut aggregator,
+                &[(LAN_A, REMOTE, bits)],
+                elapsed,
+                flushed_at,
+            );
+            assert_eq!(aggregator.speed_per_host()[&LAN_A].output(), 8000);
+            // what the tick carried, back in bits
+            let last = aggregator.last_tick_per_host().unwrap()[&LAN_A];
+            assert_eq!(last.output(), bits);
+        }
+        // however late they are handled, the ticks were dated at flush: the
+        // stalled one wasn't a gap and the window kept all of them
+        assert_eq!(aggregator.hosts_buffer.occupied_len(), 4);
+        assert_eq!(aggregator.session_average().unwrap().output(), 8000);
+    }
+
+    #[test]
+    fn a_long_tick_weighs_its_length_in_the_history() {
+        let start = Instant::now();
+        let mut aggregator = StatsAggregator::new();
+        // 4 s at 4 kb/s then a second at 24 kb/s, the same bits either way
+        tick_over(
+            &mut aggregator,
+            &[(LAN_A, REMOTE, 16_000)],
+            Duration::from_secs(4),
+            start + Duration::from_secs(4),
+        );
+        tick_over(
+            &mut aggregator,
+            &[(LAN_A, REMOTE, 24_000)],
+            Duration::from_secs(1),
+            start + Duration::from_secs(5),
+        );
+        let total = aggregator.history().total();
+        assert_eq!(total.span(), Duration::from_secs(5));
+        assert_eq!(
+            total.average(Duration::from_secs(5)).unwrap().output(),
+            8000
+        );
+        assert_eq!(aggregator.session_average().unwrap().output(), 8000);
+    }
+
+    #[test]
+    fn canonical_keys_give_the_speeds_of_one_key_per_direction() {
+        let other_remote = Ipv4Addr::new(1, 1, 1, 1);
+        // frames of a second: (src, dst, bits), each direction several times
+        let frames = [
+            ((LAN_A, 50000), (REMOTE, 443), 1000),
+            ((REMOTE, 443), (LAN_A, 50000), 10_000),
+            ((REMOTE, 443), (LAN_A, 50000), 20_000),
+            ((LAN_A, 50000), (REMOTE, 443), 600),
+            ((LAN_B, 40000), (REMOTE, 443), 500),
+            ((REMOTE, 443), (LAN_B, 40000), 2000),
+            ((LAN_B, 445), (LAN_A, 50001), 6000),
+            ((LAN_A, 50001), (LAN_B, 445), 400),
+            ((PEER, 51820), (LAN_A, 51820), 3000),
+            ((REMOTE, 443), (other_remote, 53), 700),
+        ];
+        let networks = dual_subnet();
+        let mut stats = StatsMap::new();
+        // what the keys of each direction added up to, a host's own view
+        let mut external: HashMap<Ipv4Addr, Speed> = HashMap::new();
+        let mut local: HashMap<Ipv4Addr, Speed> = HashMap::new();
+        let (mut lan_bits, mut foreign_bits) = (0, 0);
+        for (src, dst, bits) in frames {
+            let direction = Direction::classify(src.0, dst.0, &networks);
+            match direction.0 {
+                Direction::Outgoing => *external.entry(src.0).or_default() += Speed::new(0, bits),
+                Direction::Incomming => *external.entry(dst.0).or_default() += Speed::new(bits, 0),
+                Direction::Local => {
+                    *local.entry(src.0).or_default() += Speed::new(0, bits);
+                    *local.entry(dst.0).or_default() += Speed::new(bits, 0);
+                    lan_bits += bits;
+                }
+                Direction::None => foreign_bits += bits,
+            }
+            let (key, by_a) = StatKey::of_frame(Transport::Tcp, src, dst, direction);
+            *stats.entry(key).or_default() += StatValues::sent(by_a, bits);
+        }
+        // one entry per connection
+        assert_eq!(stats.len(), 5);
+
+        let mut aggregator = StatsAggregator::new();
+        aggregator.tick(stats, Duration::from_secs(1), Instant::now());
+        // the ends of a foreign flow are listed without speed, as before
+        let per_host = |speeds: HashMap<Ipv4Addr, Speed>| {
+            speeds
+                .into_iter()
+                .filter(|(_, speed)| speed.total() > 0)
+                .map(|(ip, speed)| (ip, in_out(speed)))
+                .collect::<HashMap<_, _>>()
+        };
+        assert_eq!(
+            per_host(aggregator.speed_per_host()),
+            per_host(external.clone())
+        );
+        assert_eq!(per_host(aggregator.local_speed_per_host()), per_host(local));
+        let wan = external.values().fold(Speed::default(), |a, b| a + *b);
+        assert_eq!(in_out(wan), (32_000, 2100));
+        assert_eq!(
+            aggregator.total(TotalScope::External).map(in_out),
+            Some(in_out(wan))
+        );
+        assert_eq!(
+            aggregator.total(TotalScope::Everything).map(in_out),
+            Some(in_out(wan + Speed::new(lan_bits + foreign_bits, 0)))
+        );
+    }
+
+    #[test]
+    fn an_idle_host_decays_to_zero_over_the_window() {
+        let upload = |aggregator: &StatsAggregator| {
+            aggregator
+                .speed_per_host()
+                .get(&LAN_A)
+                .map_or(0, Speed::output)
+        };
+        let mut aggregator = StatsAggregator::new();
+        for _ in 0..DEFAULT_WINDOW {
+            tick(&mut aggregator, &[(LAN_A, REMOTE, 8000)]);
+        }
+        assert_eq!(upload(&aggregator), 8000);
+        // another host keeps the ticks busy, LAN_A's quiet ones count as zero
+        let mut curve = vec![];
+        for _ in 0..DEFAULT_WINDOW + 2 {
+            tick(&mut aggregator, &[(LAN_B, REMOTE, 800)]);
+            curve.push(upload(&aggregator));
+        }
+        assert_eq!(
+            curve,
+            [7200, 6400, 5600, 4800, 4000, 3200, 2400, 1600, 800, 0, 0, 0]
+        );
+
+        // a burst at the start of a session, averaged over the ticks so far
+        let mut aggregator = StatsAggregator::new();
+        tick(&mut aggregator, &[(LAN_A, REMOTE, 12_000)]);
+        let mut curve = vec![upload(&aggregator)];
+        for _ in 0..4 {
+            tick(&mut aggregator, &[]);
+            curve.push(upload(&aggregator));
+        }
+        assert_eq!(curve, [12_000, 6000, 4000, 3000, 2400]);
+    }
+
+    #[test]
+    fn both_ends_of_a_lan_transfer_see_their_own_direction() {
+        // whichever end has the lowest address, or in another subnet
+        for (sender, receiver) in [(LAN_A, LAN_B), (LAN_B, LAN_A), (LAN_A, PEER), (PEER, LAN_A)] {
+            let mut aggregator = StatsAggregator::new();
+            tick(&mut aggregator, &[(sender, receiver, 8000)]);
+            let speeds = aggregator.local_speed_per_host();
+            assert_eq!(in_out(speeds[&sender]), (0, 8000));
+            assert_eq!(in_out(speeds[&receiver]), (8000, 0));
+        }
+    }
+
+    #[test]
+    fn the_breakdown_splits_internet_lan_and_broadcast() {
+        let gateway = Ipv4Addr::new(192, 168, 1, 1);
+        let broadcast = Ipv4Addr::new(192, 168, 1, 255);
+        let mut aggregator = StatsAggregator::new();
+        aggregator.set_lan(Some(gateway), vec![lan().unwrap()]);
+        tick(
+            &mut aggregator,
+            &[
+                (LAN_A, REMOTE, 8000),
+                // the gateway itself counts as the internet
+                (LAN_A, gateway, 1600),
+                (LAN_B, LAN_A, 4000),
+                (LAN_A, broadcast, 800),
+            ],
+        );
+        let breakdown = aggregator.host_breakdown(LAN_A);
+        assert_eq!(in_out(breakdown.internet.speed), (0, 9600));
+        assert_eq!(breakdown.internet.bytes, 1200);
+        assert_eq!(in_out(breakdown.local.speed), (4000, 0));
+        assert_eq!(breakdown.local.bytes, 500);
+        assert_eq!(in_out(breakdown.broadcast.speed), (0, 800));
+        assert_eq!(breakdown.broadcast.bytes, 100);
+        // the other end of the LAN transfer
+        let breakdown = aggregator.host_breakdown(LAN_B);
+        assert_eq!(in_out(breakdown.local.speed), (0, 4000));
+        assert_eq!(breakdown.internet.bytes, 0);
+
+        // a quiet tick halves the speeds over the window, not the bytes
+        tick(&mut aggregator, &[]);
+        let breakdown = aggregator.host_breakdown(LAN_A);
+        assert_eq!(in_out(breakdown.internet.speed), (0, 4800));
+        assert_eq!(breakdown.internet.bytes, 1200);
+        assert_eq!(in_out(breakdown.local.speed), (2000, 0));
+    }
+
+    #[test]
+    fn top_peers_are_seen_from_the_host_busiest_first() {
+        let mut aggregator = StatsAggregator::new();
+        tick(
+            &mut aggregator,
+            &[
+                (LAN_A, LAN_B, 8000),
+                (REMOTE, LAN_A, 16000),
+                (LAN_A, PEER, 800),
+            ],
+        );
+        let peers = aggregator.top_peers(LAN_A, 2);
+        let summary: Vec<_> = peers
+            .iter()
+            .map(|peer| (peer.peer, in_out(peer.speed), peer.bytes))
+            .collect();
+        assert_eq!(
+            summary,
+            [(REMOTE, (16000, 0), 2000), (LAN_B, (0, 8000), 1000)]
+        );
+        // and from the LAN peer's side
+        let peers = aggregator.top_peers(LAN_B, 5);
+        assert_eq!(peers.len(), 1);
+        assert_eq!((peers[0].peer, in_out(peers[0].speed)), (LAN_A, (8000, 0)));
+    }
+
+    #[test]
+    fn same_subnet_is_local_under_that_subnet() {
+        let networks = dual_subnet();
+        assert_eq!(
+            Direction::classify(LAN_A, LAN_B, &networks),
+            (Direction::Local, lan())
+        );
+        assert_eq!(
+            Direction::classify(PEER, US_ON_LINK, &networks),
+            (Direction::Local, link())
+        );
+    }
+
+    #[test]
+    fn cross_subnet_is_local_to_both_ends() {
+        let networks = dual_subnet();
+        let there = Direction::classify(LAN_A, PEER, &networks);
+        let back = Direction::classify(PEER, LAN_A, &networks);
+        assert_eq!(there, (Direction::Local, link()));
+        assert_eq!(back, (Direction::Local, link()));
+        // both directions are one local flow, the lowest address first
+        let (there, _) = StatKey::of_frame(Transport::Tcp, (LAN_A, 50000), (PEER, 22), there);
+        let (back, _) = StatKey::of_frame(Transport::Tcp, (PEER, 22), (LAN_A, 50000), back);
+        assert_eq!(there, back);
+        assert_eq!(there.scope, FlowScope::Local);
+        assert_eq!(there.a_ip, PEER);
+        assert_eq!(there.lan_ips(), [PEER, LAN_A]);
+    }
+
+    #[test]
+    fn off_link_is_external_under_the_local_end_subnet() {
+        let networks = dual_subnet();
+        assert_eq!(
+            Direction::classify(LAN_A, REMOTE, &networks),
+            (Direction::Outgoing, lan())
+        );
+        assert_eq!(
+            Direction::classify(REMOTE, PEER, &networks),
+            (Direction::Incomming, link())
+        );
+        assert_eq!(
+            Direction::classify(REMOTE, Ipv4Addr::new(1, 1, 1, 1), &networks),
+            (Direction::None, None)
+        );
+    }
+
+    #[test]
+    fn nested_networks_pick_the_most_specific() {
+        let wide = Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 1), 8).unwrap();
+        let narrow = Ipv4Network::new(Ipv4Addr::new(10, 1, 0, 1), 16).unwrap();
+        let networks = [IpNetwork::V4(wide), IpNetwork::V4(narrow)];
+        let inside = Ipv4Addr::new(10, 1, 2, 3);
+        assert_eq!(
+            Direction::classify(inside, Ipv4Addr::new(10, 1, 9, 9), &networks),
+            (Direction::Local, Some(narrow))
+        );
+        assert_eq!(
+            Direction::classify(inside, Ipv4Addr::new(10, 2, 0, 1), &networks),
+            (Direction::Local, Some(wide))
+        );
+        assert_eq!(
+            Direction::classify(inside, REMOTE, &networks),
+            (Direction::Outgoing, Some(narrow))
+        );
+    }
 }
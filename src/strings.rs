@@ -0,0 +1,349 @@
+use std::{env, fmt::Display, sync::OnceLock};
+
+use clap::ValueEnum;
+
+/// Languages of the interface, English is the fallback of every text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    Fr,
+}
+
+impl Lang {
+    /// The language of `LC_ALL`, `LC_MESSAGES` or `LANG`, the first one set,
+    /// English when it isn't shipped.
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Self::from_locale(&locale)
+    }
+
+    /// The language of a locale like `fr_FR.UTF-8`.
+    fn from_locale(locale: &str) -> Self {
+        match locale.split(['_', '.', '@']).next() {
+            Some("fr") => Lang::Fr,
+            _ => Lang::En,
+        }
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Lang::En => EN,
+            Lang::Fr => FR,
+        }
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Picks the language for the whole run, the first call wins.
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+pub fn lang() -> Lang {
+    LANG.get().copied().unwrap_or_default()
+}
+
+/// The text of `key` in the current language, in English when it has no
+/// translation yet.
+pub fn text(key: &'static str) -> &'static str {
+    lookup(lang(), key)
+        .or_else(|| lookup(Lang::En, key))
+        .unwrap_or(key)
+}
+
+/// `text` with its `{name}` placeholders filled in.
+pub fn format(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter()
+        .fold(text(key).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), &value.to_string())
+        })
+}
+
+/// The keys of the English catalog `lang` lacks.
+pub fn missing_keys(lang: Lang) -> Vec<&'static str> {
+    EN.iter()
+        .map(|(key, _)| *key)
+        .filter(|key| lookup(lang, key).is_none())
+        .collect()
+}
+
+fn lookup(lang: Lang, key: &str) -> Option<&'static str> {
+    lang.catalog()
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, text)| *text)
+}
+
+const EN: &[(&str, &str)] = &[
+    (
+        "help.hosts.nav",
+        "(q) quit | (Q) force quit | (k) move up | (j) move down | (h) move left | (l) move right | (<>) column width | (=) auto width | (Enter) details | (Tab) connections | (:) command",
+    ),
+    (
+        "help.hosts.actions",
+        "(s) send ARP requests | (c) clean current and older hosts | (e) label | (F) solo | (b) watch | (I) interface info | (S) status | (D) capture stats | (t) freeze time | (T) theme | (g) footer speed | (v) speed source | (m) merge speeds | (a) activity | (,) settings | (x) export | (R) report | (w) snapshot | (W) compare",
+    ),
+    (
+        "help.hosts.passive_actions",
+        "(c) clean current and older hosts | (e) label | (F) solo | (b) watch | (I) interface info | (S) status | (D) capture stats | (t) freeze time | (T) theme | (g) footer speed | (v) speed source | (m) merge speeds | (a) activity | (,) settings | (x) export | (R) report | (w) snapshot | (W) compare",
+    ),
+    (
+        "help.connections.nav",
+        "(q) quit | (Tab) destinations | (k) move up | (j) move down | (h) move left | (l) move right | (Enter) local host details",
+    ),
+    (
+        "help.connections.actions",
+        "(o) next order | (O) reverse order | (:) command",
+    ),
+    (
+        "help.destinations.nav",
+        "(q) quit | (Tab) hosts | (k) move up | (j) move down | (h) move left | (l) move right",
+    ),
+    (
+        "help.destinations.actions",
+        "(p) next grouping | (R) report | (:) command",
+    ),
+    ("hints.more", "… ? for more"),
+    ("hint.sweep", "sweep"),
+    ("hint.details", "details"),
+    ("hint.label", "label"),
+    ("hint.solo", "solo"),
+    ("hint.command", "command"),
+    ("hint.all_keys", "all keys"),
+    ("hint.next_order", "next order"),
+    ("hint.host_details", "host details"),
+    ("hint.destinations", "destinations"),
+    ("hint.next_grouping", "next grouping"),
+    ("hint.report", "report"),
+    ("hint.hosts", "hosts"),
+    ("hint.quit", "quit"),
+    ("hint.cancel", "cancel"),
+    ("hint.save", "save"),
+    ("hint.delete", "delete"),
+    ("hint.run", "run"),
+    ("hint.complete", "complete"),
+    ("hint.close", "close"),
+    ("hint.move", "move"),
+    ("hint.edit", "edit"),
+    ("hint.apply", "apply"),
+    ("hint.discard", "discard"),
+    ("hint.copy_ip", "copy IP"),
+    ("hint.go_to", "go to"),
+    ("footer.state", "State"),
+    ("footer.hosts", "Hosts (active/listed/seen)"),
+    ("footer.interface", "Interface"),
+    ("footer.speed", "Speed ({metric}, g to change)"),
+    ("footer.averages", "External average (1m/5m/15m)"),
+    ("state.passive", "Passive"),
+    ("state.no_capture", "No capture"),
+    ("state.monitor_only", "Monitor only"),
+    ("state.sending_arps", "Sending ARPs"),
+    ("state.idle", "Idle"),
+    (
+        "state.failed_sends",
+        "{state} (last sweep: {failed} sends failed)",
+    ),
+    (
+        "state.sampling",
+        "{state} (capture lagging, sampling 1 in {factor})",
+    ),
+    (
+        "alert.arp_scan",
+        "{ip} sends many ARP requests, it may be scanning the network",
+    ),
+    (
+        "alert.budget_warning",
+        "{ip} is close to its daily budget of {budget}",
+    ),
+    (
+        "alert.budget_exceeded",
+        "{ip} went over its daily budget of {budget}",
+    ),
+    (
+        "banner.no_capture",
+        "No capture (--no-capture): no speeds, connections nor ARP sweeps. Run as root or grant CAP_NET_RAW to get them.",
+    ),
+    ("empty.no_traffic", "no traffic yet"),
+    ("empty.interface", "interface not ready yet"),
+    ("empty.not_ready", "not ready yet"),
+];
+
+const FR: &[(&str, &str)] = &[
+    (
+        "help.hosts.nav",
+        "(q) quitter | (Q) forcer | (k) haut | (j) bas | (h) gauche | (l) droite | (<>) largeur | (=) largeur auto | (Enter) détails | (Tab) connexions | (:) commande",
+    ),
+    (
+        "help.hosts.actions",
+        "(s) requêtes ARP | (c) oublier l'hôte et les plus anciens | (e) nom | (F) solo | (b) guetter | (I) interface | (S) état | (D) capture | (t) figer l'heure | (T) thème | (g) débit du bas | (v) source du débit | (m) débits groupés | (a) activité | (,) réglages | (x) export | (R) rapport | (w) instantané | (W) comparer",
+    ),
+    (
+        "help.hosts.passive_actions",
+        "(c) oublier l'hôte et les plus anciens | (e) nom | (F) solo | (b) guetter | (I) interface | (S) état | (D) capture | (t) figer l'heure | (T) thème | (g) débit du bas | (v) source du débit | (m) débits groupés | (a) activité | (,) réglages | (x) export | (R) rapport | (w) instantané | (W) comparer",
+    ),
+    (
+        "help.connections.nav",
+        "(q) quitter | (Tab) destinations | (k) haut | (j) bas | (h) gauche | (l) droite | (Enter) détails de l'hôte local",
+    ),
+    (
+        "help.connections.actions",
+        "(o) ordre suivant | (O) inverser l'ordre | (:) commande",
+    ),
+    (
+        "help.destinations.nav",
+        "(q) quitter | (Tab) hôtes | (k) haut | (j) bas | (h) gauche | (l) droite",
+    ),
+    (
+        "help.destinations.actions",
+        "(p) regroupement suivant | (R) rapport | (:) commande",
+    ),
+    ("hints.more", "… ? pour plus"),
+    ("hint.sweep", "balayer"),
+    ("hint.details", "détails"),
+    ("hint.label", "étiquette"),
+    ("hint.solo", "solo"),
+    ("hint.command", "commande"),
+    ("hint.all_keys", "toutes les touches"),
+    ("hint.next_order", "ordre suivant"),
+    ("hint.host_details", "détails de l'hôte"),
+    ("hint.destinations", "destinations"),
+    ("hint.next_grouping", "regroupement suivant"),
+    ("hint.report", "rapport"),
+    ("hint.hosts", "hôtes"),
+    ("hint.quit", "quitter"),
+    ("hint.cancel", "annuler"),
+    ("hint.save", "enregistrer"),
+    ("hint.delete", "effacer"),
+    ("hint.run", "exécuter"),
+    ("hint.complete", "compléter"),
+    ("hint.close", "fermer"),
+    ("hint.move", "déplacer"),
+    ("hint.edit", "modifier"),
+    ("hint.apply", "appliquer"),
+    ("hint.discard", "abandonner"),
+    ("hint.copy_ip", "copier l'IP"),
+    ("hint.go_to", "aller à"),
+    ("footer.state", "État"),
+    ("footer.hosts", "Hôtes (actifs/listés/vus)"),
+    ("footer.interface", "Interface"),
+    ("footer.speed", "Débit ({metric}, g pour changer)"),
+    ("footer.averages", "Moyenne externe (1m/5m/15m)"),
+    ("state.passive", "Passif"),
+    ("state.no_capture", "Sans capture"),
+    ("state.monitor_only", "Écoute seule"),
+    ("state.sending_arps", "Envoi ARP"),
+    ("state.idle", "Au repos"),
+    (
+        "state.failed_sends",
+        "{state} (dernier balayage : {failed} envois en échec)",
+    ),
+    (
+        "state.sampling",
+        "{state} (capture en retard, échantillonnage 1 sur {factor})",
+    ),
+    (
+        "alert.arp_scan",
+        "{ip} envoie beaucoup de requêtes ARP, il balaie peut-être le réseau",
+    ),
+    (
+        "alert.budget_warning",
+        "{ip} approche de son budget quotidien de {budget}",
+    ),
+    (
+        "alert.budget_exceeded",
+        "{ip} a dépassé son budget quotidien de {budget}",
+    ),
+    (
+        "banner.no_capture",
+        "Sans capture (--no-capture) : ni débits, ni connexions, ni balayages ARP. Lancez en root ou accordez CAP_NET_RAW pour les avoir.",
+    ),
+    ("empty.no_traffic", "pas encore de trafic"),
+    ("empty.interface", "interface pas encore prête"),
+    ("empty.not_ready", "pas encore prêt"),
+];
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    fn placeholders(text: &str) -> BTreeSet<&str> {
+        text.split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn every_key_is_in_every_locale() {
+        for lang in Lang::value_variants() {
+            assert!(
+                missing_keys(*lang).is_empty(),
+                "{:?} lacks {:?}",
+                lang,
+                missing_keys(*lang)
+            );
+            let keys = lang
+                .catalog()
+                .iter()
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>();
+            let unique = keys.iter().collect::<BTreeSet<_>>();
+            assert_eq!(unique.len(), keys.len(), "{:?} has a key twice", lang);
+            for key in keys {
+                assert!(
+                    lookup(Lang::En, key).is_some(),
+                    "{:?} has {} English lacks",
+                    lang,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn translations_keep_the_placeholders() {
+        for lang in Lang::value_variants() {
+            for (key, english) in EN {
+                let translated = lookup(*lang, key).unwrap();
+                assert_eq!(
+                    placeholders(translated),
+                    placeholders(english),
+                    "{:?} {}",
+                    lang,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_locale_picks_a_shipped_language_or_english() {
+        for locale in ["fr_FR.UTF-8", "fr", "fr_BE@euro"] {
+            assert_eq!(Lang::from_locale(locale), Lang::Fr, "{}", locale);
+        }
+        for locale in ["en_US.UTF-8", "de_DE", "C", "POSIX", ""] {
+            assert_eq!(Lang::from_locale(locale), Lang::En, "{}", locale);
+        }
+    }
+
+    #[test]
+    fn placeholders_are_filled_and_unknown_keys_shown_as_is() {
+        let budget = format(
+            "alert.budget_exceeded",
+            &[("ip", &"192.168.1.20"), ("budget", &"2.00 GiB")],
+        );
+        assert_eq!(
+            budget,
+            "192.168.1.20 went over its daily budget of 2.00 GiB"
+        );
+        assert_eq!(text("no.such.key"), "no.such.key");
+    }
+}
@@ -0,0 +1,123 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use pnet::{ipnetwork::IpNetwork, util::MacAddr};
+
+/// What an ARP sweep of `network` found, shown once it's done: the hosts
+/// seen for the first time and the known ones that didn't answer.
+///
+/// Replies arriving after the last request still count, the popup shows
+/// them as they come.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SweepReport {
+    pub network: IpNetwork,
+    /// requests sent, set when the sweep is done
+    pub probed: Option<usize>,
+    /// hosts of the network known when the sweep began
+    known: Vec<(Ipv4Addr, MacAddr)>,
+    /// hosts that answered one of the sweep's requests, in order
+    replied: Vec<(Ipv4Addr, MacAddr)>,
+}
+
+impl SweepReport {
+    /// A sweep of `network` beginning, with the hosts known so far.
+    pub fn begin(network: IpNetwork, known: impl IntoIterator<Item = (Ipv4Addr, MacAddr)>) -> Self {
+        let known = known
+            .into_iter()
+            .filter(|(ip, _)| network.contains(IpAddr::V4(*ip)))
+            .collect();
+        Self {
+            network,
+            probed: None,
+            known,
+            replied: vec![],
+        }
+    }
+
+    /// `ip` answered with `mac`, ignored outside the network.
+    pub fn reply(&mut self, ip: Ipv4Addr, mac: MacAddr) {
+        if self.network.contains(IpAddr::V4(ip)) && !self.replied.contains(&(ip, mac)) {
+            self.replied.push((ip, mac));
+        }
+    }
+
+    pub fn replied(&self) -> usize {
+        self.replied.len()
+    }
+
+    /// Hosts that answered and weren't known, in the order they did.
+    pub fn first_seen(&self) -> Vec<(Ipv4Addr, MacAddr)> {
+        self.replied
+            .iter()
+            .filter(|host| !self.known.contains(host))
+            .copied()
+            .collect()
+    }
+
+    /// Known hosts that didn't answer, by address. A known address now
+    /// answering with another MAC leaves the old MAC here.
+    pub fn silent(&self) -> Vec<(Ipv4Addr, MacAddr)> {
+        let mut silent: Vec<_> = self
+            .known
+            .iter()
+            .filter(|host| !self.replied.contains(host))
+            .copied()
+            .collect();
+        silent.sort();
+        silent
+    }
+
+    /// The rows of the popup, the first seen then the silent.
+    pub fn entries(&self) -> Vec<(Ipv4Addr, MacAddr)> {
+        let mut entries = self.first_seen();
+        entries.extend(self.silent());
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(last: u8) -> (Ipv4Addr, MacAddr) {
+        (
+            Ipv4Addr::new(192, 168, 1, last),
+            MacAddr(0, 0x1b, 0x63, 0, 0, last),
+        )
+    }
+
+    fn report(known: &[(Ipv4Addr, MacAddr)]) -> SweepReport {
+        SweepReport::begin("192.168.1.0/24".parse().unwrap(), known.iter().copied())
+    }
+
+    #[test]
+    fn known_hosts_that_did_not_answer_are_silent() {
+        let mut report = report(&[host(30), host(1), host(20)]);
+        report.reply(host(20).0, host(20).1);
+        report.reply(host(87).0, host(87).1);
+        assert_eq!(report.silent(), [host(1), host(30)]);
+        assert_eq!(report.first_seen(), [host(87)]);
+        assert_eq!(report.entries(), [host(87), host(1), host(30)]);
+    }
+
+    #[test]
+    fn hosts_outside_the_network_are_left_out() {
+        let other = (Ipv4Addr::new(10, 0, 0, 5), MacAddr(0, 0x1b, 0x63, 0, 1, 5));
+        let mut report = report(&[host(1), other]);
+        report.reply(other.0, other.1);
+        assert_eq!(report.silent(), [host(1)]);
+        assert!(report.first_seen().is_empty());
+        assert_eq!(report.replied(), 0);
+    }
+
+    #[test]
+    fn a_known_address_with_another_mac_is_new_and_the_old_one_silent() {
+        let mut report = report(&[host(20)]);
+        let replaced = (host(20).0, MacAddr(0, 0x1b, 0x63, 0, 0, 99));
+        report.reply(replaced.0, replaced.1);
+        // answering twice counts once
+        report.reply(replaced.0, replaced.1);
+        assert_eq!(report.replied(), 1);
+        assert_eq!(report.first_seen(), [replaced]);
+        assert_eq!(report.silent(), [host(20)]);
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    fmt::{self, Write},
+    io,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+
+use tracing::{field::Field, Event, Level, Subscriber};
+use tracing_subscriber::{field::Visit, layer::Context, Layer};
+
+/// Target of the host and alert events, the only ones sent to syslog.
+pub const EVENT_TARGET: &str = "netui::events";
+
+/// `daemon`, as for other long-running monitors
+const FACILITY: u8 = 3;
+
+/// Sockets of the local syslog daemon, Linux's then macOS'.
+#[cfg(unix)]
+const SOCKETS: [&str; 2] = ["/dev/log", "/var/run/syslog"];
+
+/// Sends the events of `EVENT_TARGET` to the local syslog daemon (RFC 3164),
+/// one `name key=value...` message each, for a SIEM to pick up.
+pub struct SyslogLayer {
+    #[cfg(unix)]
+    socket: UnixDatagram,
+}
+
+impl SyslogLayer {
+    #[cfg(unix)]
+    pub fn connect() -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        let mut last_error = None;
+        for path in SOCKETS {
+            match socket.connect(path) {
+                Ok(()) => return Ok(Self { socket }),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| io::Error::other("no syslog socket")))
+    }
+
+    #[cfg(not(unix))]
+    pub fn connect() -> io::Result<Self> {
+        Err(io::Error::other("syslog is only supported on unix"))
+    }
+
+    fn send(&self, message: &str) {
+        // nowhere left to report a failure, the event is still in the file log
+        #[cfg(unix)]
+        let _ = self.socket.send(message.as_bytes());
+        #[cfg(not(unix))]
+        let _ = message;
+    }
+}
+
+impl<S: Subscriber> Layer<S> for SyslogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if metadata.target() != EVENT_TARGET {
+            return;
+        }
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let message = format!(
+            "<{}>{} {}[{}]: {}{}",
+            FACILITY * 8 + severity(*metadata.level()),
+            chrono::Local::now().format("%b %e %H:%M:%S"),
+            env!("CARGO_PKG_NAME"),
+            std::process::id(),
+            fields.name,
+            fields.pairs
+        );
+        self.send(&message);
+    }
+}
+
+/// Alerts are warnings, discoveries and hosts going offline are notices.
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::ERROR => 3,
+        Level::WARN => 4,
+        Level::INFO => 5,
+        Level::DEBUG => 6,
+        Level::TRACE => 7,
+    }
+}
+
+/// The event's message as its name, its other fields as `key=value`.
+#[derive(Default)]
+struct Fields {
+    name: String,
+    pairs: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.name = value.to_string();
+        } else if value.contains(|c: char| c.is_whitespace() || c == '"') || value.is_empty() {
+            let _ = write!(self.pairs, " {}={:?}", field.name(), value);
+        } else {
+            let _ = write!(self.pairs, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
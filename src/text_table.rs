@@ -0,0 +1,185 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns between two table columns.
+const GAP: usize = 2;
+/// Narrowest a shrunk column gets, its content then ends with `…`.
+const MIN_SHRUNK_WIDTH: usize = 9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Rows of text in aligned columns. Past the available width the columns
+/// marked shrinkable give way first, widest first, then lines are cut.
+pub struct TextTable {
+    headers: Vec<(&'static str, Align, bool)>,
+    rows: Vec<Vec<String>>,
+}
+
+impl TextTable {
+    pub fn new(headers: &[(&'static str, Align, bool)]) -> Self {
+        Self {
+            headers: headers.to_vec(),
+            rows: vec![],
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    fn widths(&self, width: Option<usize>) -> Vec<usize> {
+        let mut widths: Vec<usize> = self
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, (header, ..))| {
+                self.rows
+                    .iter()
+                    .map(|row| row[i].width())
+                    .chain([header.width()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let Some(width) = width else {
+            return widths;
+        };
+        let total =
+            |widths: &[usize]| widths.iter().sum::<usize>() + GAP * widths.len().saturating_sub(1);
+        while total(&widths) > width {
+            let widest = widths
+                .iter()
+                .enumerate()
+                .filter(|(i, w)| self.headers[*i].2 && **w > MIN_SHRUNK_WIDTH)
+                .max_by_key(|(_, w)| **w)
+                .map(|(i, _)| i);
+            let Some(i) = widest else {
+                break;
+            };
+            let excess = total(&widths) - width;
+            widths[i] = widths[i].saturating_sub(excess).max(MIN_SHRUNK_WIDTH);
+        }
+        widths
+    }
+
+    /// The header line then a line per row, fitting `width` when given.
+    pub fn render(&self, width: Option<usize>) -> String {
+        let widths = self.widths(width);
+        let headers = self
+            .headers
+            .iter()
+            .map(|(header, ..)| header.to_string())
+            .collect();
+        [headers]
+            .iter()
+            .chain(&self.rows)
+            .map(|row| {
+                let line = row
+                    .iter()
+                    .zip(&widths)
+                    .zip(&self.headers)
+                    .map(|((cell, &w), &(_, align, _))| {
+                        let cell = fit(cell, w);
+                        let pad = " ".repeat(w - cell.width());
+                        match align {
+                            Align::Left => cell + &pad,
+                            Align::Right => pad + &cell,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(&" ".repeat(GAP));
+                let line = line.trim_end();
+                match width {
+                    Some(width) => fit(line, width),
+                    None => line.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// `text` cut to `width` columns, ending with `…` when cut.
+pub fn fit(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut fitted = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        fitted.push(c);
+        used += w;
+    }
+    if width > 0 {
+        fitted.push('…');
+    }
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> TextTable {
+        let mut table = TextTable::new(&[
+            ("Local", Align::Left, true),
+            ("Remote", Align::Left, true),
+            ("Speed", Align::Right, false),
+        ]);
+        table.push(vec![
+            "192.168.1.20:51234".to_string(),
+            "93.184.216.34:443".to_string(),
+            "1.00 Mib/s".to_string(),
+        ]);
+        table.push(vec![
+            "192.168.1.3:53".to_string(),
+            "192.168.1.20:40000".to_string(),
+            "0.00 bit/s".to_string(),
+        ]);
+        table
+    }
+
+    #[test]
+    fn columns_are_aligned_without_a_width() {
+        let text = table().render(None);
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "Local               Remote                   Speed",
+                "192.168.1.20:51234  93.184.216.34:443   1.00 Mib/s",
+                "192.168.1.3:53      192.168.1.20:40000  0.00 bit/s",
+            ]
+        );
+    }
+
+    #[test]
+    fn the_shrinkable_columns_give_way_first() {
+        let text = table().render(Some(44));
+        for line in text.lines() {
+            assert!(line.width() <= 44, "{:?}", line);
+        }
+        let lines: Vec<_> = text.lines().collect();
+        // the speeds are kept whole, the endpoints end with `…`
+        assert!(lines[1].ends_with("1.00 Mib/s"), "{:?}", lines[1]);
+        assert!(lines[1].contains('…'));
+        // narrower than the unshrinkable columns, lines are cut
+        for line in table().render(Some(12)).lines() {
+            assert!(line.width() <= 12, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn fit_counts_wide_characters() {
+        assert_eq!(fit("Client→Server", 20), "Client→Server");
+        assert_eq!(fit("日本語のホスト", 7), "日本語…");
+        assert_eq!(fit("abc", 0), "");
+    }
+}
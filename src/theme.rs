@@ -0,0 +1,429 @@
+use std::{env, sync::OnceLock};
+
+use clap::ValueEnum;
+use ratatui::style::{palette::tailwind, Color, Modifier, Style};
+
+/// What `--color` asks for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// what the terminal advertises, none when the output isn't one
+    #[default]
+    Auto,
+    /// what the terminal advertises, at least the 16 ANSI colors
+    Always,
+    Never,
+    #[value(name = "256")]
+    Ansi256,
+    Truecolor,
+}
+
+impl ColorChoice {
+    /// The colors to draw with, `tty` telling whether the output is a terminal.
+    pub fn depth(self, tty: bool) -> ColorDepth {
+        match self {
+            ColorChoice::Auto if !tty => ColorDepth::None,
+            ColorChoice::Auto => ColorDepth::detect(),
+            ColorChoice::Always => ColorDepth::detect().max(ColorDepth::Ansi16),
+            ColorChoice::Never => ColorDepth::None,
+            ColorChoice::Ansi256 => ColorDepth::Ansi256,
+            ColorChoice::Truecolor => ColorDepth::Truecolor,
+        }
+    }
+}
+
+/// How many colors the output can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    None,
+    Ansi16,
+    Ansi256,
+    Truecolor,
+}
+
+impl ColorDepth {
+    /// From `NO_COLOR`, then `COLORTERM`, then `TERM`.
+    pub fn detect() -> Self {
+        let var = |name| env::var(name).unwrap_or_default();
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return ColorDepth::None;
+        }
+        if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            return ColorDepth::Truecolor;
+        }
+        let term = var("TERM");
+        if term.is_empty() || term == "dumb" {
+            ColorDepth::None
+        } else if term.contains("truecolor") || term.contains("direct") {
+            ColorDepth::Truecolor
+        } else if term.contains("256") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+static COLOR_DEPTH: OnceLock<ColorDepth> = OnceLock::new();
+
+/// Picks the colors of every theme for the whole run, the first call wins.
+pub fn set_color_depth(depth: ColorDepth) {
+    let _ = COLOR_DEPTH.set(depth);
+}
+
+pub fn color_depth() -> ColorDepth {
+    COLOR_DEPTH.get().copied().unwrap_or(ColorDepth::Truecolor)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    /// for terminals with a light background
+    Light,
+    HighContrast,
+}
+
+impl ThemeName {
+    pub fn next(self) -> Self {
+        match self {
+            ThemeName::Dark => ThemeName::Light,
+            ThemeName::Light => ThemeName::HighContrast,
+            ThemeName::HighContrast => ThemeName::Dark,
+        }
+    }
+}
+
+/// Every style the UI draws with.
+///
+/// Rows are styled in layers, from the lowest to the highest: the zebra
+/// `row`/`alt_row` base, then the cell's own style, then the selection
+/// (`selected_row`, `selected_column`, `selected_cell`, patched by the table
+/// after the cells). The selection styles always set both colors so they stay
+/// legible whatever the layers below them did.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: ThemeName,
+    pub background: Style,
+    pub header: Style,
+    pub row: Style,
+    pub alt_row: Style,
+    pub selected_row: Style,
+    pub selected_column: Style,
+    pub selected_cell: Style,
+    /// help, footer and popup content
+    pub text: Style,
+    pub border: Style,
+    /// getting close to a limit
+    pub warning: Style,
+    /// over a limit
+    pub alert: Style,
+    /// trend glyphs of the speed columns
+    pub rising: Style,
+    pub falling: Style,
+    /// address of a host by how recently it had traffic, idle ones keep the row's
+    pub active: Style,
+    pub silent: Style,
+    /// say in words what the colors say, e.g. `[OFF]` after a silent host
+    pub text_markers: bool,
+}
+
+impl Theme {
+    /// The theme `name` in the colors of `color_depth()`, without any the
+    /// high-contrast styles stand in whatever the name.
+    pub fn new(name: ThemeName) -> Self {
+        let depth = color_depth();
+        if depth == ColorDepth::None {
+            let mut theme = Self::high_contrast().map_styles(|style| Style {
+                fg: None,
+                bg: None,
+                ..style
+            });
+            theme.name = name;
+            return theme;
+        }
+        let theme = match name {
+            ThemeName::Dark => Self::from_palette(name, &tailwind::BLUE),
+            ThemeName::Light => Self::light(&tailwind::BLUE),
+            ThemeName::HighContrast => Self::high_contrast(),
+        };
+        theme.map_styles(|style| Style {
+            fg: style.fg.map(|color| quantize(color, depth)),
+            bg: style.bg.map(|color| quantize(color, depth)),
+            ..style
+        })
+    }
+
+    fn map_styles(self, f: impl Fn(Style) -> Style) -> Self {
+        Self {
+            name: self.name,
+            background: f(self.background),
+            header: f(self.header),
+            row: f(self.row),
+            alt_row: f(self.alt_row),
+            selected_row: f(self.selected_row),
+            selected_column: f(self.selected_column),
+            selected_cell: f(self.selected_cell),
+            text: f(self.text),
+            border: f(self.border),
+            warning: f(self.warning),
+            alert: f(self.alert),
+            rising: f(self.rising),
+            falling: f(self.falling),
+            active: f(self.active),
+            silent: f(self.silent),
+            text_markers: self.text_markers,
+        }
+    }
+
+    fn from_palette(name: ThemeName, color: &tailwind::Palette) -> Self {
+        let background = tailwind::SLATE.c950;
+        let foreground = tailwind::SLATE.c200;
+        Self {
+            name,
+            background: Style::new().bg(background),
+            header: Style::new().fg(foreground).bg(color.c900),
+            row: Style::new().fg(foreground).bg(background),
+            alt_row: Style::new().fg(foreground).bg(tailwind::SLATE.c900),
+            selected_row: Style::new().fg(background).bg(color.c400),
+            selected_column: Style::new().fg(color.c400),
+            selected_cell: Style::new().fg(background).bg(color.c600),
+            text: Style::new().fg(foreground).bg(background),
+            border: Style::new().fg(color.c400),
+            warning: Style::new().fg(tailwind::AMBER.c400),
+            alert: Style::new().fg(tailwind::RED.c500),
+            rising: Style::new().fg(tailwind::GREEN.c400),
+            falling: Style::new().fg(tailwind::RED.c400),
+            active: Style::new().fg(tailwind::EMERALD.c300),
+            silent: Style::new().fg(tailwind::SLATE.c500),
+            text_markers: false,
+        }
+    }
+
+    /// Dark text on pale rows, the accents are a shade darker than the dark
+    /// theme's so they keep their contrast on white.
+    fn light(color: &tailwind::Palette) -> Self {
+        let background = tailwind::SLATE.c50;
+        let foreground = tailwind::SLATE.c900;
+        Self {
+            name: ThemeName::Light,
+            background: Style::new().bg(background),
+            header: Style::new().fg(tailwind::SLATE.c50).bg(color.c700),
+            row: Style::new().fg(foreground).bg(background),
+            alt_row: Style::new().fg(foreground).bg(tailwind::SLATE.c200),
+            selected_row: Style::new().fg(tailwind::SLATE.c50).bg(color.c600),
+            selected_column: Style::new().fg(color.c700),
+            selected_cell: Style::new().fg(tailwind::SLATE.c50).bg(color.c800),
+            text: Style::new().fg(foreground).bg(background),
+            border: Style::new().fg(color.c700),
+            warning: Style::new().fg(tailwind::AMBER.c700),
+            alert: Style::new().fg(tailwind::RED.c700),
+            rising: Style::new().fg(tailwind::GREEN.c700),
+            falling: Style::new().fg(tailwind::RED.c700),
+            active: Style::new().fg(tailwind::EMERALD.c700),
+            silent: Style::new().fg(tailwind::SLATE.c400),
+            text_markers: false,
+        }
+    }
+
+    /// Pure black and white with bold emphasis, no shade carries meaning.
+    fn high_contrast() -> Self {
+        let normal = Style::new().fg(Color::White).bg(Color::Black);
+        let inverted = Style::new()
+            .fg(Color::Black)
+            .bg(Color::White)
+            .add_modifier(Modifier::BOLD);
+        Self {
+            name: ThemeName::HighContrast,
+            background: Style::new().bg(Color::Black),
+            header: inverted,
+            row: normal,
+            alt_row: normal,
+            selected_row: inverted,
+            selected_column: Style::new()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            selected_cell: normal.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            text: normal,
+            border: Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+            warning: Style::new().add_modifier(Modifier::BOLD),
+            alert: inverted,
+            // the glyph's shape already tells the direction
+            rising: Style::new().add_modifier(Modifier::BOLD),
+            falling: Style::new(),
+            active: Style::new().add_modifier(Modifier::BOLD),
+            silent: Style::new().add_modifier(Modifier::DIM),
+            // bold and dim alone don't tell every reader apart
+            text_markers: true,
+        }
+    }
+
+    /// `marker` when the theme spells out its states, else nothing.
+    pub fn marker(&self, marker: &'static str) -> &'static str {
+        if self.text_markers {
+            marker
+        } else {
+            ""
+        }
+    }
+
+    /// Base style of the `index`th row.
+    pub fn row_style(&self, index: usize) -> Style {
+        match index % 2 {
+            0 => self.row,
+            _ => self.alt_row,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(ThemeName::default())
+    }
+}
+
+/// levels of each channel in the 6x6x6 cube of the 256 colors
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The 16 ANSI colors, as xterm draws them.
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The nearest color `depth` can show, named and indexed ones are kept.
+pub fn quantize(color: Color, depth: ColorDepth) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    match depth {
+        ColorDepth::Truecolor | ColorDepth::None => color,
+        ColorDepth::Ansi256 => Color::Indexed(rgb_to_256((r, g, b))),
+        ColorDepth::Ansi16 => rgb_to_16((r, g, b)),
+    }
+}
+
+/// The nearest of the cube and the gray ramp of the 256 colors.
+pub fn rgb_to_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |value: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|i| CUBE_LEVELS[*i].abs_diff(value))
+            .unwrap_or_default()
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+    // 24 grays from 8 to 238, 10 apart
+    let average = (u16::from(rgb.0) + u16::from(rgb.1) + u16::from(rgb.2)) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + gray_step * 10;
+    if distance(rgb, (gray, gray, gray)) < distance(rgb, cube) {
+        232 + gray_step
+    } else {
+        16 + 36 * r as u8 + 6 * g as u8 + b as u8
+    }
+}
+
+pub fn rgb_to_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map_or(Color::White, |(color, _)| *color)
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |x: u8, y: u8| u32::from(x.abs_diff(y)).pow(2);
+    channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selections_set_both_colors_in_every_theme() {
+        for name in [ThemeName::Dark, ThemeName::Light, ThemeName::HighContrast] {
+            let theme = Theme::new(name);
+            for style in [theme.selected_row, theme.selected_cell] {
+                assert!(style.fg.is_some() && style.bg.is_some(), "{:?}", name);
+                assert_ne!(style.fg, style.bg, "{:?}", name);
+            }
+            // the cell stands out of its row
+            assert_ne!(theme.selected_cell, theme.selected_row, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn colors_of_the_256_map_back_to_their_index() {
+        for index in 16..232 {
+            let cube = index - 16;
+            let rgb = (
+                CUBE_LEVELS[usize::from(cube / 36)],
+                CUBE_LEVELS[usize::from(cube / 6 % 6)],
+                CUBE_LEVELS[usize::from(cube % 6)],
+            );
+            assert_eq!(rgb_to_256(rgb), index, "{:?}", rgb);
+        }
+        for step in 0..24 {
+            let gray = 8 + step * 10;
+            assert_eq!(rgb_to_256((gray, gray, gray)), 232 + step, "{}", gray);
+        }
+    }
+
+    #[test]
+    fn rgb_colors_go_to_the_nearest_of_the_256() {
+        assert_eq!(rgb_to_256((0, 0, 0)), 16);
+        assert_eq!(rgb_to_256((255, 255, 255)), 231);
+        assert_eq!(rgb_to_256((250, 5, 5)), 196);
+        assert_eq!(rgb_to_256((100, 140, 170)), 67);
+        // a gray between two cube levels is nearer the ramp
+        assert_eq!(rgb_to_256((130, 130, 130)), 244);
+        assert_eq!(rgb_to_256((240, 240, 240)), 255);
+    }
+
+    #[test]
+    fn rgb_colors_go_to_the_nearest_of_the_16() {
+        for (color, rgb) in ANSI_16 {
+            assert_eq!(rgb_to_16(rgb), color);
+        }
+        assert_eq!(rgb_to_16((190, 20, 10)), Color::Red);
+        assert_eq!(rgb_to_16((240, 30, 30)), Color::LightRed);
+        assert_eq!(rgb_to_16((120, 125, 130)), Color::DarkGray);
+        assert_eq!(rgb_to_16((80, 90, 250)), Color::LightBlue);
+        assert_eq!(rgb_to_16((10, 10, 20)), Color::Black);
+    }
+
+    #[test]
+    fn quantizing_keeps_named_and_indexed_colors() {
+        let rgb = Color::Rgb(100, 140, 170);
+        assert_eq!(quantize(rgb, ColorDepth::Truecolor), rgb);
+        assert_eq!(quantize(rgb, ColorDepth::Ansi256), Color::Indexed(67));
+        assert_eq!(quantize(rgb, ColorDepth::Ansi16), Color::DarkGray);
+        for depth in [ColorDepth::Ansi256, ColorDepth::Ansi16] {
+            assert_eq!(quantize(Color::Cyan, depth), Color::Cyan);
+            assert_eq!(quantize(Color::Indexed(42), depth), Color::Indexed(42));
+        }
+    }
+
+    #[test]
+    fn the_color_choice_sets_the_depth() {
+        assert_eq!(ColorChoice::Auto.depth(false), ColorDepth::None);
+        assert_eq!(ColorChoice::Never.depth(true), ColorDepth::None);
+        assert_eq!(ColorChoice::Ansi256.depth(false), ColorDepth::Ansi256);
+        assert_eq!(ColorChoice::Truecolor.depth(false), ColorDepth::Truecolor);
+        // whatever the terminal advertises
+        assert!(ColorChoice::Always.depth(false) >= ColorDepth::Ansi16);
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::Display;
+
+/// Rough kind of activity behind some traffic, guessed from its ports.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TrafficCategory {
+    Web,
+    Streaming,
+    Gaming,
+    FileTransfer,
+    Voip,
+    Other,
+}
+
+/// Well-known service ports, the first range containing a port wins.
+const PORT_CATEGORIES: &[(u16, u16, TrafficCategory)] = &[
+    (80, 80, TrafficCategory::Web),
+    (443, 443, TrafficCategory::Web),
+    (8080, 8080, TrafficCategory::Web),
+    (8443, 8443, TrafficCategory::Web),
+    // RTSP, RTMP, MMS, HLS/DASH dev servers
+    (554, 554, TrafficCategory::Streaming),
+    (1755, 1755, TrafficCategory::Streaming),
+    (1935, 1935, TrafficCategory::Streaming),
+    (8554, 8554, TrafficCategory::Streaming),
+    // SIP, STUN/TURN, Zoom, Google Meet
+    (5060, 5061, TrafficCategory::Voip),
+    (3478, 3479, TrafficCategory::Voip),
+    (8801, 8810, TrafficCategory::Voip),
+    (19302, 19309, TrafficCategory::Voip),
+    // Xbox Live, PSN, Battle.net, Steam, Minecraft
+    (3074, 3074, TrafficCategory::Gaming),
+    (3480, 3480, TrafficCategory::Gaming),
+    (3724, 3724, TrafficCategory::Gaming),
+    (6112, 6112, TrafficCategory::Gaming),
+    (25565, 25565, TrafficCategory::Gaming),
+    (27000, 27050, TrafficCategory::Gaming),
+    // FTP, TFTP, SMB, AFP, rsync, NFS, BitTorrent
+    (20, 21, TrafficCategory::FileTransfer),
+    (69, 69, TrafficCategory::FileTransfer),
+    (139, 139, TrafficCategory::FileTransfer),
+    (445, 445, TrafficCategory::FileTransfer),
+    (548, 548, TrafficCategory::FileTransfer),
+    (873, 873, TrafficCategory::FileTransfer),
+    (2049, 2049, TrafficCategory::FileTransfer),
+    (6881, 6889, TrafficCategory::FileTransfer),
+];
+
+impl TrafficCategory {
+    /// Category of a flow from its two ports, the well-known one decides.
+    pub fn from_ports(src_port: u16, dst_port: u16) -> Self {
+        Self::from_port(src_port)
+            .or_else(|| Self::from_port(dst_port))
+            .unwrap_or(TrafficCategory::Other)
+    }
+
+    fn from_port(port: u16) -> Option<Self> {
+        PORT_CATEGORIES
+            .iter()
+            .find(|(first, last, _)| (*first..=*last).contains(&port))
+            .map(|(_, _, category)| *category)
+    }
+}
+
+impl Display for TrafficCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TrafficCategory::Web => "web",
+            TrafficCategory::Streaming => "streaming",
+            TrafficCategory::Gaming => "gaming",
+            TrafficCategory::FileTransfer => "file transfer",
+            TrafficCategory::Voip => "VoIP",
+            TrafficCategory::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::app::{App, AppResult};
+use crate::picker::InterfacePicker;
 // use crate::event::EventHandler;
 use crate::ui;
 use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
@@ -53,6 +54,13 @@ impl<B: Backend> Tui<B> {
         Ok(())
     }
 
+    /// Draws the interface picker, before there is an app to draw.
+    pub fn draw_picker(&mut self, picker: &mut InterfacePicker) -> AppResult<()> {
+        self.terminal
+            .draw(|frame| ui::render_interface_picker(picker, frame))?;
+        Ok(())
+    }
+
     /// Resets the terminal interface.
     ///
     /// This function is also used for the panic hook to revert
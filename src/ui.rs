@@ -1,38 +1,728 @@
+use std::{
+    net::Ipv4Addr,
+    time::{Duration, Instant},
+};
+
+use pnet::util::MacAddr;
+
 use ratatui::prelude::*;
-use ratatui::style::palette::tailwind;
-use ratatui::widgets::{Block, BorderType, Paragraph};
+use ratatui::widgets::{Block, BorderType, Cell, Clear, Paragraph, Row, Table};
 use ratatui::Frame;
 
-use crate::app::App;
+use crate::activity::shown_hosts;
+use crate::app::{App, Host, Popup, View, STAT_TICK_SKEW_WARNING};
+use crate::budget::{BudgetLevel, DailyUsage};
+use crate::capture_stats::SATURATED;
+use crate::command;
+use crate::config::Config;
+use crate::connections_table::ConnectionsTable;
+use crate::destinations_table::DestinationsTable;
+use crate::format::{format_age, format_bytes, format_link_speed, format_pps};
+use crate::hints;
 use crate::hosts_table::HostsTable;
+use crate::interface_info::{utilization_str, InterfaceInfo};
+use crate::inventory::ChangeKind;
+use crate::logging::get_data_dir;
+use crate::mac_format::MacFormat;
+use crate::picker::InterfacePicker;
+use crate::settings::Setting;
+use crate::stats_aggregator::{
+    BreakdownBucket, FooterMetric, HostBreakdown, PeerTraffic, Speed, TotalScope,
+};
+use crate::strings;
+use crate::theme::Theme;
 
 /// Renders the user interface widgets.
 pub fn render(app: &mut App, frame: &mut Frame) {
+    let banner_height = if app.no_capture { 1 } else { 0 };
     let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![
+            Constraint::Length(banner_height),
             Constraint::Percentage(100),
             // Constraint::Percentage(50),
             Constraint::Length(3),
+            Constraint::Length(3),
         ]);
-    if let [table_area, 
+    if let [banner_area, table_area, 
     // middle_area,
-    footer_area] = *layout.split(frame.area()) {
-        render_hosts_table(frame, table_area, app);
+    hints_area, footer_area] = *layout.split(frame.area()) {
+        match app.view {
+            View::Hosts => render_hosts_table(frame, table_area, app),
+            View::Connections => render_connections_table(frame, table_area, app),
+            View::Destinations => render_destinations_table(frame, table_area, app),
+        }
+        render_hints(frame, hints_area, app);
         render_footer(frame, footer_area, app);
+        if app.no_capture {
+            let banner = Paragraph::new(strings::text("banner.no_capture"))
+                .style(app.theme.warning)
+                .centered();
+            frame.render_widget(banner, banner_area);
+        }
         // render_middle(frame, middle_area, app);
     }
+
+    match app.popup {
+        Some(Popup::InterfaceInfo) => render_interface_popup(frame, app),
+        Some(Popup::HostDetails(ip)) => render_host_popup(frame, app, ip),
+        Some(Popup::ConfirmQuit) => render_confirm_quit_popup(frame, app),
+        Some(Popup::ConfirmLargeScan) => render_confirm_large_scan_popup(frame, app),
+        Some(Popup::SwitchedNetwork) => render_switched_network_popup(frame, app),
+        Some(Popup::EditLabel(ip, mac)) => render_edit_label_popup(frame, app, ip, mac),
+        Some(Popup::Status) => render_status_popup(frame, app),
+        Some(Popup::CommandPalette) => render_command_palette(frame, app),
+        Some(Popup::CaptureStats) => render_capture_stats_popup(frame, app),
+        Some(Popup::Settings) => render_settings_popup(frame, app),
+        Some(Popup::CompareSnapshot) => render_compare_popup(frame, app),
+        Some(Popup::Help) => render_help_popup(frame, app),
+        Some(Popup::SweepReport) => render_sweep_report_popup(frame, app),
+        None => {}
+    }
+}
+
+/// The interfaces to pick from when netui started without `--name`.
+pub fn render_interface_picker(picker: &mut InterfacePicker, frame: &mut Frame) {
+    let theme = &picker.theme;
+    let [table_area, hints_area] =
+        *Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).split(frame.area())
+    else {
+        return;
+    };
+    let header = ["Interface", "Addresses", "MAC"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(theme.header);
+    let rows = picker
+        .rows()
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| Row::new(row).style(theme.row_style(i)));
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(12),
+            Constraint::Fill(1),
+            Constraint::Length(17),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(theme.selected_row)
+    .highlight_symbol(" ━ ")
+    .style(theme.background)
+    .block(
+        Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(theme.border)
+            .title(" Pick the interface to watch "),
+    );
+    frame.render_stateful_widget(table, table_area, &mut picker.table_state);
+    let hints = Paragraph::new(" (j/k) move | (Enter) watch | (Esc) quit ")
+        .style(theme.text)
+        .centered()
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Double)
+                .border_style(theme.border),
+        );
+    frame.render_widget(hints, hints_area);
+}
+
+/// The keys that matter where the user is, the popup's when one is open.
+fn render_hints(frame: &mut Frame, area: Rect, app: &App) {
+    let width = area.width.saturating_sub(2) as usize;
+    let line = Paragraph::new(hints::line(app.hints(), width))
+        .style(app.theme.text)
+        .centered()
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Double)
+                .border_style(app.theme.border),
+        );
+    frame.render_widget(line, area);
+}
+
+/// Every key of the view, the hint line only has room for a few.
+fn render_help_popup(frame: &mut Frame, app: &App) {
+    let keys = match app.view {
+        View::Hosts if app.passive || app.no_capture => {
+            ["help.hosts.nav", "help.hosts.passive_actions"]
+        }
+        View::Hosts => ["help.hosts.nav", "help.hosts.actions"],
+        View::Connections => ["help.connections.nav", "help.connections.actions"],
+        View::Destinations => ["help.destinations.nav", "help.destinations.actions"],
+    };
+    let lines: Vec<Line> = keys
+        .into_iter()
+        .flat_map(|key| strings::text(key).split(" | "))
+        .map(|binding| Line::from(format!("  {}", binding)))
+        .collect();
+    render_popup(frame, &app.theme, "Keys", " (Esc) close ", lines, 60);
 }
 
-fn _render_middle(frame: &mut Frame<'_>, middle_area: Rect, app: &mut App) {
-    let items = app.stats_aggregator.connections_strs();
-    // frame.render_widget(Text::from(items.len().to_string()), middle_area);
-    let paragraph = Paragraph::new(Text::from_iter(items)).block(Block::new().title("connections"));
-    frame.render_widget(paragraph, middle_area);
+fn render_confirm_quit_popup(frame: &mut Frame, app: &App) {
+    let mut lines: Vec<Line> = app
+        .operations
+        .iter()
+        .map(|operation| Line::from(format!("{},", operation.description)))
+        .collect();
+    lines.push(Line::from("quit anyway?").bold());
+    render_popup(
+        frame,
+        &app.theme,
+        "Quit",
+        " (y) quit | (n) cancel ",
+        lines,
+        60,
+    );
+}
+
+fn render_confirm_large_scan_popup(frame: &mut Frame, app: &App) {
+    let plan = app.network_plan();
+    let mut lines: Vec<Line> = plan
+        .large()
+        .map(|network| Line::from(format!("{} has {} addresses,", network, network.size())))
+        .collect();
+    lines.push(
+        Line::from(format!(
+            "the sweep sends {} ARP requests, sweep anyway?",
+            plan.addresses()
+        ))
+        .bold(),
+    );
+    render_popup(
+        frame,
+        &app.theme,
+        "Large Sweep",
+        " (y) sweep | (n) cancel ",
+        lines,
+        60,
+    );
+}
+
+fn render_edit_label_popup(frame: &mut Frame, app: &App, ip: Ipv4Addr, mac: MacAddr) {
+    let lines = vec![
+        Line::from(format!("{} ({})", ip, app.mac_format.format(mac))),
+        Line::from(""),
+        Line::from(vec![
+            Span::raw("Label: "),
+            Span::raw(app.label_input.clone()).bold(),
+            Span::raw("█"),
+        ]),
+    ];
+    render_popup(
+        frame,
+        &app.theme,
+        "Label",
+        " (Enter) save | (Esc) cancel | empty removes the label ",
+        lines,
+        60,
+    );
+}
+
+fn render_command_palette(frame: &mut Frame, app: &App) {
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw(":"),
+            Span::raw(app.command_input.clone()).bold(),
+            Span::raw("█"),
+        ]),
+        Line::from(""),
+    ];
+    let suggestions = command::suggestions(&app.command_input);
+    if suggestions.is_empty() {
+        lines.push(Line::from("no matching command").italic());
+    }
+    for (name, help) in suggestions {
+        lines.push(Line::from(format!("{:<11}{}", name, help)));
+    }
+    render_popup(
+        frame,
+        &app.theme,
+        "Command",
+        " (Enter) run | (Tab) complete | (Esc) cancel ",
+        lines,
+        60,
+    );
+}
+
+fn render_settings_popup(frame: &mut Frame, app: &App) {
+    let mut lines = vec![];
+    for (i, setting) in Setting::ALL.into_iter().enumerate() {
+        let selected = i == app.settings_selected;
+        let value = match &app.settings_input {
+            Some(input) if selected => {
+                Line::from(vec![Span::raw(input.clone()).bold(), Span::raw("█")])
+            }
+            _ => Line::from(setting.value(app)),
+        };
+        let marker = if selected { "▶ " } else { "  " };
+        let mut line = Line::from(format!("{}{:<27}", marker, setting.label()));
+        line.extend(value.spans);
+        lines.push(line);
+    }
+    if let Some(error) = &app.settings_error {
+        lines.push(Line::from(""));
+        lines.push(
+            Line::from(format!("{}{}", app.theme.marker("[ERROR] "), error))
+                .style(app.theme.falling),
+        );
+    }
+    let hint = if app.settings_input.is_some() {
+        " (Enter) apply | (Esc) discard "
+    } else {
+        " (j/k) move | (Enter) edit | (Esc) close "
+    };
+    render_popup(frame, &app.theme, "Settings", hint, lines, 60);
+}
+
+/// The hosts the last sweep found for the first time, then the known ones
+/// that stayed silent, with what is known about them.
+fn render_sweep_report_popup(frame: &mut Frame, app: &App) {
+    let Some(report) = &app.sweep_report else {
+        return;
+    };
+    let probed = report
+        .probed
+        .map_or_else(|| "…".to_string(), |probed| probed.to_string());
+    let mut lines = vec![
+        Line::from(format!(
+            "  Probed: {}   Replied: {}",
+            probed,
+            report.replied()
+        )),
+        Line::from(""),
+    ];
+    let mut row = 0;
+    for (title, hosts) in [("New", report.first_seen()), ("Silent", report.silent())] {
+        lines.push(Line::from(format!("{} ({})", title, hosts.len())).bold());
+        for (ip, mac) in hosts {
+            let host = app.hosts.iter().find(|h| h.ipv4 == ip && h.mac == mac);
+            let marker = if row == app.sweep_report_selected {
+                "▶ "
+            } else {
+                "  "
+            };
+            lines.push(Line::from(format!(
+                "{}{:<16}{:<19}{:<21}{}",
+                marker,
+                ip,
+                app.mac_format.format(mac),
+                host.and_then(Host::vendor).unwrap_or(""),
+                host.and_then(|h| h.hostname.as_deref()).unwrap_or("")
+            )));
+            row += 1;
+        }
+        lines.push(Line::from(""));
+    }
+    lines.pop();
+    render_popup(
+        frame,
+        &app.theme,
+        &format!(
+            "Sweep of {}/{}",
+            report.network.network(),
+            report.network.prefix()
+        ),
+        " (j/k) move | (Enter) go to | (Esc) close ",
+        lines,
+        90,
+    );
+}
+
+fn render_switched_network_popup(frame: &mut Frame, app: &App) {
+    let lines = vec![
+        Line::from("Hosts answer ARP but only this device's traffic is captured."),
+        Line::from(""),
+        Line::from("A switch only forwards to this port the traffic addressed to it"),
+        Line::from("(and broadcasts), so other hosts' bandwidth can't be seen here."),
+        Line::from(""),
+        Line::from("To watch the whole network, run netui on a switch mirror (SPAN)"),
+        Line::from("port, or on the router itself."),
+        Line::from(""),
+        Line::from("Pass --no-switch-hint to never show this again.").italic(),
+    ];
+    render_popup(
+        frame,
+        &app.theme,
+        "Switched network",
+        " (Esc) close ",
+        lines,
+        70,
+    );
+}
+
+fn render_host_popup(frame: &mut Frame, app: &App, ip: Ipv4Addr) {
+    let lines = match app.hosts.iter().find(|h| h.ipv4 == ip) {
+        Some(host) => {
+            let mut lines = host_details_lines(
+                host,
+                app.time_reference(),
+                &app.stats_aggregator.host_breakdown(ip),
+                app.mac_format,
+            );
+            lines.extend(top_peers_lines(
+                &app.stats_aggregator.top_peers(ip, TOP_PEERS),
+                &app.hosts,
+            ));
+            lines.extend(daily_usage_lines(host, &app.daily_usage, &app.theme));
+            let history = app.state.audit().describe(host.ipv4, host.mac);
+            lines.extend(history.into_iter().take(MAX_HISTORY_LINES).enumerate().map(
+                |(i, line)| {
+                    let label = if i == 0 { "History:" } else { "" };
+                    Line::from(format!("{:<13}{}", label, line))
+                },
+            ));
+            lines
+        }
+        None => vec![Line::from(format!("{} is no longer in the list", ip))],
+    };
+    render_popup(frame, &app.theme, "Host", " (Esc) close ", lines, 60);
+}
+
+fn host_details_lines(
+    host: &Host,
+    now: Instant,
+    breakdown: &HostBreakdown,
+    mac_format: MacFormat,
+) -> Vec<Line<'static>> {
+    let speed_line = |label: &str, speed: Option<Speed>| {
+        Line::from(format!(
+            "{:<13}{}",
+            label,
+            speed.map_or_else(
+                || strings::text("empty.no_traffic").to_string(),
+                |s| s.to_string()
+            )
+        ))
+    };
+    let mut lines = vec![
+        Line::from(format!("{:<13}{}", "IP:", host.ipv4)),
+        Line::from(format!("{:<13}{}", "MAC:", mac_format.format(host.mac))),
+        Line::from(format!(
+            "{:<13}{}",
+            "Vendor:",
+            host.vendor().unwrap_or("unknown")
+        )),
+        Line::from(format!(
+            "{:<13}{}",
+            "Label:",
+            host.label.as_deref().unwrap_or("none, (e) to set")
+        )),
+        Line::from(format!(
+            "{:<13}{}",
+            "Last seen:",
+            host.time.format("%Y-%m-%d %H:%M:%S")
+        )),
+        speed_line("Speed:", host.speed),
+        speed_line("Peak speed:", host.peak_speed),
+        Line::from(format!(
+            "{:<13}{}",
+            "Last traffic:",
+            host.last_active.map_or_else(
+                || strings::text("empty.no_traffic").to_string(),
+                |at| format!("{} ago", format_age(now.saturating_duration_since(at)))
+            )
+        )),
+        Line::from(format!(
+            "{:<13}{}",
+            "Activity:",
+            host.category.map_or_else(
+                || strings::text("empty.no_traffic").to_string(),
+                |c| format!("mostly {}", c)
+            )
+        )),
+        Line::from(format!(
+            "{:<13}{}",
+            "Service:",
+            host.service.map_or_else(
+                || strings::text("empty.no_traffic").to_string(),
+                |s| s.to_string()
+            )
+        )),
+    ];
+    let bucket_line = |label: &str, bucket: &BreakdownBucket| {
+        Line::from(format!(
+            "  {:<11}{} ({} total)",
+            label,
+            bucket.speed,
+            format_bytes(bucket.bytes)
+        ))
+    };
+    lines.push(Line::from("Traffic with:"));
+    lines.push(bucket_line("internet", &breakdown.internet));
+    lines.push(bucket_line("LAN", &breakdown.local));
+    lines.push(bucket_line("broadcast", &breakdown.broadcast));
+    if let Some(hostname) = &host.hostname {
+        lines.insert(2, Line::from(format!("{:<13}{}", "Hostname:", hostname)));
+    }
+    lines.push(Line::from(format!(
+        "{:<13}{}",
+        "ARP RTT:",
+        host.arp_rtt.map_or_else(
+            || "not measured, (s) to sweep".to_string(),
+            |rtt| format!("{:.2} ms", rtt.as_secs_f64() * 1000.0)
+        )
+    )));
+    lines.push(Line::from(format!(
+        "{:<13}{} requests / {} replies ({} requests last minute)",
+        "ARP:", host.arp.requests, host.arp.replies, host.arp.requests_per_window
+    )));
+    if host.arp.flagged && !host.is_my_device_mac {
+        lines.push(Line::from("sends many ARP requests, it may be scanning the network").bold());
+    }
+    if host.is_my_device_mac {
+        lines.push(Line::from("this device").italic());
+    }
+    lines
+}
+
+/// peers listed in the host popup
+const TOP_PEERS: usize = 5;
+/// past addresses and claims listed in the host popup, latest first
+const MAX_HISTORY_LINES: usize = 5;
+
+/// Known hosts are named by their label or hostname.
+fn top_peers_lines(peers: &[PeerTraffic], hosts: &[Host]) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from("Top peers:")];
+    if peers.is_empty() {
+        lines.push(Line::from("  none yet"));
+    }
+    for peer in peers {
+        let name = hosts
+            .iter()
+            .find(|h| h.ipv4 == peer.peer)
+            .and_then(|h| h.label.as_ref().or(h.hostname.as_ref()))
+            .map_or_else(String::new, |name| format!(" {}", name));
+        lines.push(Line::from(format!(
+            "  {:<15}{} ({} total){}",
+            peer.peer,
+            peer.speed,
+            format_bytes(peer.bytes),
+            name
+        )));
+    }
+    lines
+}
+
+const BUDGET_BAR_WIDTH: usize = 20;
+
+fn daily_usage_lines(host: &Host, usage: &DailyUsage, theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(format!(
+        "{:<13}{}",
+        "Today:",
+        format_bytes(usage.used(&host.mac))
+    ))];
+    if let Some(budget) = usage.budget_usage(&host.mac) {
+        let filled = ((budget.ratio() * BUDGET_BAR_WIDTH as f64) as usize).min(BUDGET_BAR_WIDTH);
+        let (style, marker) = match budget.level() {
+            BudgetLevel::Normal => (theme.text, ""),
+            BudgetLevel::Warning => (theme.warning, theme.marker(" [WARNING]")),
+            BudgetLevel::Exceeded => (theme.alert, theme.marker(" [ALERT]")),
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!("{:<13}", "Budget:")),
+            Span::styled(
+                format!(
+                    "[{}{}]",
+                    "█".repeat(filled),
+                    "░".repeat(BUDGET_BAR_WIDTH - filled)
+                ),
+                style,
+            ),
+            Span::raw(format!(
+                " {:.0}% of {}",
+                budget.ratio() * 100.0,
+                format_bytes(budget.budget)
+            )),
+            Span::styled(marker, style),
+        ]));
+    }
+    lines
+}
+
+fn render_interface_popup(frame: &mut Frame, app: &App) {
+    let lines = match &app.interface_info {
+        Some(info) => interface_info_lines(info, app.mac_format),
+        None => vec![Line::from(strings::text("empty.interface"))],
+    };
+    render_popup(
+        frame,
+        &app.theme,
+        "Interface",
+        " (y) copy IPv4 | (Esc) close ",
+        lines,
+        70,
+    );
+}
+
+fn interface_info_lines(info: &InterfaceInfo, mac_format: MacFormat) -> Vec<Line<'static>> {
+    let na = || "n/a".to_string();
+    let mut lines = vec![
+        Line::from(format!("Name:        {} (index {})", info.name, info.index)),
+        Line::from(format!(
+            "MAC:         {}",
+            info.mac.map_or_else(na, |m| mac_format.format(m))
+        )),
+        Line::from(format!(
+            "MTU:         {}",
+            info.mtu.map_or_else(na, |m| m.to_string())
+        )),
+        Line::from(format!(
+            "Link speed:  {}",
+            info.link_speed.map_or_else(na, format_link_speed)
+        )),
+        Line::from(format!("Flags:       {}", info.flags.join(", "))),
+        Line::from(format!(
+            "Gateway:     {}",
+            info.gateway.map_or_else(na, |g| g.to_string())
+        )),
+    ];
+    if !info.description.is_empty() {
+        lines.insert(1, Line::from(format!("Description: {}", info.description)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from("Networks:").bold());
+    if info.networks.is_empty() {
+        lines.push(Line::from("  none"));
+    }
+    for network in &info.networks {
+        let mut line = format!("  {}", network.network);
+        if let Some(broadcast) = network.broadcast {
+            line += &format!("  broadcast {}", broadcast);
+        }
+        if let Some(scannable) = network.scannable {
+            line += &format!("  {} scannable", scannable);
+        }
+        lines.push(Line::from(line));
+    }
+    lines
+}
+
+/// Draws `lines` in a bordered box centered over the rest of the UI.
+fn render_popup(
+    frame: &mut Frame,
+    theme: &Theme,
+    title: &str,
+    hint: &str,
+    lines: Vec<Line>,
+    width: u16,
+) {
+    let area = popup_area(frame.area(), width, lines.len() as u16 + 2);
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).style(theme.text).block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .border_style(theme.border)
+                .title(title)
+                .title_bottom(Line::from(hint).centered()),
+        ),
+        area,
+    );
+}
+
+/// The hosts against the last snapshot, a row per host that changed.
+fn render_compare_popup(frame: &mut Frame, app: &App) {
+    let Some((name, changes)) = &app.snapshot_diff else {
+        return;
+    };
+    let area = popup_area(frame.area(), 110, changes.len().max(1) as u16 + 3);
+    let block = Block::bordered()
+        .border_type(BorderType::Rounded)
+        .border_style(app.theme.border)
+        .title(format!("Since {}", name))
+        .title_bottom(Line::from(" (Esc) close ").centered());
+    frame.render_widget(Clear, area);
+    if changes.is_empty() {
+        let paragraph = Paragraph::new("no change")
+            .style(app.theme.text)
+            .block(block);
+        frame.render_widget(paragraph, area);
+        return;
+    }
+    let mac = |mac: &String| {
+        mac.parse::<MacAddr>()
+            .map_or(mac.clone(), |mac| app.mac_format.format(mac))
+    };
+    let rows = changes.iter().map(|change| {
+        let style = match change.kind {
+            ChangeKind::Added => app.theme.rising,
+            ChangeKind::Removed => app.theme.alert,
+            ChangeKind::Changed { .. } => app.theme.warning,
+        };
+        [
+            change.kind.label(),
+            change.describe(|host| host.ip.to_string()),
+            change.describe(|host| mac(&host.mac)),
+            change.describe(|host| host.name().unwrap_or("").to_string()),
+        ]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(style)
+    });
+    let widths = [
+        Constraint::Length(14),
+        Constraint::Length(32),
+        Constraint::Length(38),
+        Constraint::Min(10),
+    ];
+    let header = ["Change", "IP", "MAC", "Name"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(app.theme.header);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .style(app.theme.background)
+        .block(block);
+    frame.render_widget(table, area);
+}
+
+/// A rectangle centered in `area`, clamped to fit in it.
+fn popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn render_connections_table(frame: &mut Frame<'_>, area: Rect, app: &mut App) {
+    let connections = app.stats_aggregator.connections(app.connections_sort);
+    ConnectionsTable::new(&connections, &app.theme)
+        .sort(app.connections_sort)
+        .draw(&mut app.connections_state, frame, area);
+}
+
+fn render_destinations_table(frame: &mut Frame<'_>, area: Rect, app: &mut App) {
+    let destinations = app.stats_aggregator.destinations(app.destination_mask);
+    DestinationsTable::new(&destinations, &app.theme)
+        .mask(app.destination_mask)
+        .draw(&mut app.destinations_state, frame, area);
 }
 
 fn render_hosts_table(frame: &mut Frame<'_>, area: Rect, app: &mut App) {
-    let mut hosts_table = HostsTable::new(&app.hosts);
+    let gateway = app.interface_info.as_ref().and_then(|info| info.gateway);
+    let now = app.time_reference();
+    let mut hosts_table = HostsTable::new(
+        shown_hosts(&app.hosts, app.activity_filter, now),
+        &app.theme,
+        now,
+        app.frozen_time.is_some(),
+    )
+    .activity_filter(app.activity_filter)
+    .gateway(gateway, app.stats_aggregator.wan_speed())
+    .mac_format(app.mac_format)
+    .speed_source(app.speed_source)
+    .combined_speed(app.combined_speed)
+    .column_widths(app.state.column_widths());
+    app.hosts_column_layout = hosts_table.laid_out(area.width);
     hosts_table.draw(&mut app.table_state, &mut app.scroll_state, frame, area);
 }
 
@@ -41,34 +731,338 @@ fn render_footer(frame: &mut Frame, area: Rect, app: &App) {
         .direction(Direction::Horizontal)
         .constraints(vec![
             Constraint::Fill(1),
+            Constraint::Fill(2),
             Constraint::Fill(1),
-            Constraint::Fill(1),
-            Constraint::Fill(5),
+            Constraint::Fill(3),
+            Constraint::Fill(4),
         ])
         .split(area);
-    let state = if app.sending_arps {
-        "Sending ARPs"
-    } else {
-        "Idle"
-    };
-    render_widget(frame, "State", state, layout[0]);
+    let theme = &app.theme;
+    render_widget(
+        frame,
+        theme,
+        strings::text("footer.state"),
+        &scan_state_str(app),
+        layout[0],
+    );
+    let (active, present, seen) = app.host_counts();
     render_widget(
         frame,
-        "Number of hosts",
-        app.hosts.len().to_string().as_str(),
+        theme,
+        strings::text("footer.hosts"),
+        &format!("{} / {} / {}", active, present, seen),
         layout[1],
     );
-    render_widget(frame, "Interface", &app.interface, layout[2]);
-    render_widget(frame, "Speed", &app.stats_aggregator.speed_str(), layout[3]);
+    let mut interface = app.interface.clone();
+    if let Some(vlan) = app.vlan {
+        interface.push_str(&format!(", vlan {}", vlan));
+    }
+    if let Some(ip) = app.solo {
+        interface.push_str(&format!(", solo: {}", ip));
+    }
+    render_widget(
+        frame,
+        theme,
+        strings::text("footer.interface"),
+        &interface,
+        layout[2],
+    );
+    render_widget(
+        frame,
+        theme,
+        &strings::format("footer.speed", &[("metric", &app.footer_metric.label())]),
+        &footer_metric_str(app, app.footer_metric),
+        layout[3],
+    );
+    render_widget(
+        frame,
+        theme,
+        strings::text("footer.averages"),
+        &load_averages_str(app),
+        layout[4],
+    );
+}
+
+/// A failed task, else the notice if there is one, else what the scanner is doing.
+fn scan_state_str(app: &App) -> String {
+    // a dead task outranks everything, the numbers around it are stale
+    if let Some(failure) = app.failed_tasks.first() {
+        return failure.clone();
+    }
+    let state = if let Some(notice) = app.notice() {
+        notice
+    } else if app.no_capture {
+        strings::text("state.no_capture")
+    } else if app.passive {
+        strings::text("state.passive")
+    } else if app.monitor_only {
+        strings::text("state.monitor_only")
+    } else if app.sending_arps {
+        strings::text("state.sending_arps")
+    } else {
+        strings::text("state.idle")
+    };
+    let state = match app.last_sweep {
+        Some(sweep) if sweep.failed > 0 && !app.sending_arps => strings::format(
+            "state.failed_sends",
+            &[("state", &state), ("failed", &sweep.failed)],
+        ),
+        _ => state.to_string(),
+    };
+    match app.capture_stats.sampling_factor {
+        factor if factor > 1 => {
+            strings::format("state.sampling", &[("state", &state), ("factor", &factor)])
+        }
+        _ => state,
+    }
+}
+
+fn total_str(app: &App, scope: TotalScope) -> String {
+    let Some(speed) = app.stats_aggregator.total(scope) else {
+        return String::new();
+    };
+    match app.link_speed() {
+        Some(link_speed) => format!(
+            "{} ({})",
+            speed,
+            utilization_str(speed.input().max(speed.output()), link_speed)
+        ),
+        None => speed.to_string(),
+    }
+}
+
+fn footer_metric_str(app: &App, metric: FooterMetric) -> String {
+    let aggregator = &app.stats_aggregator;
+    match metric {
+        FooterMetric::Total(scope) => total_str(app, scope),
+        FooterMetric::Peak => aggregator
+            .session_peak()
+            .map_or_else(String::new, |speed| speed.to_string()),
+        FooterMetric::Average => aggregator
+            .session_average()
+            .map_or_else(String::new, |speed| speed.to_string()),
+        FooterMetric::Utilization => match (aggregator.wan_speed(), app.link_speed()) {
+            (Some(speed), Some(link_speed)) => {
+                utilization_str(speed.input().max(speed.output()), link_speed)
+            }
+            (_, None) => "link speed unknown".to_string(),
+            (None, _) => String::new(),
+        },
+    }
+}
+
+/// The footer's boxes in full, plus what doesn't fit in them.
+fn render_status_popup(frame: &mut Frame, app: &App) {
+    let section = |title: &str| Line::from(title.to_string()).bold();
+    let field = |label: &str, value: String| Line::from(format!("  {:<18}{}", label, value));
+    let mut lines = vec![section("Interface")];
+    match &app.interface_info {
+        Some(info) => lines.extend(
+            interface_info_lines(info, app.mac_format)
+                .into_iter()
+                .map(|line| Line::from(format!("  {}", line))),
+        ),
+        None => lines.push(Line::from(format!(
+            "  {}",
+            strings::text("empty.not_ready")
+        ))),
+    }
+
+    lines.push(Line::from(""));
+    lines.push(section("Scan"));
+    lines.push(field("State:", scan_state_str(app)));
+    lines.push(field("Hosts:", app.hosts.len().to_string()));
+    lines.push(field(
+        "Solo:",
+        app.solo.map_or_else(
+            || "off, every host is accounted".to_string(),
+            |ip| ip.to_string(),
+        ),
+    ));
+    lines.push(field(
+        "Last sweep:",
+        app.last_sweep.map_or_else(
+            || "none yet".to_string(),
+            |sweep| format!("{} sent, {} failed", sweep.sent, sweep.failed),
+        ),
+    ));
+
+    lines.push(Line::from(""));
+    lines.push(section("Traffic"));
+    for scope in [
+        TotalScope::External,
+        TotalScope::WithLan,
+        TotalScope::Everything,
+    ] {
+        lines.push(field(
+            &format!("Total {}:", scope.label()),
+            total_str(app, scope),
+        ));
+    }
+    lines.push(field("Avg 1m/5m/15m:", load_averages_str(app)));
+
+    lines.push(Line::from(""));
+    lines.push(section("Integrations"));
+    lines.push(field(
+        "Web dashboard:",
+        app.web_address
+            .map_or_else(|| "off".to_string(), |address| address.to_string()),
+    ));
+
+    lines.push(Line::from(""));
+    lines.push(section("Files"));
+    lines.push(field("Config:", Config::path().display().to_string()));
+    lines.push(field(
+        "State:",
+        match (app.state.saved_to(), &app.state_error) {
+            (Some(path), _) => path.display().to_string(),
+            (None, Some(reason)) => format!("not saved, {}", reason),
+            (None, None) => "not saved".to_string(),
+        },
+    ));
+    lines.push(field("Data:", get_data_dir().display().to_string()));
+    lines.push(field(
+        "Log:",
+        match (&app.log_file, &app.logging_error) {
+            (Some(path), _) => path.display().to_string(),
+            (None, Some(reason)) => format!("off, {}", reason),
+            (None, None) => "off".to_string(),
+        },
+    ));
+    if let Some(path) = &app.export_nmap_path {
+        lines.push(field("nmap export:", path.display().to_string()));
+    }
+    render_popup(frame, &app.theme, "Status", " (Esc) close ", lines, 90);
+}
+
+/// netui's own processing, not the network's traffic.
+fn render_capture_stats_popup(frame: &mut Frame, app: &App) {
+    let stats = &app.capture_stats;
+    let field = |label: &str, value: String| Line::from(format!("  {:<18}{}", label, value));
+    let mut lines = vec![
+        field("Uptime:", format_age(stats.uptime)),
+        field("Frames:", stats.frames.to_string()),
+        field("Frames per sec:", format_pps(app.capture_frames_per_sec)),
+        field("Bytes:", format_bytes(u128::from(stats.bytes))),
+        field("ARP:", stats.arp.to_string()),
+        field("IPv4 accounted:", stats.accounted.to_string()),
+        field("  TCP unparsed:", stats.tcp_unparsed.to_string()),
+        field("  UDP unparsed:", stats.udp_unparsed.to_string()),
+        if stats.truncated > 0 {
+            let mut truncated =
+                field("  Truncated:", stats.truncated.to_string()).style(app.theme.warning);
+            truncated.push_span(app.theme.marker(" [WARNING] raise --capture-buffer"));
+            truncated
+        } else {
+            field("  Truncated:", "0".to_string())
+        },
+        Line::from(""),
+        Line::from("Ignored").bold(),
+        field("Malformed:", stats.malformed.to_string()),
+        field("Not ARP or IPv4:", stats.other_ether_type.to_string()),
+        field("Not TCP or UDP:", stats.other_protocol.to_string()),
+        field("Outside solo:", stats.solo.to_string()),
+        field("Other VLAN:", stats.other_vlan.to_string()),
+        field("Read errors:", stats.read_errors.to_string()),
+        Line::from(""),
+        Line::from("Health").bold(),
+        {
+            let busy = field("Busy:", format!("{:.0}%", stats.busy * 100.0));
+            if stats.busy > SATURATED {
+                let mut busy = busy.style(app.theme.warning);
+                busy.push_span(app.theme.marker(" [WARNING]"));
+                busy
+            } else {
+                busy
+            }
+        },
+        if stats.sampling_factor > 1 {
+            let mut sampling = field(
+                "Sampling:",
+                format!("1 IPv4 frame in {}, scaled", stats.sampling_factor),
+            )
+            .style(app.theme.warning);
+            sampling.push_span(app.theme.marker(" [WARNING]"));
+            sampling
+        } else {
+            field("Sampling:", "off".to_string())
+        },
+        field("Sampled out:", stats.sampled_out.to_string()),
+        Line::from(""),
+        Line::from("Stat ticks").bold(),
+        {
+            let skew = field(
+                "Handling delay:",
+                format!(
+                    "{} ms (max {} ms)",
+                    app.stat_tick_skew.as_millis(),
+                    app.max_stat_tick_skew.as_millis()
+                ),
+            );
+            if app.stat_tick_skew > STAT_TICK_SKEW_WARNING {
+                let mut skew = skew.style(app.theme.warning);
+                skew.push_span(app.theme.marker(" [WARNING]"));
+                skew
+            } else {
+                skew
+            }
+        },
+        Line::from(""),
+    ];
+    if let Some(resolver) = &app.resolver {
+        let metrics = resolver.metrics();
+        lines.extend([
+            Line::from("Names").bold(),
+            field("Queued:", metrics.queued.to_string()),
+            field("In flight:", metrics.in_flight.to_string()),
+            field("Resolved:", metrics.completed.to_string()),
+            field("Not found:", metrics.failed.to_string()),
+            field("Dropped:", metrics.dropped.to_string()),
+            Line::from(""),
+        ]);
+    }
+    lines.push(Line::from("Memory").bold());
+    lines.extend(app.collection_sizes().into_iter().map(|size| {
+        field(
+            &format!("{}:", size.name),
+            format!("{} / {}", size.len, size.limit),
+        )
+    }));
+    render_popup(frame, &app.theme, "Capture", " (Esc) close ", lines, 50);
+}
+
+const LOAD_AVERAGE_WINDOWS: [Duration; 3] = [
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(15 * 60),
+];
+
+/// `↓ a / b / c | ↑ a / b / c`, windows the history doesn't cover yet show "–".
+fn load_averages_str(app: &App) -> String {
+    let averages = app
+        .stats_aggregator
+        .history()
+        .averages(&LOAD_AVERAGE_WINDOWS);
+    let join = |direction: fn(&Speed) -> String| {
+        averages
+            .iter()
+            .map(|average| average.as_ref().map_or("–".to_string(), direction))
+            .collect::<Vec<_>>()
+            .join(" / ")
+    };
+    format!(
+        "↓ {} | ↑ {}",
+        join(Speed::to_string_input),
+        join(Speed::to_string_output)
+    )
 }
 
-fn render_widget(frame: &mut Frame, title: &str, content: &str, area: Rect) {
-    let style = Style::new().fg(tailwind::BLUE.c400);
+fn render_widget(frame: &mut Frame, theme: &Theme, title: &str, content: &str, area: Rect) {
     frame.render_widget(
-        Paragraph::new(content).centered().block(
+        Paragraph::new(content).style(theme.text).centered().block(
             Block::bordered()
                 .border_type(BorderType::Rounded)
-                .border_style(style)
+                .border_style(theme.border)
                 .title(title),
         ),
         area,
@@ -0,0 +1,132 @@
+use serde::Deserialize;
+
+/// Which changes of a watched host are announced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchFor {
+    /// the host starts sending
+    Start,
+    /// the host goes quiet
+    Stop,
+    #[default]
+    Both,
+}
+
+impl WatchFor {
+    pub fn announces(self, transition: Transition) -> bool {
+        matches!(
+            (self, transition),
+            (WatchFor::Both, _)
+                | (WatchFor::Start, Transition::Started)
+                | (WatchFor::Stop, Transition::Stopped)
+        )
+    }
+}
+
+/// How watched hosts are announced, the `[watch]` table of `config.toml`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchOptions {
+    pub on: WatchFor,
+    /// also ring the terminal bell
+    pub bell: bool,
+    /// stat ticks in a row a host must be sending, or quiet, before it counts
+    pub debounce: u32,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            on: WatchFor::Both,
+            bell: true,
+            debounce: 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    Started,
+    Stopped,
+}
+
+/// A host the user waits on, e.g. a server coming back from a reboot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Watch {
+    /// as last announced
+    sending: bool,
+    /// stat ticks in a row the host did otherwise
+    streak: u32,
+}
+
+impl Watch {
+    pub fn new(sending: bool) -> Self {
+        Self { sending, streak: 0 }
+    }
+
+    /// Feeds whether the host had traffic during the last stat tick, a
+    /// change counts once it lasted `debounce` ticks: a stray packet or a
+    /// short pause isn't one.
+    pub fn observe(&mut self, sending: bool, debounce: u32) -> Option<Transition> {
+        if sending == self.sending {
+            self.streak = 0;
+            return None;
+        }
+        self.streak += 1;
+        if self.streak < debounce.max(1) {
+            return None;
+        }
+        self.sending = sending;
+        self.streak = 0;
+        Some(if sending {
+            Transition::Started
+        } else {
+            Transition::Stopped
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(watch: &mut Watch, ticks: &[bool], debounce: u32) -> Vec<Option<Transition>> {
+        ticks
+            .iter()
+            .map(|sending| watch.observe(*sending, debounce))
+            .collect()
+    }
+
+    #[test]
+    fn a_host_starts_once_it_sent_for_debounce_ticks() {
+        let mut watch = Watch::new(false);
+        let transitions = feed(&mut watch, &[true, true, true, true], 3);
+        assert_eq!(transitions, [None, None, Some(Transition::Started), None]);
+    }
+
+    #[test]
+    fn a_stray_packet_changes_nothing() {
+        let mut watch = Watch::new(false);
+        let transitions = feed(&mut watch, &[true, false, true, true, false, false], 3);
+        assert!(transitions.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn a_host_stops_after_debounce_quiet_ticks() {
+        let mut watch = Watch::new(true);
+        let transitions = feed(&mut watch, &[false, true, false, false, false], 3);
+        assert_eq!(transitions[4], Some(Transition::Stopped));
+        assert!(transitions[..4].iter().all(Option::is_none));
+        // and starts again
+        let transitions = feed(&mut watch, &[true], 1);
+        assert_eq!(transitions, [Some(Transition::Started)]);
+    }
+
+    #[test]
+    fn only_the_chosen_transitions_are_announced() {
+        assert!(WatchFor::Both.announces(Transition::Stopped));
+        assert!(WatchFor::Start.announces(Transition::Started));
+        assert!(!WatchFor::Start.announces(Transition::Stopped));
+        assert!(!WatchFor::Stop.announces(Transition::Started));
+    }
+}
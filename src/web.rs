@@ -0,0 +1,206 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+
+/// The last [`HostsSnapshot`](crate::snapshot::HostsSnapshot) served by `/hosts.json`,
+/// replaced on every stat tick.
+pub type WebSnapshot = Arc<Mutex<String>>;
+
+/// requests are a single line plus a few headers, anything bigger is dropped
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// a client that hasn't sent its request by then is dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The dashboard only answers this device unless `--web-bind` says otherwise.
+pub const DEFAULT_BIND: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>netui</title>
+<style>
+body { font-family: monospace; background: #020617; color: #e2e8f0; margin: 1em; }
+table { border-collapse: collapse; width: 100%; }
+th { background: #1e3a8a; text-align: left; }
+th, td { padding: 0.2em 0.6em; white-space: nowrap; }
+tr:nth-child(even) td { background: #0f172a; }
+</style>
+</head>
+<body>
+<h3 id="title">netui</h3>
+<p id="total"></p>
+<table>
+<thead><tr><th>IP Address</th><th>Mac Address</th><th>Speed ↓</th><th>Speed ↑</th><th>Activity</th><th>Service</th><th>Last seen</th></tr></thead>
+<tbody id="hosts"></tbody>
+</table>
+<script>
+function cell(row, text) {
+  const td = document.createElement("td");
+  td.textContent = text;
+  row.appendChild(td);
+}
+async function refresh() {
+  try {
+    const data = await (await fetch("/hosts.json", { cache: "no-store" })).json();
+    document.getElementById("title").textContent = "netui on " + data.interface;
+    document.getElementById("total").textContent =
+      "Total ↓ " + data.total.input + " | ↑ " + data.total.output;
+    const body = document.getElementById("hosts");
+    body.replaceChildren();
+    for (const host of data.hosts) {
+      const row = document.createElement("tr");
+      cell(row, host.ip);
+      cell(row, host.mac + (host.is_my_device ? " (*)" : ""));
+      cell(row, host.speed ? host.speed.input : "");
+      cell(row, host.speed ? host.speed.output : "");
+      cell(row, host.category || "");
+      cell(row, host.service || "");
+      cell(row, host.last_seen);
+      body.appendChild(row);
+    }
+  } catch (e) {
+    document.getElementById("total").textContent = "disconnected";
+  }
+}
+refresh();
+setInterval(refresh, 1000);
+</script>
+</body>
+</html>
+"#;
+
+/// Binds the dashboard on `address`, there's no authentication: an address
+/// other devices can reach lets anyone on the network see the hosts.
+pub async fn bind(address: IpAddr, port: u16) -> std::io::Result<TcpListener> {
+    TcpListener::bind(SocketAddr::from((address, port))).await
+}
+
+/// Serves the dashboard page and its JSON endpoint until the process exits.
+pub async fn serve(listener: TcpListener, snapshot: WebSnapshot) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let snapshot = snapshot.clone();
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, snapshot, REQUEST_TIMEOUT).await;
+        });
+    }
+}
+
+/// Reads up to the end of the request headers, `None` if the client hung up
+/// or sent too much.
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<Vec<u8>>> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_SIZE {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+    Ok(Some(request))
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    snapshot: WebSnapshot,
+    read_timeout: Duration,
+) -> std::io::Result<()> {
+    let Ok(request) = timeout(read_timeout, read_request(&mut stream)).await else {
+        return Ok(());
+    };
+    let Some(request) = request? else {
+        return Ok(());
+    };
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/") => ("200 OK", "text/html; charset=utf-8", INDEX_HTML.to_string()),
+        ("GET", "/hosts.json") => {
+            let json = snapshot.lock().map(|json| json.clone()).unwrap_or_default();
+            ("200 OK", "application/json", json)
+        }
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn listen() -> (TcpListener, SocketAddr) {
+        let listener = bind(DEFAULT_BIND, 0).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        (listener, address)
+    }
+
+    #[tokio::test]
+    async fn the_dashboard_listens_on_loopback_by_default() {
+        let (_listener, address) = listen().await;
+        assert!(address.ip().is_loopback());
+    }
+
+    #[tokio::test]
+    async fn a_client_that_sends_nothing_is_dropped() {
+        let (listener, address) = listen().await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let snapshot = WebSnapshot::default();
+        let served = handle_connection(stream, snapshot, Duration::from_millis(50));
+        timeout(Duration::from_secs(5), served)
+            .await
+            .expect("the read timed out")
+            .unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(client.read(&mut buf).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn a_request_is_answered() {
+        let (listener, address) = listen().await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let snapshot = WebSnapshot::new(Mutex::new("{}".to_string()));
+        client
+            .write_all(b"GET /hosts.json HTTP/1.1\r\nHost: x\r\n\r\n")
+            .await
+            .unwrap();
+        handle_connection(stream, snapshot, REQUEST_TIMEOUT)
+            .await
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n{}"));
+    }
+}